@@ -0,0 +1,674 @@
+use crate::drift;
+use crate::exposure::{self, ExposureDecision};
+use crate::models::{AppState, ConvertToStableAction, ExecutionLatency, ExecutionReport, FeeBudgetExceeded, HedgeAction, LimitOrder, OrderAction, OrderType, StreamEvent, SwapRequest, SwapResponse, TokenBalance, TransactionFee, TransferAction, Wallet};
+use crate::price;
+use crate::swap;
+use crate::wallet::KnownTokens;
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// Default stable token of convert-to-stable actions
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+// Result of running an order's action. Errors returned instead of a result happened before
+// anything was sent, so the order can go back to waiting for its trigger.
+pub enum ActionResult {
+    Completed(Box<ActionOutcome>),
+    // A swap was sent but not confirmed in time, so its outcome is unknown. Nothing of it is
+    // booked until its signature is seen landing.
+    Unconfirmed(Box<UnconfirmedSwap>),
+    // The action was refused or failed on chain, the order fails with this reason
+    Failed(String),
+}
+
+// What a completed action did
+pub struct ActionOutcome {
+    // Unset when the action sent no transaction
+    pub transaction_signature: Option<String>,
+    pub execution: Option<ExecutionReport>,
+    // Phases of the swap, for actions that made one
+    pub latency: Option<ExecutionLatency>,
+    pub fee: Option<TransactionFee>,
+    // USD value the action moved, metered against the owner's notional quota
+    pub notional_usd: f64,
+}
+
+// Swap an action sent without seeing it confirmed, with what is booked once it lands
+pub struct UnconfirmedSwap {
+    pub swap_request: SwapRequest,
+    pub swap_result: SwapResponse,
+    // USD value of the swap, metered once it lands
+    pub notional_usd: f64,
+    // What the action did, its notional covering only the swaps it saw confirmed
+    pub outcome: ActionOutcome,
+}
+
+// Book a confirmed swap of an order: its ledger entry and any requote around the route denylist
+pub fn book_swap(app_state: &AppState, wallet: &str, order_id: &str, swap_request: &SwapRequest, swap_result: &SwapResponse) {
+    crate::ledger::record_trade(app_state, wallet, swap_request, swap_result, Some(order_id));
+    record_route_exclusions(app_state, order_id, swap_request, swap_result);
+}
+
+// Run the action of a triggered order
+pub async fn execute(app_state: &AppState, wallet: &Wallet, order: &LimitOrder) -> Result<ActionResult> {
+    // Only swaps have a paper fill, anything else would move real funds
+    let simulated = order.dry_run || crate::paper::simulation_mode();
+    if simulated && matches!(order.action, OrderAction::Hedge(_) | OrderAction::Transfer(_)) {
+        return Ok(ActionResult::Failed(format!("The {} action cannot be simulated", order.action)));
+    }
+
+    match &order.action {
+        OrderAction::Swap => execute_swap(app_state, wallet, order).await,
+        OrderAction::Hedge(hedge) => execute_hedge(app_state, order, hedge).await,
+        OrderAction::Transfer(transfer) => execute_transfer(app_state, wallet, order, transfer).await,
+        OrderAction::ConvertToStable(convert) => execute_convert_to_stable(app_state, wallet, order, convert).await,
+        OrderAction::SafeExit => execute_safe_exit(app_state, wallet, order).await,
+        OrderAction::NotifyOnly => {
+            info!("Order {} triggered, notifying only", order.id);
+            Ok(ActionResult::Completed(Box::new(ActionOutcome {
+                transaction_signature: None,
+                execution: None,
+                latency: None,
+                fee: None,
+                notional_usd: 0.0,
+            })))
+        }
+    }
+}
+
+// Check an order's action can run before accepting the order
+pub fn validate(action: &OrderAction, order_type: &OrderType, target_token: &str) -> Result<()> {
+    match action {
+        OrderAction::Hedge(hedge) => {
+            if let Some(size_percent) = hedge.size_percent {
+                if size_percent <= 0.0 || size_percent > 100.0 {
+                    return Err(anyhow!("Hedge size_percent must be greater than 0 and at most 100"));
+                }
+            }
+            hedge_market(hedge, target_token)?;
+            if !drift::trading_enabled() {
+                return Err(anyhow!("Hedge orders need a Drift gateway, set DRIFT_GATEWAY_URL"));
+            }
+            Ok(())
+        }
+        OrderAction::Transfer(transfer) => {
+            Pubkey::from_str(&transfer.destination)
+                .map_err(|_| anyhow!("Invalid transfer destination: {}", transfer.destination))?;
+            Ok(())
+        }
+        OrderAction::ConvertToStable(convert) => {
+            let stable_token = convert.stable_token.as_deref().unwrap_or(USDC_MINT);
+            KnownTokens::get_decimals(stable_token)
+                .map_err(|_| anyhow!("Unknown stable token: {}", stable_token))?;
+            Ok(())
+        }
+        OrderAction::SafeExit => {
+            // Buy amounts are in the target token, there is no source amount to replace
+            if !order_type.is_sell_side() {
+                return Err(anyhow!("Safe exit only applies to sell side orders"));
+            }
+            Ok(())
+        }
+        OrderAction::Swap | OrderAction::NotifyOnly => Ok(()),
+    }
+}
+
+// USD value of an order's amount at current prices
+fn order_notional(app_state: &AppState, order: &LimitOrder) -> Result<f64> {
+    let amount_mint = if order.order_type.is_sell_side() { &order.source_token } else { &order.target_token };
+    let price = price::get_token_price(app_state, amount_mint)
+        .map_err(|e| anyhow!("Failed to get price for {}: {}", KnownTokens::get_symbol(amount_mint), e))?;
+    Ok(order.amount * price)
+}
+
+// Drift market a hedge shorts
+fn hedge_market(hedge: &HedgeAction, target_token: &str) -> Result<String> {
+    match &hedge.market {
+        Some(market) => {
+            let market = market.trim().to_uppercase();
+            if drift::perp_market_index(&market).is_none() {
+                return Err(anyhow!("Hedging on {} is not supported", market));
+            }
+            Ok(market)
+        }
+        None => drift::perp_market_for_mint(target_token)
+            .map(|(market, _)| market.to_string())
+            .ok_or_else(|| anyhow!(
+                "No Drift perp market tracks {}, set the hedge market explicitly",
+                KnownTokens::get_symbol(target_token)
+            )),
+    }
+}
+
+// Open a short of the order's notional on Drift, leaving the spot position untouched
+async fn execute_hedge(app_state: &AppState, order: &LimitOrder, hedge: &HedgeAction) -> Result<ActionResult> {
+    let market = hedge_market(hedge, &order.target_token)?;
+    let notional_usd = order_notional(app_state, order)? * hedge.size_percent.unwrap_or(100.0) / 100.0;
+
+    // Size the short at the mark price it will fill around
+    drift::refresh_markets(app_state, std::slice::from_ref(&market)).await?;
+    let mark_price = drift::market_mark_price(app_state, &market)?;
+    let base_amount = notional_usd / mark_price;
+
+    info!(
+        "Hedging order {}: shorting {} {} (${:.2} at mark {})",
+        order.id, base_amount, market, notional_usd, mark_price
+    );
+
+    match drift::open_short(&market, base_amount).await {
+        Ok(signature) => Ok(ActionResult::Completed(Box::new(ActionOutcome {
+            transaction_signature: Some(signature),
+            execution: None,
+            latency: None,
+            fee: None,
+            notional_usd,
+        }))),
+        Err(err) => {
+            error!("Failed to open hedge for order {}: {}", order.id, err);
+            Ok(ActionResult::Failed(format!("Failed to open {} hedge: {}", market, err)))
+        }
+    }
+}
+
+// Send the order's amount of the source token to the safe wallet
+async fn execute_transfer(app_state: &AppState, wallet: &Wallet, order: &LimitOrder, transfer: &TransferAction) -> Result<ActionResult> {
+    let has_balance = crate::wallet::has_sufficient_balance(&app_state.rpc_client, wallet, &order.source_token, order.amount).await?;
+    if !has_balance {
+        return Ok(ActionResult::Failed("Insufficient balance of source token".to_string()));
+    }
+
+    let notional_usd = order.amount * price::get_token_price(app_state, &order.source_token).unwrap_or(0.0);
+
+    match crate::wallet::transfer_tokens(&app_state.rpc_client, wallet, &order.source_token, &transfer.destination, order.amount).await {
+        Ok(signature) => {
+            crate::ledger::record_transfer(
+                app_state,
+                &wallet.pubkey.to_string(),
+                &transfer.destination,
+                &order.source_token,
+                order.amount,
+                &signature,
+                Some(&order.id),
+            );
+
+            info!(
+                "Moved {} {} to {} for order {}. Signature: {}",
+                order.amount, KnownTokens::get_symbol(&order.source_token), transfer.destination, order.id, signature
+            );
+
+            Ok(ActionResult::Completed(Box::new(ActionOutcome {
+                transaction_signature: Some(signature),
+                execution: None,
+                latency: None,
+                fee: None,
+                notional_usd,
+            })))
+        }
+        Err(err) => {
+            error!("Failed to transfer funds for order {}: {}", order.id, err);
+            Ok(ActionResult::Failed(format!("Failed to transfer to {}: {}", transfer.destination, err)))
+        }
+    }
+}
+
+// Swap every token the wallet holds into the stable token, keeping some SOL back for fees.
+// Tokens that fail to swap are left in place; the action fails only if nothing was converted.
+async fn execute_convert_to_stable(
+    app_state: &AppState,
+    wallet: &Wallet,
+    order: &LimitOrder,
+    convert: &ConvertToStableAction,
+) -> Result<ActionResult> {
+    let stable_token = convert.stable_token.clone().unwrap_or_else(|| USDC_MINT.to_string());
+    let balances = crate::wallet::get_token_balances(&app_state.rpc_client, wallet).await?;
+
+    let mut signatures = Vec::new();
+    let mut notional_usd = 0.0;
+    let mut failures = Vec::new();
+
+    for balance in balances {
+        if balance.mint == stable_token || KnownTokens::get_decimals(&balance.mint).is_err() {
+            continue;
+        }
+
+        let amount = if balance.mint == SOL_MINT {
+            balance.amount - crate::liquidation::SOL_FEE_RESERVE
+        } else {
+            balance.amount
+        };
+        if amount <= 0.0 {
+            continue;
+        }
+
+        let swap_request = SwapRequest {
+            source_token: balance.mint.clone(),
+            target_token: stable_token.clone(),
+            amount,
+            amount_percent: None,
+            amount_usd: None,
+            slippage: Some(order.slippage),
+            wallet_pubkey: Some(wallet.pubkey.to_string()),
+            dry_run: order.dry_run,
+            priority_fee: order.priority_fee,
+            mev_protection: order.mev_protection,
+        };
+
+        match swap::execute_swap(app_state, wallet, &swap_request).await {
+            // The rest waits: converting more before this swap's outcome is known could oversell
+            Ok(mut swap_result) if !swap_result.success => {
+                price::value_fee(app_state, &mut swap_result.fee_details);
+                warn!(
+                    "Conversion of {} for order {} not confirmed, stopping. Signature: {}",
+                    balance.symbol, order.id, swap_result.transaction_signature
+                );
+                return Ok(ActionResult::Unconfirmed(Box::new(UnconfirmedSwap {
+                    notional_usd: amount * price::get_token_price(app_state, &balance.mint).unwrap_or(0.0),
+                    outcome: ActionOutcome {
+                        transaction_signature: Some(swap_result.transaction_signature.clone()),
+                        execution: None,
+                        latency: None,
+                        fee: None,
+                        notional_usd,
+                    },
+                    swap_request,
+                    swap_result,
+                })));
+            }
+            Ok(mut swap_result) => {
+                price::value_fee(app_state, &mut swap_result.fee_details);
+                book_swap(app_state, &wallet.pubkey.to_string(), &order.id, &swap_request, &swap_result);
+                notional_usd += amount * price::get_token_price(app_state, &balance.mint).unwrap_or(0.0);
+                info!(
+                    "Converted {} {} into {} for order {}. Signature: {}",
+                    amount, balance.symbol, KnownTokens::get_symbol(&stable_token), order.id, swap_result.transaction_signature
+                );
+                signatures.push(swap_result.transaction_signature);
+            }
+            Err(err) => {
+                error!("Failed to convert {} for order {}: {}", balance.symbol, order.id, err);
+                failures.push(format!("{}: {}", balance.symbol, err));
+            }
+        }
+    }
+
+    if signatures.is_empty() {
+        let reason = if failures.is_empty() {
+            "Nothing to convert".to_string()
+        } else {
+            format!("Failed to convert to stable: {}", failures.join("; "))
+        };
+        return Ok(ActionResult::Failed(reason));
+    }
+
+    // Every swap is in the ledger under the order id, the order keeps the first signature
+    Ok(ActionResult::Completed(Box::new(ActionOutcome {
+        transaction_signature: signatures.into_iter().next(),
+        execution: None,
+        latency: None,
+        fee: None,
+        notional_usd,
+    })))
+}
+
+// Sell the wallet's whole current balance of the source token, keeping some SOL back for fees
+async fn execute_safe_exit(app_state: &AppState, wallet: &Wallet, order: &LimitOrder) -> Result<ActionResult> {
+    let balances = crate::wallet::get_token_balances(&app_state.rpc_client, wallet).await?;
+    let held = balances
+        .iter()
+        .find(|balance| balance.mint == order.source_token)
+        .map_or(0.0, |balance| balance.amount);
+
+    let amount = if order.source_token == SOL_MINT {
+        held - crate::liquidation::SOL_FEE_RESERVE
+    } else {
+        held
+    };
+    if amount <= 0.0 {
+        return Ok(ActionResult::Failed(format!(
+            "No {} left to sell",
+            KnownTokens::get_symbol(&order.source_token)
+        )));
+    }
+
+    info!(
+        "Safe exit for order {}: selling {} {} instead of the {} set at creation",
+        order.id, amount, KnownTokens::get_symbol(&order.source_token), order.amount
+    );
+
+    let mut order = order.clone();
+    set_order_amount(app_state, &mut order, amount);
+    execute_swap(app_state, wallet, &order).await
+}
+
+// Amount of an order sized as a share of the wallet's source token balance. Buy amounts are in the
+// target token, so the share is converted at current prices, leaving room for slippage.
+pub fn amount_from_percent(
+    app_state: &AppState,
+    order_type: &OrderType,
+    source_token: &str,
+    target_token: &str,
+    balances: &[TokenBalance],
+    amount_percent: f64,
+    slippage: f64,
+) -> Result<f64> {
+    let spend = crate::wallet::balance_share(balances, source_token, amount_percent);
+    if order_type.is_sell_side() {
+        return Ok(spend);
+    }
+
+    let source_price = price::get_token_price(app_state, source_token)
+        .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
+    let target_price = price::get_token_price(app_state, target_token)
+        .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
+    if target_price <= 0.0 {
+        return Err(anyhow!("No price for {} to size the buy with", KnownTokens::get_symbol(target_token)));
+    }
+    Ok(spend * source_price / target_price / (1.0 + slippage / 100.0))
+}
+
+// Resize an order set with amount_percent from the wallet's balance at trigger time. Returns why
+// the order cannot run when the wallet has nothing left to trade.
+pub async fn resolve_amount_percent(app_state: &AppState, wallet: &Wallet, order: &mut LimitOrder) -> Result<Option<String>> {
+    let amount_percent = match order.amount_percent {
+        Some(amount_percent) => amount_percent,
+        None => return Ok(None),
+    };
+
+    let balances = crate::wallet::get_token_balances(&app_state.rpc_client, wallet).await?;
+    let amount = amount_from_percent(
+        app_state,
+        &order.order_type,
+        &order.source_token,
+        &order.target_token,
+        &balances,
+        amount_percent,
+        order.slippage,
+    )?;
+    if amount <= 0.0 {
+        return Ok(Some(format!("No {} left to trade", KnownTokens::get_symbol(&order.source_token))));
+    }
+
+    info!(
+        "Order {} trades {}% of the wallet's {}: {} instead of the {} estimated at creation",
+        order.id, amount_percent, KnownTokens::get_symbol(&order.source_token), amount, order.amount
+    );
+    set_order_amount(app_state, order, amount);
+    Ok(None)
+}
+
+// Units of a token worth `amount_usd` at its current price
+pub fn amount_from_usd(app_state: &AppState, mint: &str, amount_usd: f64) -> Result<f64> {
+    let price_usd = price::get_token_price(app_state, mint)
+        .map_err(|e| anyhow!("Failed to get price for {}: {}", KnownTokens::get_symbol(mint), e))?;
+    if price_usd <= 0.0 {
+        return Err(anyhow!("No price for {} to size the amount with", KnownTokens::get_symbol(mint)));
+    }
+    Ok(amount_usd / price_usd)
+}
+
+// Resize an order set with amount_usd at the prices it triggered at
+pub fn resolve_amount_usd(app_state: &AppState, order: &mut LimitOrder) -> Result<()> {
+    let amount_usd = match order.amount_usd {
+        Some(amount_usd) => amount_usd,
+        None => return Ok(()),
+    };
+
+    // Sell side amounts are in the source token, buy amounts in the target token
+    let amount_mint = if order.order_type.is_sell_side() { &order.source_token } else { &order.target_token };
+    let amount = amount_from_usd(app_state, amount_mint, amount_usd)?;
+    info!(
+        "Order {} trades ${} worth: {} instead of the {} estimated at creation",
+        order.id, amount_usd, amount, order.amount
+    );
+    set_order_amount(app_state, order, amount);
+    Ok(())
+}
+
+// Change the amount an order trades, keeping the stored order in step
+fn set_order_amount(app_state: &AppState, order: &mut LimitOrder, amount: f64) {
+    order.amount = amount;
+
+    // Sell side amounts are in the source token, buy amounts in the target token
+    let amount_mint = if order.order_type.is_sell_side() { &order.source_token } else { &order.target_token };
+    let amount_raw = KnownTokens::get_decimals(amount_mint)
+        .ok()
+        .map(|decimals| crate::utils::raw_amount_string(amount, decimals as u8));
+    order.amount_raw = amount_raw.clone();
+
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    if let Some(stored) = orders.get_mut(&order.id) {
+        stored.amount = amount;
+        stored.amount_raw = amount_raw;
+    }
+}
+
+// Note on the order's audit trail when its swap was requoted around the route denylist
+fn record_route_exclusions(app_state: &AppState, order_id: &str, swap_request: &SwapRequest, swap_result: &SwapResponse) {
+    if swap_result.route_exclusions.is_empty() {
+        return;
+    }
+
+    crate::orders::record_audit(
+        app_state,
+        order_id,
+        "route_requoted",
+        format!(
+            "Rejected a {} -> {} quote routed through denylisted {}, executed a requote excluding them",
+            KnownTokens::get_symbol(&swap_request.source_token),
+            KnownTokens::get_symbol(&swap_request.target_token),
+            swap_result.route_exclusions.join(", ")
+        ),
+    );
+}
+
+// Compare what a swap delivered with what the order should have received at the prices it executed at
+fn build_execution_report(app_state: &AppState, order: &LimitOrder, swap_result: &SwapResponse) -> ExecutionReport {
+    let source_price = price::get_token_price(app_state, &order.source_token).unwrap_or(0.0);
+    let target_price = price::get_token_price(app_state, &order.target_token).unwrap_or(0.0);
+    let input_value_usd = swap_result.source_amount * source_price;
+
+    let expected_target_amount = if target_price > 0.0 { input_value_usd / target_price } else { 0.0 };
+    let slippage_percent = if expected_target_amount > 0.0 {
+        (expected_target_amount - swap_result.target_amount) / expected_target_amount * 100.0
+    } else {
+        0.0
+    };
+
+    ExecutionReport {
+        source_amount: swap_result.source_amount,
+        target_amount: swap_result.target_amount,
+        expected_target_amount,
+        slippage_percent,
+        input_value_usd,
+        executed_at: swap_result.timestamp,
+        source_price_usd: (source_price > 0.0).then_some(source_price),
+        target_price_usd: (target_price > 0.0).then_some(target_price),
+    }
+}
+
+// Validate balances and swap the source token into the target token
+async fn execute_swap(app_state: &AppState, wallet: &Wallet, order: &LimitOrder) -> Result<ActionResult> {
+    // Estimate transaction fees, including rent if the target token account has to be created
+    let fee_estimate = crate::wallet::estimate_order_fees(&app_state.rpc_client, &wallet.pubkey, &order.target_token).await;
+    let estimated_fee = fee_estimate.total_sol;
+
+    info!(
+        "Estimated fees for order execution: {} SOL network fee + {} SOL token account rent",
+        fee_estimate.network_fee_sol, fee_estimate.ata_rent_sol
+    );
+
+    // Get current prices for calculation
+    let target_price = price::get_token_price(app_state, &order.target_token)
+        .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
+
+    // Automated buys must stay within the exposure limit of the token they buy
+    let mut order = order.clone();
+    if !order.order_type.is_sell_side() {
+        match exposure::check_buy(app_state, wallet, &order, target_price).await? {
+            ExposureDecision::Allowed => {}
+            ExposureDecision::Downsized(amount) => {
+                info!("Downsizing order {} from {} to {} to respect the exposure limit", order.id, order.amount, amount);
+                set_order_amount(app_state, &mut order, amount);
+            }
+            ExposureDecision::Rejected(reason) => return Ok(ActionResult::Failed(reason)),
+        }
+    }
+
+    // Double-check balance before executing based on order type. Paper orders are checked
+    // against their paper balances when the swap is filled instead.
+    if order.dry_run || crate::paper::simulation_mode() {
+        info!("Order {} trades on paper, skipping on-chain balance checks", order.id);
+    } else if order.order_type.is_sell_side() {
+        // For sell side orders, check if the wallet still has enough of the source token
+        let has_balance = crate::wallet::has_sufficient_balance(
+            &app_state.rpc_client,
+            wallet,
+            &order.source_token,
+            order.amount
+        ).await?;
+
+        if !has_balance {
+            let order_type_str = order.order_type.to_string();
+            error!("{} order {} failed: Insufficient balance of {} to execute",
+                   order_type_str, order.id, KnownTokens::get_symbol(&order.source_token));
+
+            return Ok(ActionResult::Failed("Insufficient balance of source token".to_string()));
+        }
+    } else {
+        // For buy orders, we need to calculate the estimated cost in the source token
+        // Get current price of the source token
+        let source_price = price::get_token_price(app_state, &order.source_token)
+            .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
+
+        // Calculate estimated amount needed in source token using current prices
+        let price_ratio = if source_price > 0.0 { target_price / source_price } else { 0.0 };
+        let estimated_source_amount = order.amount * price_ratio * (1.0 + order.slippage / 100.0);
+
+        info!(
+            "Buy order execution calculation: Target price: ${}, Source price: ${}, Price ratio: {}, Estimated source amount needed: {}",
+            target_price, source_price, price_ratio, estimated_source_amount
+        );
+
+        // Check if the wallet has enough of the source token for the estimated cost
+        let has_enough_source = crate::wallet::has_sufficient_balance(
+            &app_state.rpc_client,
+            wallet,
+            &order.source_token,
+            estimated_source_amount
+        ).await?;
+
+        if !has_enough_source {
+            let order_type_str = order.order_type.to_string();
+            error!(
+                "{} order {} failed: Insufficient balance of {} to execute. Needed: {}, Current price: ${}",
+                order_type_str, order.id,
+                KnownTokens::get_symbol(&order.source_token),
+                estimated_source_amount,
+                source_price
+            );
+
+            return Ok(ActionResult::Failed("Insufficient balance of source token".to_string()));
+        }
+
+        // Also ensure they have some SOL for transaction fees
+        let has_sol = crate::wallet::has_sufficient_balance(
+            &app_state.rpc_client,
+            wallet,
+            "So11111111111111111111111111111111111111112",
+            estimated_fee
+        ).await?;
+
+        if !has_sol {
+            error!("Order {} failed: Insufficient SOL for transaction fees. Need at least {} SOL ({} SOL token account rent)",
+                   order.id, estimated_fee, fee_estimate.ata_rent_sol);
+
+            return Ok(ActionResult::Failed("Insufficient SOL for transaction fees".to_string()));
+        }
+    }
+
+    // Create swap request
+    let swap_request = SwapRequest {
+        source_token: order.source_token.clone(),
+        target_token: order.target_token.clone(),
+        amount: order.amount,
+        amount_percent: None,
+        amount_usd: None,
+        slippage: Some(order.slippage),
+        wallet_pubkey: Some(order.wallet_pubkey.clone()),
+        dry_run: order.dry_run,
+        priority_fee: order.priority_fee,
+        mev_protection: order.mev_protection,
+    };
+
+    info!("Executing limit order {} - {:?} order for {} {} at price target {}",
+           order.id,
+           order.order_type,
+           order.amount,
+           KnownTokens::get_symbol(&order.source_token),
+           order.price_target);
+
+    // Execute swap
+    match swap::execute_swap_with_fee_budget(app_state, wallet, &swap_request, order.max_fee_usd).await {
+        // Sent but not seen landing: the order waits for its signature before anything is booked
+        Ok(mut swap_result) if !swap_result.success => {
+            price::value_fee(app_state, &mut swap_result.fee_details);
+            let execution = build_execution_report(app_state, &order, &swap_result);
+            warn!(
+                "Swap for order {} not confirmed in time. Signature: {}",
+                order.id, swap_result.transaction_signature
+            );
+
+            Ok(ActionResult::Unconfirmed(Box::new(UnconfirmedSwap {
+                notional_usd: execution.input_value_usd,
+                outcome: ActionOutcome {
+                    transaction_signature: Some(swap_result.transaction_signature.clone()),
+                    notional_usd: 0.0,
+                    execution: Some(execution),
+                    latency: Some(swap_result.latency.clone()),
+                    fee: Some(swap_result.fee_details.clone()),
+                },
+                swap_request,
+                swap_result,
+            })))
+        }
+        Ok(mut swap_result) => {
+            price::value_fee(app_state, &mut swap_result.fee_details);
+            book_swap(app_state, &wallet.pubkey.to_string(), &order.id, &swap_request, &swap_result);
+
+            let execution = build_execution_report(app_state, &order, &swap_result);
+
+            info!(
+                "Swapped {} {} -> {} for order {} at price {}. Signature: {}",
+                order.amount,
+                KnownTokens::get_symbol(&order.source_token),
+                KnownTokens::get_symbol(&order.target_token),
+                order.id,
+                order.price_target,
+                swap_result.transaction_signature
+            );
+
+            Ok(ActionResult::Completed(Box::new(ActionOutcome {
+                transaction_signature: Some(swap_result.transaction_signature),
+                notional_usd: execution.input_value_usd,
+                execution: Some(execution),
+                latency: Some(swap_result.latency),
+                fee: Some(swap_result.fee_details),
+            })))
+        }
+        // Nothing was sent, so the order stays active and tries again once fees come down
+        Err(err) if err.downcast_ref::<FeeBudgetExceeded>().is_some() => {
+            warn!("Order {} held back: {}", order.id, err);
+            crate::orders::record_audit(app_state, &order.id, "fee_budget_exceeded", err.to_string());
+            crate::events::publish(app_state, StreamEvent::OrderWarning { order: order.clone(), warning: err.to_string() });
+            Err(err)
+        }
+        Err(err) => {
+            error!("Failed to execute order {}: {}", order.id, err);
+            Ok(ActionResult::Failed(err.to_string()))
+        }
+    }
+}
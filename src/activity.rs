@@ -0,0 +1,308 @@
+use crate::events;
+use crate::models::{ActivityDirection, AppState, NotificationEvent, StreamEvent, WalletActivity};
+use crate::notify;
+use crate::utils;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+use futures_util::StreamExt;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiLoadedAddresses, UiMessage,
+    UiRawMessage, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::{task::JoinHandle, time};
+use tracing::{error, info, warn};
+
+// Native SOL, reported under the wrapped SOL mint
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// How often the set of tracked wallets is compared with the running subscriptions
+const WALLET_SCAN_INTERVAL_SECS: u64 = 30;
+
+// Wait before re-subscribing after the feed drops
+const RESUBSCRIBE_DELAY_SECS: u64 = 5;
+
+// How many times to look for a notified transaction before giving up on it
+const FETCH_ATTEMPTS: u32 = 10;
+
+// WebSocket endpoint of the RPC node, derived from the HTTP one unless set explicitly
+fn get_ws_url() -> String {
+    std::env::var("SOLANA_WS_URL").unwrap_or_else(|_| {
+        let rpc_url = wallet::get_rpc_url();
+        if let Some(rest) = rpc_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            rpc_url
+        }
+    })
+}
+
+// Background task keeping one account subscription per tracked wallet
+pub async fn monitor_wallet_activity(app_state: Arc<AppState>) {
+    info!("Starting wallet activity monitor task");
+    let mut watchers: HashMap<String, JoinHandle<()>> = HashMap::new();
+
+    loop {
+        let tracked = {
+            let wallets = app_state.wallets.lock().unwrap();
+            wallets.keys().cloned().collect::<Vec<_>>()
+        };
+
+        // Stop watching wallets that were removed or locked
+        watchers.retain(|pubkey, handle| {
+            if tracked.contains(pubkey) {
+                return true;
+            }
+            handle.abort();
+            info!("Stopped watching wallet {}", pubkey);
+            false
+        });
+
+        for pubkey in tracked {
+            watchers
+                .entry(pubkey.clone())
+                .or_insert_with(|| tokio::spawn(watch_wallet(app_state.clone(), pubkey)));
+        }
+
+        time::sleep(time::Duration::from_secs(WALLET_SCAN_INTERVAL_SECS)).await;
+    }
+}
+
+// Follow every transaction mentioning the wallet, re-subscribing whenever the feed drops
+async fn watch_wallet(app_state: Arc<AppState>, pubkey: String) {
+    info!("Watching wallet {} for deposits and withdrawals", pubkey);
+
+    loop {
+        if let Err(err) = follow_wallet(&app_state, &pubkey).await {
+            warn!("Activity feed for wallet {} dropped: {}", pubkey, err);
+        }
+        time::sleep(time::Duration::from_secs(RESUBSCRIBE_DELAY_SECS)).await;
+    }
+}
+
+// Subscribe to the wallet's transaction logs and report each successful transaction
+async fn follow_wallet(app_state: &AppState, pubkey: &str) -> Result<()> {
+    let client = PubsubClient::new(&get_ws_url()).await?;
+    let (mut notifications, _unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![pubkey.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await?;
+
+    while let Some(notification) = notifications.next().await {
+        // Failed transactions moved nothing but the fee
+        if notification.value.err.is_some() {
+            continue;
+        }
+
+        let signature = notification.value.signature;
+        if let Err(err) = report_transaction(app_state, pubkey, &signature).await {
+            error!("Failed to inspect transaction {} for wallet {}: {}", signature, pubkey, err);
+        }
+    }
+
+    Err(anyhow!("subscription closed"))
+}
+
+// Fetch a confirmed transaction, retrying while the node catches up
+async fn fetch_transaction(app_state: &AppState, signature: &str) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let signature = Signature::from_str(signature)?;
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for _ in 0..FETCH_ATTEMPTS {
+        if let Ok(transaction) = app_state.rpc_client.get_transaction_with_config(&signature, config).await {
+            return Ok(transaction);
+        }
+        time::sleep(time::Duration::from_secs(1)).await;
+    }
+
+    Err(anyhow!("transaction not found"))
+}
+
+// Notify about the deposits and withdrawals in one transaction, unless this server sent it
+async fn report_transaction(app_state: &AppState, pubkey: &str, signature: &str) -> Result<()> {
+    let transaction = fetch_transaction(app_state, signature).await?;
+
+    // Swaps and transfers made through the API are already in the ledger
+    let known = {
+        let ledger = app_state.ledger.lock().unwrap();
+        ledger.iter().any(|entry| entry.transaction_signature == signature)
+    };
+    if known {
+        return Ok(());
+    }
+
+    for activity in parse_activity(pubkey, signature, &transaction)? {
+        let counterparty = activity.counterparty.as_deref().unwrap_or("unknown");
+        let (event, message) = match activity.direction {
+            ActivityDirection::Deposit => (
+                NotificationEvent::WalletDeposit,
+                format!(
+                    "Deposit of {} {} into wallet {} from {}. Signature: {}",
+                    activity.amount, activity.symbol, activity.wallet, counterparty, activity.signature
+                ),
+            ),
+            ActivityDirection::Withdrawal => (
+                NotificationEvent::WalletWithdrawal,
+                format!(
+                    "Withdrawal of {} {} from wallet {} to {}. Signature: {}",
+                    activity.amount, activity.symbol, activity.wallet, counterparty, activity.signature
+                ),
+            ),
+        };
+        info!("{}", message);
+
+        events::publish(app_state, StreamEvent::WalletActivity { activity: activity.clone() });
+        let owner = app_state.wallet_owners.lock().unwrap().get(&activity.wallet).cloned();
+        notify::notify_with_details(app_state, event, owner.as_deref(), message, serde_json::to_value(&activity).ok()).await;
+    }
+
+    Ok(())
+}
+
+// Message of a JSON encoded transaction
+pub fn raw_message(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Result<&UiRawMessage> {
+    match &transaction.transaction.transaction {
+        EncodedTransaction::Json(ui_transaction) => match &ui_transaction.message {
+            UiMessage::Raw(message) => Ok(message),
+            UiMessage::Parsed(_) => Err(anyhow!("unexpected parsed message")),
+        },
+        _ => Err(anyhow!("unexpected transaction encoding")),
+    }
+}
+
+// Accounts of a transaction, static keys first, then the ones loaded from lookup tables,
+// matching the balance and instruction indexes
+pub fn account_keys(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Result<Vec<String>> {
+    let mut account_keys = raw_message(transaction)?.account_keys.clone();
+    let loaded: Option<UiLoadedAddresses> = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.loaded_addresses.clone().into());
+    if let Some(loaded) = loaded {
+        account_keys.extend(loaded.writable);
+        account_keys.extend(loaded.readonly);
+    }
+    Ok(account_keys)
+}
+
+// Change of every owner's balance per mint in a transaction, native SOL under the wrapped SOL
+// mint. The fee paid by the first account is not counted as a change.
+pub fn balance_changes(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Result<HashMap<String, HashMap<String, f64>>> {
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow!("transaction has no status meta"))?;
+    let account_keys = account_keys(transaction)?;
+
+    let mut changes: HashMap<String, HashMap<String, f64>> = HashMap::new();
+
+    for (index, key) in account_keys.iter().enumerate() {
+        let (pre, post) = match (meta.pre_balances.get(index), meta.post_balances.get(index)) {
+            (Some(pre), Some(post)) => (*pre, *post),
+            _ => continue,
+        };
+        let mut change = post as i128 - pre as i128;
+        // The fee payer's balance also dropped by the fee, which is not a withdrawal
+        if index == 0 {
+            change += meta.fee as i128;
+        }
+        if change != 0 {
+            let sol = utils::lamports_to_sol(change.unsigned_abs() as u64) * change.signum() as f64;
+            *changes.entry(SOL_MINT.to_string()).or_default().entry(key.clone()).or_insert(0.0) += sol;
+        }
+    }
+
+    let pre_tokens: Option<Vec<UiTransactionTokenBalance>> = meta.pre_token_balances.clone().into();
+    let post_tokens: Option<Vec<UiTransactionTokenBalance>> = meta.post_token_balances.clone().into();
+    for (balances, sign) in [(pre_tokens, -1.0), (post_tokens, 1.0)] {
+        for balance in balances.unwrap_or_default() {
+            let owner: Option<String> = balance.owner.into();
+            let owner = match owner {
+                Some(owner) => owner,
+                None => continue,
+            };
+            let amount = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+            *changes.entry(balance.mint.clone()).or_default().entry(owner).or_insert(0.0) += sign * amount;
+        }
+    }
+
+    Ok(changes)
+}
+
+// Balance changes of the wallet in a transaction, as deposits and withdrawals. Transactions where
+// the wallet both gained and lost tokens are trades, not treasury moves, and yield nothing.
+fn parse_activity(
+    pubkey: &str,
+    signature: &str,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<Vec<WalletActivity>> {
+    // Change per mint for the wallet, and the same change for every other owner as candidate counterparties
+    let changes = balance_changes(transaction)?;
+
+    let wallet_changes = changes
+        .iter()
+        .filter_map(|(mint, by_owner)| {
+            let change = by_owner.get(pubkey).copied().unwrap_or(0.0);
+            (change.abs() > f64::EPSILON).then(|| (mint.clone(), change))
+        })
+        .collect::<Vec<_>>();
+
+    let gained = wallet_changes.iter().any(|(_, change)| *change > 0.0);
+    let lost = wallet_changes.iter().any(|(_, change)| *change < 0.0);
+    if gained && lost {
+        return Ok(Vec::new());
+    }
+
+    let timestamp = transaction
+        .block_time
+        .and_then(|block_time| Utc.timestamp_opt(block_time, 0).single())
+        .unwrap_or_else(Utc::now);
+
+    Ok(wallet_changes
+        .into_iter()
+        .map(|(mint, change)| {
+            // The counterparty moved the most of the same token the other way
+            let counterparty = changes[&mint]
+                .iter()
+                .filter(|(owner, other)| owner.as_str() != pubkey && other.signum() != change.signum())
+                .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(owner, _)| owner.clone());
+
+            WalletActivity {
+                wallet: pubkey.to_string(),
+                direction: if change > 0.0 {
+                    ActivityDirection::Deposit
+                } else {
+                    ActivityDirection::Withdrawal
+                },
+                symbol: KnownTokens::get_symbol(&mint),
+                mint,
+                amount: change.abs(),
+                counterparty,
+                signature: signature.to_string(),
+                slot: transaction.slot,
+                timestamp,
+            }
+        })
+        .collect())
+}
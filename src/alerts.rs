@@ -0,0 +1,287 @@
+use crate::drift;
+use crate::models::{AppState, BalanceAlert, BalanceAlertRequest, FundingCondition, FundingRateAlert, FundingRateAlertRequest, NotificationEvent};
+use crate::notify;
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+// Create a balance alert for an imported wallet the tenant may use
+pub fn create_balance_alert(app_state: &AppState, tenant: &str, request: BalanceAlertRequest) -> Result<BalanceAlert> {
+    if request.threshold <= 0.0 {
+        return Err(anyhow!("threshold must be greater than zero"));
+    }
+
+    wallet::find_tenant_wallet(app_state, tenant, Some(&request.wallet))?;
+
+    let alert = BalanceAlert {
+        id: Uuid::new_v4().to_string(),
+        wallet: request.wallet,
+        mint: request.mint,
+        threshold: request.threshold,
+        triggered: false,
+        last_balance: None,
+        last_checked: None,
+        created_at: Utc::now(),
+    };
+
+    let mut alerts = app_state.balance_alerts.lock().unwrap();
+    alerts.insert(alert.id.clone(), alert.clone());
+
+    info!(
+        "Balance alert {} created: {} {} below {}",
+        alert.id, alert.wallet, KnownTokens::get_symbol(&alert.mint), alert.threshold
+    );
+    Ok(alert)
+}
+
+// All balance alerts
+pub fn get_balance_alerts(app_state: &AppState) -> Vec<BalanceAlert> {
+    let alerts = app_state.balance_alerts.lock().unwrap();
+    alerts.values().cloned().collect()
+}
+
+// Balance alerts on the wallets a tenant may use
+pub fn get_tenant_balance_alerts(app_state: &AppState, tenant: &str) -> Vec<BalanceAlert> {
+    get_balance_alerts(app_state)
+        .into_iter()
+        .filter(|alert| tenant::can_use_wallet(app_state, tenant, &alert.wallet))
+        .collect()
+}
+
+// Delete a balance alert on a wallet the tenant may use
+pub fn remove_balance_alert(app_state: &AppState, tenant: &str, alert_id: &str) -> Result<BalanceAlert> {
+    let mut alerts = app_state.balance_alerts.lock().unwrap();
+    let allowed = alerts
+        .get(alert_id)
+        .is_some_and(|alert| tenant::can_use_wallet(app_state, tenant, &alert.wallet));
+    if !allowed {
+        return Err(anyhow!("Balance alert not found: {}", alert_id));
+    }
+
+    alerts
+        .remove(alert_id)
+        .ok_or_else(|| anyhow!("Balance alert not found: {}", alert_id))
+}
+
+// Check every alert against fresh balances, notifying when a threshold is crossed
+pub async fn check_balance_alerts(app_state: &AppState) {
+    let alerts = get_balance_alerts(app_state);
+    if alerts.is_empty() {
+        return;
+    }
+
+    // Fetch each wallet's balances once, even if it has several alerts
+    let mut balances = HashMap::new();
+    for alert in &alerts {
+        if balances.contains_key(&alert.wallet) {
+            continue;
+        }
+
+        let alert_wallet = {
+            let wallets = app_state.wallets.lock().unwrap();
+            wallets.get(&alert.wallet).cloned()
+        };
+
+        let alert_wallet = match alert_wallet {
+            Some(alert_wallet) => alert_wallet,
+            None => {
+                warn!("Balance alert {} refers to a wallet that is no longer imported", alert.id);
+                continue;
+            }
+        };
+
+        match wallet::get_token_balances(&app_state.rpc_client, &alert_wallet).await {
+            Ok(wallet_balances) => {
+                balances.insert(alert.wallet.clone(), wallet_balances);
+            }
+            Err(err) => error!("Failed to load balances for {}: {}", alert.wallet, err),
+        }
+    }
+
+    let now = Utc::now();
+    let mut crossed = Vec::new();
+    {
+        let mut stored = app_state.balance_alerts.lock().unwrap();
+        for alert in stored.values_mut() {
+            let wallet_balances = match balances.get(&alert.wallet) {
+                Some(wallet_balances) => wallet_balances,
+                None => continue,
+            };
+
+            // A missing token account is a zero balance
+            let balance = wallet_balances
+                .iter()
+                .find(|balance| balance.mint == alert.mint)
+                .map(|balance| balance.amount)
+                .unwrap_or(0.0);
+
+            alert.last_balance = Some(balance);
+            alert.last_checked = Some(now);
+
+            if balance < alert.threshold && !alert.triggered {
+                alert.triggered = true;
+                crossed.push(alert.clone());
+            } else if balance >= alert.threshold && alert.triggered {
+                info!("Balance alert {} re-armed", alert.id);
+                alert.triggered = false;
+            }
+        }
+    }
+
+    for alert in crossed {
+        warn!(
+            "Wallet {} holds {} {}, below the alert threshold of {}",
+            alert.wallet,
+            alert.last_balance.unwrap_or(0.0),
+            KnownTokens::get_symbol(&alert.mint),
+            alert.threshold
+        );
+        let owner = app_state.wallet_owners.lock().unwrap().get(&alert.wallet).cloned();
+        let vars = serde_json::json!({
+            "wallet": alert.wallet,
+            "balance": alert.last_balance.unwrap_or(0.0),
+            "symbol": KnownTokens::get_symbol(&alert.mint),
+            "mint": alert.mint,
+            "threshold": alert.threshold,
+        });
+        notify::notify_event(app_state, NotificationEvent::LowBalance, owner.as_deref(), "low_balance", vars).await;
+    }
+}
+
+// Create a funding rate alert on a Drift perp market
+pub fn create_funding_alert(app_state: &AppState, tenant: &str, request: FundingRateAlertRequest) -> Result<FundingRateAlert> {
+    let market = request.market.trim().to_uppercase();
+    if !drift::is_perp_market(&market) {
+        return Err(anyhow!("Unknown perp market {}, expected a name like SOL-PERP", request.market));
+    }
+    if !request.threshold_percent.is_finite() {
+        return Err(anyhow!("threshold_percent must be a number"));
+    }
+
+    let alert = FundingRateAlert {
+        id: Uuid::new_v4().to_string(),
+        market,
+        condition: request.condition,
+        threshold_percent: request.threshold_percent,
+        triggered: false,
+        last_rate_percent: None,
+        last_checked: None,
+        created_at: Utc::now(),
+        api_key: Some(tenant.to_string()),
+    };
+
+    let mut alerts = app_state.funding_alerts.lock().unwrap();
+    alerts.insert(alert.id.clone(), alert.clone());
+
+    info!(
+        "Funding rate alert {} created: {} {:?} {}%",
+        alert.id, alert.market, alert.condition, alert.threshold_percent
+    );
+    Ok(alert)
+}
+
+// All funding rate alerts
+pub fn get_funding_alerts(app_state: &AppState) -> Vec<FundingRateAlert> {
+    let alerts = app_state.funding_alerts.lock().unwrap();
+    alerts.values().cloned().collect()
+}
+
+// Funding rate alerts created by a tenant
+pub fn get_tenant_funding_alerts(app_state: &AppState, tenant: &str) -> Vec<FundingRateAlert> {
+    get_funding_alerts(app_state)
+        .into_iter()
+        .filter(|alert| tenant::can_access(tenant, alert.api_key.as_deref()))
+        .collect()
+}
+
+// Delete a funding rate alert created by the tenant
+pub fn remove_funding_alert(app_state: &AppState, tenant: &str, alert_id: &str) -> Result<FundingRateAlert> {
+    let mut alerts = app_state.funding_alerts.lock().unwrap();
+    let allowed = alerts
+        .get(alert_id)
+        .is_some_and(|alert| tenant::can_access(tenant, alert.api_key.as_deref()));
+    if !allowed {
+        return Err(anyhow!("Funding rate alert not found: {}", alert_id));
+    }
+
+    alerts
+        .remove(alert_id)
+        .ok_or_else(|| anyhow!("Funding rate alert not found: {}", alert_id))
+}
+
+// Check every funding alert against fresh Drift funding rates, notifying when a threshold is crossed
+pub async fn check_funding_alerts(app_state: &AppState) {
+    let alerts = get_funding_alerts(app_state);
+    if alerts.is_empty() {
+        return;
+    }
+
+    let mut markets = alerts.iter().map(|alert| alert.market.clone()).collect::<Vec<_>>();
+    markets.sort();
+    markets.dedup();
+
+    // Markets that failed to refresh keep their last rate and are checked against it
+    if let Err(err) = drift::refresh_markets(app_state, &markets).await {
+        error!("{}", err);
+    }
+
+    let rates = drift::get_perp_markets(app_state)
+        .into_iter()
+        .filter_map(|market| market.funding_rate_percent.map(|rate| (market.market, rate)))
+        .collect::<HashMap<_, _>>();
+
+    let now = Utc::now();
+    let mut crossed = Vec::new();
+    {
+        let mut stored = app_state.funding_alerts.lock().unwrap();
+        for alert in stored.values_mut() {
+            let rate = match rates.get(&alert.market) {
+                Some(rate) => *rate,
+                None => continue,
+            };
+
+            alert.last_rate_percent = Some(rate);
+            alert.last_checked = Some(now);
+
+            let beyond = match alert.condition {
+                FundingCondition::Above => rate > alert.threshold_percent,
+                FundingCondition::Below => rate < alert.threshold_percent,
+            };
+
+            if beyond && !alert.triggered {
+                alert.triggered = true;
+                crossed.push(alert.clone());
+            } else if !beyond && alert.triggered {
+                info!("Funding rate alert {} re-armed", alert.id);
+                alert.triggered = false;
+            }
+        }
+    }
+
+    for alert in crossed {
+        let side = match alert.condition {
+            FundingCondition::Above => "above",
+            FundingCondition::Below => "below",
+        };
+        let message = format!(
+            "{} hourly funding rate is {:.4}%, {} the alert threshold of {}%",
+            alert.market,
+            alert.last_rate_percent.unwrap_or(0.0),
+            side,
+            alert.threshold_percent
+        );
+        warn!("{}", message);
+        notify::notify_with_details(
+            app_state,
+            NotificationEvent::FundingRate,
+            alert.api_key.as_deref(),
+            message,
+            serde_json::to_value(&alert).ok(),
+        )
+        .await;
+    }
+}
@@ -1,27 +1,116 @@
 use crate::models::{
-    AppState, CancelOrderRequest, ImportWalletRequest, LimitOrderRequest, SwapRequest, CreateWalletResponse,
+    AppState, BalanceAlertRequest, CreateApiKeyRequest, DcaOrderRequest, FundingRateAlertRequest, UsageQuotas, TenantLimitsConfig, RateLimitConfig, ExposureLimit, RouteDenylist, ValidationPolicy, CancelOrderRequest, FailedOrdersQuery, RetryFailedRequest, StrategyStatsQuery, SlippageStatsQuery, HeartbeatRequest, ImportWalletRequest, UnlockWalletRequest, LockWalletRequest, LimitOrderRequest, LiquidationPolicy, NotificationConfig, NotificationLocaleRequest, NotificationTemplateRequest, OrderType, SolReservePolicy, OrderValidation, PriceGuardConfig, PricePushRequest, ProviderPreference, WhatIfQuery, DepthQuery, QuoteRequest, Preferences, TimezoneQuery, TransactionsQuery, ListOrdersQuery, SwapRequest, SwapRouteRequest, TransferRequest, WatchdogConfig, WatchlistRequest, PaperBalanceRequest, CreateWalletResponse, WalletPreview, WalletQuery, RemoveWalletRequest, ExportWalletRequest, FreezeWalletRequest, TokenListQuery, UpstreamCacheRefreshRequest, BlackoutWindowRequest,
 };
+// Response types, referenced by the OpenAPI annotations of the handlers
+use crate::models::{
+    ApiResponse as Envelope, ApiKeyRecord, BalanceAlert, BlackoutWindow, CreatedApiKey, CreationQuarantine, DcaOrder, DepthResponse, EngineHealth, ExecutionReceipt, ExportedWallet, FailedOrderGroup, FundingRateAlert, IntegrityReport, JanitorReport, LatencyMetrics, LedgerEntry, LimitOrder, MintPrice, NotificationLocale, NotificationTemplate, OrderBacktrace, OrderEvent, PaperAccount, PerpMarket, PnlReport, PortfolioSnapshot, PortfolioValuation, PriceDivergence, PriceProviders, PublicStatus, QueuedOrder, ReconciledAnomaly, RemovedWallet, RetryFailedResponse, RiskSummary, SlippageStats, StrategyStats, SwapQuotePreview, SwapResponse, SwapRouteResponse, TokenBalance, TokenInfo, TokenPrice, UpstreamCacheEntry, WalletTransaction, UsageReport, WalletFreeze, WalletSummary, WatchlistEntry, WhatIfOutcome,
+};
+use crate::actions;
+use crate::alerts;
+use crate::auth;
+use crate::blackout;
+use crate::creation_guard;
+use crate::dca;
+use crate::denylist;
+use crate::drift;
+use crate::engine;
+use crate::error::ApiError;
+use crate::exposure;
+use crate::freeze;
+use crate::history;
+use crate::janitor;
+use crate::keystore;
+use crate::latency;
+use crate::ledger;
+use crate::liquidation;
+use crate::metering;
+use crate::notify;
 use crate::orders;
+use crate::paper;
+use crate::pnl;
+use crate::portfolio;
 use crate::price;
+use crate::rate_limit;
+use crate::receipts;
+use crate::reserve;
+use crate::slippage;
+use crate::risk;
 use crate::swap;
-use crate::utils;
+use crate::templates;
+use crate::tenant;
+use crate::upstream_cache;
+use crate::utils::{self, ApiResponse};
 use crate::wallet;
+use crate::watchdog;
+use crate::watchlist;
 use axum::{
-    extract::{Json, Extension},
-    http::StatusCode,
+    extract::{Json, Extension, Path, Query},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+// Build a success response with timestamps in the requested timezone,
+// falling back to the preferred timezone and then UTC
+fn localized_response<T: serde::Serialize>(
+    app_state: &AppState,
+    tz: Option<String>,
+    data: T,
+) -> utils::ApiResponse {
+    let tz = tz.or_else(|| app_state.preferences.lock().unwrap().timezone.clone());
+    
+    let offset = match tz.as_deref().map(utils::parse_timezone) {
+        Some(Ok(offset)) => offset,
+        Some(Err(err)) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+        None => return utils::build_success_response(data),
+    };
+    
+    match serde_json::to_value(data) {
+        Ok(mut value) => {
+            utils::localize_timestamps(&mut value, &offset);
+            utils::build_success_response(value)
+        }
+        Err(err) => utils::build_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to serialize response: {}", err)
+        ),
+    }
+}
 
 // Handler for health check
 pub async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, "OK")
 }
 
+// Handler for the public status page; plain JSON so the static site can embed it directly
+#[utoipa::path(
+    get,
+    path = "/status.json",
+    tag = "status",
+    responses((status = 200, description = "Engine status", body = PublicStatus))
+)]
+pub async fn get_public_status(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    Json(engine::public_status(&app_state))
+}
+
 // Handler for generating a new wallet
+#[utoipa::path(
+    post,
+    path = "/generate_wallet",
+    tag = "wallets",
+    responses((status = 200, description = "Success", body = Envelope<CreateWalletResponse>))
+)]
 pub async fn generate_wallet(
     Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     info!("Generating new wallet");
     
@@ -30,9 +119,14 @@ pub async fn generate_wallet(
         Ok((wallet, mnemonic)) => {
             let pubkey = wallet.pubkey.to_string();
             
+            // A freshly generated wallet cannot belong to anyone else yet
+            let tenant = metering::api_key_from_headers(&headers);
+            if let Err(err) = tenant::register_wallet(&app_state, &tenant, &pubkey) {
+                return utils::build_error_response(StatusCode::CONFLICT, &err.to_string());
+            }
+            
             // Store the wallet in app state
-            let mut wallets = app_state.wallets.lock().unwrap();
-            wallets.insert(pubkey.clone(), wallet);
+            wallet::add_wallet(&app_state, wallet);
             
             info!("Wallet generated successfully: {}", pubkey);
             
@@ -55,32 +149,91 @@ pub async fn generate_wallet(
 }
 
 // Handler for importing a wallet
+#[utoipa::path(
+    post,
+    path = "/import_wallet",
+    tag = "wallets",
+    request_body = ImportWalletRequest,
+    responses((status = 200, description = "Success", body = Envelope<serde_json::Value>))
+)]
 pub async fn import_wallet(
     Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ImportWalletRequest>,
 ) -> impl IntoResponse {
     info!("Importing wallet");
     
-    // Import wallet based on the type of key provided
-    let wallet_result = if let Some(private_key) = request.private_key {
-        wallet::import_from_private_key(&private_key)
-    } else if let Some(mnemonic) = request.mnemonic {
-        wallet::import_from_mnemonic(&mnemonic)
-    } else {
+    // Batches report each entry, and fail as a whole only if nothing was imported
+    if let Some(entries) = &request.wallets {
+        if request.preview {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                "Preview is only supported for single wallet imports"
+            );
+        }
+        
+        let tenant = metering::api_key_from_headers(&headers);
+        let response = wallet::import_wallets(&app_state, &tenant, entries, request.passphrase.as_deref(), request.mode);
+        if response.imported == 0 && response.failed > 0 {
+            return utils::build_api_response(
+                StatusCode::BAD_REQUEST,
+                Some(response),
+                Some("No wallet was imported".to_string())
+            );
+        }
+        return utils::build_success_response(response);
+    }
+    
+    if request.private_key.is_none() && request.mnemonic.is_none() {
         return utils::build_error_response(
             StatusCode::BAD_REQUEST,
-            "Either private_key or mnemonic must be provided"
+            "Either private_key, mnemonic or wallets must be provided"
         );
-    };
+    }
+    
+    // Import wallet based on the type of key provided
+    let wallet_result = wallet::import_from_key(request.private_key.as_deref(), request.mnemonic.as_deref());
     
     // Handle the import result
     match wallet_result {
+        Ok(wallet) if request.preview => {
+            let pubkey = wallet.pubkey.to_string();
+            
+            // Nothing is registered or stored, the key is dropped with the request
+            let balances = match wallet::get_token_balances(&app_state.rpc_client, &wallet).await {
+                Ok(balances) => Some(balances),
+                Err(err) => {
+                    error!("Failed to get balances for wallet preview {}: {}", pubkey, err);
+                    None
+                }
+            };
+            
+            info!("Previewed wallet import: {}", pubkey);
+            utils::build_success_response(WalletPreview { pubkey, balances })
+        }
         Ok(wallet) => {
             let pubkey = wallet.pubkey.to_string();
             
+            // In hosted mode a wallet stays with the tenant that imported it first
+            let tenant = metering::api_key_from_headers(&headers);
+            if let Err(err) = tenant::register_wallet(&app_state, &tenant, &pubkey) {
+                return utils::build_error_response(StatusCode::CONFLICT, &err.to_string());
+            }
+            
+            // Persist the wallet encrypted so it survives restarts
+            if let Some(passphrase) = &request.passphrase {
+                if let Err(err) = keystore::store_wallet(&app_state, &tenant, &wallet, passphrase) {
+                    error!("Failed to store wallet {}: {}", pubkey, err);
+                    return utils::build_error_response(
+                        StatusCode::BAD_REQUEST,
+                        &format!("Failed to store wallet: {}", err)
+                    );
+                }
+            }
+            
             // Store the wallet in app state
-            let mut wallets = app_state.wallets.lock().unwrap();
-            wallets.insert(pubkey.clone(), wallet);
+            wallet::set_label(&app_state, &pubkey, request.label.as_deref());
+            wallet::add_wallet(&app_state, wallet);
             
             info!("Wallet imported successfully: {}", pubkey);
             
@@ -98,189 +251,2766 @@ pub async fn import_wallet(
     }
 }
 
-// Handler for getting wallet balances
-pub async fn get_balances(
+// Handler for decrypting a wallet from the keystore
+#[utoipa::path(
+    post,
+    path = "/unlock_wallet",
+    tag = "wallets",
+    request_body = UnlockWalletRequest,
+    responses((status = 200, description = "Success", body = Envelope<serde_json::Value>))
+)]
+pub async fn unlock_wallet(
     Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<UnlockWalletRequest>,
 ) -> impl IntoResponse {
-    info!("Getting wallet balances");
+    info!("Unlocking wallet {}", request.pubkey);
     
-    // Get the wallets (for now, just use the first one if any)
-    let wallets = app_state.wallets.lock().unwrap();
-    
-    if wallets.is_empty() {
-        return utils::build_error_response(
-            StatusCode::BAD_REQUEST,
-            "No wallet imported"
-        );
+    let tenant = metering::api_key_from_headers(&headers);
+    match keystore::unlock_wallet(&app_state, &tenant, &request.pubkey, &request.passphrase) {
+        Ok(()) => utils::build_success_response(serde_json::json!({
+            "pubkey": request.pubkey
+        })),
+        Err(err) => {
+            error!("Failed to unlock wallet: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
     }
+}
+
+// Handler for removing a wallet's keypair from memory
+#[utoipa::path(
+    post,
+    path = "/lock_wallet",
+    tag = "wallets",
+    request_body = LockWalletRequest,
+    responses((status = 200, description = "Success", body = Envelope<serde_json::Value>))
+)]
+pub async fn lock_wallet(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LockWalletRequest>,
+) -> impl IntoResponse {
+    info!("Locking wallet {}", request.pubkey);
     
-    // Use the first wallet
-    let wallet = wallets.values().next().unwrap();
+    let tenant = metering::api_key_from_headers(&headers);
+    match keystore::lock_wallet(&app_state, &tenant, &request.pubkey, request.passphrase.as_deref()) {
+        Ok(()) => utils::build_success_response(serde_json::json!({
+            "pubkey": request.pubkey
+        })),
+        Err(err) => {
+            error!("Failed to lock wallet: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for removing a wallet together with its open orders
+#[utoipa::path(
+    post,
+    path = "/remove_wallet",
+    tag = "wallets",
+    request_body = RemoveWalletRequest,
+    responses((status = 200, description = "Success", body = Envelope<RemovedWallet>))
+)]
+pub async fn remove_wallet(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RemoveWalletRequest>,
+) -> impl IntoResponse {
+    info!("Removing wallet {}", request.pubkey);
     
-    // Get balances
-    match wallet::get_token_balances(wallet).await {
-        Ok(balances) => utils::build_success_response(balances),
+    let tenant = metering::api_key_from_headers(&headers);
+    match wallet::remove_wallet(&app_state, &tenant, &request.pubkey) {
+        Ok(removed) => utils::build_success_response(removed),
         Err(err) => {
-            error!("Failed to get balances: {}", err);
+            error!("Failed to remove wallet: {}", err);
             utils::build_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                &format!("Failed to get balances: {}", err)
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
             )
         }
     }
 }
 
-// Handler for getting token prices
-pub async fn get_prices(
+// Handler for exporting a wallet's secret key, guarded by the export password
+#[utoipa::path(
+    post,
+    path = "/export_wallet",
+    tag = "wallets",
+    request_body = ExportWalletRequest,
+    responses((status = 200, description = "Success", body = Envelope<ExportedWallet>))
+)]
+pub async fn export_wallet(
     Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ExportWalletRequest>,
 ) -> impl IntoResponse {
-    info!("Getting token prices");
+    info!("Exporting wallet {}", request.pubkey);
     
-    // Update prices first
-    if let Err(err) = price::update_prices(app_state.clone()).await {
-        error!("Failed to update prices: {}", err);
+    if let Err(err) = wallet::check_export_password(&request.password) {
+        warn!("Refused to export wallet {}: {}", request.pubkey, err);
         return utils::build_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            &format!("Failed to update prices: {}", err)
+            StatusCode::FORBIDDEN,
+            &err.to_string()
         );
     }
     
-    // Get prices from app state
-    let price_map = app_state.token_prices.lock().unwrap();
+    let tenant = metering::api_key_from_headers(&headers);
+    match wallet::export_wallet(&app_state, &tenant, &request.pubkey) {
+        Ok(exported) => utils::build_success_response(exported),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing the caller's wallets, the default one first
+#[utoipa::path(
+    get,
+    path = "/list_wallets",
+    tag = "wallets",
+    responses((status = 200, description = "Success", body = Envelope<Vec<WalletSummary>>))
+)]
+pub async fn list_wallets(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing wallets");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(wallet::list_wallets(&app_state, &tenant))
+}
+
+// Handler for the kill switch suspending a wallet's automatic executions and new orders
+#[utoipa::path(
+    post,
+    path = "/wallets/{pubkey}/freeze",
+    tag = "wallets",
+    request_body = FreezeWalletRequest,
+    params(("pubkey" = String, Path, description = "Wallet public key")),
+    responses((status = 200, description = "Success", body = Envelope<WalletFreeze>))
+)]
+pub async fn freeze_wallet(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(pubkey): Path<String>,
+    request: Option<Json<FreezeWalletRequest>>,
+) -> Result<ApiResponse, ApiError> {
+    warn!("Freezing wallet {}", pubkey);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let reason = request.and_then(|Json(request)| request.reason);
+    let freeze = freeze::freeze_wallet(&app_state, &tenant, &pubkey, reason).await?;
+    Ok(utils::build_success_response(freeze))
+}
+
+// Handler for lifting a wallet's freeze
+#[utoipa::path(
+    post,
+    path = "/wallets/{pubkey}/unfreeze",
+    tag = "wallets",
+    params(("pubkey" = String, Path, description = "Wallet public key")),
+    responses((status = 200, description = "Success", body = Envelope<WalletFreeze>))
+)]
+pub async fn unfreeze_wallet(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(pubkey): Path<String>,
+) -> Result<ApiResponse, ApiError> {
+    info!("Unfreezing wallet {}", pubkey);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let freeze = freeze::unfreeze_wallet(&app_state, &tenant, &pubkey)?;
+    Ok(utils::build_success_response(freeze))
+}
+
+// Handler for getting wallet balances
+#[utoipa::path(
+    get,
+    path = "/get_balances",
+    tag = "wallets",
+    params(WalletQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<TokenBalance>>))
+)]
+pub async fn get_balances(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<WalletQuery>,
+) -> Result<ApiResponse, ApiError> {
+    info!("Getting wallet balances");
+    
+    // Use the requested wallet, or the caller's first one
+    let tenant = metering::api_key_from_headers(&headers);
+    let wallet = wallet::find_tenant_wallet(&app_state, &tenant, query.wallet.as_deref())?;
+    
+    // Get balances
+    let balances = wallet::get_token_balances(&app_state.rpc_client, &wallet).await.map_err(|err| {
+        error!("Failed to get balances: {}", err);
+        ApiError::UpstreamRpcError(format!("Failed to get balances: {}", err))
+    })?;
+    Ok(utils::build_success_response(balances))
+}
+
+// Handler for the price of a single mint, fetched on demand when the cache does not cover it
+#[utoipa::path(
+    get,
+    path = "/price/{mint}",
+    tag = "prices",
+    params(("mint" = String, Path, description = "Token mint")),
+    responses((status = 200, description = "Success", body = Envelope<MintPrice>))
+)]
+pub async fn get_mint_price(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(mint): Path<String>,
+) -> Result<ApiResponse, ApiError> {
+    info!("Getting price of {}", mint);
     
-    // Convert to a Vec of TokenPrice for the response
-    let prices = price_map
-        .iter()
-        .map(|(mint, price)| {
-            serde_json::json!({
-                "mint": mint,
-                "symbol": wallet::KnownTokens::get_symbol(mint),
-                "price_usd": price,
-                "last_updated": chrono::Utc::now().to_rfc3339()
-            })
-        })
-        .collect::<Vec<_>>();
+    let price = price::get_mint_price(&app_state, &mint).await.map_err(|err| {
+        error!("Failed to get price of {}: {}", mint, err);
+        match ApiError::from(err) {
+            ApiError::BadRequest(message) => ApiError::PriceUnavailable(message),
+            error => error,
+        }
+    })?;
+    Ok(utils::build_success_response(price))
+}
+
+// Handler for getting token prices
+#[utoipa::path(
+    get,
+    path = "/get_prices",
+    tag = "prices",
+    responses((status = 200, description = "Success", body = Envelope<Vec<TokenPrice>>))
+)]
+pub async fn get_prices(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting token prices");
+    let format = utils::ResponseFormat::from_headers(&headers);
     
-    utils::build_success_response(prices)
+    // Only prices older than the cache TTL are fetched; cached ones are still served, with
+    // their age in last_updated, if the sources are down
+    if let Err(err) = price::refresh_stale_prices(app_state.clone()).await {
+        error!("Failed to update prices: {}", err);
+        if app_state.token_prices.lock().unwrap().is_empty() {
+            return utils::build_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to update prices: {}", err)
+            )
+            .with_format(format);
+        }
+    }
+    
+    utils::build_success_response(price::cached_prices(&app_state)).with_format(format)
 }
 
 // Handler for swapping tokens
+#[utoipa::path(
+    post,
+    path = "/swap_token",
+    tag = "trading",
+    request_body = SwapRequest,
+    responses((status = 200, description = "Success", body = Envelope<SwapResponse>))
+)]
 pub async fn swap_token(
     Extension(app_state): Extension<Arc<AppState>>,
-    Json(request): Json<SwapRequest>,
-) -> impl IntoResponse {
+    headers: HeaderMap,
+    Json(mut request): Json<SwapRequest>,
+) -> Result<ApiResponse, ApiError> {
     info!(
         "Swapping {} of {} to {}",
         request.amount, request.source_token, request.target_token
     );
     
+    // Refuse manual swaps while trading is paused
+    if let Some(reason) = engine::trading_paused(&app_state) {
+        return Err(ApiError::ServiceUnavailable(format!("Trading is paused: {}", reason)));
+    }
+    
     // Validate the request
-    if let Err(err) = utils::validate_amount(request.amount) {
-        return utils::build_error_response(
-            StatusCode::BAD_REQUEST,
-            &err.to_string()
-        );
+    match (request.amount_usd, request.amount_percent) {
+        (Some(amount_usd), _) => utils::validate_amount_usd(amount_usd, request.amount, request.amount_percent),
+        (None, Some(amount_percent)) => utils::validate_amount_percent(amount_percent, request.amount),
+        (None, None) => utils::validate_amount(request.amount),
     }
+    .map_err(|err| ApiError::BadRequest(err.to_string()))?;
     
-    // Get the wallet
-    let wallets = app_state.wallets.lock().unwrap();
+    // Swap from the requested wallet, or the first one if none was given
+    let api_key = metering::api_key_from_headers(&headers);
+    let wallet = wallet::find_tenant_wallet(&app_state, &api_key, request.wallet_pubkey.as_deref())?;
     
-    if wallets.is_empty() {
-        return utils::build_error_response(
-            StatusCode::BAD_REQUEST,
-            "No wallet imported"
-        );
+    // A share of the balance is swapped from the balance right now
+    if let Some(amount_percent) = request.amount_percent {
+        let balances = wallet::get_token_balances(&app_state.rpc_client, &wallet).await.map_err(|err| {
+            error!("Failed to fetch balances: {}", err);
+            ApiError::UpstreamRpcError(format!("Failed to fetch balances: {}", err))
+        })?;
+        request.amount = wallet::balance_share(&balances, &request.source_token, amount_percent);
+        if request.amount <= 0.0 {
+            return Err(ApiError::InsufficientBalance(format!(
+                "No {} to swap",
+                wallet::KnownTokens::get_symbol(&request.source_token)
+            )));
+        }
+        info!("Swapping {}% of the wallet's balance: {}", amount_percent, request.amount);
+    }
+    
+    // A dollar amount is converted into the source token at its current price
+    if let Some(amount_usd) = request.amount_usd {
+        request.amount = actions::amount_from_usd(&app_state, &request.source_token, amount_usd)?;
+        info!("Swapping ${} worth of {}: {}", amount_usd, wallet::KnownTokens::get_symbol(&request.source_token), request.amount);
     }
     
-    // Use the first wallet
-    let wallet = wallets.values().next().unwrap();
+    // Manual swaps count against the caller's monthly notional quota
+    let notional_usd = request.amount * price::get_token_price(&app_state, &request.source_token).unwrap_or(0.0);
+    metering::check_notional(&app_state, &api_key, notional_usd).map_err(|err| ApiError::QuotaExceeded(err.to_string()))?;
     
     // Check if the wallet has sufficient balance
-    match wallet::has_sufficient_balance(wallet, &request.source_token, request.amount).await {
-        Ok(has_balance) => {
-            if !has_balance {
-                return utils::build_error_response(
-                    StatusCode::BAD_REQUEST,
-                    &format!(
-                        "Insufficient balance of {} to execute swap", 
-                        wallet::KnownTokens::get_symbol(&request.source_token)
-                    )
-                );
-            }
-        },
-        Err(err) => {
+    let has_balance = wallet::has_sufficient_balance(&app_state.rpc_client, &wallet, &request.source_token, request.amount)
+        .await
+        .map_err(|err| {
             error!("Failed to check balance: {}", err);
-            return utils::build_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                &format!("Failed to check balance: {}", err)
-            );
-        }
+            ApiError::UpstreamRpcError(format!("Failed to check balance: {}", err))
+        })?;
+    if !has_balance {
+        return Err(ApiError::InsufficientBalance(format!(
+            "Insufficient balance of {} to execute swap",
+            wallet::KnownTokens::get_symbol(&request.source_token)
+        )));
     }
     
     // Execute the swap
-    match swap::execute_swap(wallet, &request).await {
-        Ok(result) => utils::build_success_response(result),
-        Err(err) => {
-            error!("Failed to execute swap: {}", err);
-            utils::build_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                &format!("Failed to execute swap: {}", err)
-            )
+    let mut result = swap::execute_swap(&app_state, &wallet, &request).await.map_err(|err| {
+        error!("Failed to execute swap: {}", err);
+        // Failures nothing more specific explains came from sending the swap
+        match ApiError::from(err) {
+            ApiError::BadRequest(message) => ApiError::Internal(format!("Failed to execute swap: {}", message)),
+            error => error,
         }
-    }
+    })?;
+    price::value_fee(&app_state, &mut result.fee_details);
+    metering::record_notional(&app_state, &api_key, notional_usd);
+    ledger::record_trade(&app_state, &wallet.pubkey.to_string(), &request, &result, None);
+    Ok(utils::build_success_response(result))
 }
 
 // Handler for setting a limit order
+#[utoipa::path(
+    post,
+    path = "/set_limit_order",
+    tag = "orders",
+    request_body = LimitOrderRequest,
+    responses((status = 200, description = "Success", body = Envelope<LimitOrder>))
+)]
 pub async fn set_limit_order(
     Extension(app_state): Extension<Arc<AppState>>,
-    Json(request): Json<LimitOrderRequest>,
+    headers: HeaderMap,
+    Json(mut request): Json<LimitOrderRequest>,
 ) -> impl IntoResponse {
     info!("Creating limit order: {:?}", request);
+    request.api_key = Some(metering::api_key_from_headers(&headers));
     
-    if request.price_target <= 0.0 {
+    if request.price_target <= 0.0 && request.order_type != OrderType::DeadMansSwitch {
         return utils::build_error_response(
             StatusCode::BAD_REQUEST,
             "Price target must be greater than zero"
         );
     }
     
-    match orders::create_limit_order(app_state, request).await {
+    let mut validation = OrderValidation::default();
+    
+    match orders::create_limit_order(app_state, request, &mut validation).await {
         Ok(order) => utils::build_success_response(order),
         Err(err) => {
             error!("Failed to create limit order: {}", err);
-            utils::build_error_response(
-                StatusCode::BAD_REQUEST,
-                &format!("Failed to create limit order: {}", err)
+            // Return what the validation saw so the failure can be debugged from the response
+            let error = ApiError::from(err);
+            utils::build_coded_response(
+                error.status(),
+                Some(validation),
+                Some(format!("Failed to create limit order: {}", error)),
+                Some(error.code())
             )
         }
     }
 }
 
 // Handler for listing limit orders
+#[utoipa::path(
+    get,
+    path = "/list_limit_orders",
+    tag = "orders",
+    params(ListOrdersQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<LimitOrder>>))
+)]
 pub async fn list_limit_orders(
     Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<ListOrdersQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing limit orders (archived: {})", query.archived);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let orders = if query.archived {
+        orders::filter_orders(orders::get_archived_orders(&app_state, &tenant), &query)
+    } else {
+        orders::get_limit_orders(app_state.clone(), &tenant, &query)
+    };
+    localized_response(&app_state, query.tz, orders).with_format(utils::ResponseFormat::from_headers(&headers))
+}
+
+// Handler for soft-deleting a finished order into the archive
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/archive",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order id")),
+    responses((status = 200, description = "Success", body = Envelope<LimitOrder>))
+)]
+pub async fn archive_order(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Archiving order {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::archive_order(&app_state, &tenant, &id) {
+        Ok(order) => utils::build_success_response(order),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the state transitions an order went through
+#[utoipa::path(
+    get,
+    path = "/order_history/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order id")),
+    responses((status = 200, description = "Success", body = Envelope<Vec<OrderEvent>>))
+)]
+pub async fn get_order_history(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting history of order {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::get_order_history(&app_state, &tenant, &id) {
+        Ok(history) => utils::build_success_response(history),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the signed execution receipt of a completed order
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/receipt",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order id")),
+    responses((status = 200, description = "Success", body = Envelope<ExecutionReceipt>))
+)]
+pub async fn get_order_receipt(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    info!("Listing limit orders");
+    info!("Getting execution receipt of order {}", id);
     
-    let orders = orders::get_limit_orders(app_state);
-    utils::build_success_response(orders)
+    let tenant = metering::api_key_from_headers(&headers);
+    match receipts::get_receipt(&app_state, &tenant, &id) {
+        Ok(receipt) => utils::build_success_response(receipt),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
 }
 
 // Handler for canceling a limit order
+#[utoipa::path(
+    post,
+    path = "/cancel_limit_order",
+    tag = "orders",
+    request_body = CancelOrderRequest,
+    responses((status = 200, description = "Success", body = Envelope<LimitOrder>))
+)]
 pub async fn cancel_limit_order(
     Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<CancelOrderRequest>,
 ) -> impl IntoResponse {
     info!("Canceling limit order: {}", request.order_id);
     
-    match orders::cancel_limit_order(app_state, &request.order_id) {
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::cancel_limit_order(app_state, &tenant, &request.order_id).await {
         Ok(order) => utils::build_success_response(order),
         Err(err) => {
             error!("Failed to cancel order: {}", err);
+            ApiError::from(err).into_api_response()
+        }
+    }
+}
+
+// Handler for creating a recurring DCA buy
+#[utoipa::path(
+    post,
+    path = "/dca_orders",
+    tag = "dca",
+    request_body = DcaOrderRequest,
+    responses((status = 200, description = "Success", body = Envelope<DcaOrder>))
+)]
+pub async fn create_dca_order(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<DcaOrderRequest>,
+) -> impl IntoResponse {
+    info!("Creating DCA order: {:?}", request);
+    request.api_key = Some(metering::api_key_from_headers(&headers));
+    
+    match dca::create_dca_order(&app_state, request).await {
+        Ok(order) => utils::build_success_response(order),
+        Err(err) => {
+            error!("Failed to create DCA order: {}", err);
+            let error = ApiError::from(err);
+            utils::build_coded_response::<()>(
+                error.status(),
+                None,
+                Some(format!("Failed to create DCA order: {}", error)),
+                Some(error.code())
+            )
+        }
+    }
+}
+
+// Handler for listing DCA orders with their fills
+#[utoipa::path(
+    get,
+    path = "/dca_orders",
+    tag = "dca",
+    responses((status = 200, description = "Success", body = Envelope<Vec<DcaOrder>>))
+)]
+pub async fn list_dca_orders(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing DCA orders");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(dca::get_dca_orders(&app_state, &tenant))
+}
+
+// Handler for cancelling a DCA order
+#[utoipa::path(
+    delete,
+    path = "/dca_orders/{id}",
+    tag = "dca",
+    params(("id" = String, Path, description = "DCA order id")),
+    responses((status = 200, description = "Success", body = Envelope<DcaOrder>))
+)]
+pub async fn cancel_dca_order(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Cancelling DCA order {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match dca::cancel_dca_order(&app_state, &tenant, &id) {
+        Ok(order) => utils::build_success_response(order),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the value of the caller's wallets together, or of one with `?wallet=`, including
+// staked and LP positions
+#[utoipa::path(
+    get,
+    path = "/portfolio",
+    tag = "portfolio",
+    params(WalletQuery),
+    responses((status = 200, description = "Success", body = Envelope<PortfolioValuation>))
+)]
+pub async fn get_portfolio(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<WalletQuery>,
+) -> impl IntoResponse {
+    info!("Getting portfolio");
+    
+    // Refresh prices, but fall back to cached ones if the sources are down
+    if let Err(err) = price::update_prices(app_state.clone()).await {
+        error!("Failed to update prices for portfolio: {}", err);
+    }
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let wallets = match portfolio::portfolio_wallets(&app_state, &tenant, query.wallet.as_deref()) {
+        Ok(wallets) => wallets,
+        Err(err) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+    };
+    
+    match portfolio::get_portfolio_valuation(&app_state, &wallets).await {
+        Ok(valuation) => utils::build_success_response(valuation),
+        Err(err) => {
+            error!("Failed to get portfolio: {}", err);
+            utils::build_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to get portfolio: {}", err)
+            )
+        }
+    }
+}
+
+// Handler for viewing the order execution queue
+#[utoipa::path(
+    get,
+    path = "/engine/queue",
+    tag = "engine",
+    responses((status = 200, description = "Success", body = Envelope<Vec<QueuedOrder>>))
+)]
+pub async fn get_execution_queue(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting execution queue");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let queue = engine::get_execution_queue(&app_state, &tenant);
+    utils::build_success_response(queue)
+}
+
+// Handler for dead man's switch heartbeats
+#[utoipa::path(
+    post,
+    path = "/heartbeat",
+    tag = "orders",
+    request_body = HeartbeatRequest,
+    responses((status = 200, description = "Success", body = Envelope<Vec<LimitOrder>>))
+)]
+pub async fn heartbeat(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<HeartbeatRequest>,
+) -> impl IntoResponse {
+    info!("Heartbeat received");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::record_heartbeat(app_state, &tenant, request.order_id.as_deref()) {
+        Ok(orders) => utils::build_success_response(orders),
+        Err(err) => {
+            error!("Failed to record heartbeat: {}", err);
             utils::build_error_response(
                 StatusCode::BAD_REQUEST,
                 &err.to_string()
             )
         }
     }
-} 
\ No newline at end of file
+}
+
+// Handler for getting the vacation mode policy
+#[utoipa::path(
+    get,
+    path = "/liquidation_policy",
+    tag = "risk",
+    responses((status = 200, description = "Success", body = Envelope<Option<LiquidationPolicy>>))
+)]
+pub async fn get_liquidation_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting vacation mode policy");
+    
+    utils::build_success_response(liquidation::get_policy(&app_state))
+}
+
+// Handler for setting the vacation mode policy
+#[utoipa::path(
+    post,
+    path = "/liquidation_policy",
+    tag = "risk",
+    request_body = LiquidationPolicy,
+    responses((status = 200, description = "Success", body = Envelope<LiquidationPolicy>))
+)]
+pub async fn set_liquidation_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LiquidationPolicy>,
+) -> impl IntoResponse {
+    info!("Setting vacation mode policy: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change the vacation mode policy"
+        );
+    }
+    
+    match liquidation::set_policy(&app_state, request) {
+        Ok(policy) => utils::build_success_response(policy),
+        Err(err) => {
+            error!("Failed to set vacation mode policy: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for getting the SOL reserve policy
+#[utoipa::path(
+    get,
+    path = "/sol_reserve_policy",
+    tag = "risk",
+    responses((status = 200, description = "Success", body = Envelope<Option<SolReservePolicy>>))
+)]
+pub async fn get_sol_reserve_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting SOL reserve policy");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(reserve::get_policy(&app_state, &tenant))
+}
+
+// Handler for setting the SOL reserve policy
+#[utoipa::path(
+    post,
+    path = "/sol_reserve_policy",
+    tag = "risk",
+    request_body = SolReservePolicy,
+    responses((status = 200, description = "Success", body = Envelope<SolReservePolicy>))
+)]
+pub async fn set_sol_reserve_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SolReservePolicy>,
+) -> impl IntoResponse {
+    info!("Setting SOL reserve policy: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match reserve::set_policy(&app_state, &tenant, request) {
+        Ok(policy) => utils::build_success_response(policy),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the funds-at-risk summary of the caller's wallets, or of one with `?wallet=`
+#[utoipa::path(
+    get,
+    path = "/risk",
+    tag = "risk",
+    params(WalletQuery),
+    responses((status = 200, description = "Success", body = Envelope<RiskSummary>))
+)]
+pub async fn get_risk(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<WalletQuery>,
+) -> impl IntoResponse {
+    info!("Getting funds at risk");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match risk::get_risk_summary(&app_state, &tenant, query.wallet.as_deref()).await {
+        Ok(summary) => utils::build_success_response(summary),
+        Err(err) => {
+            error!("Failed to get funds at risk: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for the portfolio value history
+#[utoipa::path(
+    get,
+    path = "/portfolio/history",
+    tag = "portfolio",
+    params(TimezoneQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<PortfolioSnapshot>>))
+)]
+pub async fn get_portfolio_history(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<TimezoneQuery>,
+) -> impl IntoResponse {
+    info!("Getting portfolio history");
+    
+    localized_response(&app_state, query.tz, liquidation::get_history(&app_state))
+}
+
+// Handler for resuming trading after a pause
+#[utoipa::path(
+    post,
+    path = "/engine/resume",
+    tag = "engine",
+    responses((status = 200, description = "Success", body = Envelope<serde_json::Value>))
+)]
+pub async fn resume_trading(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Resuming trading");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can resume trading"
+        );
+    }
+    
+    engine::resume_trading(&app_state);
+    utils::build_success_response(serde_json::json!({
+        "trading_paused": false
+    }))
+}
+
+// Handler for listing price guards
+#[utoipa::path(
+    get,
+    path = "/price_guards",
+    tag = "prices",
+    responses((status = 200, description = "Success", body = Envelope<Vec<PriceGuardConfig>>))
+)]
+pub async fn list_price_guards(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Listing price guards");
+    
+    let guards = app_state.price_guards.lock().unwrap();
+    utils::build_success_response(guards.values().cloned().collect::<Vec<_>>())
+}
+
+// Handler for setting a price guard on a token
+#[utoipa::path(
+    post,
+    path = "/price_guards",
+    tag = "prices",
+    request_body = PriceGuardConfig,
+    responses((status = 200, description = "Success", body = Envelope<PriceGuardConfig>))
+)]
+pub async fn set_price_guard(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<PriceGuardConfig>,
+) -> impl IntoResponse {
+    info!("Setting price guard: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change price guards"
+        );
+    }
+    
+    match price::set_price_guard(&app_state, request) {
+        Ok(guard) => utils::build_success_response(guard),
+        Err(err) => {
+            error!("Failed to set price guard: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for removing a price guard
+#[utoipa::path(
+    delete,
+    path = "/price_guards/{mint}",
+    tag = "prices",
+    params(("mint" = String, Path, description = "Token mint")),
+    responses((status = 200, description = "Success", body = Envelope<PriceGuardConfig>))
+)]
+pub async fn remove_price_guard(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(mint): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing price guard for {}", mint);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change price guards"
+        );
+    }
+    
+    match price::remove_price_guard(&app_state, &mint) {
+        Ok(guard) => utils::build_success_response(guard),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing price providers and per-token preferences
+#[utoipa::path(
+    get,
+    path = "/price_providers",
+    tag = "prices",
+    responses((status = 200, description = "Success", body = Envelope<PriceProviders>))
+)]
+pub async fn list_price_providers(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Listing price providers");
+    
+    utils::build_success_response(price::get_price_providers(&app_state))
+}
+
+// Handler for setting the order price providers are tried in for a token
+#[utoipa::path(
+    post,
+    path = "/price_providers",
+    tag = "prices",
+    request_body = ProviderPreference,
+    responses((status = 200, description = "Success", body = Envelope<ProviderPreference>))
+)]
+pub async fn set_provider_preference(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ProviderPreference>,
+) -> impl IntoResponse {
+    info!("Setting price provider preference: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change price provider preferences"
+        );
+    }
+    
+    match price::set_provider_preference(&app_state, request) {
+        Ok(preference) => utils::build_success_response(preference),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing a token's price provider preference
+#[utoipa::path(
+    delete,
+    path = "/price_providers/{mint}",
+    tag = "prices",
+    params(("mint" = String, Path, description = "Token mint")),
+    responses((status = 200, description = "Success", body = Envelope<ProviderPreference>))
+)]
+pub async fn remove_provider_preference(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(mint): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing price provider preference for {}", mint);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change price provider preferences"
+        );
+    }
+    
+    match price::remove_provider_preference(&app_state, &mint) {
+        Ok(preference) => utils::build_success_response(preference),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for execution latency percentiles
+#[utoipa::path(
+    get,
+    path = "/engine/latency",
+    tag = "engine",
+    responses((status = 200, description = "Success", body = Envelope<LatencyMetrics>))
+)]
+pub async fn get_latency_metrics(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting execution latency metrics");
+    
+    utils::build_success_response(latency::get_latency_metrics(&app_state))
+}
+
+// Handler for price source divergence metrics
+#[utoipa::path(
+    get,
+    path = "/prices/divergence",
+    tag = "prices",
+    responses((status = 200, description = "Success", body = Envelope<Vec<PriceDivergence>>))
+)]
+pub async fn get_price_divergence(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting price divergence");
+    
+    utils::build_success_response(price::get_price_divergence(&app_state))
+}
+
+// Handler for merging prices pushed by an external feed
+#[utoipa::path(
+    post,
+    path = "/prices/push",
+    tag = "admin",
+    request_body = PricePushRequest,
+    responses((status = 200, description = "Success", body = Envelope<Vec<MintPrice>>))
+)]
+pub async fn push_prices(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<PricePushRequest>,
+) -> impl IntoResponse {
+    info!("Receiving {} pushed prices", request.prices.len());
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can push prices"
+        );
+    }
+    
+    match price::push_external_prices(&app_state, &request.prices) {
+        Ok(prices) => {
+            // Pushed prices can trigger orders without waiting for the next price update
+            let mints = prices.iter().map(|price| price.mint.clone()).collect::<Vec<_>>();
+            orders::check_orders_on_prices(&app_state, &mints).await;
+            utils::build_success_response(prices)
+        }
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for simulating an order at hypothetical prices
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/what_if",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order id"), WhatIfQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<WhatIfOutcome>>))
+)]
+pub async fn what_if_order(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(order_id): Path<String>,
+    Query(query): Query<WhatIfQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Simulating order {} at prices {}", order_id, query.prices);
+    
+    let prices = match utils::parse_positive_list(&query.prices) {
+        Ok(prices) => prices,
+        Err(err) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+    };
+    
+    // Another tenant's order answers as missing
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::simulate_order_outcomes(app_state, &tenant, &order_id, &prices).await {
+        Ok(outcomes) => utils::build_success_response(outcomes),
+        Err(err) => {
+            error!("Failed to simulate order {}: {}", order_id, err);
+            ApiError::from(err).into_api_response()
+        }
+    }
+}
+
+// Handler for replaying an order's trigger against the stored price history
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/backtrace",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order id")),
+    responses((status = 200, description = "Success", body = Envelope<OrderBacktrace>))
+)]
+pub async fn backtrace_order(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(order_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Backtracing order {}", order_id);
+    
+    // Another tenant's order answers as missing
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::backtrace_order(&app_state, &tenant, &order_id) {
+        Ok(backtrace) => utils::build_success_response(backtrace),
+        Err(err) => {
+            error!("Failed to backtrace order {}: {}", order_id, err);
+            ApiError::from(err).into_api_response()
+        }
+    }
+}
+
+// Handler for quoting a swap without executing it
+#[utoipa::path(
+    post,
+    path = "/get_quote",
+    tag = "trading",
+    request_body = QuoteRequest,
+    responses((status = 200, description = "Success", body = Envelope<SwapQuotePreview>))
+)]
+pub async fn get_quote(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(request): Json<QuoteRequest>,
+) -> impl IntoResponse {
+    info!("Quoting {} {} -> {}", request.amount, request.source_token, request.target_token);
+    
+    match swap::preview_swap(&app_state, &request).await {
+        Ok(preview) => utils::build_success_response(preview),
+        Err(err) => {
+            error!("Failed to get quote: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &format!("Failed to get quote: {}", err)
+            )
+        }
+    }
+}
+
+// Handler for quoted depth across a ladder of input sizes
+#[utoipa::path(
+    get,
+    path = "/depth",
+    tag = "trading",
+    params(DepthQuery),
+    responses((status = 200, description = "Success", body = Envelope<DepthResponse>))
+)]
+pub async fn get_depth(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<DepthQuery>,
+) -> impl IntoResponse {
+    info!("Getting depth for {} -> {}", query.input, query.output);
+    
+    let sizes = match query.sizes.as_deref().map(utils::parse_positive_list) {
+        Some(Ok(sizes)) => sizes,
+        Some(Err(err)) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+        None => swap::DEFAULT_DEPTH_SIZES.to_vec(),
+    };
+    if let Err(err) = swap::depth_sizes(&sizes) {
+        return utils::build_error_response(StatusCode::BAD_REQUEST, &err.to_string());
+    }
+    
+    match swap::get_depth(&app_state, &query.input, &query.output, &sizes).await {
+        Ok(depth) => utils::build_success_response(depth),
+        Err(err) => {
+            error!("Failed to get depth: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_GATEWAY,
+                &format!("Failed to get depth: {}", err)
+            )
+        }
+    }
+}
+
+// Handler for multi-leg swaps through intermediate tokens
+#[utoipa::path(
+    post,
+    path = "/swap_route",
+    tag = "trading",
+    request_body = SwapRouteRequest,
+    responses((status = 200, description = "Success", body = Envelope<SwapRouteResponse>))
+)]
+pub async fn swap_route(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SwapRouteRequest>,
+) -> impl IntoResponse {
+    info!(
+        "Swapping {} of {} through {} legs",
+        request.amount, request.source_token, request.legs.len()
+    );
+    
+    if let Some(reason) = engine::trading_paused(&app_state) {
+        return utils::build_error_response(
+            StatusCode::CONFLICT,
+            &format!("Trading is paused: {}", reason)
+        );
+    }
+    
+    if let Err(err) = utils::validate_amount(request.amount) {
+        return utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        );
+    }
+    
+    // The route's input counts against the caller's monthly notional quota
+    let api_key = metering::api_key_from_headers(&headers);
+    let notional_usd = request.amount * price::get_token_price(&app_state, &request.source_token).unwrap_or(0.0);
+    if let Err(err) = metering::check_notional(&app_state, &api_key, notional_usd) {
+        return utils::build_error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            &err.to_string()
+        );
+    }
+    
+    let wallet = match wallet::find_tenant_wallet(&app_state, &api_key, None) {
+        Ok(wallet) => wallet,
+        Err(err) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+    };
+    
+    let mut result = swap::execute_swap_route(&app_state, &wallet, &request).await;
+    
+    if let Ok(route) = &mut result {
+        for leg in route.completed_legs.iter_mut() {
+            price::value_fee(&app_state, &mut leg.fee_details);
+        }
+        
+        // The input was spent once the first leg went through
+        if !route.completed_legs.is_empty() {
+            metering::record_notional(&app_state, &api_key, notional_usd);
+        }
+    }
+    
+    // Every completed leg is a trade, even if the route stopped early
+    if let Ok(route) = &result {
+        let mut leg_source = request.source_token.clone();
+        for (leg, swap_result) in request.legs.iter().zip(route.completed_legs.iter()) {
+            let leg_request = SwapRequest {
+                source_token: leg_source.clone(),
+                target_token: leg.target_token.clone(),
+                amount: swap_result.source_amount,
+                amount_percent: None,
+                amount_usd: None,
+                slippage: leg.slippage,
+                wallet_pubkey: Some(wallet.pubkey.to_string()),
+                dry_run: false,
+                priority_fee: None,
+                mev_protection: false,
+            };
+            ledger::record_trade(&app_state, &wallet.pubkey.to_string(), &leg_request, swap_result, None);
+            leg_source = leg.target_token.clone();
+        }
+    }
+    
+    match result {
+        Ok(result) if result.complete => utils::build_success_response(result),
+        Ok(result) => {
+            // Report the partial completion alongside the error
+            let error = format!(
+                "Route stopped at leg {}: {}",
+                result.failed_leg.map(|leg| leg + 1).unwrap_or(0),
+                result.error.clone().unwrap_or_default()
+            );
+            error!("{}", error);
+            utils::build_api_response(StatusCode::BAD_GATEWAY, Some(result), Some(error))
+        }
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid route: {}", err)
+        ),
+    }
+}
+
+// Handler for transferring tokens to another address
+#[utoipa::path(
+    post,
+    path = "/transfer",
+    tag = "trading",
+    request_body = TransferRequest,
+    responses((status = 200, description = "Success", body = Envelope<LedgerEntry>))
+)]
+pub async fn transfer(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<TransferRequest>,
+) -> impl IntoResponse {
+    info!("Transferring {} of {} to {}", request.amount, request.token, request.destination);
+    
+    // Transfers are not simulated, so paper trading never moves real funds
+    if paper::simulation_mode() {
+        return utils::build_error_response(
+            StatusCode::CONFLICT,
+            "Transfers are disabled in simulation mode"
+        );
+    }
+    
+    if let Err(err) = utils::validate_amount(request.amount) {
+        return utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        );
+    }
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let wallet = match wallet::find_tenant_wallet(&app_state, &tenant, None) {
+        Ok(wallet) => wallet,
+        Err(err) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+    };
+    
+    match wallet::transfer_tokens(&app_state.rpc_client, &wallet, &request.token, &request.destination, request.amount).await {
+        Ok(signature) => {
+            let entry = ledger::record_transfer(
+                &app_state,
+                &wallet.pubkey.to_string(),
+                &request.destination,
+                &request.token,
+                request.amount,
+                &signature,
+                None,
+            );
+            utils::build_success_response(entry)
+        }
+        Err(err) => {
+            error!("Failed to transfer: {}", err);
+            utils::build_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to transfer: {}", err)
+            )
+        }
+    }
+}
+
+// Handler for the trade and transfer ledger
+#[utoipa::path(
+    get,
+    path = "/ledger",
+    tag = "portfolio",
+    params(TimezoneQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<LedgerEntry>>))
+)]
+pub async fn get_ledger(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<TimezoneQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting ledger");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    localized_response(&app_state, query.tz, ledger::get_ledger(&app_state, &tenant))
+}
+
+// Handler for the realized and unrealized profit of the caller's wallets, or of one with `?wallet=`
+#[utoipa::path(
+    get,
+    path = "/pnl",
+    tag = "portfolio",
+    params(WalletQuery),
+    responses((status = 200, description = "Success", body = Envelope<PnlReport>))
+)]
+pub async fn get_pnl(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<WalletQuery>,
+    headers: HeaderMap,
+) -> Result<ApiResponse, ApiError> {
+    info!("Getting profit and loss");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let report = pnl::get_pnl(&app_state, &tenant, query.wallet.as_deref())?;
+    Ok(utils::build_success_response(report))
+}
+
+// Handler for a wallet's recent on-chain transactions, including those made outside the API
+#[utoipa::path(
+    get,
+    path = "/transactions",
+    tag = "portfolio",
+    params(TransactionsQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<WalletTransaction>>))
+)]
+pub async fn get_transactions(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<TransactionsQuery>,
+    headers: HeaderMap,
+) -> Result<ApiResponse, ApiError> {
+    info!("Getting transactions of wallet {:?}", query.wallet);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let transactions = history::get_transactions(&app_state, &tenant, &query).await?;
+    Ok(utils::build_success_response(transactions))
+}
+
+// Handler for getting the notification config
+#[utoipa::path(
+    get,
+    path = "/notifications/config",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<NotificationConfig>))
+)]
+pub async fn get_notification_config(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting notification config");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(notify::get_config(&app_state, &tenant))
+}
+
+// Handler for setting notification rate limits and digests
+#[utoipa::path(
+    post,
+    path = "/notifications/config",
+    tag = "account",
+    request_body = NotificationConfig,
+    responses((status = 200, description = "Success", body = Envelope<NotificationConfig>))
+)]
+pub async fn set_notification_config(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<NotificationConfig>,
+) -> impl IntoResponse {
+    info!("Setting notification config: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(notify::set_config(&app_state, &tenant, request))
+}
+
+// Handler for listing notification templates by locale
+#[utoipa::path(
+    get,
+    path = "/notifications/templates",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<Vec<NotificationTemplate>>))
+)]
+pub async fn get_notification_templates(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting notification templates");
+    
+    utils::build_success_response(templates::get_templates(&app_state))
+}
+
+// Handler for overriding a notification template
+#[utoipa::path(
+    post,
+    path = "/notifications/templates",
+    tag = "admin",
+    request_body = NotificationTemplateRequest,
+    responses((status = 200, description = "Success", body = Envelope<NotificationTemplate>))
+)]
+pub async fn set_notification_template(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<NotificationTemplateRequest>,
+) -> impl IntoResponse {
+    info!("Overriding notification template {} for locale {}", request.name, request.locale);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change notification templates"
+        );
+    }
+    
+    match templates::set_template(&app_state, request) {
+        Ok(template) => utils::build_success_response(template),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing a notification template override
+#[utoipa::path(
+    delete,
+    path = "/notifications/templates/{locale}/{name}",
+    tag = "admin",
+    params(
+        ("locale" = String, Path, description = "Locale of the override"),
+        ("name" = String, Path, description = "Template name"),
+    ),
+    responses((status = 200, description = "Success", body = Envelope<NotificationTemplate>))
+)]
+pub async fn remove_notification_template(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((locale, name)): Path<(String, String)>,
+) -> impl IntoResponse {
+    info!("Removing override of notification template {} for locale {}", name, locale);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change notification templates"
+        );
+    }
+    
+    match templates::remove_template(&app_state, &locale, &name) {
+        Ok(template) => utils::build_success_response(template),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for getting the caller's notification language
+#[utoipa::path(
+    get,
+    path = "/notifications/locale",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<NotificationLocale>))
+)]
+pub async fn get_notification_locale(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting notification locale");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(templates::get_locale(&app_state, &tenant))
+}
+
+// Handler for setting the caller's notification language
+#[utoipa::path(
+    post,
+    path = "/notifications/locale",
+    tag = "account",
+    request_body = NotificationLocaleRequest,
+    responses((status = 200, description = "Success", body = Envelope<NotificationLocale>))
+)]
+pub async fn set_notification_locale(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<NotificationLocaleRequest>,
+) -> impl IntoResponse {
+    info!("Setting notification locale: {:?}", request.locale);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match templates::set_locale(&app_state, &tenant, request.locale.as_deref()) {
+        Ok(locale) => utils::build_success_response(locale),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for getting operator preferences
+#[utoipa::path(
+    get,
+    path = "/preferences",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<Preferences>))
+)]
+pub async fn get_preferences(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting preferences");
+    
+    let preferences = app_state.preferences.lock().unwrap().clone();
+    utils::build_success_response(preferences)
+}
+
+// Handler for setting operator preferences
+#[utoipa::path(
+    post,
+    path = "/preferences",
+    tag = "account",
+    request_body = Preferences,
+    responses((status = 200, description = "Success", body = Envelope<Preferences>))
+)]
+pub async fn set_preferences(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(mut request): Json<Preferences>,
+) -> impl IntoResponse {
+    info!("Setting preferences: {:?}", request);
+    
+    if let Some(Err(err)) = request.timezone.as_deref().map(utils::parse_timezone) {
+        return utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        );
+    }
+    
+    match request.locale.as_deref().map(templates::parse_locale) {
+        Some(Ok(locale)) => request.locale = Some(locale),
+        Some(Err(err)) => {
+            return utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            );
+        }
+        None => {}
+    }
+    
+    let mut preferences = app_state.preferences.lock().unwrap();
+    *preferences = request.clone();
+    utils::build_success_response(request)
+}
+
+// Handler for engine health as seen by the upstream watchdog
+#[utoipa::path(
+    get,
+    path = "/engine/health",
+    tag = "engine",
+    responses((status = 200, description = "Success", body = Envelope<EngineHealth>))
+)]
+pub async fn get_engine_health(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting engine health");
+    
+    utils::build_success_response(watchdog::get_health(&app_state))
+}
+
+// Handler for configuring the upstream watchdog
+#[utoipa::path(
+    post,
+    path = "/engine/watchdog",
+    tag = "engine",
+    request_body = WatchdogConfig,
+    responses((status = 200, description = "Success", body = Envelope<WatchdogConfig>))
+)]
+pub async fn set_watchdog_config(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<WatchdogConfig>,
+) -> impl IntoResponse {
+    info!("Setting watchdog config: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change the watchdog config"
+        );
+    }
+    
+    match watchdog::set_config(&app_state, request) {
+        Ok(config) => utils::build_success_response(config),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the currently injected faults
+#[cfg(feature = "chaos")]
+pub async fn get_chaos() -> impl IntoResponse {
+    utils::build_success_response(crate::chaos::get_faults())
+}
+
+// Handler for injecting faults into upstream calls
+#[cfg(feature = "chaos")]
+pub async fn set_chaos(
+    Json(request): Json<crate::chaos::ChaosConfig>,
+) -> impl IntoResponse {
+    match crate::chaos::set_faults(request) {
+        Ok(config) => utils::build_success_response(config),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing every injected fault
+#[cfg(feature = "chaos")]
+pub async fn clear_chaos() -> impl IntoResponse {
+    crate::chaos::clear_faults();
+    utils::build_success_response(crate::chaos::get_faults())
+}
+
+// Handler for listing balance alerts
+#[utoipa::path(
+    get,
+    path = "/alerts/balance",
+    tag = "alerts",
+    responses((status = 200, description = "Success", body = Envelope<Vec<BalanceAlert>>))
+)]
+pub async fn list_balance_alerts(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing balance alerts");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(alerts::get_tenant_balance_alerts(&app_state, &tenant))
+}
+
+// Handler for creating a balance alert
+#[utoipa::path(
+    post,
+    path = "/alerts/balance",
+    tag = "alerts",
+    request_body = BalanceAlertRequest,
+    responses((status = 200, description = "Success", body = Envelope<BalanceAlert>))
+)]
+pub async fn create_balance_alert(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<BalanceAlertRequest>,
+) -> impl IntoResponse {
+    info!("Creating balance alert: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match alerts::create_balance_alert(&app_state, &tenant, request) {
+        Ok(alert) => utils::build_success_response(alert),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for deleting a balance alert
+#[utoipa::path(
+    delete,
+    path = "/alerts/balance/{id}",
+    tag = "alerts",
+    params(("id" = String, Path, description = "Alert id")),
+    responses((status = 200, description = "Success", body = Envelope<BalanceAlert>))
+)]
+pub async fn remove_balance_alert(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing balance alert {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match alerts::remove_balance_alert(&app_state, &tenant, &id) {
+        Ok(alert) => utils::build_success_response(alert),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing the paper accounts of the caller's wallets
+#[utoipa::path(
+    get,
+    path = "/paper_accounts",
+    tag = "paper",
+    responses((status = 200, description = "Success", body = Envelope<Vec<PaperAccount>>))
+)]
+pub async fn list_paper_accounts(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing paper accounts");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(paper::get_paper_accounts(&app_state, &tenant))
+}
+
+// Handler for setting a paper balance
+#[utoipa::path(
+    post,
+    path = "/paper_accounts/balances",
+    tag = "paper",
+    request_body = PaperBalanceRequest,
+    responses((status = 200, description = "Success", body = Envelope<PaperAccount>))
+)]
+pub async fn set_paper_balance(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<PaperBalanceRequest>,
+) -> impl IntoResponse {
+    info!("Setting paper balance: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match paper::set_paper_balance(&app_state, &tenant, request).await {
+        Ok(account) => utils::build_success_response(account),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for resetting a wallet's paper account
+#[utoipa::path(
+    delete,
+    path = "/paper_accounts/{wallet}",
+    tag = "paper",
+    params(("wallet" = String, Path, description = "Paper wallet")),
+    responses((status = 200, description = "Success", body = Envelope<PaperAccount>))
+)]
+pub async fn reset_paper_account(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(wallet): Path<String>,
+) -> impl IntoResponse {
+    info!("Resetting paper account of wallet {}", wallet);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match paper::reset_paper_account(&app_state, &tenant, &wallet) {
+        Ok(account) => utils::build_success_response(account),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing the caller's watchlist with current prices
+#[utoipa::path(
+    get,
+    path = "/watchlist",
+    tag = "prices",
+    responses((status = 200, description = "Success", body = Envelope<Vec<WatchlistEntry>>))
+)]
+pub async fn get_watchlist(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting watchlist");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(watchlist::get_watchlist(&app_state, &tenant))
+}
+
+// Handler for adding a token to the caller's watchlist
+#[utoipa::path(
+    post,
+    path = "/watchlist",
+    tag = "prices",
+    request_body = WatchlistRequest,
+    responses((status = 200, description = "Success", body = Envelope<WatchlistEntry>))
+)]
+pub async fn add_to_watchlist(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<WatchlistRequest>,
+) -> impl IntoResponse {
+    info!("Adding to watchlist: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match watchlist::add_to_watchlist(&app_state, &tenant, request) {
+        Ok(entry) => utils::build_success_response(entry),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing a token from the caller's watchlist
+#[utoipa::path(
+    delete,
+    path = "/watchlist/{mint}",
+    tag = "prices",
+    params(("mint" = String, Path, description = "Token mint")),
+    responses((status = 200, description = "Success", body = Envelope<WatchlistEntry>))
+)]
+pub async fn remove_from_watchlist(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(mint): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing {} from watchlist", mint);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match watchlist::remove_from_watchlist(&app_state, &tenant, &mint) {
+        Ok(entry) => utils::build_success_response(entry),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing funding rate alerts
+#[utoipa::path(
+    get,
+    path = "/alerts/funding",
+    tag = "alerts",
+    responses((status = 200, description = "Success", body = Envelope<Vec<FundingRateAlert>>))
+)]
+pub async fn list_funding_alerts(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing funding rate alerts");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(alerts::get_tenant_funding_alerts(&app_state, &tenant))
+}
+
+// Handler for creating a funding rate alert
+#[utoipa::path(
+    post,
+    path = "/alerts/funding",
+    tag = "alerts",
+    request_body = FundingRateAlertRequest,
+    responses((status = 200, description = "Success", body = Envelope<FundingRateAlert>))
+)]
+pub async fn create_funding_alert(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<FundingRateAlertRequest>,
+) -> impl IntoResponse {
+    info!("Creating funding rate alert: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match alerts::create_funding_alert(&app_state, &tenant, request) {
+        Ok(alert) => utils::build_success_response(alert),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for deleting a funding rate alert
+#[utoipa::path(
+    delete,
+    path = "/alerts/funding/{id}",
+    tag = "alerts",
+    params(("id" = String, Path, description = "Alert id")),
+    responses((status = 200, description = "Success", body = Envelope<FundingRateAlert>))
+)]
+pub async fn remove_funding_alert(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing funding rate alert {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match alerts::remove_funding_alert(&app_state, &tenant, &id) {
+        Ok(alert) => utils::build_success_response(alert),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for the mark price and funding rate of a Drift perp market
+#[utoipa::path(
+    get,
+    path = "/perps/{market}",
+    tag = "prices",
+    params(("market" = String, Path, description = "Perp market such as SOL-PERP")),
+    responses((status = 200, description = "Success", body = Envelope<PerpMarket>))
+)]
+pub async fn get_perp_market(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Path(market): Path<String>,
+) -> impl IntoResponse {
+    let market = market.to_uppercase();
+    info!("Getting perp market {}", market);
+    
+    if !drift::is_perp_market(&market) {
+        return utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("Unknown perp market {}, expected a name like SOL-PERP", market)
+        );
+    }
+    
+    if let Err(err) = drift::refresh_markets(&app_state, std::slice::from_ref(&market)).await {
+        return utils::build_error_response(
+            StatusCode::BAD_GATEWAY,
+            &err.to_string()
+        );
+    }
+    
+    match drift::get_perp_markets(&app_state).into_iter().find(|cached| cached.market == market) {
+        Some(cached) => utils::build_success_response(cached),
+        None => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &format!("No data for perp market {}", market)
+        ),
+    }
+}
+
+// Handler for failed orders grouped by cause with suggested remediation
+#[utoipa::path(
+    get,
+    path = "/orders/failed",
+    tag = "orders",
+    params(FailedOrdersQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<FailedOrderGroup>>))
+)]
+pub async fn list_failed_orders(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<FailedOrdersQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing failed orders since {:?}", query.since);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let groups = orders::get_failed_orders(&app_state, &tenant, query.since);
+    localized_response(&app_state, query.tz, groups)
+}
+
+// Handler for reactivating failed orders in bulk
+#[utoipa::path(
+    post,
+    path = "/orders/retry_failed",
+    tag = "orders",
+    request_body = RetryFailedRequest,
+    responses((status = 200, description = "Success", body = Envelope<RetryFailedResponse>))
+)]
+pub async fn retry_failed_orders(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RetryFailedRequest>,
+) -> impl IntoResponse {
+    info!("Retrying failed orders: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match orders::retry_failed_orders(app_state, &tenant, request).await {
+        Ok(result) => utils::build_success_response(result),
+        Err(err) => {
+            error!("Failed to retry failed orders: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_REQUEST,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for listing exposure limits
+#[utoipa::path(
+    get,
+    path = "/exposure_limits",
+    tag = "risk",
+    responses((status = 200, description = "Success", body = Envelope<Vec<ExposureLimit>>))
+)]
+pub async fn list_exposure_limits(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Listing exposure limits");
+    
+    utils::build_success_response(exposure::get_limits(&app_state))
+}
+
+// Handler for setting the exposure limit of a token
+#[utoipa::path(
+    post,
+    path = "/exposure_limits",
+    tag = "risk",
+    request_body = ExposureLimit,
+    responses((status = 200, description = "Success", body = Envelope<ExposureLimit>))
+)]
+pub async fn set_exposure_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ExposureLimit>,
+) -> impl IntoResponse {
+    info!("Setting exposure limit: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change exposure limits"
+        );
+    }
+    
+    match exposure::set_limit(&app_state, request) {
+        Ok(limit) => utils::build_success_response(limit),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing the exposure limit of a token
+#[utoipa::path(
+    delete,
+    path = "/exposure_limits/{mint}",
+    tag = "risk",
+    params(("mint" = String, Path, description = "Token mint")),
+    responses((status = 200, description = "Success", body = Envelope<ExposureLimit>))
+)]
+pub async fn remove_exposure_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(mint): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing exposure limit for {}", mint);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change exposure limits"
+        );
+    }
+    
+    match exposure::remove_limit(&app_state, &mint) {
+        Ok(limit) => utils::build_success_response(limit),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing the blackout windows covering the caller's orders
+#[utoipa::path(
+    get,
+    path = "/blackout_windows",
+    tag = "risk",
+    responses((status = 200, description = "Success", body = Envelope<Vec<BlackoutWindow>>))
+)]
+pub async fn list_blackout_windows(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing blackout windows");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(blackout::get_windows(&app_state, &tenant))
+}
+
+// Handler for scheduling a blackout window
+#[utoipa::path(
+    post,
+    path = "/blackout_windows",
+    tag = "risk",
+    request_body = BlackoutWindowRequest,
+    responses((status = 200, description = "Success", body = Envelope<BlackoutWindow>))
+)]
+pub async fn create_blackout_window(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<BlackoutWindowRequest>,
+) -> impl IntoResponse {
+    info!("Creating blackout window: {:?}", request);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match blackout::create_window(&app_state, &tenant, request) {
+        Ok(window) => utils::build_success_response(window),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for removing a blackout window
+#[utoipa::path(
+    delete,
+    path = "/blackout_windows/{id}",
+    tag = "risk",
+    params(("id" = String, Path, description = "Blackout window id")),
+    responses((status = 200, description = "Success", body = Envelope<BlackoutWindow>))
+)]
+pub async fn remove_blackout_window(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Removing blackout window {}", id);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    match blackout::remove_window(&app_state, &tenant, &id) {
+        Ok(window) => utils::build_success_response(window),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for viewing the route denylist
+#[utoipa::path(
+    get,
+    path = "/route_denylist",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<RouteDenylist>))
+)]
+pub async fn get_route_denylist(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting route denylist");
+    
+    utils::build_success_response(denylist::get_denylist(&app_state))
+}
+
+// Handler for replacing the route denylist
+#[utoipa::path(
+    post,
+    path = "/route_denylist",
+    tag = "admin",
+    request_body = RouteDenylist,
+    responses((status = 200, description = "Success", body = Envelope<RouteDenylist>))
+)]
+pub async fn set_route_denylist(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RouteDenylist>,
+) -> impl IntoResponse {
+    info!("Setting route denylist: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change the route denylist"
+        );
+    }
+    
+    match denylist::set_denylist(&app_state, request).await {
+        Ok(denylist) => utils::build_success_response(denylist),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for Jupiter's verified token list, served from the upstream cache
+#[utoipa::path(
+    get,
+    path = "/tokens",
+    tag = "prices",
+    params(TokenListQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<TokenInfo>>))
+)]
+pub async fn get_token_list(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<TokenListQuery>,
+) -> impl IntoResponse {
+    info!("Getting token list");
+    
+    match upstream_cache::get_token_list(&app_state, query.search.as_deref()).await {
+        Ok(tokens) => utils::build_success_response(tokens),
+        Err(err) => {
+            error!("Failed to get token list: {}", err);
+            utils::build_error_response(
+                StatusCode::BAD_GATEWAY,
+                &err.to_string()
+            )
+        }
+    }
+}
+
+// Handler for viewing the cached upstream payloads
+#[utoipa::path(
+    get,
+    path = "/upstream_cache",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<Vec<UpstreamCacheEntry>>))
+)]
+pub async fn get_upstream_cache(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting upstream cache status");
+    
+    utils::build_success_response(upstream_cache::get_cache_status(&app_state))
+}
+
+// Handler for downloading cached upstream payloads again ahead of their TTL
+#[utoipa::path(
+    post,
+    path = "/upstream_cache/refresh",
+    tag = "admin",
+    request_body = UpstreamCacheRefreshRequest,
+    responses((status = 200, description = "Success", body = Envelope<Vec<UpstreamCacheEntry>>))
+)]
+pub async fn refresh_upstream_cache(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    request: Option<Json<UpstreamCacheRefreshRequest>>,
+) -> impl IntoResponse {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    info!("Refreshing upstream cache: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can refresh the upstream cache"
+        );
+    }
+    
+    match upstream_cache::refresh(&app_state, request.name.as_deref()).await {
+        Ok(entries) => utils::build_success_response(entries),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_GATEWAY,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for scanning for stale or orphaned state
+#[utoipa::path(
+    get,
+    path = "/janitor",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<JanitorReport>))
+)]
+pub async fn get_janitor_report(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Scanning for stale or orphaned state");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can view the janitor report"
+        );
+    }
+    
+    utils::build_success_response(janitor::scan(&app_state))
+}
+
+// Handler for the startup integrity report, which explains why the server is in safe mode
+#[utoipa::path(
+    get,
+    path = "/integrity",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<IntegrityReport>))
+)]
+pub async fn get_integrity_report(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting the startup integrity report");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can view the integrity report"
+        );
+    }
+    
+    let report = app_state.integrity_report.lock().unwrap().clone();
+    match report {
+        Some(report) => utils::build_success_response(report),
+        None => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            "The startup integrity check has not run"
+        ),
+    }
+}
+
+// Handler for reconciling stale or orphaned state
+#[utoipa::path(
+    post,
+    path = "/janitor/reconcile",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<Vec<ReconciledAnomaly>>))
+)]
+pub async fn reconcile_janitor(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Reconciling stale or orphaned state");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can reconcile state"
+        );
+    }
+    
+    utils::build_success_response(janitor::reconcile(&app_state).await)
+}
+
+// Handler for viewing the order validation policy
+#[utoipa::path(
+    get,
+    path = "/validation_policy",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<ValidationPolicy>))
+)]
+pub async fn get_validation_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    info!("Getting validation policy");
+    
+    utils::build_success_response(orders::get_validation_policy(&app_state))
+}
+
+// Handler for replacing the order validation policy
+#[utoipa::path(
+    post,
+    path = "/validation_policy",
+    tag = "admin",
+    request_body = ValidationPolicy,
+    responses((status = 200, description = "Success", body = Envelope<ValidationPolicy>))
+)]
+pub async fn set_validation_policy(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ValidationPolicy>,
+) -> impl IntoResponse {
+    info!("Setting validation policy: {:?}", request);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change the validation policy"
+        );
+    }
+    
+    match orders::set_validation_policy(&app_state, request) {
+        Ok(policy) => utils::build_success_response(policy),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for comparing order outcomes per strategy tag
+#[utoipa::path(
+    get,
+    path = "/stats/strategies",
+    tag = "orders",
+    params(StrategyStatsQuery),
+    responses((status = 200, description = "Success", body = Envelope<Vec<StrategyStats>>))
+)]
+pub async fn get_strategy_stats(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<StrategyStatsQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Getting strategy stats from {:?} to {:?}", query.since, query.until);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let stats = orders::get_strategy_stats(&app_state, &tenant, query.since, query.until);
+    utils::build_success_response(stats)
+}
+
+// Handler for quote against fill slippage of recent executions, to tune default slippage and
+// priority fees from real fills
+#[utoipa::path(
+    get,
+    path = "/stats/slippage",
+    tag = "trading",
+    params(SlippageStatsQuery),
+    responses((status = 200, description = "Success", body = Envelope<SlippageStats>))
+)]
+pub async fn get_slippage_stats(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Query(query): Query<SlippageStatsQuery>,
+    headers: HeaderMap,
+) -> Result<ApiResponse, ApiError> {
+    info!("Getting slippage stats from {:?} to {:?}", query.since, query.until);
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    let stats = slippage::get_slippage_stats(&app_state, &tenant, &query)?;
+    Ok(utils::build_success_response(stats))
+}
+
+// Handler for the calling API key's usage this month
+#[utoipa::path(
+    get,
+    path = "/usage",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<UsageReport>))
+)]
+pub async fn get_usage(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let api_key = metering::api_key_from_headers(&headers);
+    utils::build_success_response(metering::get_usage(&app_state, &api_key))
+}
+
+// Handler for viewing the monthly usage quotas
+#[utoipa::path(
+    get,
+    path = "/usage/quotas",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<UsageQuotas>))
+)]
+pub async fn get_usage_quotas(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    utils::build_success_response(metering::get_quotas(&app_state))
+}
+
+// Handler for setting the monthly usage quotas
+#[utoipa::path(
+    post,
+    path = "/usage/quotas",
+    tag = "admin",
+    request_body = UsageQuotas,
+    responses((status = 200, description = "Success", body = Envelope<UsageQuotas>))
+)]
+pub async fn set_usage_quotas(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(quotas): Json<UsageQuotas>,
+) -> impl IntoResponse {
+    info!("Setting usage quotas");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change usage quotas"
+        );
+    }
+    
+    match metering::set_quotas(&app_state, quotas) {
+        Ok(quotas) => utils::build_success_response(quotas),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing the registered API keys, or the caller's subaccount keys
+#[utoipa::path(
+    get,
+    path = "/api_keys",
+    tag = "account",
+    responses((status = 200, description = "Success", body = Envelope<Vec<ApiKeyRecord>>))
+)]
+pub async fn list_api_keys(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing API keys");
+    
+    let tenant = metering::api_key_from_headers(&headers);
+    utils::build_success_response(auth::list_keys(&app_state, &tenant))
+}
+
+// Handler for creating an API key for a new user
+#[utoipa::path(
+    post,
+    path = "/api_keys",
+    tag = "account",
+    request_body = CreateApiKeyRequest,
+    responses((status = 200, description = "Success", body = Envelope<CreatedApiKey>))
+)]
+pub async fn create_api_key(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    info!("Creating API key: {:?}", request.label);
+    
+    // Tenants can create subaccount keys for their own wallets, only the admin creates full keys
+    let creator = metering::api_key_from_headers(&headers);
+    if request.scope.is_none() && !tenant::is_admin(&creator) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can create unscoped API keys"
+        );
+    }
+    
+    match auth::create_key(&app_state, &creator, &request.label, request.scope) {
+        Ok(created) => utils::build_success_response(created),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for revoking an API key
+#[utoipa::path(
+    delete,
+    path = "/api_keys/{id}",
+    tag = "account",
+    params(("id" = String, Path, description = "API key id")),
+    responses((status = 200, description = "Success", body = Envelope<ApiKeyRecord>))
+)]
+pub async fn revoke_api_key(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Revoking API key {}", id);
+    
+    // Tenants can only revoke their own subaccount keys; other keys are reported as unknown
+    let tenant = metering::api_key_from_headers(&headers);
+    match auth::revoke_key(&app_state, &tenant, &id) {
+        Ok(record) => utils::build_success_response(record),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for viewing the per-tenant limits
+#[utoipa::path(
+    get,
+    path = "/tenants/limits",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<TenantLimitsConfig>))
+)]
+pub async fn get_tenant_limits(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    utils::build_success_response(tenant::get_limits(&app_state))
+}
+
+// Handler for setting the per-tenant rate limits and engine budgets
+#[utoipa::path(
+    post,
+    path = "/tenants/limits",
+    tag = "admin",
+    request_body = TenantLimitsConfig,
+    responses((status = 200, description = "Success", body = Envelope<TenantLimitsConfig>))
+)]
+pub async fn set_tenant_limits(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(config): Json<TenantLimitsConfig>,
+) -> impl IntoResponse {
+    info!("Setting tenant limits");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change tenant limits"
+        );
+    }
+    
+    match tenant::set_limits(&app_state, config) {
+        Ok(config) => utils::build_success_response(config),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for viewing the per-client request budgets by route
+#[utoipa::path(
+    get,
+    path = "/rate_limits",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<RateLimitConfig>))
+)]
+pub async fn get_rate_limits(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    utils::build_success_response(rate_limit::get_config(&app_state))
+}
+
+// Handler for setting the per-client request budgets by route
+#[utoipa::path(
+    post,
+    path = "/rate_limits",
+    tag = "admin",
+    request_body = RateLimitConfig,
+    responses((status = 200, description = "Success", body = Envelope<RateLimitConfig>))
+)]
+pub async fn set_rate_limits(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(config): Json<RateLimitConfig>,
+) -> impl IntoResponse {
+    info!("Setting rate limits");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can change rate limits"
+        );
+    }
+    
+    match rate_limit::set_config(&app_state, config) {
+        Ok(config) => utils::build_success_response(config),
+        Err(err) => utils::build_error_response(
+            StatusCode::BAD_REQUEST,
+            &err.to_string()
+        ),
+    }
+}
+
+// Handler for listing API keys whose order creations are quarantined
+#[utoipa::path(
+    get,
+    path = "/order_quarantines",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = Envelope<Vec<CreationQuarantine>>))
+)]
+pub async fn list_order_quarantines(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    info!("Listing order creation quarantines");
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can view order creation quarantines"
+        );
+    }
+    
+    utils::build_success_response(creation_guard::list_quarantines(&app_state))
+}
+
+// Handler for lifting an order creation quarantine before it ends
+#[utoipa::path(
+    delete,
+    path = "/order_quarantines/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Quarantine id")),
+    responses((status = 200, description = "Success", body = Envelope<CreationQuarantine>))
+)]
+pub async fn lift_order_quarantine(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!("Lifting order creation quarantine {}", id);
+    
+    if !tenant::is_admin(&metering::api_key_from_headers(&headers)) {
+        return utils::build_error_response(
+            StatusCode::FORBIDDEN,
+            "Only the admin API key can lift order creation quarantines"
+        );
+    }
+    
+    match creation_guard::lift_quarantine(&app_state, &id) {
+        Ok(quarantine) => utils::build_success_response(quarantine),
+        Err(err) => utils::build_error_response(
+            StatusCode::NOT_FOUND,
+            &err.to_string()
+        ),
+    }
+}
@@ -0,0 +1,285 @@
+#[cfg(feature = "server")]
+use crate::metering::{self, API_KEY_HEADER};
+use crate::models::{ApiKeyRecord, ApiOperation, AppState, CreatedApiKey, KeyScope, OrderAction, OrderType};
+use crate::tenant;
+#[cfg(feature = "server")]
+use crate::utils;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "server")]
+use axum::{
+    body::Body,
+    http::{HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use tracing::info;
+#[cfg(feature = "server")]
+use tracing::warn;
+use uuid::Uuid;
+
+// Comma separated API keys registered at startup, for keys that must survive restarts
+const API_KEYS_ENV: &str = "API_KEYS";
+
+// Prefix of generated keys, so leaked keys are easy to recognize
+const KEY_PREFIX: &str = "sld_";
+
+// Characters of a key kept in listings to tell keys apart
+const VISIBLE_KEY_CHARS: usize = 8;
+
+// Only hashes of the keys are kept in memory
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Build the record of a key without keeping the key itself
+fn new_record(key: &str, label: &str, scope: Option<KeyScope>) -> ApiKeyRecord {
+    ApiKeyRecord {
+        id: Uuid::new_v4().to_string(),
+        label: label.to_string(),
+        key_prefix: key.chars().take(VISIBLE_KEY_CHARS).collect(),
+        key_hash: hash_key(key),
+        created_at: Utc::now(),
+        last_used_at: None,
+        scope,
+    }
+}
+
+// Keys configured through the environment, keyed by hash
+pub fn load_env_keys() -> HashMap<String, ApiKeyRecord> {
+    crate::secrets::get_secret(API_KEYS_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| {
+            let record = new_record(key, "environment", None);
+            (record.key_hash.clone(), record)
+        })
+        .collect()
+}
+
+// Whether a key is registered, recording when it was last used
+pub fn validate_key(app_state: &AppState, key: &str) -> bool {
+    let mut keys = app_state.api_keys.lock().unwrap();
+    match keys.get_mut(&hash_key(key)) {
+        Some(record) => {
+            record.last_used_at = Some(Utc::now());
+            true
+        }
+        None => false,
+    }
+}
+
+// Scope of a subaccount key. It acts for the owner of its wallet, so only the admin or a tenant that
+// may use the wallet can create one.
+fn subaccount_scope(app_state: &AppState, creator: &str, mut scope: KeyScope) -> Result<KeyScope> {
+    scope.wallet = scope.wallet.trim().to_string();
+    scope
+        .wallet
+        .parse::<Pubkey>()
+        .map_err(|_| anyhow!("Invalid wallet public key: {}", scope.wallet))?;
+    if scope.operations.is_empty() {
+        return Err(anyhow!("A subaccount key needs at least one operation"));
+    }
+    if !tenant::is_admin(creator) && !tenant::can_use_wallet(app_state, creator, &scope.wallet) {
+        return Err(anyhow!("Wallet {} is not imported", scope.wallet));
+    }
+
+    let owners = app_state.wallet_owners.lock().unwrap();
+    scope.tenant = owners.get(&scope.wallet).cloned().unwrap_or_else(|| creator.to_string());
+    scope.operations.sort();
+    scope.operations.dedup();
+    Ok(scope)
+}
+
+// Generate a key for a new user, or a subaccount key limited to `scope`. The key is only ever returned here.
+// Only the admin creates unscoped keys.
+pub fn create_key(app_state: &AppState, creator: &str, label: &str, scope: Option<KeyScope>) -> Result<CreatedApiKey> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(anyhow!("A label is required to tell keys apart"));
+    }
+
+    let scope = match scope {
+        Some(scope) => Some(subaccount_scope(app_state, creator, scope)?),
+        None if tenant::is_admin(creator) => None,
+        None => return Err(anyhow!("Only the admin API key can create unscoped API keys")),
+    };
+
+    let secret: [u8; 32] = rand::random();
+    let key = format!("{}{}", KEY_PREFIX, bs58::encode(secret).into_string());
+    let record = new_record(&key, label, scope);
+
+    let mut keys = app_state.api_keys.lock().unwrap();
+    keys.insert(record.key_hash.clone(), record.clone());
+
+    info!("Created API key {} ({})", record.id, record.label);
+    Ok(CreatedApiKey { key, record })
+}
+
+// Whether the caller manages a key: the admin manages every key, tenants their subaccount keys
+fn manages_key(tenant: &str, record: &ApiKeyRecord) -> bool {
+    tenant::is_admin(tenant) || matches!(&record.scope, Some(scope) if scope.tenant == tenant)
+}
+
+// Keys the caller manages, without their hashes
+pub fn list_keys(app_state: &AppState, tenant: &str) -> Vec<ApiKeyRecord> {
+    let keys = app_state.api_keys.lock().unwrap();
+    let mut records = keys
+        .values()
+        .filter(|record| manages_key(tenant, record))
+        .cloned()
+        .collect::<Vec<_>>();
+    records.sort_by_key(|record| record.created_at);
+    records
+}
+
+// Revoke a key. Its wallets and orders stay in place but nobody can reach them through the API.
+pub fn revoke_key(app_state: &AppState, tenant: &str, id: &str) -> Result<ApiKeyRecord> {
+    let mut keys = app_state.api_keys.lock().unwrap();
+    let hash = keys
+        .iter()
+        .find(|(_, record)| record.id == id && manages_key(tenant, record))
+        .map(|(hash, _)| hash.clone())
+        .ok_or_else(|| anyhow!("API key not found: {}", id))?;
+
+    let record = keys.remove(&hash).expect("key found above");
+    info!("Revoked API key {} ({})", record.id, record.label);
+    Ok(record)
+}
+
+// Scope of a key, if it is a subaccount key
+pub fn key_scope(app_state: &AppState, key: &str) -> Option<KeyScope> {
+    let keys = app_state.api_keys.lock().unwrap();
+    keys.get(&hash_key(key)).and_then(|record| record.scope.clone())
+}
+
+// Operation a route performs, for the routes subaccount keys can be granted
+#[cfg(feature = "server")]
+fn route_operation(method: &Method, path: &str) -> Option<ApiOperation> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["get_prices"]) | (&Method::GET, ["price", _]) => Some(ApiOperation::ViewPrices),
+        (&Method::GET, ["get_balances"]) => Some(ApiOperation::ViewBalances),
+        (&Method::GET, ["order_history", _]) | (&Method::GET, ["orders", _, "receipt"]) => Some(ApiOperation::ViewOrders),
+        (&Method::POST, ["set_limit_order"]) => Some(ApiOperation::CreateOrders),
+        (&Method::POST, ["cancel_limit_order"]) => Some(ApiOperation::CancelOrders),
+        (&Method::POST, ["swap_token"]) => Some(ApiOperation::Swap),
+        _ => None,
+    }
+}
+
+// Wallet of an order, live or archived
+fn order_wallet(app_state: &AppState, id: &str) -> Option<String> {
+    if let Some(order) = app_state.limit_orders.lock().unwrap().get(id) {
+        return Some(order.wallet_pubkey.clone());
+    }
+    let archived = app_state.archived_orders.lock().unwrap();
+    archived.get(id).map(|order| order.wallet_pubkey.clone())
+}
+
+// Check a request of a subaccount key against its scope. Requests must name the key's wallet
+// rather than rely on the default one.
+pub fn check_scope(
+    app_state: &AppState,
+    scope: &KeyScope,
+    operation: ApiOperation,
+    path: &str,
+    query: Option<&str>,
+    body: &[u8],
+) -> Result<()> {
+    if !scope.operations.contains(&operation) {
+        return Err(anyhow!("This API key is not allowed to {:?}", operation));
+    }
+
+    let body = || serde_json::from_slice::<serde_json::Value>(body).unwrap_or_default();
+    let text = |value: &serde_json::Value, field: &str| value.get(field).and_then(|v| v.as_str()).map(str::to_string);
+    let wrong_wallet = || anyhow!("This API key can only act on wallet {}", scope.wallet);
+
+    let wallet = match operation {
+        ApiOperation::ViewPrices => return Ok(()),
+        ApiOperation::ViewBalances => query
+            .unwrap_or_default()
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("wallet="))
+            .map(str::to_string),
+        ApiOperation::Swap => {
+            let body = body();
+            text(&body, "wallet_pubkey").or_else(|| text(&body, "wallet"))
+        }
+        ApiOperation::CreateOrders => {
+            let body = body();
+            let order_type = body.get("order_type").cloned().map(serde_json::from_value::<OrderType>);
+            if let Some(Ok(order_type)) = order_type {
+                if !scope.order_types.is_empty() && !scope.order_types.contains(&order_type) {
+                    return Err(anyhow!("This API key is not allowed to create {:?} orders", order_type));
+                }
+            }
+            // Transfers move funds out of the wallet, which a trading key must never do
+            let action = body.get("action").cloned().map(serde_json::from_value::<OrderAction>);
+            if let Some(Ok(OrderAction::Transfer(_))) = action {
+                return Err(anyhow!("This API key is not allowed to create transfer orders"));
+            }
+            text(&body, "wallet_pubkey")
+        }
+        ApiOperation::CancelOrders => text(&body(), "order_id").and_then(|id| order_wallet(app_state, &id)),
+        ApiOperation::ViewOrders => path
+            .trim_matches('/')
+            .split('/')
+            .nth(1)
+            .and_then(|id| order_wallet(app_state, id)),
+    };
+
+    match wallet {
+        Some(wallet) if wallet == scope.wallet => Ok(()),
+        _ => Err(wrong_wallet()),
+    }
+}
+
+// Middleware confining subaccount keys to their scope. Allowed requests then act as the tenant owning
+// the key's wallet, so handlers see that tenant's wallets and orders.
+#[cfg(feature = "server")]
+pub async fn enforce_scope(request: Request<Body>, next: Next<Body>) -> Response {
+    let app_state = match request.extensions().get::<Arc<AppState>>().cloned() {
+        Some(app_state) => app_state,
+        None => return next.run(request).await,
+    };
+    let scope = match key_scope(&app_state, &metering::api_key_from_headers(request.headers())) {
+        Some(scope) => scope,
+        None => return next.run(request).await,
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let path = parts.uri.path().to_string();
+    let operation = match route_operation(&parts.method, &path) {
+        Some(operation) => operation,
+        None => {
+            warn!("Subaccount key refused {} {}", parts.method, path);
+            return utils::build_error_response(StatusCode::FORBIDDEN, "This API key cannot use this route").into_response();
+        }
+    };
+
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(err) => return utils::build_error_response(StatusCode::BAD_REQUEST, &err.to_string()).into_response(),
+    };
+    if let Err(err) = check_scope(&app_state, &scope, operation, &path, parts.uri.query(), &body) {
+        warn!("Subaccount key refused {} {}: {}", parts.method, path, err);
+        return utils::build_error_response(StatusCode::FORBIDDEN, &err.to_string()).into_response();
+    }
+
+    match HeaderValue::from_str(&scope.tenant) {
+        Ok(tenant) => parts.headers.insert(API_KEY_HEADER, tenant),
+        Err(_) => parts.headers.remove(API_KEY_HEADER),
+    };
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
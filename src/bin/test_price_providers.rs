@@ -0,0 +1,15 @@
+use solana_wallet_api::test_price_providers;
+use anyhow::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("===== Testing Price Providers =====");
+    println!("This test will verify that prices fail over between providers and follow per-token preferences.");
+    println!("===================================\n");
+    
+    test_price_providers::test_price_providers().await?;
+    
+    println!("\n===================================");
+    println!("All tests completed successfully!");
+    Ok(())
+}
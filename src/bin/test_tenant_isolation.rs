@@ -0,0 +1,15 @@
+use solana_wallet_api::test_tenant_isolation;
+use anyhow::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("===== Testing Tenant Isolation =====");
+    println!("This test will verify that tenants in hosted mode cannot see or act on each other's wallets and orders.");
+    println!("====================================\n");
+    
+    test_tenant_isolation::test_tenant_isolation().await?;
+    
+    println!("\n====================================");
+    println!("All tests completed successfully!");
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use crate::models::{AppState, BlackoutWindow, BlackoutWindowRequest, DcaOrder, LimitOrder, NotificationEvent};
+use crate::notify;
+use crate::tenant;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// Validate and store a blackout window. The admin's windows cover every tenant, others only their own orders.
+pub fn create_window(app_state: &AppState, tenant: &str, request: BlackoutWindowRequest) -> Result<BlackoutWindow> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(anyhow!("A name is required to tell blackout windows apart"));
+    }
+    if request.ends_at <= request.starts_at {
+        return Err(anyhow!("ends_at must be after starts_at"));
+    }
+    if request.ends_at <= Utc::now() {
+        return Err(anyhow!("The blackout window has already ended"));
+    }
+
+    let mints = request
+        .mints
+        .iter()
+        .map(|mint| mint.trim().to_string())
+        .filter(|mint| !mint.is_empty())
+        .collect::<Vec<_>>();
+    for mint in &mints {
+        Pubkey::from_str(mint).map_err(|_| anyhow!("Invalid token mint: {}", mint))?;
+    }
+
+    let window = BlackoutWindow {
+        id: Uuid::new_v4().to_string(),
+        name,
+        starts_at: request.starts_at,
+        ends_at: request.ends_at,
+        mints,
+        suppressed_orders: Vec::new(),
+        created_at: Utc::now(),
+        api_key: if tenant::is_admin(tenant) { None } else { Some(tenant.to_string()) },
+    };
+
+    info!("Blackout window {} ({}) scheduled from {} to {}", window.id, window.name, window.starts_at, window.ends_at);
+    let mut windows = app_state.blackout_windows.lock().unwrap();
+    windows.insert(window.id.clone(), window.clone());
+    Ok(window)
+}
+
+// Whether a window applies to a tenant's orders
+fn applies_to(window: &BlackoutWindow, api_key: Option<&str>) -> bool {
+    match &window.api_key {
+        Some(owner) => api_key == Some(owner.as_str()),
+        None => true,
+    }
+}
+
+// Blackout windows covering the tenant's orders, soonest first
+pub fn get_windows(app_state: &AppState, tenant: &str) -> Vec<BlackoutWindow> {
+    let windows = app_state.blackout_windows.lock().unwrap();
+    let mut windows = windows
+        .values()
+        .filter(|window| !tenant::hosted_mode() || applies_to(window, Some(tenant)))
+        .cloned()
+        .collect::<Vec<_>>();
+    windows.sort_by_key(|window| window.starts_at);
+    windows
+}
+
+// Remove a blackout window. Tenants can only remove their own, the admin any of them.
+pub fn remove_window(app_state: &AppState, tenant: &str, id: &str) -> Result<BlackoutWindow> {
+    let mut windows = app_state.blackout_windows.lock().unwrap();
+    let managed = matches!(
+        windows.get(id),
+        Some(window) if tenant::is_admin(tenant) || window.api_key.as_deref() == Some(tenant)
+    );
+    if !managed {
+        return Err(anyhow!("Blackout window not found: {}", id));
+    }
+
+    let window = windows.remove(id).expect("window found above");
+    info!("Removed blackout window {} ({})", window.id, window.name);
+    Ok(window)
+}
+
+// Window in effect at `now` for an order of the tenant trading the given tokens
+fn active_window(app_state: &AppState, api_key: Option<&str>, mints: &[&str], now: DateTime<Utc>) -> Option<BlackoutWindow> {
+    let windows = app_state.blackout_windows.lock().unwrap();
+    windows
+        .values()
+        .filter(|window| window.starts_at <= now && now < window.ends_at)
+        .filter(|window| applies_to(window, api_key))
+        .find(|window| window.mints.is_empty() || window.mints.iter().any(|mint| mints.contains(&mint.as_str())))
+        .cloned()
+}
+
+// Window suspending a limit order's execution, if one is in effect
+pub fn window_for_order(app_state: &AppState, order: &LimitOrder, now: DateTime<Utc>) -> Option<BlackoutWindow> {
+    let mut mints = vec![order.source_token.as_str(), order.target_token.as_str(), order.trigger_mint()];
+    mints.extend(order.quote_mint.as_deref());
+    active_window(app_state, order.api_key.as_deref(), &mints, now)
+}
+
+// Window suspending a DCA order's buys, if one is in effect
+pub fn window_for_dca(app_state: &AppState, order: &DcaOrder, now: DateTime<Utc>) -> Option<BlackoutWindow> {
+    let mints = [order.source_token.as_str(), order.target_token.as_str()];
+    active_window(app_state, order.api_key.as_deref(), &mints, now)
+}
+
+// Hold back triggered orders covered by a blackout window, leaving them active. Each order is
+// reported once per window, so the monitor and price stream do not repeat the warning every pass.
+pub async fn suppress_triggers(app_state: &AppState, triggered: Vec<(LimitOrder, f64)>) -> Vec<(LimitOrder, f64)> {
+    let now = Utc::now();
+    let mut allowed = Vec::new();
+
+    for (order, trigger_price) in triggered {
+        let window = match window_for_order(app_state, &order, now) {
+            Some(window) => window,
+            None => {
+                allowed.push((order, trigger_price));
+                continue;
+            }
+        };
+
+        let first_trigger = {
+            let mut windows = app_state.blackout_windows.lock().unwrap();
+            match windows.get_mut(&window.id) {
+                Some(window) if !window.suppressed_orders.contains(&order.id) => {
+                    window.suppressed_orders.push(order.id.clone());
+                    true
+                }
+                _ => false,
+            }
+        };
+
+        if first_trigger {
+            warn!("Order {} triggered during blackout window {} ({}), not executing it", order.id, window.id, window.name);
+            let vars = serde_json::json!({
+                "order_type": order.order_type,
+                "order_id": order.id,
+                "price": trigger_price,
+                "window": window.name,
+                "until": window.ends_at,
+            });
+            notify::notify_event(app_state, NotificationEvent::BlackoutTrigger, order.api_key.as_deref(), "blackout_trigger", vars).await;
+        }
+    }
+
+    allowed
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::time;
+use tracing::warn;
+
+// Faults currently injected. Quote, RPC and swap helpers do not take the app
+// state, so the config lives in a global rather than in AppState.
+static FAULTS: Mutex<Option<ChaosConfig>> = Mutex::new(None);
+
+// Faults to inject into upstream calls
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ChaosConfig {
+    // Extra latency added to every Jupiter quote
+    #[serde(default)]
+    pub quote_delay_ms: Option<u64>,
+    // Stop refreshing cached prices
+    #[serde(default)]
+    pub stale_prices: bool,
+    // Probability (0 to 1) that an RPC call fails
+    #[serde(default)]
+    pub rpc_error_rate: f64,
+    // Fraction (0 to 1) of the quoted output reported as filled
+    #[serde(default)]
+    pub partial_fill_ratio: Option<f64>,
+}
+
+// Faults currently injected, if any
+pub fn get_faults() -> Option<ChaosConfig> {
+    FAULTS.lock().unwrap().clone()
+}
+
+// Validate and install a set of faults
+pub fn set_faults(config: ChaosConfig) -> Result<ChaosConfig> {
+    if !(0.0..=1.0).contains(&config.rpc_error_rate) {
+        return Err(anyhow!("rpc_error_rate must be between 0 and 1"));
+    }
+    if let Some(ratio) = config.partial_fill_ratio {
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err(anyhow!("partial_fill_ratio must be between 0 and 1"));
+        }
+    }
+
+    warn!("Chaos faults enabled: {:?}", config);
+    *FAULTS.lock().unwrap() = Some(config.clone());
+    Ok(config)
+}
+
+// Remove every injected fault
+pub fn clear_faults() {
+    warn!("Chaos faults cleared");
+    *FAULTS.lock().unwrap() = None;
+}
+
+// Delay a quote request
+pub async fn delay_quote() {
+    let delay_ms = get_faults().and_then(|config| config.quote_delay_ms);
+    if let Some(delay_ms) = delay_ms {
+        warn!("Chaos: delaying quote by {}ms", delay_ms);
+        time::sleep(time::Duration::from_millis(delay_ms)).await;
+    }
+}
+
+// Whether price refreshes should be skipped
+pub fn prices_stale() -> bool {
+    get_faults().map_or(false, |config| config.stale_prices)
+}
+
+// Fail an RPC call at the configured rate
+pub fn rpc_fault(call: &str) -> Result<()> {
+    let rate = get_faults().map_or(0.0, |config| config.rpc_error_rate);
+    if rate > 0.0 && rand::random::<f64>() < rate {
+        warn!("Chaos: injecting RPC error into {}", call);
+        return Err(anyhow!("Injected RPC error in {}", call));
+    }
+    Ok(())
+}
+
+// Scale a filled amount down to simulate a partial fill
+pub fn partial_fill(amount: f64) -> f64 {
+    match get_faults().and_then(|config| config.partial_fill_ratio) {
+        Some(ratio) => {
+            warn!("Chaos: reporting {}% of {} as filled", ratio * 100.0, amount);
+            amount * ratio
+        }
+        None => amount,
+    }
+}
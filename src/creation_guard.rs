@@ -0,0 +1,120 @@
+use crate::models::{AppState, CreationQuarantine, CreationQuarantined, NotificationEvent};
+use crate::notify;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// Orders an API key may create within the window before its creations are quarantined. Requests
+// are each valid, so this sits on top of the plain per-minute rate limit rather than replacing it.
+const ORDER_BURST_LIMIT_ENV: &str = "ORDER_BURST_LIMIT";
+const DEFAULT_ORDER_BURST_LIMIT: usize = 20;
+const ORDER_BURST_WINDOW_ENV: &str = "ORDER_BURST_WINDOW_SECS";
+const DEFAULT_ORDER_BURST_WINDOW_SECS: i64 = 60;
+
+// How long a quarantine lasts unless the admin lifts it earlier
+const ORDER_QUARANTINE_ENV: &str = "ORDER_QUARANTINE_SECS";
+const DEFAULT_ORDER_QUARANTINE_SECS: i64 = 900;
+
+// Characters of a key kept in listings to tell keys apart
+const VISIBLE_KEY_CHARS: usize = 8;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+// Current quarantine of a key, dropping one that has run out
+fn active_quarantine(app_state: &AppState, api_key: &str) -> Option<CreationQuarantine> {
+    let mut quarantines = app_state.creation_quarantines.lock().unwrap();
+    match quarantines.get(api_key) {
+        Some(quarantine) if quarantine.until > Utc::now() => Some(quarantine.clone()),
+        Some(_) => {
+            quarantines.remove(api_key);
+            info!("Order creation quarantine of {} ended", masked(api_key));
+            None
+        }
+        None => None,
+    }
+}
+
+fn masked(api_key: &str) -> String {
+    api_key.chars().take(VISIBLE_KEY_CHARS).collect()
+}
+
+// Refuse an order creation while the key is quarantined, quarantining it when it already created the
+// most orders the window allows. A new quarantine is alerted on once.
+pub async fn check_creation(app_state: &AppState, api_key: &str) -> Result<(), CreationQuarantined> {
+    if let Some(quarantine) = active_quarantine(app_state, api_key) {
+        return Err(CreationQuarantined::from(&quarantine));
+    }
+
+    let limit = env_or(ORDER_BURST_LIMIT_ENV, DEFAULT_ORDER_BURST_LIMIT);
+    let window_secs = env_or(ORDER_BURST_WINDOW_ENV, DEFAULT_ORDER_BURST_WINDOW_SECS);
+    let now = Utc::now();
+
+    let creations = {
+        let mut creations = app_state.order_creations.lock().unwrap();
+        let recent = creations.entry(api_key.to_string()).or_default();
+        while recent.front().map(|created_at| now - *created_at >= Duration::seconds(window_secs)).unwrap_or(false) {
+            recent.pop_front();
+        }
+        if recent.len() < limit {
+            return Ok(());
+        }
+        let count = recent.len();
+        recent.clear();
+        count
+    };
+
+    let quarantine = CreationQuarantine {
+        id: Uuid::new_v4().to_string(),
+        key_prefix: masked(api_key),
+        api_key: api_key.to_string(),
+        creations,
+        window_secs,
+        quarantined_at: now,
+        until: now + Duration::seconds(env_or(ORDER_QUARANTINE_ENV, DEFAULT_ORDER_QUARANTINE_SECS)),
+    };
+    app_state.creation_quarantines.lock().unwrap().insert(api_key.to_string(), quarantine.clone());
+
+    let message = format!(
+        "API key {}... created {} orders in {} seconds, new orders from it are refused until {}",
+        quarantine.key_prefix, creations, window_secs, quarantine.until
+    );
+    warn!("{}", message);
+    notify::notify(app_state, NotificationEvent::RunawayClient, message).await;
+
+    Err(CreationQuarantined::from(&quarantine))
+}
+
+// Count an order the key created
+pub fn record_creation(app_state: &AppState, api_key: &str) {
+    let mut creations = app_state.order_creations.lock().unwrap();
+    creations.entry(api_key.to_string()).or_default().push_back(Utc::now());
+}
+
+// Quarantines still in force, soonest to end first
+pub fn list_quarantines(app_state: &AppState) -> Vec<CreationQuarantine> {
+    let now = Utc::now();
+    let mut quarantines = app_state.creation_quarantines.lock().unwrap();
+    quarantines.retain(|_, quarantine| quarantine.until > now);
+
+    let mut active = quarantines.values().cloned().collect::<Vec<_>>();
+    active.sort_by_key(|quarantine| quarantine.until);
+    active
+}
+
+// Let a quarantined key create orders again before its quarantine ends
+pub fn lift_quarantine(app_state: &AppState, id: &str) -> Result<CreationQuarantine> {
+    let mut quarantines = app_state.creation_quarantines.lock().unwrap();
+    let api_key = quarantines
+        .iter()
+        .find(|(_, quarantine)| quarantine.id == id)
+        .map(|(api_key, _)| api_key.clone())
+        .ok_or_else(|| anyhow!("Quarantine not found: {}", id))?;
+
+    let quarantine = quarantines.remove(&api_key).ok_or_else(|| anyhow!("Quarantine not found: {}", id))?;
+    app_state.order_creations.lock().unwrap().remove(&api_key);
+    info!("Lifted the order creation quarantine of {}", quarantine.key_prefix);
+    Ok(quarantine)
+}
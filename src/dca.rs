@@ -0,0 +1,255 @@
+use crate::blackout;
+use crate::creation_guard;
+use crate::engine;
+use crate::freeze;
+use crate::ledger;
+use crate::metering;
+use crate::models::{AppState, DcaFill, DcaOrder, DcaOrderRequest, DcaStatus, SwapRequest};
+use crate::price;
+use crate::swap;
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::time;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+// How often the scheduler looks for buys that are due
+const DCA_CHECK_INTERVAL_SECS: u64 = 15;
+
+// Shortest interval between buys, so a typo cannot turn a DCA into a stream of swaps
+const MIN_DCA_INTERVAL_SECS: u64 = 60;
+
+// Validate and store a DCA order. The first buy is made on the next scheduler pass.
+pub async fn create_dca_order(app_state: &AppState, request: DcaOrderRequest) -> Result<DcaOrder> {
+    if request.amount_per_buy <= 0.0 {
+        return Err(anyhow!("amount_per_buy must be greater than zero"));
+    }
+    if request.total_budget < request.amount_per_buy {
+        return Err(anyhow!("total_budget must cover at least one buy of amount_per_buy"));
+    }
+    if request.interval_secs < MIN_DCA_INTERVAL_SECS {
+        return Err(anyhow!("interval_secs must be at least {}", MIN_DCA_INTERVAL_SECS));
+    }
+    if request.source_token == request.target_token {
+        return Err(anyhow!("Source and target tokens must differ"));
+    }
+    KnownTokens::get_decimals(&request.source_token)?;
+    KnownTokens::get_decimals(&request.target_token)?;
+
+    let tenant = request.api_key.clone().unwrap_or_else(|| metering::ANONYMOUS_KEY.to_string());
+    let wallet = wallet::find_tenant_wallet(app_state, &tenant, request.wallet_pubkey.as_deref())?;
+    freeze::check_order(app_state, &wallet.pubkey.to_string())?;
+    creation_guard::check_creation(app_state, &tenant).await?;
+
+    let now = Utc::now();
+    let order = DcaOrder {
+        id: Uuid::new_v4().to_string(),
+        source_token: request.source_token,
+        target_token: request.target_token,
+        amount_per_buy: request.amount_per_buy,
+        interval_secs: request.interval_secs,
+        total_budget: request.total_budget,
+        slippage: request.slippage.unwrap_or(0.5),
+        status: DcaStatus::Active,
+        spent: 0.0,
+        received: 0.0,
+        fills: Vec::new(),
+        next_buy_at: now,
+        last_error: None,
+        wallet_pubkey: wallet.pubkey.to_string(),
+        created_at: now,
+        updated_at: now,
+        api_key: request.api_key,
+    };
+
+    info!(
+        "Creating DCA order {}: {} {} into {} every {}s, budget {}",
+        order.id,
+        order.amount_per_buy,
+        KnownTokens::get_symbol(&order.source_token),
+        KnownTokens::get_symbol(&order.target_token),
+        order.interval_secs,
+        order.total_budget
+    );
+
+    app_state.dca_orders.lock().unwrap().insert(order.id.clone(), order.clone());
+    creation_guard::record_creation(app_state, &tenant);
+    Ok(order)
+}
+
+// DCA orders the tenant may see
+pub fn get_dca_orders(app_state: &AppState, tenant: &str) -> Vec<DcaOrder> {
+    let orders = app_state.dca_orders.lock().unwrap();
+    let mut orders = orders
+        .values()
+        .filter(|order| tenant::can_access(tenant, order.api_key.as_deref()))
+        .cloned()
+        .collect::<Vec<_>>();
+    orders.sort_by_key(|order| order.created_at);
+    orders
+}
+
+// Stop an active DCA order; the fills it already made stay on it
+pub fn cancel_dca_order(app_state: &AppState, tenant: &str, id: &str) -> Result<DcaOrder> {
+    let mut orders = app_state.dca_orders.lock().unwrap();
+    let order = orders
+        .get_mut(id)
+        .filter(|order| tenant::can_access(tenant, order.api_key.as_deref()))
+        .ok_or_else(|| anyhow!("DCA order not found: {}", id))?;
+
+    if order.status != DcaStatus::Active {
+        return Err(anyhow!("DCA order {} is already {:?}", id, order.status));
+    }
+
+    order.status = DcaStatus::Cancelled;
+    order.updated_at = Utc::now();
+    info!("Cancelled DCA order {}", id);
+    Ok(order.clone())
+}
+
+// Stop the active DCA orders buying from a wallet, before the wallet is removed
+pub fn cancel_wallet_dca_orders(app_state: &AppState, pubkey: &str) -> Vec<String> {
+    let mut orders = app_state.dca_orders.lock().unwrap();
+    let mut cancelled = Vec::new();
+    for order in orders.values_mut() {
+        if order.wallet_pubkey == pubkey && order.status == DcaStatus::Active {
+            order.status = DcaStatus::Cancelled;
+            order.updated_at = Utc::now();
+            cancelled.push(order.id.clone());
+        }
+    }
+    cancelled
+}
+
+// Source tokens left to spend
+fn remaining_budget(order: &DcaOrder) -> f64 {
+    (order.total_budget - order.spent).max(0.0)
+}
+
+// Make one buy of a DCA order, at most what is left of its budget
+async fn execute_dca_buy(app_state: &AppState, order: &DcaOrder) -> Result<DcaFill> {
+    let trading_wallet = wallet::find_wallet(app_state, Some(&order.wallet_pubkey))
+        .map_err(|_| anyhow!("Wallet {} was removed", order.wallet_pubkey))?;
+
+    let amount = order.amount_per_buy.min(remaining_budget(order));
+    let notional_usd = amount * price::get_token_price(app_state, &order.source_token).unwrap_or(0.0);
+
+    if let Some(api_key) = &order.api_key {
+        metering::check_notional(app_state, api_key, notional_usd)?;
+    }
+
+    let swap_request = SwapRequest {
+        source_token: order.source_token.clone(),
+        target_token: order.target_token.clone(),
+        amount,
+        amount_percent: None,
+        amount_usd: None,
+        slippage: Some(order.slippage),
+        wallet_pubkey: Some(order.wallet_pubkey.clone()),
+        dry_run: false,
+        priority_fee: None,
+        mev_protection: false,
+    };
+
+    let mut result = swap::execute_swap(app_state, &trading_wallet, &swap_request).await?;
+    price::value_fee(app_state, &mut result.fee_details);
+    ledger::record_trade(app_state, &order.wallet_pubkey, &swap_request, &result, Some(&order.id));
+
+    if let Some(api_key) = &order.api_key {
+        metering::record_notional(app_state, api_key, notional_usd);
+    }
+
+    Ok(DcaFill {
+        source_amount: result.source_amount,
+        target_amount: result.target_amount,
+        price: if result.target_amount > 0.0 { result.source_amount / result.target_amount } else { 0.0 },
+        transaction_signature: result.transaction_signature,
+        fee: result.fee_details,
+        executed_at: result.timestamp,
+    })
+}
+
+// Run the buys that are due, recording each fill on its order
+async fn run_due_buys(app_state: &AppState) {
+    let now = Utc::now();
+    let due = {
+        let orders = app_state.dca_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.status == DcaStatus::Active && order.next_buy_at <= now)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    for order in due {
+        // The buy stays due and is made on the first pass after the window ends
+        if let Some(window) = blackout::window_for_dca(app_state, &order, now) {
+            warn!("Skipping DCA order {} buy during blackout window {} ({})", order.id, window.id, window.name);
+            continue;
+        }
+        if freeze::is_frozen(app_state, &order.wallet_pubkey) {
+            info!("Skipping DCA order {} buy, wallet {} is frozen", order.id, order.wallet_pubkey);
+            continue;
+        }
+
+        let result = execute_dca_buy(app_state, &order).await;
+
+        let mut orders = app_state.dca_orders.lock().unwrap();
+        let current = match orders.get_mut(&order.id) {
+            Some(current) => current,
+            None => continue,
+        };
+
+        // Wait a full interval after each attempt, so buys missed during downtime are not made in a burst
+        current.next_buy_at = Utc::now() + Duration::seconds(current.interval_secs as i64);
+        current.updated_at = Utc::now();
+
+        match result {
+            Ok(fill) => {
+                info!(
+                    "DCA order {} bought {} {} for {} {}. Signature: {}",
+                    current.id,
+                    fill.target_amount,
+                    KnownTokens::get_symbol(&current.target_token),
+                    fill.source_amount,
+                    KnownTokens::get_symbol(&current.source_token),
+                    fill.transaction_signature
+                );
+
+                current.spent += fill.source_amount;
+                current.received += fill.target_amount;
+                current.fills.push(fill);
+                current.last_error = None;
+
+                // A buy finishing while the order was cancelled is still recorded, but does not complete it
+                if current.status == DcaStatus::Active && remaining_budget(current) <= 0.0 {
+                    current.status = DcaStatus::Completed;
+                    info!("DCA order {} spent its budget of {}", current.id, current.total_budget);
+                }
+            }
+            Err(err) => {
+                error!("DCA order {} buy failed: {}", current.id, err);
+                current.last_error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+// Background task making the periodic buys of DCA orders
+pub async fn run_dca_scheduler(app_state: Arc<AppState>) {
+    info!("Starting DCA scheduler task");
+
+    loop {
+        time::sleep(time::Duration::from_secs(DCA_CHECK_INTERVAL_SECS)).await;
+
+        if let Some(reason) = engine::trading_paused(&app_state) {
+            warn!("Skipping DCA buys, trading is paused: {}", reason);
+            continue;
+        }
+
+        run_due_buys(&app_state).await;
+    }
+}
@@ -0,0 +1,85 @@
+use crate::models::{AppState, RouteDenylist};
+use crate::upstream_cache;
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::info;
+
+// Quotes only name the DEX of each hop, so denied programs are matched through their label
+async fn fetch_program_labels(app_state: &AppState) -> Result<HashMap<String, String>> {
+    let body = upstream_cache::fetch(app_state, &upstream_cache::JUPITER_PROGRAM_LABELS).await?;
+    serde_json::from_str::<HashMap<String, String>>(&body)
+        .map_err(|e| anyhow!("Failed to parse Jupiter program labels: {}", e))
+}
+
+// Validate and store the route denylist, resolving denied programs to the labels quotes use
+pub async fn set_denylist(app_state: &AppState, request: RouteDenylist) -> Result<RouteDenylist> {
+    let program_ids = request
+        .program_ids
+        .iter()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect::<Vec<_>>();
+    for id in &program_ids {
+        Pubkey::from_str(id).map_err(|_| anyhow!("Invalid program id: {}", id))?;
+    }
+
+    let labels = request
+        .labels
+        .iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut excluded_labels = labels.clone();
+
+    if !program_ids.is_empty() {
+        let program_labels = fetch_program_labels(app_state).await?;
+        for id in &program_ids {
+            let label = program_labels
+                .get(id)
+                .ok_or_else(|| anyhow!("Jupiter does not route through program {}", id))?;
+            excluded_labels.push(label.clone());
+        }
+    }
+
+    excluded_labels.sort();
+    excluded_labels.dedup();
+
+    let denylist = RouteDenylist {
+        program_ids,
+        labels,
+        excluded_labels,
+    };
+
+    let mut current = app_state.route_denylist.lock().unwrap();
+    *current = denylist.clone();
+
+    info!("Route denylist set: {}", denylist.excluded_labels.join(", "));
+    Ok(denylist)
+}
+
+// Current route denylist
+pub fn get_denylist(app_state: &AppState) -> RouteDenylist {
+    let denylist = app_state.route_denylist.lock().unwrap();
+    denylist.clone()
+}
+
+// DEX labels swaps must not route through
+pub fn excluded_labels(app_state: &AppState) -> Vec<String> {
+    let denylist = app_state.route_denylist.lock().unwrap();
+    denylist.excluded_labels.clone()
+}
+
+// Labels of a route that are on the denylist, compared without case
+pub fn denied_labels(route_labels: &[String], excluded: &[String]) -> Vec<String> {
+    let mut denied = route_labels
+        .iter()
+        .filter(|label| excluded.iter().any(|excluded| excluded.eq_ignore_ascii_case(label)))
+        .cloned()
+        .collect::<Vec<_>>();
+    denied.sort();
+    denied.dedup();
+    denied
+}
@@ -0,0 +1,278 @@
+use crate::models::{AppState, OrderStatus, PerpMarket, TriggerSource};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use tracing::{error, info};
+
+// Drift's public orderbook server, serving the perp mark and oracle prices
+const DRIFT_DLOB_URL_ENV: &str = "DRIFT_DLOB_URL";
+const DEFAULT_DRIFT_DLOB_URL: &str = "https://dlob.drift.trade";
+
+// Drift's public data API, serving funding rate history
+const DRIFT_DATA_URL_ENV: &str = "DRIFT_DATA_URL";
+const DEFAULT_DRIFT_DATA_URL: &str = "https://data.api.drift.trade";
+
+// Drift quotes prices with 6 decimals and funding rates with 9
+const PRICE_PRECISION: f64 = 1e6;
+const FUNDING_RATE_PRECISION: f64 = 1e9;
+
+// Self-hosted Drift gateway that signs and sends orders for its configured Drift account
+const DRIFT_GATEWAY_URL_ENV: &str = "DRIFT_GATEWAY_URL";
+
+// Mark prices older than this are not used to trigger orders
+const MAX_MARK_PRICE_AGE_SECS: i64 = 120;
+
+// Orderbook snapshot, only the top of book and the oracle are used
+#[derive(Deserialize, Debug)]
+struct L2Response {
+    bids: Vec<L2Level>,
+    asks: Vec<L2Level>,
+    #[serde(default)]
+    oracle: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct L2Level {
+    price: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct FundingRatesResponse {
+    #[serde(rename = "fundingRates")]
+    funding_rates: Vec<FundingRateRecord>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FundingRateRecord {
+    ts: serde_json::Value,
+    #[serde(rename = "fundingRate")]
+    funding_rate: serde_json::Value,
+    #[serde(rename = "oraclePriceTwap")]
+    oracle_price_twap: serde_json::Value,
+}
+
+// Perp market tracking a spot token, and how many tokens one perp unit stands for
+pub fn perp_market_for_mint(mint: &str) -> Option<(&'static str, f64)> {
+    match mint {
+        "So11111111111111111111111111111111111111112" => Some(("SOL-PERP", 1.0)),
+        // Drift lists BONK per million tokens
+        "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263" => Some(("1MBONK-PERP", 1_000_000.0)),
+        // Liquid staking tokens trade at a premium to SOL, so SOL-PERP does not track them
+        _ => None,
+    }
+}
+
+// Drift's index of the perp markets orders can be placed on
+pub fn perp_market_index(market: &str) -> Option<u16> {
+    match market {
+        "SOL-PERP" => Some(0),
+        "BTC-PERP" => Some(1),
+        "ETH-PERP" => Some(2),
+        "1MBONK-PERP" => Some(4),
+        _ => None,
+    }
+}
+
+// Whether a name looks like a Drift perp market
+pub fn is_perp_market(market: &str) -> bool {
+    market.ends_with("-PERP") && market.len() > "-PERP".len()
+}
+
+// Drift serves large integers either as numbers or as strings
+fn parse_number(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+// Mark price of a perp market, the middle of the best bid and ask, and its oracle price
+async fn fetch_mark_price(client: &Client, market: &str) -> Result<(f64, Option<f64>)> {
+    let base_url = std::env::var(DRIFT_DLOB_URL_ENV).unwrap_or_else(|_| DEFAULT_DRIFT_DLOB_URL.to_string());
+    let url = format!("{}/l2?marketName={}&depth=1&includeOracle=true", base_url, market);
+
+    let book = client.get(&url).send().await?.error_for_status()?.json::<L2Response>().await?;
+
+    let oracle_price = book.oracle.as_ref().and_then(parse_number).map(|price| price / PRICE_PRECISION);
+    let best_bid = book.bids.first().and_then(|level| parse_number(&level.price));
+    let best_ask = book.asks.first().and_then(|level| parse_number(&level.price));
+
+    // A one-sided book has no mark, fall back to the oracle rather than a stale side
+    let mark_price = match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) => (bid + ask) / 2.0 / PRICE_PRECISION,
+        _ => oracle_price.ok_or_else(|| anyhow!("Drift orderbook for {} is empty", market))?,
+    };
+
+    Ok((mark_price, oracle_price))
+}
+
+// Latest hourly funding rate of a perp market in percent
+async fn fetch_funding_rate(client: &Client, market: &str) -> Result<f64> {
+    let base_url = std::env::var(DRIFT_DATA_URL_ENV).unwrap_or_else(|_| DEFAULT_DRIFT_DATA_URL.to_string());
+    let url = format!("{}/fundingRates?marketName={}", base_url, market);
+
+    let response = client.get(&url).send().await?.error_for_status()?.json::<FundingRatesResponse>().await?;
+
+    let latest = response
+        .funding_rates
+        .iter()
+        .max_by(|a, b| {
+            let a = parse_number(&a.ts).unwrap_or(0.0);
+            let b = parse_number(&b.ts).unwrap_or(0.0);
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .ok_or_else(|| anyhow!("No funding rates published for {}", market))?;
+
+    // The rate is quoted in quote units per base unit, relative to the oracle TWAP
+    let funding_rate = parse_number(&latest.funding_rate).ok_or_else(|| anyhow!("Invalid funding rate for {}", market))?;
+    let oracle_twap = parse_number(&latest.oracle_price_twap).ok_or_else(|| anyhow!("Invalid oracle TWAP for {}", market))?;
+    if oracle_twap <= 0.0 {
+        return Err(anyhow!("Invalid oracle TWAP for {}", market));
+    }
+
+    Ok(funding_rate / FUNDING_RATE_PRECISION / (oracle_twap / PRICE_PRECISION) * 100.0)
+}
+
+// Fetch the mark price and funding rate of each market and cache them. A missing funding
+// rate keeps the last one known, a missing mark price fails the market.
+pub async fn refresh_markets(app_state: &AppState, markets: &[String]) -> Result<()> {
+    let client = Client::new();
+    let mut failed = Vec::new();
+
+    for market in markets {
+        let (mark_price, oracle_price) = match fetch_mark_price(&client, market).await {
+            Ok(prices) => prices,
+            Err(err) => {
+                error!("Failed to get Drift mark price for {}: {}", market, err);
+                failed.push(market.clone());
+                continue;
+            }
+        };
+
+        let funding_rate_percent = match fetch_funding_rate(&client, market).await {
+            Ok(rate) => Some(rate),
+            Err(err) => {
+                error!("Failed to get Drift funding rate for {}: {}", market, err);
+                let cached = app_state.perp_markets.lock().unwrap();
+                cached.get(market).and_then(|cached| cached.funding_rate_percent)
+            }
+        };
+
+        let mut cached = app_state.perp_markets.lock().unwrap();
+        cached.insert(market.clone(), PerpMarket {
+            market: market.clone(),
+            mark_price,
+            oracle_price,
+            funding_rate_percent,
+            updated_at: Utc::now(),
+        });
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!("Failed to refresh Drift markets: {}", failed.join(", ")));
+    }
+    Ok(())
+}
+
+// Refresh the markets active orders trigger on
+pub async fn update_perp_prices(app_state: &AppState) -> Result<()> {
+    let mut markets = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.status == OrderStatus::Active && order.trigger_source == TriggerSource::PerpMark)
+            .filter_map(|order| perp_market_for_mint(order.trigger_mint()))
+            .map(|(market, _)| market.to_string())
+            .collect::<Vec<_>>()
+    };
+    markets.sort();
+    markets.dedup();
+
+    if markets.is_empty() {
+        return Ok(());
+    }
+
+    refresh_markets(app_state, &markets).await?;
+    info!("Updated Drift mark prices for {}", markets.join(", "));
+    Ok(())
+}
+
+// Cached Drift markets
+pub fn get_perp_markets(app_state: &AppState) -> Vec<PerpMarket> {
+    let cached = app_state.perp_markets.lock().unwrap();
+    cached.values().cloned().collect()
+}
+
+// Cached mark price of a market, refusing to use a stale one
+pub fn market_mark_price(app_state: &AppState, market: &str) -> Result<f64> {
+    let cached = {
+        let cached = app_state.perp_markets.lock().unwrap();
+        cached.get(market).cloned()
+    };
+    let cached = cached.ok_or_else(|| anyhow!("No Drift mark price for {} yet", market))?;
+
+    let age = (Utc::now() - cached.updated_at).num_seconds();
+    if age > MAX_MARK_PRICE_AGE_SECS {
+        return Err(anyhow!("Drift mark price for {} is {}s old, refusing to evaluate triggers", market, age));
+    }
+
+    Ok(cached.mark_price)
+}
+
+// Price of one token implied by the perp mark price, refusing to use a stale mark
+pub fn get_mark_price(app_state: &AppState, token_mint: &str) -> Result<f64> {
+    let (market, units) = perp_market_for_mint(token_mint)
+        .ok_or_else(|| anyhow!("No Drift perp market tracks token {}", token_mint))?;
+
+    Ok(market_mark_price(app_state, market)? / units)
+}
+
+// Whether a Drift gateway is configured to place orders through
+pub fn trading_enabled() -> bool {
+    std::env::var(DRIFT_GATEWAY_URL_ENV).is_ok_and(|url| !url.is_empty())
+}
+
+#[derive(Deserialize, Debug)]
+struct GatewayTxResponse {
+    tx: String,
+}
+
+// Open a short with a market order through the Drift gateway, returning the transaction signature.
+// The gateway trades from its own Drift account, which holds the collateral for hedges.
+pub async fn open_short(market: &str, base_amount: f64) -> Result<String> {
+    let gateway_url = std::env::var(DRIFT_GATEWAY_URL_ENV)
+        .map_err(|_| anyhow!("{} is not set", DRIFT_GATEWAY_URL_ENV))?;
+    let market_index = perp_market_index(market).ok_or_else(|| anyhow!("Drift perp market {} is not supported", market))?;
+    if base_amount <= 0.0 || !base_amount.is_finite() {
+        return Err(anyhow!("Invalid short size {} for {}", base_amount, market));
+    }
+
+    // Negative amounts are shorts
+    let body = serde_json::json!({
+        "orders": [{
+            "marketIndex": market_index,
+            "marketType": "perp",
+            "amount": -base_amount,
+            "orderType": "market",
+            "reduceOnly": false,
+        }]
+    });
+
+    let response = Client::new()
+        .post(format!("{}/v2/orders", gateway_url.trim_end_matches('/')))
+        .json(&body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Drift gateway returned {}: {}", status, text));
+    }
+
+    let tx = response.json::<GatewayTxResponse>().await?;
+    info!("Opened {} {} short. Signature: {}", base_amount, market, tx.tx);
+    Ok(tx.tx)
+}
@@ -0,0 +1,195 @@
+use crate::models::{AppState, LimitOrder, OrderStatus, OrderType, PublicStatus, QueuedOrder};
+use crate::metering::ANONYMOUS_KEY;
+use crate::price;
+use crate::tenant;
+use chrono::Utc;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+// Execution rank by order type: protective exits go first, buys last
+fn order_type_rank(order_type: &OrderType) -> u8 {
+    match order_type {
+        OrderType::StopLoss | OrderType::DeadMansSwitch => 0,
+        OrderType::Sell | OrderType::TakeProfit => 1,
+        OrderType::Buy | OrderType::StopBuy => 2,
+    }
+}
+
+// USD value of an order at current prices, used to execute larger orders first
+fn order_notional_usd(app_state: &AppState, order: &LimitOrder) -> f64 {
+    // Sell side orders spend the source token, buy orders are sized in the target token
+    let mint = if order.order_type.is_sell_side() {
+        &order.source_token
+    } else {
+        &order.target_token
+    };
+
+    price::get_token_price(app_state, mint)
+        .map(|price| order.amount * price)
+        .unwrap_or(0.0)
+}
+
+// Deterministic execution order: user priority (highest first), then order type,
+// then largest notional, then oldest order, then id as a final tie breaker
+fn compare_queued(a: &(QueuedOrder, LimitOrder), b: &(QueuedOrder, LimitOrder)) -> Ordering {
+    b.0.priority
+        .cmp(&a.0.priority)
+        .then_with(|| order_type_rank(&a.0.order_type).cmp(&order_type_rank(&b.0.order_type)))
+        .then_with(|| {
+            b.0.notional_usd
+                .partial_cmp(&a.0.notional_usd)
+                .unwrap_or(Ordering::Equal)
+        })
+        .then_with(|| a.1.created_at.cmp(&b.1.created_at))
+        .then_with(|| a.0.order_id.cmp(&b.0.order_id))
+}
+
+// Build the execution queue for the orders triggered in one monitor pass
+pub fn build_execution_queue(
+    app_state: &AppState,
+    triggered: Vec<(LimitOrder, f64)>,
+) -> Vec<QueuedOrder> {
+    let now = Utc::now();
+
+    let mut entries = triggered
+        .into_iter()
+        .map(|(order, trigger_price)| {
+            let queued = QueuedOrder {
+                position: 0,
+                order_id: order.id.clone(),
+                order_type: order.order_type.clone(),
+                priority: order.priority,
+                notional_usd: order_notional_usd(app_state, &order),
+                trigger_price,
+                queued_at: now,
+            };
+            (queued, order)
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort_by(compare_queued);
+
+    // Each tenant gets its own execution budget per pass; orders over it stay active for the next pass
+    let mut executions: HashMap<String, usize> = HashMap::new();
+    entries.retain(|(queued, order)| {
+        let tenant = order.api_key.as_deref().unwrap_or(ANONYMOUS_KEY);
+        let budget = tenant::execution_budget(app_state, tenant);
+        let used = executions.entry(tenant.to_string()).or_insert(0);
+        if budget.is_some_and(|budget| *used >= budget) {
+            info!("Deferring order {}: tenant execution budget for this pass is used up", queued.order_id);
+            return false;
+        }
+        *used += 1;
+        true
+    });
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(position, (mut queued, _))| {
+            queued.position = position;
+            queued
+        })
+        .collect()
+}
+
+// Publish the queue so it is visible through the API
+pub fn set_execution_queue(app_state: &AppState, queue: Vec<QueuedOrder>) {
+    let mut execution_queue = app_state.execution_queue.lock().unwrap();
+    *execution_queue = queue;
+}
+
+// Remove an order from the visible queue once it has been handled
+pub fn remove_from_queue(app_state: &AppState, order_id: &str) {
+    let mut execution_queue = app_state.execution_queue.lock().unwrap();
+    execution_queue.retain(|queued| queued.order_id != order_id);
+}
+
+// Snapshot of the current execution queue, limited to the tenant's orders
+pub fn get_execution_queue(app_state: &AppState, tenant: &str) -> Vec<QueuedOrder> {
+    let queue = {
+        let execution_queue = app_state.execution_queue.lock().unwrap();
+        execution_queue.clone()
+    };
+
+    let orders = app_state.limit_orders.lock().unwrap();
+    queue
+        .into_iter()
+        .filter(|queued| orders.get(&queued.order_id).is_some_and(|order| tenant::can_access_order(tenant, order)))
+        .collect()
+}
+
+// Stop all automatic executions and swaps until trading is resumed. Each reason is kept, so
+// trading stays paused until every one of them is lifted.
+pub fn pause_trading(app_state: &AppState, reason: &str) {
+    let mut pause_reasons = app_state.pause_reasons.lock().unwrap();
+    warn!("Trading paused: {}", reason);
+    if !pause_reasons.iter().any(|existing| existing == reason) {
+        pause_reasons.push(reason.to_string());
+    }
+}
+
+// Resume trading after a pause, whatever paused it
+pub fn resume_trading(app_state: &AppState) {
+    let mut pause_reasons = app_state.pause_reasons.lock().unwrap();
+    if !pause_reasons.is_empty() {
+        pause_reasons.clear();
+        info!("Trading resumed");
+    }
+}
+
+// Lift the pauses whose reason starts with the prefix, leaving the others in place
+pub fn lift_pause(app_state: &AppState, reason_prefix: &str) {
+    let mut pause_reasons = app_state.pause_reasons.lock().unwrap();
+    let paused = pause_reasons.len();
+    pause_reasons.retain(|reason| !reason.starts_with(reason_prefix));
+    if pause_reasons.len() == paused {
+        return;
+    }
+    if pause_reasons.is_empty() {
+        info!("Trading resumed");
+    } else {
+        info!("Trading still paused: {}", pause_reasons.join("; "));
+    }
+}
+
+// Why trading is paused, if it is
+pub fn trading_paused(app_state: &AppState) -> Option<String> {
+    let pause_reasons = app_state.pause_reasons.lock().unwrap();
+    (!pause_reasons.is_empty()).then(|| pause_reasons.join("; "))
+}
+
+// Operational status that is safe to publish: counts and ages only
+pub fn public_status(app_state: &AppState) -> PublicStatus {
+    let now = Utc::now();
+    let degraded = app_state.engine_health.lock().unwrap().degraded;
+    let trading_paused = trading_paused(app_state).is_some();
+    let active_orders = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.values().filter(|order| order.status == OrderStatus::Active).count()
+    };
+    let price_update_age_secs = app_state
+        .prices_updated_at
+        .lock()
+        .unwrap()
+        .map(|updated_at| (now - updated_at).num_seconds());
+
+    let status = if degraded {
+        "degraded"
+    } else if trading_paused {
+        "paused"
+    } else {
+        "ok"
+    };
+
+    PublicStatus {
+        status: status.to_string(),
+        uptime_secs: (now - app_state.started_at).num_seconds(),
+        degraded,
+        trading_paused,
+        active_orders,
+        price_update_age_secs,
+        timestamp: now,
+    }
+}
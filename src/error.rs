@@ -0,0 +1,222 @@
+use crate::models::{CreationQuarantined, InvalidTransition, QuotaExceeded, QuotaKind, WalletFrozen};
+use crate::utils::{self, ApiResponse};
+use axum::{
+    body::{Body, BoxBody},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::fmt;
+
+// Error returned by the API, with a machine-readable code clients can branch on and the status it
+// is answered with. The message stays human-readable and may change; the codes do not.
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    WalletNotFound(String),
+    WalletFrozen(String),
+    OrderNotFound(String),
+    InvalidTransition(String),
+    InsufficientBalance(String),
+    InsufficientFees(String),
+    PriceUnavailable(String),
+    QuotaExceeded(String),
+    RateLimited(String),
+    CreationQuarantined(String),
+    UpstreamJupiterError(String),
+    UpstreamRpcError(String),
+    ServiceUnavailable(String),
+    Internal(String),
+}
+
+impl ApiError {
+    // Machine-readable code sent as `code` next to the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::WalletNotFound(_) => "wallet_not_found",
+            ApiError::WalletFrozen(_) => "wallet_frozen",
+            ApiError::OrderNotFound(_) => "order_not_found",
+            ApiError::InvalidTransition(_) => "invalid_transition",
+            ApiError::InsufficientBalance(_) => "insufficient_balance",
+            ApiError::InsufficientFees(_) => "insufficient_fees",
+            ApiError::PriceUnavailable(_) => "price_unavailable",
+            ApiError::QuotaExceeded(_) => "quota_exceeded",
+            ApiError::RateLimited(_) => "rate_limited",
+            ApiError::CreationQuarantined(_) => "order_creation_quarantined",
+            ApiError::UpstreamJupiterError(_) => "upstream_jupiter_error",
+            ApiError::UpstreamRpcError(_) => "upstream_rpc_error",
+            ApiError::ServiceUnavailable(_) => "service_unavailable",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    // Status the error is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::InsufficientBalance(_) | ApiError::InsufficientFees(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) | ApiError::WalletNotFound(_) | ApiError::OrderNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::InvalidTransition(_) | ApiError::WalletFrozen(_) => StatusCode::CONFLICT,
+            ApiError::QuotaExceeded(_) | ApiError::RateLimited(_) | ApiError::CreationQuarantined(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::UpstreamJupiterError(_) | ApiError::UpstreamRpcError(_) => StatusCode::BAD_GATEWAY,
+            ApiError::PriceUnavailable(_) | ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::WalletNotFound(message)
+            | ApiError::WalletFrozen(message)
+            | ApiError::OrderNotFound(message)
+            | ApiError::InvalidTransition(message)
+            | ApiError::InsufficientBalance(message)
+            | ApiError::InsufficientFees(message)
+            | ApiError::PriceUnavailable(message)
+            | ApiError::QuotaExceeded(message)
+            | ApiError::RateLimited(message)
+            | ApiError::CreationQuarantined(message)
+            | ApiError::UpstreamJupiterError(message)
+            | ApiError::UpstreamRpcError(message)
+            | ApiError::ServiceUnavailable(message)
+            | ApiError::Internal(message) => message,
+        }
+    }
+
+    // Error a message describes, for errors only known by their text such as those raised deep in
+    // the swap and wallet code
+    pub fn from_message(message: &str) -> Option<Self> {
+        let lower = message.to_lowercase();
+        let message = message.to_string();
+        let error = if lower.contains("insufficient sol") || lower.contains("transaction fees") {
+            ApiError::InsufficientFees(message)
+        } else if lower.contains("insufficient balance") {
+            ApiError::InsufficientBalance(message)
+        } else if lower.contains("no wallet imported") || (lower.contains("wallet") && lower.contains("not imported")) {
+            ApiError::WalletNotFound(message)
+        } else if lower.starts_with("order not found") {
+            ApiError::OrderNotFound(message)
+        } else if lower.contains("jupiter") {
+            ApiError::UpstreamJupiterError(message)
+        } else if lower.contains("rpc") {
+            ApiError::UpstreamRpcError(message)
+        } else if lower.contains("failed to get price") || lower.contains("price not found") || lower.contains("no price") {
+            ApiError::PriceUnavailable(message)
+        } else {
+            return None;
+        };
+        Some(error)
+    }
+
+    // Error of a response a handler answered with the given status, classified by its message
+    // first so specific causes keep their code
+    pub fn with_status(status: StatusCode, message: &str) -> Self {
+        if let Some(error) = Self::from_message(message) {
+            return error;
+        }
+
+        let message = message.to_string();
+        match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized(message),
+            StatusCode::FORBIDDEN => ApiError::Forbidden(message),
+            StatusCode::NOT_FOUND => ApiError::NotFound(message),
+            StatusCode::CONFLICT => ApiError::InvalidTransition(message),
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited(message),
+            StatusCode::SERVICE_UNAVAILABLE => ApiError::ServiceUnavailable(message),
+            status if status.is_server_error() => ApiError::Internal(message),
+            _ => ApiError::BadRequest(message),
+        }
+    }
+
+    // Response carrying the error's own status and code
+    pub fn into_api_response(self) -> ApiResponse {
+        utils::build_coded_response::<()>(self.status(), None, Some(self.message().to_string()), Some(self.code()))
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Errors of the engine, typed where the engine raises a typed error and classified by message otherwise
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        let message = err.to_string();
+        if let Some(error) = err.downcast_ref::<ApiError>() {
+            return error.clone();
+        }
+        if err.downcast_ref::<InvalidTransition>().is_some() {
+            return ApiError::InvalidTransition(message);
+        }
+        if err.downcast_ref::<WalletFrozen>().is_some() {
+            return ApiError::WalletFrozen(message);
+        }
+        if err.downcast_ref::<CreationQuarantined>().is_some() {
+            return ApiError::CreationQuarantined(message);
+        }
+        if let Some(quota) = err.downcast_ref::<QuotaExceeded>() {
+            return match quota.kind {
+                QuotaKind::RequestsPerMinute => ApiError::RateLimited(message),
+                _ => ApiError::QuotaExceeded(message),
+            };
+        }
+        Self::from_message(&message).unwrap_or(ApiError::BadRequest(message))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        self.into_api_response().into_response()
+    }
+}
+
+// Middleware giving error responses that bypass the handlers, such as axum's plain text
+// rejections of malformed JSON or query strings, the same structured body with a code
+pub async fn structure_errors(request: Request<Body>, next: Next<Body>) -> Response {
+    let format = utils::ResponseFormat::from_headers(request.headers());
+    let response = next.run(request).await;
+
+    let status = response.status();
+    let structured = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/json") || content_type.starts_with(utils::MSGPACK_CONTENT_TYPE))
+        .unwrap_or(false);
+    // WebSocket upgrades answer 101 and success responses are left alone
+    if !(status.is_client_error() || status.is_server_error()) || structured {
+        return response;
+    }
+
+    let body: BoxBody = response.into_body();
+    let text = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).trim().to_string(),
+        Err(err) => err.to_string(),
+    };
+    let message = if text.is_empty() {
+        status.canonical_reason().unwrap_or("Request failed").to_string()
+    } else {
+        text
+    };
+
+    let error = ApiError::with_status(status, &message);
+    utils::build_coded_response::<()>(status, None, Some(message), Some(error.code()))
+        .with_format(format)
+        .into_response()
+}
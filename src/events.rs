@@ -0,0 +1,27 @@
+use crate::models::{AppState, LimitOrder, StreamEvent};
+use chrono::Utc;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+// Send an event to every subscriber; having none is fine
+pub fn publish(app_state: &AppState, event: StreamEvent) {
+    let _ = app_state.events.send(event);
+}
+
+// Announce an order's current state
+pub fn publish_order(app_state: &AppState, order: &LimitOrder) {
+    publish(app_state, StreamEvent::OrderUpdated { order: order.clone() });
+}
+
+// Announce the prices that changed in an update
+pub fn publish_prices(app_state: &AppState, prices: HashMap<String, f64>) {
+    if prices.is_empty() {
+        return;
+    }
+    publish(app_state, StreamEvent::PricesUpdated { prices, updated_at: Utc::now() });
+}
+
+// Receive events published from now on
+pub fn subscribe(app_state: &AppState) -> broadcast::Receiver<StreamEvent> {
+    app_state.events.subscribe()
+}
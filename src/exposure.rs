@@ -0,0 +1,99 @@
+use crate::models::{AppState, ExposureAction, ExposureLimit, LimitOrder, Wallet};
+use crate::price;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+
+// Outcome of checking a buy against the exposure limit of its target token
+#[derive(Debug, PartialEq)]
+pub enum ExposureDecision {
+    Allowed,
+    Downsized(f64),
+    Rejected(String),
+}
+
+// Validate and store an exposure limit, replacing any existing limit for the token
+pub fn set_limit(app_state: &AppState, limit: ExposureLimit) -> Result<ExposureLimit> {
+    if limit.max_portfolio_percent <= 0.0 || limit.max_portfolio_percent > 100.0 {
+        return Err(anyhow!("max_portfolio_percent must be greater than 0 and at most 100"));
+    }
+
+    let mut limits = app_state.exposure_limits.lock().unwrap();
+    limits.insert(limit.mint.clone(), limit.clone());
+
+    info!(
+        "Exposure limit for {} set to {}% of the portfolio ({:?})",
+        KnownTokens::get_symbol(&limit.mint), limit.max_portfolio_percent, limit.action
+    );
+    Ok(limit)
+}
+
+// Remove the exposure limit for a token
+pub fn remove_limit(app_state: &AppState, mint: &str) -> Result<ExposureLimit> {
+    let mut limits = app_state.exposure_limits.lock().unwrap();
+    limits
+        .remove(mint)
+        .ok_or_else(|| anyhow!("No exposure limit for {}", mint))
+}
+
+// All exposure limits
+pub fn get_limits(app_state: &AppState) -> Vec<ExposureLimit> {
+    let limits = app_state.exposure_limits.lock().unwrap();
+    limits.values().cloned().collect()
+}
+
+// Check a buy order against the exposure limit of the token it buys, at current balances and prices
+pub async fn check_buy(
+    app_state: &AppState,
+    owner: &Wallet,
+    order: &LimitOrder,
+    target_price: f64,
+) -> Result<ExposureDecision> {
+    let limit = {
+        let limits = app_state.exposure_limits.lock().unwrap();
+        limits.get(&order.target_token).cloned()
+    };
+
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(ExposureDecision::Allowed),
+    };
+
+    let balances = wallet::get_token_balances(&app_state.rpc_client, owner).await?;
+
+    // Tokens without a price count as worthless, which keeps the cap conservative
+    let portfolio_value: f64 = balances
+        .iter()
+        .map(|balance| balance.amount * price::get_token_price(app_state, &balance.mint).unwrap_or(0.0))
+        .sum();
+    let current_value = balances
+        .iter()
+        .find(|balance| balance.mint == order.target_token)
+        .map(|balance| balance.amount * target_price)
+        .unwrap_or(0.0);
+
+    let max_value = portfolio_value * limit.max_portfolio_percent / 100.0;
+    let order_value = order.amount * target_price;
+
+    if current_value + order_value <= max_value {
+        return Ok(ExposureDecision::Allowed);
+    }
+
+    let headroom = (max_value - current_value).max(0.0);
+    let reason = format!(
+        "Exposure limit of {}% for {} would be exceeded: holding ${:.2}, buying ${:.2}, cap ${:.2}",
+        limit.max_portfolio_percent,
+        KnownTokens::get_symbol(&order.target_token),
+        current_value,
+        order_value,
+        max_value
+    );
+    warn!("Order {}: {}", order.id, reason);
+
+    match limit.action {
+        ExposureAction::Downsize if headroom > 0.0 && target_price > 0.0 => {
+            Ok(ExposureDecision::Downsized(headroom / target_price))
+        }
+        _ => Ok(ExposureDecision::Rejected(reason)),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::models::{AppState, LimitOrder, NotificationEvent, OrderStatus, WalletFreeze, WalletFrozen};
+use crate::notify;
+use crate::orders;
+use crate::tenant;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+// Reason recorded on orders the freeze cancels
+const FROZEN_REASON: &str = "Wallet frozen";
+
+// Tenants may freeze their own wallets, the admin any of them. The keypair does not have to be
+// unlocked, a wallet whose key leaked may well be locked.
+fn check_access(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<()> {
+    Pubkey::from_str(pubkey).map_err(|_| anyhow!("Invalid wallet public key: {}", pubkey))?;
+    if !tenant::is_admin(tenant) && !tenant::can_use_wallet(app_state, tenant, pubkey) {
+        // Another tenant's wallet is reported exactly like one that does not exist
+        return Err(anyhow!("Wallet {} is not imported", pubkey));
+    }
+    Ok(())
+}
+
+// Freeze of a wallet, if it is frozen
+pub fn frozen(app_state: &AppState, pubkey: &str) -> Option<WalletFreeze> {
+    app_state.frozen_wallets.lock().unwrap().get(pubkey).cloned()
+}
+
+pub fn is_frozen(app_state: &AppState, pubkey: &str) -> bool {
+    app_state.frozen_wallets.lock().unwrap().contains_key(pubkey)
+}
+
+// Suspend a wallet's automatic executions, cancel its orders waiting to retry and refuse new
+// orders for it. Freezing a frozen wallet returns the freeze in force.
+pub async fn freeze_wallet(app_state: &AppState, tenant: &str, pubkey: &str, reason: Option<String>) -> Result<WalletFreeze> {
+    check_access(app_state, tenant, pubkey)?;
+
+    // Recorded before anything else, so no pass starts executing the wallet's orders meanwhile
+    {
+        let mut frozen_wallets = app_state.frozen_wallets.lock().unwrap();
+        if let Some(freeze) = frozen_wallets.get(pubkey) {
+            return Ok(freeze.clone());
+        }
+        let freeze = WalletFreeze {
+            pubkey: pubkey.to_string(),
+            reason: reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty()),
+            frozen_at: Utc::now(),
+            cancelled_retries: Vec::new(),
+        };
+        frozen_wallets.insert(pubkey.to_string(), freeze);
+    }
+
+    let cancelled_retries = cancel_retries(app_state, pubkey);
+    let freeze = {
+        let mut frozen_wallets = app_state.frozen_wallets.lock().unwrap();
+        let freeze = frozen_wallets.get_mut(pubkey).ok_or_else(|| anyhow!("Wallet {} was unfrozen meanwhile", pubkey))?;
+        freeze.cancelled_retries = cancelled_retries;
+        freeze.clone()
+    };
+
+    let message = format!(
+        "Wallet {} frozen{}: automatic executions suspended, {} pending retries cancelled",
+        pubkey,
+        freeze.reason.as_deref().map(|reason| format!(" ({})", reason)).unwrap_or_default(),
+        freeze.cancelled_retries.len()
+    );
+    warn!("{}", message);
+    notify::notify(app_state, NotificationEvent::WalletFrozen, message).await;
+
+    Ok(freeze)
+}
+
+// Let a frozen wallet's orders execute again. Orders whose trigger is still met execute on the
+// next pass.
+pub fn unfreeze_wallet(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<WalletFreeze> {
+    check_access(app_state, tenant, pubkey)?;
+
+    let freeze = app_state
+        .frozen_wallets
+        .lock()
+        .unwrap()
+        .remove(pubkey)
+        .ok_or_else(|| anyhow!("Wallet {} is not frozen", pubkey))?;
+    info!("Wallet {} unfrozen after being frozen since {}", pubkey, freeze.frozen_at);
+    Ok(freeze)
+}
+
+// Cancel the wallet's orders waiting to retry a failed execution
+fn cancel_retries(app_state: &AppState, pubkey: &str) -> Vec<String> {
+    let retrying = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.wallet_pubkey == pubkey && order.status == OrderStatus::Retrying)
+            .map(|order| order.id.clone())
+            .collect::<Vec<_>>()
+    };
+
+    retrying
+        .into_iter()
+        .filter(|order_id| match orders::transition_order(app_state, order_id, OrderStatus::Cancelled, Some(FROZEN_REASON.to_string())) {
+            Ok(_) => true,
+            Err(err) => {
+                error!("Failed to cancel retry of order {}: {}", order_id, err);
+                false
+            }
+        })
+        .collect()
+}
+
+// Cancel an order that came up for a retry after its wallet was frozen, such as one whose
+// execution was already underway when the freeze came in. Returns whether it was cancelled.
+pub fn cancel_frozen_retry(app_state: &AppState, order: &LimitOrder) -> bool {
+    if !is_frozen(app_state, &order.wallet_pubkey) {
+        return false;
+    }
+    if let Err(err) = orders::transition_order(app_state, &order.id, OrderStatus::Cancelled, Some(FROZEN_REASON.to_string())) {
+        error!("Failed to cancel retry of order {}: {}", order.id, err);
+    }
+    true
+}
+
+// Refuse a new order for a frozen wallet
+pub fn check_order(app_state: &AppState, pubkey: &str) -> Result<(), WalletFrozen> {
+    match frozen(app_state, pubkey) {
+        Some(freeze) => Err(WalletFrozen {
+            pubkey: freeze.pubkey,
+            frozen_at: freeze.frozen_at,
+        }),
+        None => Ok(()),
+    }
+}
+
+// Hold back triggered orders of frozen wallets, leaving them active
+pub fn suppress_triggers(app_state: &AppState, triggered: Vec<(LimitOrder, f64)>) -> Vec<(LimitOrder, f64)> {
+    let (held, allowed): (Vec<_>, Vec<_>) = triggered
+        .into_iter()
+        .partition(|(order, _)| is_frozen(app_state, &order.wallet_pubkey));
+
+    if !held.is_empty() {
+        info!("Holding back {} triggered orders of frozen wallets", held.len());
+    }
+    allowed
+}
@@ -0,0 +1,247 @@
+use crate::activity;
+use crate::models::{AppState, TokenChange, TransactionKind, TransactionStatus, TransactionsQuery, WalletTransaction};
+use crate::wallet::{self, KnownTokens};
+use crate::utils;
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+use futures_util::StreamExt;
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction, UiTransactionEncoding,
+};
+use std::str::FromStr;
+use tracing::warn;
+
+// Transactions returned per page unless the caller asks for fewer or more, up to the maximum
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_HISTORY_LIMIT: usize = 100;
+
+// Transactions fetched from the RPC node at once
+const FETCH_CONCURRENCY: usize = 8;
+
+// Programs swaps go through
+const JUPITER_V6_PROGRAM: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+const JUPITER_LIMIT_ORDER_PROGRAM: &str = "jupoNjAxXgZ4rjzxzPMP4oxduvQsQtZzyknqvzYNrNu";
+
+// Programs that take part in plain transfers without making them anything else
+const COMPUTE_BUDGET_PROGRAM: &str = "ComputeBudget111111111111111111111111111111";
+const MEMO_PROGRAMS: &[&str] = &["MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"];
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+// Balance changes smaller than this are rounding noise
+const MIN_CHANGE: f64 = 1e-9;
+
+// Name of a well known program
+fn program_name(program_id: &str) -> Option<&'static str> {
+    let name = if program_id == solana_sdk::system_program::id().to_string() {
+        "system"
+    } else if program_id == spl_token::id().to_string() || program_id == TOKEN_2022_PROGRAM {
+        "token"
+    } else if program_id == spl_associated_token_account::id().to_string() {
+        "associated_token_account"
+    } else if program_id == COMPUTE_BUDGET_PROGRAM {
+        "compute_budget"
+    } else if MEMO_PROGRAMS.contains(&program_id) {
+        "memo"
+    } else if program_id == JUPITER_V6_PROGRAM {
+        "jupiter"
+    } else if program_id == JUPITER_LIMIT_ORDER_PROGRAM {
+        "jupiter_limit_order"
+    } else {
+        return None;
+    };
+    Some(name)
+}
+
+// Programs a transaction called, its own instructions first and then the ones they invoked
+fn invoked_programs(transaction: &EncodedConfirmedTransactionWithStatusMeta, account_keys: &[String]) -> Result<Vec<String>> {
+    let message = activity::raw_message(transaction)?;
+    let inner: Option<Vec<UiInnerInstructions>> = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.inner_instructions.clone().into());
+
+    let top_level = message.instructions.iter().map(|instruction| instruction.program_id_index);
+    let invoked = inner
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|inner| inner.instructions)
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Compiled(instruction) => Some(instruction.program_id_index),
+            UiInstruction::Parsed(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut programs = Vec::new();
+    for index in top_level.chain(invoked) {
+        if let Some(program_id) = account_keys.get(index as usize) {
+            if !programs.contains(program_id) {
+                programs.push(program_id.clone());
+            }
+        }
+    }
+    Ok(programs)
+}
+
+// Kind of a transaction from the programs it called and how the wallet's balances moved. Moves in
+// one direction are transfers only when nothing but transfer programs took part.
+fn classify(programs: &[String], changes: &[TokenChange]) -> TransactionKind {
+    let names = programs.iter().map(|program| program_name(program)).collect::<Vec<_>>();
+    if names.iter().any(|name| matches!(name, Some("jupiter") | Some("jupiter_limit_order"))) {
+        return TransactionKind::Swap;
+    }
+
+    let gained = changes.iter().any(|change| change.amount > 0.0);
+    let lost = changes.iter().any(|change| change.amount < 0.0);
+    let transfer_only = names
+        .iter()
+        .all(|name| matches!(name, Some("system") | Some("token") | Some("associated_token_account") | Some("compute_budget") | Some("memo")));
+
+    match (gained, lost) {
+        (true, true) => TransactionKind::Swap,
+        (true, false) if transfer_only => TransactionKind::Deposit,
+        (false, true) if transfer_only => TransactionKind::Withdrawal,
+        _ => TransactionKind::Other,
+    }
+}
+
+// Normalize a fetched transaction as seen from the wallet
+fn normalize(
+    app_state: &AppState,
+    pubkey: &str,
+    status: &RpcConfirmedTransactionStatusWithSignature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<WalletTransaction> {
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow!("transaction has no status meta"))?;
+    let account_keys = activity::account_keys(transaction)?;
+    let programs = invoked_programs(transaction, &account_keys)?;
+
+    let mut changes = activity::balance_changes(transaction)?
+        .into_iter()
+        .filter_map(|(mint, by_owner)| {
+            let amount = by_owner.get(pubkey).copied().unwrap_or(0.0);
+            (amount.abs() > MIN_CHANGE).then(|| TokenChange {
+                symbol: KnownTokens::get_symbol(&mint),
+                mint,
+                amount,
+            })
+        })
+        .collect::<Vec<_>>();
+    // Outgoing first, then by token
+    changes.sort_by(|a, b| (a.amount > 0.0, &a.symbol).cmp(&(b.amount > 0.0, &b.symbol)));
+
+    // Only the fee payer, the first account, pays the network fee
+    let fee_sol = if account_keys.first().map(String::as_str) == Some(pubkey) {
+        utils::lamports_to_sol(meta.fee)
+    } else {
+        0.0
+    };
+
+    Ok(WalletTransaction {
+        kind: classify(&programs, &changes),
+        programs: programs
+            .iter()
+            .map(|program| program_name(program).map(str::to_string).unwrap_or_else(|| program.clone()))
+            .collect(),
+        changes,
+        fee_sol,
+        ..summary(app_state, status)
+    })
+}
+
+// What the signature list tells about a transaction, for one whose details could not be fetched
+fn summary(app_state: &AppState, status: &RpcConfirmedTransactionStatusWithSignature) -> WalletTransaction {
+    let via_api = app_state
+        .ledger
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|entry| entry.transaction_signature == status.signature);
+
+    WalletTransaction {
+        signature: status.signature.clone(),
+        slot: status.slot,
+        timestamp: status.block_time.and_then(|block_time| Utc.timestamp_opt(block_time, 0).single()),
+        kind: TransactionKind::Other,
+        status: if status.err.is_some() {
+            TransactionStatus::Failed
+        } else {
+            TransactionStatus::Success
+        },
+        error: status.err.as_ref().map(|err| err.to_string()),
+        fee_sol: 0.0,
+        changes: Vec::new(),
+        programs: Vec::new(),
+        via_api,
+    }
+}
+
+async fn fetch_transaction(app_state: &AppState, signature: &str) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    Ok(app_state
+        .rpc_client
+        .get_transaction_with_config(&Signature::from_str(signature)?, config)
+        .await?)
+}
+
+// Recent on-chain transactions of a wallet the tenant may use, newest first, including those made
+// outside this API. Pages continue from the `before` signature.
+pub async fn get_transactions(app_state: &AppState, tenant: &str, query: &TransactionsQuery) -> Result<Vec<WalletTransaction>> {
+    let address = wallet::find_tenant_wallet(app_state, tenant, query.wallet.as_deref())?.pubkey;
+    let pubkey = address.to_string();
+    let before = query
+        .before
+        .as_deref()
+        .map(|before| Signature::from_str(before).map_err(|_| anyhow!("Invalid signature: {}", before)))
+        .transpose()?;
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+    let statuses = app_state
+        .rpc_client
+        .get_signatures_for_address_with_config(
+            &address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await
+        .map_err(|err| anyhow!("RPC error listing signatures of {}: {}", pubkey, err))?;
+
+    let transactions = futures_util::stream::iter(statuses)
+        .map(|status| {
+            let pubkey = pubkey.clone();
+            async move {
+                let normalized = fetch_transaction(app_state, &status.signature)
+                    .await
+                    .and_then(|transaction| normalize(app_state, &pubkey, &status, &transaction));
+                match normalized {
+                    Ok(transaction) => transaction,
+                    Err(err) => {
+                        warn!("Failed to load transaction {} of wallet {}: {}", status.signature, pubkey, err);
+                        summary(app_state, &status)
+                    }
+                }
+            }
+        })
+        .buffered(FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    Ok(transactions)
+}
@@ -0,0 +1,101 @@
+use crate::engine;
+use crate::keystore;
+use crate::models::{AppState, IntegrityCheck, IntegrityReport};
+use crate::tenant;
+#[cfg(feature = "server")]
+use crate::utils;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "server")]
+use axum::{
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use tracing::{error, info};
+
+// Reason trading stays paused while the server runs in safe mode
+const SAFE_MODE_REASON: &str = "Safe mode: the startup integrity check failed, see /integrity";
+
+fn check(name: &str, problems: Vec<String>) -> IntegrityCheck {
+    IntegrityCheck {
+        name: name.to_string(),
+        passed: problems.is_empty(),
+        problems,
+    }
+}
+
+// Stored wallets can be decrypted. Orders and the rest of the state live in memory and start
+// empty, so the keystore is the only state a restart carries over.
+fn check_keystore() -> IntegrityCheck {
+    let mut problems = keystore::verify_keystore();
+
+    // Without the master key, files stored in hosted mode were sealed with a key that is gone
+    if tenant::hosted_mode() && !tenant::master_key_configured() && keystore::has_stored_wallets() {
+        problems.push("TENANT_MASTER_KEY is not set, so the keystore cannot be decrypted with the ephemeral key".to_string());
+    }
+
+    check("keystore", problems)
+}
+
+// Verify the state the server starts with
+pub fn check_state() -> IntegrityReport {
+    let checks = vec![check_keystore()];
+    IntegrityReport {
+        safe_mode: checks.iter().any(|check| !check.passed),
+        checks,
+        checked_at: Utc::now(),
+    }
+}
+
+// Check the state at startup, entering safe mode when it fails: trading is paused, the background
+// tasks are not started and only reads are served. Returns whether the server is in safe mode.
+pub fn run_startup_check(app_state: &AppState) -> bool {
+    let report = check_state();
+    let safe_mode = report.safe_mode;
+
+    if safe_mode {
+        for check in report.checks.iter().filter(|check| !check.passed) {
+            for problem in &check.problems {
+                error!("Integrity check {} failed: {}", check.name, problem);
+            }
+        }
+        error!("Booting in read-only safe mode, fix the state and restart");
+        engine::pause_trading(app_state, SAFE_MODE_REASON);
+    } else {
+        info!("Startup integrity check passed");
+    }
+
+    *app_state.integrity_report.lock().unwrap() = Some(report);
+    safe_mode
+}
+
+// Whether the server booted in safe mode
+pub fn safe_mode(app_state: &AppState) -> bool {
+    matches!(app_state.integrity_report.lock().unwrap().as_ref(), Some(report) if report.safe_mode)
+}
+
+// Refuse changes while in safe mode
+pub fn ensure_writable(app_state: &AppState) -> Result<()> {
+    if safe_mode(app_state) {
+        return Err(anyhow!("{}", SAFE_MODE_REASON));
+    }
+    Ok(())
+}
+
+// Middleware serving only reads while in safe mode, so nothing acts on the suspect state
+#[cfg(feature = "server")]
+pub async fn enforce_safe_mode<B>(request: Request<B>, next: Next<B>) -> Response {
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if !read_only {
+        if let Some(app_state) = request.extensions().get::<Arc<AppState>>() {
+            if let Err(err) = ensure_writable(app_state) {
+                return utils::build_error_response(StatusCode::SERVICE_UNAVAILABLE, &err.to_string()).into_response();
+            }
+        }
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,295 @@
+use crate::engine;
+use crate::models::{AppState, Anomaly, AnomalyKind, DcaStatus, JanitorReport, NotificationEvent, OrderFailure, OrderStatus, ReconciledAnomaly};
+use crate::notify;
+use crate::orders;
+use crate::price;
+use crate::wallet::KnownTokens;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::time;
+use tracing::{error, info, warn};
+
+// How often the janitor scans for stale or orphaned state
+const JANITOR_INTERVAL_ENV: &str = "JANITOR_INTERVAL_SECS";
+const DEFAULT_JANITOR_INTERVAL_SECS: u64 = 900;
+
+// Hours an active order's token may go without a price before it is reported
+const STALE_PRICE_HOURS_ENV: &str = "JANITOR_STALE_PRICE_HOURS";
+const DEFAULT_STALE_PRICE_HOURS: i64 = 6;
+
+// Seconds an order may stay Executing, well past the swap's confirmation timeout and blockhash refreshes
+const EXECUTING_TIMEOUT_ENV: &str = "JANITOR_EXECUTING_TIMEOUT_SECS";
+const DEFAULT_EXECUTING_TIMEOUT_SECS: i64 = 900;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+// Open limit and DCA orders whose wallet is no longer imported
+fn orphaned_wallet_orders(app_state: &AppState) -> Vec<Anomaly> {
+    let wallets = app_state.wallets.lock().unwrap().keys().cloned().collect::<HashSet<_>>();
+    let mut anomalies = Vec::new();
+
+    let orders = app_state.limit_orders.lock().unwrap();
+    for order in orders.values() {
+        if matches!(order.status, OrderStatus::Active | OrderStatus::PendingFunds | OrderStatus::Retrying) && !wallets.contains(&order.wallet_pubkey) {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::OrphanedWalletOrder,
+                order_id: Some(order.id.clone()),
+                wallet: Some(order.wallet_pubkey.clone()),
+                mint: None,
+                detail: format!("{:?} {} order trades from wallet {}, which is no longer imported", order.status, order.order_type, order.wallet_pubkey),
+                since: None,
+            });
+        }
+    }
+
+    let dca_orders = app_state.dca_orders.lock().unwrap();
+    for order in dca_orders.values() {
+        if order.status == DcaStatus::Active && !wallets.contains(&order.wallet_pubkey) {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::OrphanedWalletOrder,
+                order_id: Some(order.id.clone()),
+                wallet: Some(order.wallet_pubkey.clone()),
+                mint: None,
+                detail: format!("DCA order buys from wallet {}, which is no longer imported", order.wallet_pubkey),
+                since: None,
+            });
+        }
+    }
+    anomalies
+}
+
+// Tokens of active orders without a price for longer than the threshold. A token never priced is
+// counted from the creation of the oldest order on it.
+fn stale_prices(app_state: &AppState, now: DateTime<Utc>) -> Vec<Anomaly> {
+    let threshold = Duration::hours(env_or(STALE_PRICE_HOURS_ENV, DEFAULT_STALE_PRICE_HOURS));
+
+    // Each token with the orders on it and the oldest of their creation times
+    let mut mints: BTreeMap<String, (Vec<String>, DateTime<Utc>)> = BTreeMap::new();
+    {
+        let orders = app_state.limit_orders.lock().unwrap();
+        for order in orders.values().filter(|order| order.status == OrderStatus::Active) {
+            let mut order_mints = vec![order.source_token.as_str(), order.target_token.as_str(), order.trigger_mint()];
+            order_mints.extend(order.quote_mint.as_deref());
+            order_mints.sort_unstable();
+            order_mints.dedup();
+            for mint in order_mints {
+                let entry = mints.entry(mint.to_string()).or_insert_with(|| (Vec::new(), order.created_at));
+                entry.0.push(order.id.clone());
+                entry.1 = entry.1.min(order.created_at);
+            }
+        }
+    }
+
+    let price_meta = app_state.price_meta.lock().unwrap();
+    mints
+        .into_iter()
+        .filter_map(|(mint, (order_ids, oldest_order))| {
+            let last_priced = price_meta.get(&mint).map(|meta| meta.updated_at);
+            let since = last_priced.unwrap_or(oldest_order);
+            if now - since <= threshold {
+                return None;
+            }
+            let detail = match last_priced {
+                Some(updated_at) => format!(
+                    "{} active orders trade {}, last priced at {}",
+                    order_ids.len(), KnownTokens::get_symbol(&mint), updated_at
+                ),
+                None => format!("{} active orders trade {}, which has never been priced", order_ids.len(), KnownTokens::get_symbol(&mint)),
+            };
+            Some(Anomaly {
+                kind: AnomalyKind::StalePrice,
+                order_id: None,
+                wallet: None,
+                mint: Some(mint),
+                detail,
+                since: Some(since),
+            })
+        })
+        .collect()
+}
+
+// Orders held in Executing past the timeout, whose execution task likely died
+fn stuck_executions(app_state: &AppState, now: DateTime<Utc>) -> Vec<Anomaly> {
+    let timeout = Duration::seconds(env_or(EXECUTING_TIMEOUT_ENV, DEFAULT_EXECUTING_TIMEOUT_SECS));
+    let orders = app_state.limit_orders.lock().unwrap();
+    orders
+        .values()
+        .filter(|order| order.status == OrderStatus::Executing && now - order.updated_at > timeout)
+        .map(|order| Anomaly {
+            kind: AnomalyKind::StuckExecuting,
+            order_id: Some(order.id.clone()),
+            wallet: Some(order.wallet_pubkey.clone()),
+            mint: None,
+            detail: format!("Executing since {}", order.updated_at),
+            since: Some(order.updated_at),
+        })
+        .collect()
+}
+
+// Execution queue entries whose order is gone or no longer waiting to execute
+fn orphaned_queue_entries(app_state: &AppState) -> Vec<Anomaly> {
+    let queue = app_state.execution_queue.lock().unwrap().clone();
+    let orders = app_state.limit_orders.lock().unwrap();
+    queue
+        .into_iter()
+        .filter_map(|queued| {
+            let detail = match orders.get(&queued.order_id) {
+                None => "Queued order no longer exists".to_string(),
+                Some(order) if order.status.is_terminal() || order.status == OrderStatus::Failed => {
+                    format!("Queued order is already {:?}", order.status)
+                }
+                Some(_) => return None,
+            };
+            Some(Anomaly {
+                kind: AnomalyKind::OrphanedQueueEntry,
+                order_id: Some(queued.order_id),
+                wallet: None,
+                mint: None,
+                detail,
+                since: Some(queued.queued_at),
+            })
+        })
+        .collect()
+}
+
+// Look for stale or orphaned state, keeping the report for the admin
+pub fn scan(app_state: &AppState) -> JanitorReport {
+    let now = Utc::now();
+    let mut anomalies = orphaned_wallet_orders(app_state);
+    anomalies.extend(stale_prices(app_state, now));
+    anomalies.extend(stuck_executions(app_state, now));
+    anomalies.extend(orphaned_queue_entries(app_state));
+
+    let report = JanitorReport {
+        anomalies,
+        generated_at: now,
+    };
+    *app_state.janitor_report.lock().unwrap() = Some(report.clone());
+    report
+}
+
+// Fix one anomaly as far as it can be fixed without guessing. Prices were refreshed at `refreshed_at`.
+fn reconcile_anomaly(app_state: &AppState, anomaly: &Anomaly, refreshed_at: DateTime<Utc>) -> Result<(bool, String)> {
+    let order_id = anomaly.order_id.as_deref().unwrap_or_default();
+    match anomaly.kind {
+        AnomalyKind::OrphanedWalletOrder => {
+            let is_dca = app_state.dca_orders.lock().unwrap().contains_key(order_id);
+            if is_dca {
+                let mut dca_orders = app_state.dca_orders.lock().unwrap();
+                if let Some(order) = dca_orders.get_mut(order_id) {
+                    order.status = DcaStatus::Cancelled;
+                    order.updated_at = Utc::now();
+                }
+            } else {
+                orders::transition_order(app_state, order_id, OrderStatus::Cancelled, Some("Wallet no longer imported".to_string()))?;
+            }
+            Ok((true, "Cancelled the order".to_string()))
+        }
+        AnomalyKind::StalePrice => {
+            let mint = anomaly.mint.as_deref().unwrap_or_default();
+            let fresh = matches!(app_state.price_meta.lock().unwrap().get(mint), Some(meta) if meta.updated_at >= refreshed_at);
+            if fresh {
+                Ok((true, "Refreshed the price".to_string()))
+            } else {
+                Ok((false, "Still no price after a refresh, check the token's price sources".to_string()))
+            }
+        }
+        AnomalyKind::StuckExecuting => {
+            // A swap may have been sent, so the order fails for review instead of going back to Active
+            let reason = format!(
+                "Stuck executing since {}, check the wallet's transactions before retrying",
+                anomaly.since.map_or("unknown".to_string(), |since| since.to_string())
+            );
+            orders::transition_order(app_state, order_id, OrderStatus::Failed, Some(reason.clone()))?;
+            if let Some(order) = app_state.limit_orders.lock().unwrap().get_mut(order_id) {
+                order.failure = Some(OrderFailure::new(&reason));
+            }
+            engine::remove_from_queue(app_state, order_id);
+            Ok((true, "Failed the order for review".to_string()))
+        }
+        AnomalyKind::OrphanedQueueEntry => {
+            engine::remove_from_queue(app_state, order_id);
+            Ok((true, "Removed the queue entry".to_string()))
+        }
+    }
+}
+
+// Scan and fix what can be fixed: cancel orphaned orders, refresh stale prices, fail stuck
+// executions for review and drop orphaned queue entries
+pub async fn reconcile(app_state: &Arc<AppState>) -> Vec<ReconciledAnomaly> {
+    let report = scan(app_state);
+    let mut reconciled = Vec::new();
+
+    // One price refresh covers every stale token
+    let refreshed_at = Utc::now();
+    if report.anomalies.iter().any(|anomaly| anomaly.kind == AnomalyKind::StalePrice) {
+        if let Err(err) = price::update_prices(app_state.clone()).await {
+            warn!("Failed to refresh prices while reconciling: {}", err);
+        }
+    }
+
+    for anomaly in report.anomalies {
+        let (resolved, action) = reconcile_anomaly(app_state, &anomaly, refreshed_at)
+            .unwrap_or_else(|err| (false, format!("Failed to reconcile: {}", err)));
+        info!("Reconciled {:?} anomaly ({}): {}", anomaly.kind, anomaly.detail, action);
+        reconciled.push(ReconciledAnomaly {
+            anomaly,
+            resolved,
+            action,
+        });
+    }
+
+    scan(app_state);
+    reconciled
+}
+
+// Identity of an anomaly across scans
+fn anomaly_key(anomaly: &Anomaly) -> (AnomalyKind, Option<String>, Option<String>) {
+    (anomaly.kind, anomaly.order_id.clone(), anomaly.mint.clone())
+}
+
+// Background task scanning for stale or orphaned state, notifying about anomalies not seen before
+pub async fn run_janitor(app_state: Arc<AppState>) {
+    let interval = env_or(JANITOR_INTERVAL_ENV, DEFAULT_JANITOR_INTERVAL_SECS);
+    info!("Starting janitor task, scanning every {} seconds", interval);
+
+    loop {
+        time::sleep(time::Duration::from_secs(interval)).await;
+
+        let previous = app_state
+            .janitor_report
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|report| report.anomalies.iter().map(anomaly_key).collect::<HashSet<_>>())
+            .unwrap_or_default();
+
+        let report = scan(&app_state);
+        let new = report
+            .anomalies
+            .iter()
+            .filter(|anomaly| !previous.contains(&anomaly_key(anomaly)))
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            continue;
+        }
+
+        for anomaly in &new {
+            warn!("Janitor found {:?}: {}", anomaly.kind, anomaly.detail);
+        }
+        let message = format!(
+            "Janitor found {} new anomalies ({} in total): {}",
+            new.len(),
+            report.anomalies.len(),
+            new.iter().map(|anomaly| anomaly.detail.as_str()).collect::<Vec<_>>().join("; ")
+        );
+        notify::notify(&app_state, NotificationEvent::StaleState, message).await;
+        if report.anomalies.iter().any(|anomaly| anomaly.kind == AnomalyKind::StuckExecuting) {
+            error!("Orders are stuck executing, reconcile them from /janitor/reconcile");
+        }
+    }
+}
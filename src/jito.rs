@@ -0,0 +1,98 @@
+use crate::models::Wallet;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::str::FromStr;
+use tracing::info;
+
+// Block engine bundles are sent to, and the tip account paid to include them
+const JITO_BUNDLE_URL_ENV: &str = "JITO_BUNDLE_URL";
+const DEFAULT_JITO_BUNDLE_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/bundles";
+const JITO_TIP_ACCOUNT_ENV: &str = "JITO_TIP_ACCOUNT";
+const DEFAULT_JITO_TIP_ACCOUNT: &str = "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5";
+
+// Tip paid with every bundle, in lamports; the block engine rejects bundles tipping under 1000
+const JITO_TIP_LAMPORTS_ENV: &str = "JITO_TIP_LAMPORTS";
+const DEFAULT_JITO_TIP_LAMPORTS: u64 = 10_000;
+
+// Block engine JSON-RPC response
+#[derive(Deserialize, Debug)]
+struct BundleResponse {
+    result: Option<String>,
+    error: Option<serde_json::Value>,
+}
+
+fn bundle_url() -> String {
+    std::env::var(JITO_BUNDLE_URL_ENV).unwrap_or_else(|_| DEFAULT_JITO_BUNDLE_URL.to_string())
+}
+
+fn tip_account() -> Result<Pubkey> {
+    let account = std::env::var(JITO_TIP_ACCOUNT_ENV).unwrap_or_else(|_| DEFAULT_JITO_TIP_ACCOUNT.to_string());
+    Pubkey::from_str(&account).map_err(|_| anyhow!("Invalid {}: {}", JITO_TIP_ACCOUNT_ENV, account))
+}
+
+// Tip paid with every bundle, in lamports
+pub fn tip_lamports() -> u64 {
+    std::env::var(JITO_TIP_LAMPORTS_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_JITO_TIP_LAMPORTS)
+}
+
+// Transfer of the tip from the wallet to the tip account, signed with the swap's blockhash so both
+// expire together
+fn tip_transaction(wallet: &Wallet, blockhash: Hash) -> Result<VersionedTransaction> {
+    let transfer = system_instruction::transfer(&wallet.pubkey, &tip_account()?, tip_lamports());
+    let transaction = Transaction::new_signed_with_payer(&[transfer], Some(&wallet.pubkey), &[&wallet.keypair], blockhash);
+    Ok(VersionedTransaction::from(transaction))
+}
+
+// Send a signed transaction to the block engine as a bundle with a tip, so it lands atomically
+// without passing through the public mempool where it could be sandwiched. Returns the bundle id.
+pub async fn send_bundle(wallet: &Wallet, transaction: &VersionedTransaction) -> Result<String> {
+    let tip = tip_transaction(wallet, *transaction.message.recent_blockhash())?;
+    let encoded = [transaction, &tip]
+        .iter()
+        .map(|transaction| Ok(bs58::encode(bincode::serialize(transaction)?).into_string()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "sendBundle",
+        "params": [encoded],
+    });
+
+    let response = Client::new()
+        .post(bundle_url())
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to send bundle to the Jito block engine: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_else(|_| "Unable to get error details".to_string());
+        return Err(anyhow!("Jito block engine returned error status {}: {}", status, error_text));
+    }
+
+    let bundle = response
+        .json::<BundleResponse>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse Jito bundle response: {}", e))?;
+
+    match (bundle.result, bundle.error) {
+        (Some(bundle_id), None) => {
+            info!("Jito accepted bundle {} with a {} lamport tip", bundle_id, tip_lamports());
+            Ok(bundle_id)
+        }
+        (_, Some(error)) => Err(anyhow!("Jito block engine rejected the bundle: {}", error)),
+        (None, None) => Err(anyhow!("Jito block engine returned no bundle id")),
+    }
+}
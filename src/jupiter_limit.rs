@@ -0,0 +1,406 @@
+use crate::models::{AppState, ExecutionReport, LimitOrder, LimitOrderRequest, OrderAction, OrderStatus, OrderType, PassiveOrder, TriggerSource, Wallet};
+use crate::orders;
+use crate::price;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use solana_sdk::{
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::time;
+use tracing::{error, info, warn};
+
+// Jupiter limit order API, which builds the program's transactions and indexes its orders
+const JUPITER_LIMIT_API_URL: &str = "https://jup.ag/api/limit/v1";
+
+// How often fills of passive orders are synced back from the program
+const PASSIVE_SYNC_INTERVAL_SECS: u64 = 30;
+
+// Unsigned transaction creating an order, and the account the order lives in
+#[derive(Deserialize, Debug)]
+struct CreateOrderResponse {
+    tx: String,
+    #[serde(rename = "orderPubkey")]
+    order_pubkey: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct CancelOrdersResponse {
+    tx: String,
+}
+
+// Order still resting on the program. The account's amounts are what is left of the original
+// amounts, under the names the API has used for them over time.
+#[derive(Deserialize, Debug)]
+struct OpenOrder {
+    #[serde(rename = "publicKey")]
+    public_key: String,
+    account: serde_json::Map<String, serde_json::Value>,
+}
+
+// Order that left the program, filled or cancelled
+#[derive(Deserialize, Debug)]
+struct OrderHistoryEntry {
+    #[serde(rename = "orderKey")]
+    order_key: String,
+    state: String,
+}
+
+// Fill of an order by a taker
+#[derive(Deserialize, Debug)]
+struct TradeHistoryEntry {
+    #[serde(rename = "txId", alias = "txid")]
+    tx_id: String,
+    order: TradeHistoryOrder,
+}
+
+#[derive(Deserialize, Debug)]
+struct TradeHistoryOrder {
+    #[serde(rename = "orderKey")]
+    order_key: String,
+}
+
+// First of the fields holding a raw amount, sent as a string or a number
+fn raw_amount(account: &serde_json::Map<String, serde_json::Value>, fields: &[&str]) -> Option<u64> {
+    fields.iter().find_map(|field| match account.get(*field)? {
+        serde_json::Value::String(amount) => amount.parse::<u64>().ok(),
+        amount => amount.as_u64(),
+    })
+}
+
+// Check that an order can rest on the program: a plain Buy or Sell swap between the two tokens it trades
+pub fn validate(request: &LimitOrderRequest) -> Result<()> {
+    if !matches!(request.order_type, OrderType::Buy | OrderType::Sell) {
+        return Err(anyhow!("Only Buy and Sell orders can be passive, {} orders have to be polled", request.order_type));
+    }
+    if request.action != OrderAction::Swap {
+        return Err(anyhow!("Passive orders can only swap, not {}", request.action));
+    }
+    if request.trigger_source != TriggerSource::Spot || request.quote_mint.is_some() {
+        return Err(anyhow!("Passive orders trigger on the spot price in USD"));
+    }
+    let trigger_mint = request.trigger_mint.as_deref().unwrap_or(&request.target_token);
+    if trigger_mint != request.source_token && trigger_mint != request.target_token {
+        return Err(anyhow!("Passive orders must trigger on the price of the source or target token"));
+    }
+    if request.dry_run || crate::paper::simulation_mode() {
+        return Err(anyhow!("Passive orders cannot be simulated, they are placed on chain"));
+    }
+    if request.mev_protection {
+        return Err(anyhow!("Passive orders are filled by takers, mev_protection does not apply"));
+    }
+    Ok(())
+}
+
+// Raw input and output amounts of the maker order. The target is a USD price of one token, so the
+// exchange rate between the two is fixed at the other token's current price.
+fn order_amounts(app_state: &AppState, order: &LimitOrder) -> Result<(u64, u64)> {
+    let source_price = price::get_token_price(app_state, &order.source_token)?;
+    let target_price = price::get_token_price(app_state, &order.target_token)?;
+    if order.price_target <= 0.0 || source_price <= 0.0 || target_price <= 0.0 {
+        return Err(anyhow!("Cannot price a passive order without positive prices"));
+    }
+
+    // Target tokens received per source token once the trigger token is at the target price
+    let rate = if order.trigger_mint() == order.source_token {
+        order.price_target / target_price
+    } else {
+        source_price / order.price_target
+    };
+    let (in_amount, out_amount) = match order.order_type {
+        OrderType::Buy => (order.amount / rate, order.amount),
+        _ => (order.amount, order.amount * rate),
+    };
+
+    let in_raw = (in_amount * 10f64.powi(KnownTokens::get_decimals(&order.source_token)?)).round() as u64;
+    let out_raw = (out_amount * 10f64.powi(KnownTokens::get_decimals(&order.target_token)?)).round() as u64;
+    if in_raw == 0 || out_raw == 0 {
+        return Err(anyhow!("Passive order amounts round to zero"));
+    }
+    Ok((in_raw, out_raw))
+}
+
+// Sign a transaction built by the API and wait for it to land
+async fn sign_and_send(app_state: &AppState, encoded: &str, signers: &[&Keypair]) -> Result<Signature> {
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
+        .map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+    let mut transaction: Transaction = bincode::deserialize(&bytes)
+        .map_err(|e| anyhow!("Failed to deserialize transaction: {}", e))?;
+
+    let blockhash = transaction.message.recent_blockhash;
+    transaction
+        .try_sign(signers, blockhash)
+        .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
+
+    app_state
+        .rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .map_err(|e| anyhow!("Failed to send transaction: {}", e))
+}
+
+async fn post<T: serde::de::DeserializeOwned>(path: &str, body: serde_json::Value) -> Result<T> {
+    let response = Client::new()
+        .post(format!("{}/{}", JUPITER_LIMIT_API_URL, path))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to call Jupiter limit order API: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_else(|_| "Unable to get error details".to_string());
+        return Err(anyhow!("Jupiter limit order API returned error status {}: {}", status, error_text));
+    }
+
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse Jupiter limit order response: {}", e))
+}
+
+async fn get<T: serde::de::DeserializeOwned>(path: &str, wallet: &str) -> Result<T> {
+    let response = Client::new()
+        .get(format!("{}/{}", JUPITER_LIMIT_API_URL, path))
+        .query(&[("wallet", wallet)])
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to call Jupiter limit order API: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Jupiter limit order API returned error status {}", response.status()));
+    }
+
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse Jupiter limit order response: {}", e))
+}
+
+// Place an order on the program as a maker order, paying the rent of its account
+pub async fn place_order(app_state: &AppState, wallet: &Wallet, order: &LimitOrder) -> Result<PassiveOrder> {
+    let (in_amount_raw, out_amount_raw) = order_amounts(app_state, order)?;
+
+    // The order account is derived from a fresh base key that signs its creation
+    let base = Keypair::new();
+    let created = post::<CreateOrderResponse>(
+        "createOrder",
+        serde_json::json!({
+            "owner": wallet.pubkey.to_string(),
+            "inAmount": in_amount_raw,
+            "outAmount": out_amount_raw,
+            "inputMint": order.source_token,
+            "outputMint": order.target_token,
+            "expiredAt": order.expiry_time.map(|expiry| expiry.timestamp()),
+            "base": base.pubkey().to_string(),
+        }),
+    )
+    .await?;
+
+    let signature = sign_and_send(app_state, &created.tx, &[&wallet.keypair, &base]).await?;
+    info!(
+        "Placed passive order {} as {} for {} -> {} raw units. Signature: {}",
+        order.id, created.order_pubkey, in_amount_raw, out_amount_raw, signature
+    );
+
+    Ok(PassiveOrder {
+        order_account: created.order_pubkey,
+        in_amount_raw,
+        out_amount_raw,
+        filled_in_raw: 0,
+        filled_out_raw: 0,
+        create_signature: signature.to_string(),
+        cancel_signature: None,
+        synced_at: Some(Utc::now()),
+    })
+}
+
+// Cancel an order on the program, returning what it had not filled to the wallet
+pub async fn cancel_order(app_state: &AppState, wallet: &Wallet, passive: &PassiveOrder) -> Result<String> {
+    let cancelled = post::<CancelOrdersResponse>(
+        "cancelOrders",
+        serde_json::json!({
+            "owner": wallet.pubkey.to_string(),
+            "feePayer": wallet.pubkey.to_string(),
+            "orders": [passive.order_account],
+        }),
+    )
+    .await?;
+
+    let signature = sign_and_send(app_state, &cancelled.tx, &[&wallet.keypair]).await?;
+    info!("Cancelled passive order {}. Signature: {}", passive.order_account, signature);
+    Ok(signature.to_string())
+}
+
+// Execution report of a filled passive order, against the amounts it was placed for
+fn execution_report(app_state: &AppState, order: &LimitOrder, passive: &PassiveOrder) -> Result<ExecutionReport> {
+    let in_scale = 10f64.powi(KnownTokens::get_decimals(&order.source_token)?);
+    let out_scale = 10f64.powi(KnownTokens::get_decimals(&order.target_token)?);
+    let source_amount = passive.filled_in_raw as f64 / in_scale;
+    let target_amount = passive.filled_out_raw as f64 / out_scale;
+    let expected_target_amount = passive.out_amount_raw as f64 / out_scale;
+    let source_price_usd = price::get_token_price(app_state, &order.source_token).ok();
+
+    Ok(ExecutionReport {
+        source_amount,
+        target_amount,
+        expected_target_amount,
+        slippage_percent: (expected_target_amount - target_amount) / expected_target_amount * 100.0,
+        input_value_usd: source_amount * source_price_usd.unwrap_or(0.0),
+        executed_at: Utc::now(),
+        source_price_usd,
+        target_price_usd: price::get_token_price(app_state, &order.target_token).ok(),
+    })
+}
+
+// Record an order that left the program: fully filled, or cancelled or expired outside this API
+fn finish_order(app_state: &AppState, order: &LimitOrder, state: &str, fill_signature: Option<String>) -> Result<()> {
+    let mut passive = order.passive.clone().expect("only passive orders are synced");
+
+    if state != "Completed" {
+        let expired = matches!(order.expiry_time, Some(expiry) if Utc::now() >= expiry);
+        let (status, reason) = if expired {
+            (OrderStatus::Expired, "Expired on the Jupiter limit order program")
+        } else {
+            (OrderStatus::Cancelled, "Cancelled on the Jupiter limit order program")
+        };
+        orders::transition_order(app_state, &order.id, status, Some(reason.to_string()))?;
+        return Ok(());
+    }
+
+    passive.filled_in_raw = passive.in_amount_raw;
+    passive.filled_out_raw = passive.filled_out_raw.max(passive.out_amount_raw);
+    let execution = execution_report(app_state, order, &passive)?;
+    let notional_usd = execution.input_value_usd;
+    {
+        let mut orders = app_state.limit_orders.lock().unwrap();
+        if let Some(stored) = orders.get_mut(&order.id) {
+            stored.passive = Some(passive);
+            stored.execution = Some(execution.clone());
+            stored.transaction_signature = fill_signature.clone();
+        }
+    }
+
+    orders::transition_order(app_state, &order.id, OrderStatus::Executing, Some("Filled on the Jupiter limit order program".to_string()))?;
+    let reason = fill_signature.as_ref().map(|signature| format!("Signature: {}", signature));
+    orders::transition_order(app_state, &order.id, OrderStatus::Completed, reason)?;
+    crate::ledger::record_fill(app_state, order, &execution, fill_signature.as_deref().unwrap_or_default());
+
+    if let Some(api_key) = &order.api_key {
+        crate::metering::record_notional(app_state, api_key, notional_usd);
+    }
+    info!("Passive order {} filled on the Jupiter limit order program", order.id);
+    Ok(())
+}
+
+// Sync the passive orders of one wallet with the program: partial fills of the orders still
+// resting, and the outcome of those that left it
+async fn sync_wallet(app_state: &AppState, wallet: &str, passive_orders: Vec<LimitOrder>) -> Result<()> {
+    let open = get::<Vec<OpenOrder>>("openOrders", wallet).await?;
+    let open = open
+        .into_iter()
+        .map(|order| (order.public_key.clone(), order))
+        .collect::<HashMap<_, _>>();
+
+    let mut gone = Vec::new();
+    for order in passive_orders {
+        let passive = order.passive.as_ref().expect("only passive orders are synced");
+        match open.get(&passive.order_account) {
+            Some(resting) => {
+                let remaining_in = raw_amount(&resting.account, &["makingAmount", "inAmount"]).unwrap_or(passive.in_amount_raw);
+                let remaining_out = raw_amount(&resting.account, &["takingAmount", "outAmount"]).unwrap_or(passive.out_amount_raw);
+
+                let mut orders = app_state.limit_orders.lock().unwrap();
+                if let Some(stored) = orders.get_mut(&order.id).and_then(|stored| stored.passive.as_mut()) {
+                    let filled_in = stored.in_amount_raw.saturating_sub(remaining_in);
+                    if filled_in > stored.filled_in_raw {
+                        info!("Passive order {} filled {} of {} raw units", order.id, filled_in, stored.in_amount_raw);
+                    }
+                    stored.filled_in_raw = filled_in;
+                    stored.filled_out_raw = stored.out_amount_raw.saturating_sub(remaining_out);
+                    stored.synced_at = Some(Utc::now());
+                }
+            }
+            None => gone.push(order),
+        }
+    }
+    if gone.is_empty() {
+        return Ok(());
+    }
+
+    let history = get::<Vec<OrderHistoryEntry>>("orderHistory", wallet).await?;
+    let trades = get::<Vec<TradeHistoryEntry>>("tradeHistory", wallet).await.unwrap_or_else(|err| {
+        warn!("Failed to fetch passive order fills of {}: {}", wallet, err);
+        Vec::new()
+    });
+
+    for order in gone {
+        let account = &order.passive.as_ref().expect("only passive orders are synced").order_account;
+        // The indexer can lag behind the program, the order is looked at again on the next pass
+        let entry = match history.iter().find(|entry| &entry.order_key == account) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let fill_signature = trades
+            .iter()
+            .find(|trade| &trade.order.order_key == account)
+            .map(|trade| trade.tx_id.clone());
+
+        if let Err(err) = finish_order(app_state, &order, &entry.state, fill_signature) {
+            error!("Failed to record the outcome of passive order {}: {}", order.id, err);
+        }
+    }
+    Ok(())
+}
+
+// Sync every active passive order with the program
+pub async fn sync_passive_orders(app_state: &AppState) {
+    let passive_orders = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.status == OrderStatus::Active && order.passive.is_some())
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let wallets = passive_orders
+        .iter()
+        .map(|order| order.wallet_pubkey.clone())
+        .collect::<HashSet<_>>();
+    for wallet in wallets {
+        let wallet_orders = passive_orders
+            .iter()
+            .filter(|order| order.wallet_pubkey == wallet)
+            .cloned()
+            .collect();
+        if let Err(err) = sync_wallet(app_state, &wallet, wallet_orders).await {
+            warn!("Failed to sync passive orders of {}: {}", wallet, err);
+        }
+    }
+}
+
+// Background task syncing fills of passive orders back into the local order model
+pub async fn run_passive_order_sync(app_state: Arc<AppState>) {
+    info!("Starting passive order sync task, syncing every {} seconds", PASSIVE_SYNC_INTERVAL_SECS);
+
+    loop {
+        time::sleep(time::Duration::from_secs(PASSIVE_SYNC_INTERVAL_SECS)).await;
+        sync_passive_orders(&app_state).await;
+    }
+}
+
+// Cancel the on-chain order of a passive order before it is cancelled locally
+pub async fn cancel_passive_order(app_state: &AppState, order: &LimitOrder) -> Result<Option<String>> {
+    let passive = match &order.passive {
+        Some(passive) => passive,
+        None => return Ok(None),
+    };
+    let owner = wallet::find_wallet(app_state, Some(&order.wallet_pubkey))?;
+    cancel_order(app_state, &owner, passive).await.map(Some)
+}
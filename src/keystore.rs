@@ -0,0 +1,271 @@
+use crate::models::{AppState, OrderStatus, Wallet};
+use crate::tenant;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+// Directory holding one encrypted file per wallet
+const KEYSTORE_DIR_ENV: &str = "KEYSTORE_DIR";
+const DEFAULT_KEYSTORE_DIR: &str = "keystore";
+
+// Current file format version
+const KEYSTORE_VERSION: u32 = 1;
+
+// Passphrases shorter than this are refused
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+// Encrypted keypair as written to disk. The raw secret key never touches the disk.
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    pubkey: String,
+    // Argon2id cost parameters the key was derived with
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    created_at: DateTime<Utc>,
+}
+
+// Directory the keystore files live in
+fn keystore_dir() -> PathBuf {
+    PathBuf::from(std::env::var(KEYSTORE_DIR_ENV).unwrap_or_else(|_| DEFAULT_KEYSTORE_DIR.to_string()))
+}
+
+// Path of a wallet's keystore file
+fn keystore_path(pubkey: &str) -> Result<PathBuf> {
+    // Reject anything that is not a public key so the path cannot escape the directory
+    pubkey
+        .parse::<solana_sdk::pubkey::Pubkey>()
+        .map_err(|_| anyhow!("Invalid wallet public key: {}", pubkey))?;
+    Ok(keystore_dir().join(format!("{}.json", pubkey)))
+}
+
+// Refuse passphrases too short to protect a stored key
+pub fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.len() < MIN_PASSPHRASE_LEN {
+        return Err(anyhow!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+// Delete a wallet's keystore file
+pub fn remove_stored(pubkey: &str) -> Result<()> {
+    fs::remove_file(keystore_path(pubkey)?)?;
+    info!("Removed wallet {} from the keystore", pubkey);
+    Ok(())
+}
+
+// Whether a wallet has a keystore file
+pub fn is_stored(pubkey: &str) -> bool {
+    keystore_path(pubkey).is_ok_and(|path| path.exists())
+}
+
+// Derive the AES key from the passphrase. In hosted mode the tenant's key is mixed in as the
+// Argon2 secret, so a file can only be opened by the tenant that stored it.
+fn derive_key(app_state: &AppState, tenant: &str, passphrase: &str, salt: &[u8], params: Params) -> Result<[u8; 32]> {
+    let tenant_key = tenant::encryption_key(app_state, tenant);
+    let argon2 = if tenant::hosted_mode() {
+        Argon2::new_with_secret(&tenant_key, Algorithm::Argon2id, Version::V0x13, params)
+            .map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?
+    } else {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    };
+
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive keystore key: {}", e))?;
+    Ok(key)
+}
+
+// Encrypt a wallet's keypair to its keystore file, replacing any previous file
+pub fn store_wallet(app_state: &AppState, tenant: &str, wallet: &Wallet, passphrase: &str) -> Result<()> {
+    check_passphrase(passphrase)?;
+
+    let params = Params::default();
+    let salt: [u8; 16] = rand::random();
+    let nonce: [u8; 12] = rand::random();
+    let key = derive_key(app_state, tenant, passphrase, &salt, params.clone())?;
+
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Invalid keystore key: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), wallet.keypair.to_bytes().as_ref())
+        .map_err(|_| anyhow!("Failed to encrypt wallet"))?;
+
+    let pubkey = wallet.pubkey.to_string();
+    let file = KeystoreFile {
+        version: KEYSTORE_VERSION,
+        pubkey: pubkey.clone(),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+        created_at: Utc::now(),
+    };
+
+    let path = keystore_path(&pubkey)?;
+    fs::create_dir_all(keystore_dir())?;
+
+    // Write to a temporary file first so a crash never leaves a truncated keystore behind
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(&file)?)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&tmp_path, &path)?;
+
+    info!("Stored wallet {} in the keystore", pubkey);
+    Ok(())
+}
+
+// Decrypt a wallet from its keystore file
+pub fn load_wallet(app_state: &AppState, tenant: &str, pubkey: &str, passphrase: &str) -> Result<Wallet> {
+    let path = keystore_path(pubkey)?;
+    let contents = fs::read(&path).map_err(|_| anyhow!("Wallet {} is not in the keystore", pubkey))?;
+    let file: KeystoreFile = serde_json::from_slice(&contents)
+        .map_err(|e| anyhow!("Corrupted keystore file for {}: {}", pubkey, e))?;
+
+    if file.version != KEYSTORE_VERSION {
+        return Err(anyhow!("Unsupported keystore version {}", file.version));
+    }
+
+    let salt = BASE64.decode(&file.salt)?;
+    let nonce = BASE64.decode(&file.nonce)?;
+    let ciphertext = BASE64.decode(&file.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err(anyhow!("Corrupted keystore file for {}: invalid nonce", pubkey));
+    }
+
+    let params = Params::new(file.m_cost, file.t_cost, file.p_cost, None)
+        .map_err(|e| anyhow!("Corrupted keystore file for {}: {}", pubkey, e))?;
+    let key = derive_key(app_state, tenant, passphrase, &salt, params)?;
+
+    // Another tenant's key fails exactly like a wrong passphrase
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Invalid keystore key: {}", e))?;
+    let secret = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow!("Wrong passphrase for wallet {}", pubkey))?;
+
+    let keypair = Keypair::from_bytes(&secret)?;
+    if keypair.pubkey().to_string() != file.pubkey || file.pubkey != pubkey {
+        return Err(anyhow!("Keystore file for {} holds a different keypair", pubkey));
+    }
+
+    let pubkey = keypair.pubkey();
+    Ok(Wallet { keypair, pubkey })
+}
+
+// Decrypt a stored wallet and make it available for signing
+pub fn unlock_wallet(app_state: &AppState, tenant: &str, pubkey: &str, passphrase: &str) -> Result<()> {
+    let wallet = load_wallet(app_state, tenant, pubkey, passphrase)?;
+
+    tenant::register_wallet(app_state, tenant, pubkey)?;
+    crate::wallet::add_wallet(app_state, wallet);
+
+    info!("Unlocked wallet {}", pubkey);
+    Ok(())
+}
+
+// Drop a wallet's keypair from memory, storing it first if it has no keystore file yet.
+// Wallets with orders still waiting to execute stay unlocked so those orders cannot fail.
+pub fn lock_wallet(app_state: &AppState, tenant: &str, pubkey: &str, passphrase: Option<&str>) -> Result<()> {
+    let wallet = crate::wallet::find_tenant_wallet(app_state, tenant, Some(pubkey))?;
+
+    let pending = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.wallet_pubkey == pubkey)
+            .filter(|order| matches!(order.status, OrderStatus::Active | OrderStatus::PendingFunds | OrderStatus::Executing | OrderStatus::Retrying))
+            .count()
+    };
+    if pending > 0 {
+        return Err(anyhow!("Wallet {} has {} open orders, cancel them before locking it", pubkey, pending));
+    }
+
+    match passphrase {
+        Some(passphrase) => store_wallet(app_state, tenant, &wallet, passphrase)?,
+        None if is_stored(pubkey) => {}
+        None => return Err(anyhow!("Wallet {} is not in the keystore yet, a passphrase is required", pubkey)),
+    }
+
+    app_state.wallets.lock().unwrap().remove(pubkey);
+    info!("Locked wallet {}", pubkey);
+    Ok(())
+}
+
+// Check one keystore file can still be decrypted given the right passphrase: it parses, has a
+// supported version, names the wallet its file is named after and carries well-formed key material
+fn verify_file(path: &std::path::Path) -> Result<()> {
+    let pubkey = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| anyhow!("File name is not a wallet public key"))?;
+    pubkey
+        .parse::<solana_sdk::pubkey::Pubkey>()
+        .map_err(|_| anyhow!("File name is not a wallet public key"))?;
+
+    let file: KeystoreFile = serde_json::from_slice(&fs::read(path)?).map_err(|e| anyhow!("Corrupted: {}", e))?;
+    if file.version != KEYSTORE_VERSION {
+        return Err(anyhow!("Unsupported keystore version {}", file.version));
+    }
+    if file.pubkey != pubkey {
+        return Err(anyhow!("Holds wallet {} instead", file.pubkey));
+    }
+
+    Params::new(file.m_cost, file.t_cost, file.p_cost, None).map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?;
+    if !matches!(BASE64.decode(&file.salt), Ok(salt) if salt.len() >= argon2::MIN_SALT_LEN) {
+        return Err(anyhow!("Invalid salt"));
+    }
+    if !matches!(BASE64.decode(&file.nonce), Ok(nonce) if nonce.len() == 12) {
+        return Err(anyhow!("Invalid nonce"));
+    }
+    // A 64 byte keypair plus the 16 byte authentication tag
+    if !matches!(BASE64.decode(&file.ciphertext), Ok(ciphertext) if ciphertext.len() == 64 + 16) {
+        return Err(anyhow!("Invalid ciphertext"));
+    }
+    Ok(())
+}
+
+// Problems with the keystore files, one per file that could not be decrypted even with the right
+// passphrase. A missing keystore directory has no problems.
+pub fn verify_keystore() -> Vec<String> {
+    let entries = match fs::read_dir(keystore_dir()) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => return vec![format!("Cannot read the keystore directory {}: {}", keystore_dir().display(), err)],
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+        .filter_map(|path| verify_file(&path).err().map(|err| format!("{}: {}", path.display(), err)))
+        .collect()
+}
+
+// Whether the keystore holds any wallet
+pub fn has_stored_wallets() -> bool {
+    match fs::read_dir(keystore_dir()) {
+        Ok(mut entries) => entries.any(|entry| {
+            matches!(entry, Ok(entry) if entry.path().extension().and_then(|extension| extension.to_str()) == Some("json"))
+        }),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,75 @@
+use crate::models::{AppState, ExecutionLatency, LatencyMetrics, PhaseLatency};
+use chrono::{DateTime, Utc};
+
+// Executions kept for the percentiles, oldest dropped first
+const MAX_LATENCY_SAMPLES: usize = 1000;
+
+// Stretches of the execution path, each from one phase to the next
+const PHASES: &[&str] = &[
+    "trigger_to_quote",
+    "quote_to_signed",
+    "signed_to_submitted",
+    "submitted_to_confirmed",
+    "trigger_to_confirmed",
+];
+
+// Keep the phases of a completed execution for the latency metrics
+pub fn record_latency(app_state: &AppState, latency: ExecutionLatency) {
+    let mut latencies = app_state.execution_latencies.lock().unwrap();
+    if latencies.len() >= MAX_LATENCY_SAMPLES {
+        latencies.pop_front();
+    }
+    latencies.push_back(latency);
+}
+
+fn elapsed_ms(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Option<i64> {
+    Some((to? - from?).num_milliseconds())
+}
+
+// Milliseconds one stretch took, when both of its ends were recorded
+fn phase_ms(latency: &ExecutionLatency, phase: &str) -> Option<i64> {
+    match phase {
+        "trigger_to_quote" => elapsed_ms(latency.trigger_detected_at, latency.quote_received_at),
+        "quote_to_signed" => elapsed_ms(latency.quote_received_at, latency.signed_at),
+        "signed_to_submitted" => elapsed_ms(latency.signed_at, latency.submitted_at),
+        "submitted_to_confirmed" => elapsed_ms(latency.submitted_at, latency.confirmed_at),
+        "trigger_to_confirmed" => elapsed_ms(latency.trigger_detected_at, latency.confirmed_at),
+        _ => None,
+    }
+}
+
+// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[i64], percent: f64) -> i64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// Latency percentiles of each stretch over the recent executions
+pub fn get_latency_metrics(app_state: &AppState) -> LatencyMetrics {
+    let latencies = app_state.execution_latencies.lock().unwrap();
+
+    let phases = PHASES
+        .iter()
+        .filter_map(|phase| {
+            let mut samples = latencies.iter().filter_map(|latency| phase_ms(latency, phase)).collect::<Vec<_>>();
+            if samples.is_empty() {
+                return None;
+            }
+            samples.sort_unstable();
+
+            Some(PhaseLatency {
+                phase: phase.to_string(),
+                samples: samples.len(),
+                p50_ms: percentile(&samples, 50.0),
+                p90_ms: percentile(&samples, 90.0),
+                p99_ms: percentile(&samples, 99.0),
+                max_ms: samples[samples.len() - 1],
+            })
+        })
+        .collect();
+
+    LatencyMetrics {
+        executions: latencies.len(),
+        phases,
+    }
+}
@@ -0,0 +1,125 @@
+use crate::models::{AppState, ExecutionReport, LedgerEntry, LedgerEntryKind, LimitOrder, SwapRequest, SwapResponse};
+use crate::pnl;
+use crate::price;
+use chrono::Utc;
+use tracing::info;
+use uuid::Uuid;
+
+// Record an executed swap as a trade
+pub fn record_trade(
+    app_state: &AppState,
+    wallet: &str,
+    swap_request: &SwapRequest,
+    swap_response: &SwapResponse,
+    order_id: Option<&str>,
+) -> Option<LedgerEntry> {
+    // Simulated fills are booked on the wallet's paper account only
+    if swap_response.simulated {
+        return None;
+    }
+
+    let entry = LedgerEntry {
+        id: Uuid::new_v4().to_string(),
+        kind: LedgerEntryKind::Trade,
+        wallet: wallet.to_string(),
+        counterparty: None,
+        source_token: swap_request.source_token.clone(),
+        source_amount: swap_response.source_amount,
+        target_token: Some(swap_request.target_token.clone()),
+        target_amount: Some(swap_response.target_amount),
+        order_id: order_id.map(|id| id.to_string()),
+        transaction_signature: swap_response.transaction_signature.clone(),
+        timestamp: swap_response.timestamp,
+        source_price_usd: price::get_token_price(app_state, &swap_request.source_token).ok(),
+        target_price_usd: price::get_token_price(app_state, &swap_request.target_token).ok(),
+    };
+
+    app_state.ledger.lock().unwrap().push(entry.clone());
+    pnl::record_trade(app_state, &entry, swap_response.fee_details.total_usd.unwrap_or(0.0));
+    Some(entry)
+}
+
+// Record a passive order the Jupiter limit order program filled as a trade, at the prices its
+// execution report was valued at. The program's keeper sent the fill, so the wallet paid no fee.
+pub fn record_fill(app_state: &AppState, order: &LimitOrder, execution: &ExecutionReport, transaction_signature: &str) -> LedgerEntry {
+    let entry = LedgerEntry {
+        id: Uuid::new_v4().to_string(),
+        kind: LedgerEntryKind::Trade,
+        wallet: order.wallet_pubkey.clone(),
+        counterparty: None,
+        source_token: order.source_token.clone(),
+        source_amount: execution.source_amount,
+        target_token: Some(order.target_token.clone()),
+        target_amount: Some(execution.target_amount),
+        order_id: Some(order.id.clone()),
+        transaction_signature: transaction_signature.to_string(),
+        timestamp: execution.executed_at,
+        source_price_usd: execution.source_price_usd,
+        target_price_usd: execution.target_price_usd,
+    };
+
+    app_state.ledger.lock().unwrap().push(entry.clone());
+    pnl::record_trade(app_state, &entry, 0.0);
+    entry
+}
+
+// Transfers to another imported wallet are internal, anything else leaves the operator's custody
+pub fn classify_transfer(app_state: &AppState, destination: &str) -> LedgerEntryKind {
+    let wallets = app_state.wallets.lock().unwrap();
+    if wallets.contains_key(destination) {
+        LedgerEntryKind::InternalTransfer
+    } else {
+        LedgerEntryKind::ExternalTransfer
+    }
+}
+
+// Record a token transfer, classifying it against the imported wallets
+pub fn record_transfer(
+    app_state: &AppState,
+    wallet: &str,
+    destination: &str,
+    token: &str,
+    amount: f64,
+    transaction_signature: &str,
+    order_id: Option<&str>,
+) -> LedgerEntry {
+    let kind = classify_transfer(app_state, destination);
+
+    info!(
+        "Recording {:?} of {} {} from {} to {}",
+        kind, amount, crate::wallet::KnownTokens::get_symbol(token), wallet, destination
+    );
+
+    let entry = LedgerEntry {
+        id: Uuid::new_v4().to_string(),
+        kind,
+        wallet: wallet.to_string(),
+        counterparty: Some(destination.to_string()),
+        source_token: token.to_string(),
+        source_amount: amount,
+        target_token: None,
+        target_amount: None,
+        order_id: order_id.map(str::to_string),
+        transaction_signature: transaction_signature.to_string(),
+        timestamp: Utc::now(),
+        source_price_usd: price::get_token_price(app_state, token).ok(),
+        target_price_usd: None,
+    };
+
+    app_state.ledger.lock().unwrap().push(entry.clone());
+    pnl::record_transfer(app_state, &entry);
+    entry
+}
+
+// Ledger entries of the wallets the tenant may use, oldest first
+pub fn get_ledger(app_state: &AppState, tenant: &str) -> Vec<LedgerEntry> {
+    let ledger = {
+        let ledger = app_state.ledger.lock().unwrap();
+        ledger.clone()
+    };
+
+    ledger
+        .into_iter()
+        .filter(|entry| crate::tenant::can_use_wallet(app_state, tenant, &entry.wallet))
+        .collect()
+}
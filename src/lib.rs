@@ -1,12 +1,63 @@
 // Library modules
+pub mod actions;
+pub mod activity;
+pub mod alerts;
+#[cfg(feature = "server")]
 pub mod api;
+pub mod auth;
+pub mod blackout;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod creation_guard;
+pub mod dca;
+pub mod denylist;
+pub mod drift;
+pub mod engine;
+#[cfg(feature = "server")]
+pub mod error;
+pub mod events;
+pub mod exposure;
+pub mod freeze;
+pub mod history;
+pub mod integrity;
+pub mod janitor;
+pub mod jito;
+pub mod jupiter_limit;
+pub mod keystore;
+pub mod latency;
+pub mod ledger;
+pub mod liquidation;
+pub mod metering;
 pub mod models;
+pub mod notify;
+#[cfg(feature = "server")]
+pub mod openapi;
 pub mod orders;
+pub mod paper;
+pub mod pnl;
+pub mod portfolio;
 pub mod price;
+pub mod price_stream;
+pub mod pyth;
+pub mod rate_limit;
+pub mod receipts;
+pub mod reserve;
+pub mod risk;
+pub mod secrets;
+pub mod slippage;
 pub mod swap;
+pub mod templates;
+pub mod tenant;
+pub mod upstream_cache;
 pub mod utils;
 pub mod wallet;
+pub mod watchdog;
+pub mod watchlist;
+#[cfg(feature = "server")]
+pub mod ws;
+pub mod test_price_providers;
 pub mod test_stop_loss;
+pub mod test_tenant_isolation;
 
 // Main application state and shared types
 pub use models::*; 
\ No newline at end of file
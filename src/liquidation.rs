@@ -0,0 +1,260 @@
+use crate::alerts;
+use crate::engine;
+use crate::freeze;
+use crate::ledger;
+use crate::models::{AppState, LiquidationPolicy, NotificationEvent, PortfolioResponse, PortfolioSnapshot, SwapRequest, Wallet};
+use crate::notify;
+use crate::portfolio;
+use crate::price;
+use crate::reserve;
+use crate::swap;
+use crate::wallet;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tokio::time;
+use tracing::{error, info, warn};
+
+// Native SOL mint, part of which is kept back to pay fees
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// SOL left in the wallet when liquidating SOL itself
+pub const SOL_FEE_RESERVE: f64 = 0.01;
+
+// How long portfolio snapshots are kept
+const HISTORY_RETENTION_HOURS: i64 = 7 * 24;
+
+// How often the portfolio is sampled
+const SNAPSHOT_INTERVAL_SECS: u64 = 60;
+
+// Validate and store a new vacation mode policy
+pub fn set_policy(app_state: &AppState, mut policy: LiquidationPolicy) -> Result<LiquidationPolicy> {
+    if policy.drawdown_percent <= 0.0 || policy.drawdown_percent >= 100.0 {
+        return Err(anyhow!("drawdown_percent must be between 0 and 100"));
+    }
+    if policy.window_hours <= 0.0 {
+        return Err(anyhow!("window_hours must be greater than zero"));
+    }
+    if policy.tokens.is_empty() {
+        return Err(anyhow!("At least one token to liquidate must be configured"));
+    }
+    if policy.tokens.contains(&policy.stable_token) {
+        return Err(anyhow!("The stable token cannot also be liquidated"));
+    }
+    policy.wallets.sort();
+    policy.wallets.dedup();
+    if policy.wallets.is_empty() {
+        return Err(anyhow!("At least one wallet to watch must be configured"));
+    }
+    for pubkey in &policy.wallets {
+        wallet::find_wallet(app_state, Some(pubkey))?;
+    }
+
+    // A new policy starts armed
+    policy.triggered_at = None;
+
+    let mut current = app_state.liquidation_policy.lock().unwrap();
+    // Samples of other wallets say nothing about these ones' drawdown
+    if current.as_ref().map(|current| current.wallets != policy.wallets).unwrap_or(true) {
+        app_state.portfolio_history.lock().unwrap().clear();
+    }
+    *current = Some(policy.clone());
+
+    info!(
+        "Vacation mode policy set: liquidate {} tokens of {} wallets if their value drops {}% within {} hours",
+        policy.tokens.len(), policy.wallets.len(), policy.drawdown_percent, policy.window_hours
+    );
+    Ok(policy)
+}
+
+// Current vacation mode policy
+pub fn get_policy(app_state: &AppState) -> Option<LiquidationPolicy> {
+    let policy = app_state.liquidation_policy.lock().unwrap();
+    policy.clone()
+}
+
+// Portfolio value history collected by the monitor
+pub fn get_history(app_state: &AppState) -> Vec<PortfolioSnapshot> {
+    let history = app_state.portfolio_history.lock().unwrap();
+    history.clone()
+}
+
+// Append a snapshot and drop the ones past the retention period
+fn record_snapshot(app_state: &AppState, snapshot: PortfolioSnapshot) {
+    let mut history = app_state.portfolio_history.lock().unwrap();
+    let cutoff = snapshot.timestamp - Duration::hours(HISTORY_RETENTION_HOURS);
+    history.retain(|s| s.timestamp >= cutoff);
+    history.push(snapshot);
+}
+
+// Drop in percent from the highest value within the window to the latest value
+fn drawdown_in_window(history: &[PortfolioSnapshot], window_hours: f64, now: DateTime<Utc>) -> Option<f64> {
+    let latest = history.last()?;
+    let window_start = now - Duration::seconds((window_hours * 3600.0) as i64);
+
+    let peak = history
+        .iter()
+        .filter(|s| s.timestamp >= window_start)
+        .map(|s| s.total_value_usd)
+        .fold(f64::MIN, f64::max);
+
+    if peak <= 0.0 {
+        return None;
+    }
+
+    Some((peak - latest.total_value_usd) / peak * 100.0)
+}
+
+// Swap every configured token of each watched wallet into the stable asset. Frozen wallets are
+// left alone.
+async fn liquidate(app_state: &AppState, policy: &LiquidationPolicy, portfolios: &[(Wallet, PortfolioResponse)]) {
+    for (wallet, portfolio) in portfolios {
+        if freeze::is_frozen(app_state, &wallet.pubkey.to_string()) {
+            error!("Vacation mode skipped wallet {}: it is frozen", wallet.pubkey);
+            continue;
+        }
+        liquidate_wallet(app_state, policy, wallet, portfolio).await;
+    }
+}
+
+// Swap the configured tokens one wallet holds into the stable asset
+async fn liquidate_wallet(app_state: &AppState, policy: &LiquidationPolicy, wallet: &Wallet, portfolio: &PortfolioResponse) {
+    for mint in &policy.tokens {
+        let held = portfolio
+            .spot
+            .iter()
+            .find(|p| &p.mint == mint)
+            .map(|p| p.amount)
+            .unwrap_or(0.0);
+
+        let amount = if mint == SOL_MINT { held - SOL_FEE_RESERVE } else { held };
+
+        if amount <= 0.0 {
+            continue;
+        }
+
+        let swap_request = SwapRequest {
+            source_token: mint.clone(),
+            target_token: policy.stable_token.clone(),
+            amount,
+            amount_percent: None,
+            amount_usd: None,
+            slippage: policy.slippage,
+            wallet_pubkey: Some(wallet.pubkey.to_string()),
+            dry_run: false,
+            priority_fee: None,
+            mev_protection: false,
+        };
+
+        match swap::execute_swap(app_state, wallet, &swap_request).await {
+            Ok(result) => {
+                ledger::record_trade(app_state, &wallet.pubkey.to_string(), &swap_request, &result, None);
+                info!(
+                    "Vacation mode liquidated {} {} of wallet {} into {}. Signature: {}",
+                    amount, mint, wallet.pubkey, policy.stable_token, result.transaction_signature
+                );
+            }
+            Err(err) => error!("Vacation mode failed to liquidate {} of wallet {}: {}", mint, wallet.pubkey, err),
+        }
+    }
+}
+
+// Wallets the portfolio monitor values: the vacation mode policy's, or every imported wallet
+// without a policy. A policy wallet that was removed is an error rather than a missing value.
+fn watched_wallets(app_state: &AppState) -> Result<Vec<Wallet>> {
+    match get_policy(app_state) {
+        Some(policy) => policy
+            .wallets
+            .iter()
+            .map(|pubkey| wallet::find_wallet(app_state, Some(pubkey)))
+            .collect(),
+        None => {
+            let wallets = app_state.wallets.lock().unwrap();
+            Ok(wallets.values().cloned().collect())
+        }
+    }
+}
+
+// Background task sampling the portfolio, checking balance and funding alerts, keeping the SOL
+// reserve topped up and enforcing the vacation mode policy
+pub async fn monitor_portfolio(app_state: Arc<AppState>) {
+    info!("Starting portfolio monitor task");
+
+    loop {
+        time::sleep(time::Duration::from_secs(SNAPSHOT_INTERVAL_SECS)).await;
+
+        alerts::check_balance_alerts(&app_state).await;
+        alerts::check_funding_alerts(&app_state).await;
+        reserve::maintain_sol_reserve(&app_state).await;
+
+        let wallets = match watched_wallets(&app_state) {
+            Ok(wallets) if !wallets.is_empty() => wallets,
+            Ok(_) => continue,
+            Err(err) => {
+                error!("Vacation mode cannot value its wallets: {}", err);
+                continue;
+            }
+        };
+
+        if let Err(err) = price::update_prices(app_state.clone()).await {
+            error!("Failed to update prices for portfolio monitor: {}", err);
+            continue;
+        }
+
+        // A sample missing a wallet would look like a drawdown, so any failure skips this one
+        let mut portfolios = Vec::new();
+        let mut complete = true;
+        for wallet in wallets {
+            match portfolio::get_portfolio(&app_state, &wallet).await {
+                Ok(portfolio) => portfolios.push((wallet, portfolio)),
+                Err(err) => {
+                    error!("Failed to value wallet {}: {}", wallet.pubkey, err);
+                    complete = false;
+                    break;
+                }
+            }
+        }
+        if !complete {
+            continue;
+        }
+
+        record_snapshot(&app_state, PortfolioSnapshot {
+            timestamp: Utc::now(),
+            total_value_usd: portfolios.iter().map(|(_, portfolio)| portfolio.total_value_usd).sum(),
+        });
+
+        let policy = match get_policy(&app_state) {
+            Some(policy) if policy.enabled && policy.triggered_at.is_none() => policy,
+            _ => continue,
+        };
+
+        let drawdown = {
+            let history = app_state.portfolio_history.lock().unwrap();
+            drawdown_in_window(&history, policy.window_hours, Utc::now())
+        };
+
+        let drawdown = match drawdown {
+            Some(drawdown) if drawdown >= policy.drawdown_percent => drawdown,
+            _ => continue,
+        };
+
+        warn!(
+            "Portfolio dropped {:.2}% within {} hours (threshold {}%), triggering vacation mode",
+            drawdown, policy.window_hours, policy.drawdown_percent
+        );
+
+        // Mark the policy as fired first so it cannot trigger twice
+        {
+            let mut current = app_state.liquidation_policy.lock().unwrap();
+            if let Some(current) = current.as_mut() {
+                current.triggered_at = Some(Utc::now());
+            }
+        }
+
+        liquidate(&app_state, &policy, &portfolios).await;
+
+        let reason = format!("Vacation mode: portfolio dropped {:.2}% within {} hours", drawdown, policy.window_hours);
+        engine::pause_trading(&app_state, &reason);
+        notify::notify(&app_state, NotificationEvent::TradingPaused, reason).await;
+    }
+}
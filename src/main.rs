@@ -1,8 +1,9 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, get_service, post},
     extract::{Extension, Json},
     Router, response::{IntoResponse},
-    http::StatusCode,
+    http::{header, StatusCode},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
@@ -10,6 +11,26 @@ use std::net::SocketAddr;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 use std::path::PathBuf;
+use solana_wallet_api::auth;
+use solana_wallet_api::activity;
+use solana_wallet_api::api;
+use solana_wallet_api::dca;
+use solana_wallet_api::error;
+use solana_wallet_api::integrity;
+use solana_wallet_api::janitor;
+use solana_wallet_api::jupiter_limit;
+use solana_wallet_api::liquidation;
+use solana_wallet_api::metering;
+use solana_wallet_api::notify;
+use solana_wallet_api::openapi;
+use solana_wallet_api::orders;
+use solana_wallet_api::price_stream;
+use solana_wallet_api::rate_limit;
+use solana_wallet_api::secrets;
+use solana_wallet_api::tenant;
+use solana_wallet_api::watchdog;
+use solana_wallet_api::ws;
+use solana_wallet_api::models::AppState as WalletState;
 
 // Our application state
 #[derive(Clone)]
@@ -71,6 +92,20 @@ async fn get_counter(
     })
 }
 
+// Serve the generated TypeScript client; ServeDir would label `.ts` files as MPEG transport streams
+async fn typescript_client() -> impl IntoResponse {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("static").join("client.ts");
+
+    match tokio::fs::read_to_string(path).await {
+        Ok(client) => (StatusCode::OK, [(header::CONTENT_TYPE, "application/typescript; charset=utf-8")], client),
+        Err(_) => (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            "Client not generated, run `cargo xtask client`".to_string(),
+        ),
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // Initialize application state
@@ -78,6 +113,49 @@ async fn main() {
         counter: Arc::new(Mutex::new(0)),
     });
 
+    // The master key and API credentials are read from the secrets backend while the state is built
+    secrets::init().expect("Invalid secrets backend configuration");
+
+    // Initialize wallet API state
+    let wallet_state = Arc::new(WalletState::new());
+
+    // Verify the keystore before anything acts on it. On failure the server only serves reads and
+    // diagnostics, and none of the background tasks below are started.
+    let safe_mode = integrity::run_startup_check(&wallet_state);
+
+    if !safe_mode {
+        // Watch prices and execute triggered orders. Spawned tasks and route handlers must be Send,
+        // so the compiler rejects any std::sync::Mutex guard held across an await in them.
+        tokio::spawn(orders::monitor_limit_orders(wallet_state.clone()));
+
+        // Check orders as soon as Pyth pushes a new price, with the monitor's polling as fallback
+        tokio::spawn(price_stream::run_price_stream(wallet_state.clone()));
+
+        // Sync fills of passive orders resting on the Jupiter limit order program
+        tokio::spawn(jupiter_limit::run_passive_order_sync(wallet_state.clone()));
+
+        // Make the periodic buys of DCA orders
+        tokio::spawn(dca::run_dca_scheduler(wallet_state.clone()));
+
+        // Sample the portfolio and enforce the vacation mode policy in the background
+        tokio::spawn(liquidation::monitor_portfolio(wallet_state.clone()));
+
+        // Notify about deposits and withdrawals made to tracked wallets from outside the API
+        tokio::spawn(activity::monitor_wallet_activity(wallet_state.clone()));
+
+        // Flush batched notifications
+        tokio::spawn(notify::run_digest_flusher(wallet_state.clone()));
+
+        // Move finished orders out of the hot order map once they are old enough
+        tokio::spawn(orders::run_order_archiver(wallet_state.clone()));
+
+        // Report stale or orphaned state, such as orders stuck executing
+        tokio::spawn(janitor::run_janitor(wallet_state.clone()));
+
+        // Canary checks against the quote API and RPC node, pausing executions when they fail
+        tokio::spawn(watchdog::run_watchdog(wallet_state.clone()));
+    }
+
     // Create CORS layer
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -94,10 +172,123 @@ async fn main() {
         .route("/increment", post(increment))
         .route("/decrement", post(decrement))
         .route("/counter", get(get_counter))
+        .route("/status.json", get(api::get_public_status))
+        .route("/client.ts", get(typescript_client))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/generate_wallet", post(api::generate_wallet))
+        .route("/import_wallet", post(api::import_wallet))
+        .route("/list_wallets", get(api::list_wallets))
+        .route("/wallets/:pubkey/freeze", post(api::freeze_wallet))
+        .route("/wallets/:pubkey/unfreeze", post(api::unfreeze_wallet))
+        .route("/get_balances", get(api::get_balances))
+        .route("/get_prices", get(api::get_prices))
+        .route("/tokens", get(api::get_token_list))
+        .route("/price/:mint", get(api::get_mint_price))
+        .route("/swap_token", post(api::swap_token))
+        .route("/set_limit_order", post(api::set_limit_order))
+        .route("/list_limit_orders", get(api::list_limit_orders))
+        .route("/cancel_limit_order", post(api::cancel_limit_order))
+        .route("/dca_orders", get(api::list_dca_orders).post(api::create_dca_order))
+        .route("/dca_orders/:id", delete(api::cancel_dca_order))
+        .route("/portfolio", get(api::get_portfolio))
+        .route("/risk", get(api::get_risk))
+        .route("/engine/queue", get(api::get_execution_queue))
+        .route("/heartbeat", post(api::heartbeat))
+        .route("/portfolio/history", get(api::get_portfolio_history))
+        .route("/liquidation_policy", get(api::get_liquidation_policy).post(api::set_liquidation_policy))
+        .route("/sol_reserve_policy", get(api::get_sol_reserve_policy).post(api::set_sol_reserve_policy))
+        .route("/engine/resume", post(api::resume_trading))
+        .route("/engine/health", get(api::get_engine_health))
+        .route("/engine/latency", get(api::get_latency_metrics))
+        .route("/engine/watchdog", post(api::set_watchdog_config))
+        .route("/price_guards", get(api::list_price_guards).post(api::set_price_guard))
+        .route("/price_guards/:mint", delete(api::remove_price_guard))
+        .route("/price_providers", get(api::list_price_providers).post(api::set_provider_preference))
+        .route("/price_providers/:mint", delete(api::remove_provider_preference))
+        .route("/watchlist", get(api::get_watchlist).post(api::add_to_watchlist))
+        .route("/watchlist/:mint", delete(api::remove_from_watchlist))
+        .route("/prices/divergence", get(api::get_price_divergence))
+        .route("/prices/push", post(api::push_prices))
+        .route("/exposure_limits", get(api::list_exposure_limits).post(api::set_exposure_limit))
+        .route("/exposure_limits/:mint", delete(api::remove_exposure_limit))
+        .route("/blackout_windows", get(api::list_blackout_windows).post(api::create_blackout_window))
+        .route("/blackout_windows/:id", delete(api::remove_blackout_window))
+        .route("/route_denylist", get(api::get_route_denylist).post(api::set_route_denylist))
+        .route("/upstream_cache", get(api::get_upstream_cache))
+        .route("/upstream_cache/refresh", post(api::refresh_upstream_cache))
+        .route("/order_quarantines", get(api::list_order_quarantines))
+        .route("/order_quarantines/:id", delete(api::lift_order_quarantine))
+        .route("/integrity", get(api::get_integrity_report))
+        .route("/janitor", get(api::get_janitor_report))
+        .route("/janitor/reconcile", post(api::reconcile_janitor))
+        .route("/validation_policy", get(api::get_validation_policy).post(api::set_validation_policy))
+        .route("/orders/:id/what_if", get(api::what_if_order))
+        .route("/orders/:id/backtrace", get(api::backtrace_order))
+        .route("/orders/failed", get(api::list_failed_orders))
+        .route("/orders/retry_failed", post(api::retry_failed_orders))
+        .route("/orders/:id/archive", post(api::archive_order))
+        .route("/orders/:id/receipt", get(api::get_order_receipt))
+        .route("/order_history/:id", get(api::get_order_history))
+        .route("/get_quote", post(api::get_quote))
+        .route("/depth", get(api::get_depth))
+        .route("/swap_route", post(api::swap_route))
+        .route("/transfer", post(api::transfer))
+        .route("/paper_accounts", get(api::list_paper_accounts))
+        .route("/paper_accounts/balances", post(api::set_paper_balance))
+        .route("/paper_accounts/:wallet", delete(api::reset_paper_account))
+        .route("/ledger", get(api::get_ledger))
+        .route("/transactions", get(api::get_transactions))
+        .route("/pnl", get(api::get_pnl))
+        .route("/stats/strategies", get(api::get_strategy_stats))
+        .route("/stats/slippage", get(api::get_slippage_stats))
+        .route("/notifications/config", get(api::get_notification_config).post(api::set_notification_config))
+        .route("/notifications/templates", get(api::get_notification_templates).post(api::set_notification_template))
+        .route("/notifications/templates/:locale/:name", delete(api::remove_notification_template))
+        .route("/notifications/locale", get(api::get_notification_locale).post(api::set_notification_locale))
+        .route("/preferences", get(api::get_preferences).post(api::set_preferences))
+        .route("/alerts/balance", get(api::list_balance_alerts).post(api::create_balance_alert))
+        .route("/alerts/balance/:id", delete(api::remove_balance_alert))
+        .route("/alerts/funding", get(api::list_funding_alerts).post(api::create_funding_alert))
+        .route("/alerts/funding/:id", delete(api::remove_funding_alert))
+        .route("/perps/:market", get(api::get_perp_market))
+        .route("/ws", get(ws::ws_handler))
+        .route("/usage", get(api::get_usage))
+        .route("/usage/quotas", get(api::get_usage_quotas).post(api::set_usage_quotas))
+        .route("/tenants/limits", get(api::get_tenant_limits).post(api::set_tenant_limits))
+        .route("/rate_limits", get(api::get_rate_limits).post(api::set_rate_limits))
+        .route("/api_keys", get(api::list_api_keys).post(api::create_api_key))
+        .route("/api_keys/:id", delete(api::revoke_api_key))
+        .route("/unlock_wallet", post(api::unlock_wallet))
+        .route("/lock_wallet", post(api::lock_wallet))
+        .route("/remove_wallet", post(api::remove_wallet))
+        .route("/export_wallet", post(api::export_wallet));
+
+    // Fault injection for resilience tests, only compiled in with the chaos feature
+    #[cfg(feature = "chaos")]
+    let app = app.route(
+        "/admin/chaos",
+        get(api::get_chaos).post(api::set_chaos).delete(api::clear_chaos),
+    );
+
+    // Require an API key in hosted mode and count every request against it; the state
+    // extension has to be layered outside so the middleware can see it. Tenant checks only
+    // wrap the API routes, not the static files served by the fallback. Error responses that
+    // bypass the handlers, such as rejected JSON bodies, get the structured error body.
+    // Per-route budgets come first, so floods without a valid key are cut off too.
+    let app = app
+        .route_layer(middleware::from_fn(error::structure_errors))
+        .route_layer(middleware::from_fn(integrity::enforce_safe_mode))
+        .route_layer(middleware::from_fn(auth::enforce_scope))
+        .route_layer(middleware::from_fn(tenant::enforce_tenant))
+        .route_layer(middleware::from_fn(rate_limit::enforce_rate_limits))
+        .layer(middleware::from_fn(metering::track_usage))
         .layer(Extension(app_state))
+        .layer(Extension(wallet_state))
         .layer(cors)
         // Serve static files from the static directory
-        .nest_service("/", ServeDir::new(static_dir));
+        .fallback(get_service(ServeDir::new(static_dir)).handle_error(|err: std::io::Error| async move {
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to serve static file: {}", err))
+        }));
 
     // Define our address
     let addr = SocketAddr::from(([127, 0, 0, 1], 3301));
@@ -105,7 +296,7 @@ async fn main() {
 
     // Start the server
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
@@ -0,0 +1,153 @@
+use crate::models::{ApiUsage, AppState, QuotaExceeded, QuotaKind, UsageQuota, UsageQuotas, UsageReport};
+#[cfg(feature = "server")]
+use crate::utils;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "server")]
+use axum::{
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use tracing::info;
+#[cfg(feature = "server")]
+use tracing::warn;
+
+// Header carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Bucket for requests made without an API key
+pub const ANONYMOUS_KEY: &str = "anonymous";
+
+// Caller's API key, or the shared anonymous bucket
+#[cfg(feature = "server")]
+pub fn api_key_from_headers(headers: &HeaderMap) -> String {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .unwrap_or(ANONYMOUS_KEY)
+        .to_string()
+}
+
+// Quotas reset at the start of every calendar month
+fn current_period(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+// Quota that applies to an API key
+fn quota_for(app_state: &AppState, api_key: &str) -> UsageQuota {
+    let quotas = app_state.usage_quotas.lock().unwrap();
+    quotas.keys.get(api_key).cloned().unwrap_or_else(|| quotas.default.clone())
+}
+
+// Run `f` on the key's usage for the current month, starting a fresh entry when the month rolled over
+fn with_usage<T>(app_state: &AppState, api_key: &str, f: impl FnOnce(&mut ApiUsage) -> T) -> T {
+    let period = current_period(Utc::now());
+    let mut usage = app_state.usage.lock().unwrap();
+    let entry = usage.entry(api_key.to_string()).or_insert_with(|| ApiUsage {
+        period: period.clone(),
+        requests: 0,
+        notional_usd: 0.0,
+    });
+
+    if entry.period != period {
+        *entry = ApiUsage {
+            period,
+            requests: 0,
+            notional_usd: 0.0,
+        };
+    }
+
+    f(entry)
+}
+
+// Count a request against the key, refusing it once the monthly request quota is used up
+pub fn record_request(app_state: &AppState, api_key: &str) -> Result<(), QuotaExceeded> {
+    let quota = quota_for(app_state, api_key);
+
+    with_usage(app_state, api_key, |usage| {
+        if let Some(max_requests) = quota.max_requests {
+            if usage.requests >= max_requests {
+                return Err(QuotaExceeded {
+                    kind: QuotaKind::Requests,
+                    used: usage.requests as f64,
+                    limit: max_requests as f64,
+                });
+            }
+        }
+        usage.requests += 1;
+        Ok(())
+    })
+}
+
+// Whether the key can still execute `notional_usd` this month
+pub fn check_notional(app_state: &AppState, api_key: &str, notional_usd: f64) -> Result<(), QuotaExceeded> {
+    let quota = quota_for(app_state, api_key);
+
+    with_usage(app_state, api_key, |usage| match quota.max_notional_usd {
+        Some(max_notional_usd) if usage.notional_usd + notional_usd > max_notional_usd => Err(QuotaExceeded {
+            kind: QuotaKind::NotionalUsd,
+            used: usage.notional_usd,
+            limit: max_notional_usd,
+        }),
+        _ => Ok(()),
+    })
+}
+
+// Add executed notional to the key's usage
+pub fn record_notional(app_state: &AppState, api_key: &str, notional_usd: f64) {
+    with_usage(app_state, api_key, |usage| usage.notional_usd += notional_usd);
+}
+
+// Usage of one key against its quota
+pub fn get_usage(app_state: &AppState, api_key: &str) -> UsageReport {
+    let quota = quota_for(app_state, api_key);
+    let usage = with_usage(app_state, api_key, |usage| usage.clone());
+
+    UsageReport {
+        api_key: api_key.to_string(),
+        usage,
+        quota,
+    }
+}
+
+// Current quota configuration
+pub fn get_quotas(app_state: &AppState) -> UsageQuotas {
+    let quotas = app_state.usage_quotas.lock().unwrap();
+    quotas.clone()
+}
+
+// Replace the quota configuration
+pub fn set_quotas(app_state: &AppState, quotas: UsageQuotas) -> Result<UsageQuotas> {
+    let all = std::iter::once(&quotas.default).chain(quotas.keys.values());
+    for quota in all {
+        if quota.max_notional_usd.is_some_and(|max| max < 0.0) {
+            return Err(anyhow!("max_notional_usd cannot be negative"));
+        }
+    }
+
+    let mut current = app_state.usage_quotas.lock().unwrap();
+    *current = quotas.clone();
+    info!("Usage quotas updated ({} key overrides)", quotas.keys.len());
+    Ok(quotas)
+}
+
+// Middleware counting every request against the caller's API key
+#[cfg(feature = "server")]
+pub async fn track_usage<B>(request: Request<B>, next: Next<B>) -> Response {
+    let app_state = request.extensions().get::<Arc<AppState>>().cloned();
+
+    if let Some(app_state) = app_state {
+        let api_key = api_key_from_headers(request.headers());
+        if let Err(err) = record_request(&app_state, &api_key) {
+            warn!("Rejected request to {}: {}", request.uri().path(), err);
+            return utils::build_error_response(StatusCode::TOO_MANY_REQUESTS, &err.to_string()).into_response();
+        }
+    }
+
+    next.run(request).await
+}
@@ -1,20 +1,94 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     pubkey::Pubkey,
     signature::Keypair,
 };
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     fmt,
     sync::Mutex,
 };
+use tokio::sync::broadcast;
+use utoipa::{IntoParams, ToSchema};
+
+// Events buffered per subscriber before slow subscribers start missing them
+const EVENT_BUFFER: usize = 256;
 
 // Main application state
 pub struct AppState {
     pub wallets: Mutex<HashMap<String, Wallet>>,
     pub limit_orders: Mutex<HashMap<String, LimitOrder>>,
+    pub dca_orders: Mutex<HashMap<String, DcaOrder>>,
+    pub order_events: Mutex<HashMap<String, Vec<OrderEvent>>>,
+    pub execution_receipts: Mutex<HashMap<String, ExecutionReceipt>>,
     pub token_prices: Mutex<HashMap<String, f64>>,
+    pub price_meta: Mutex<HashMap<String, PriceMeta>>,
+    pub price_history: Mutex<HashMap<String, VecDeque<PricePoint>>>,
+    pub execution_queue: Mutex<Vec<QueuedOrder>>,
+    // Held while triggered orders execute, so the monitor and the price stream never run one order twice
+    pub execution_pass: tokio::sync::Mutex<()>,
+    pub execution_latencies: Mutex<VecDeque<ExecutionLatency>>,
+    // Quote against fill of recent executions, oldest first
+    pub slippage_samples: Mutex<VecDeque<SlippageSample>>,
+    // Why trading is paused, one reason per source that paused it
+    pub pause_reasons: Mutex<Vec<String>>,
+    pub portfolio_history: Mutex<Vec<PortfolioSnapshot>>,
+    pub liquidation_policy: Mutex<Option<LiquidationPolicy>>,
+    pub source_prices: Mutex<HashMap<String, HashMap<String, SourcePrice>>>,
+    pub price_guards: Mutex<HashMap<String, PriceGuardConfig>>,
+    pub provider_preferences: Mutex<HashMap<String, ProviderPreference>>,
+    pub watchlists: Mutex<HashMap<String, Vec<WatchlistEntry>>>,
+    pub paper_accounts: Mutex<HashMap<String, PaperAccount>>,
+    pub depth_cache: Mutex<HashMap<String, DepthResponse>>,
+    pub upstream_cache: Mutex<HashMap<String, CachedPayload>>,
+    pub ledger: Mutex<Vec<LedgerEntry>>,
+    // Average cost basis per wallet and token, and the profit of each trade, kept from the ledger
+    pub cost_basis: Mutex<HashMap<(String, String), CostBasis>>,
+    pub trade_pnl: Mutex<Vec<TradePnl>>,
+    pub notifier: Mutex<NotifierState>,
+    pub preferences: Mutex<Preferences>,
+    pub engine_health: Mutex<EngineHealth>,
+    pub balance_alerts: Mutex<HashMap<String, BalanceAlert>>,
+    pub funding_alerts: Mutex<HashMap<String, FundingRateAlert>>,
+    pub perp_markets: Mutex<HashMap<String, PerpMarket>>,
+    // SOL reserve policy of each tenant, under its settings key
+    pub sol_reserve_policies: Mutex<HashMap<String, SolReservePolicy>>,
+    pub archived_orders: Mutex<HashMap<String, LimitOrder>>,
+    // Order swaps sent but not confirmed in time, by order id
+    pub unconfirmed_executions: Mutex<HashMap<String, UnconfirmedExecution>>,
+    pub exposure_limits: Mutex<HashMap<String, ExposureLimit>>,
+    pub blackout_windows: Mutex<HashMap<String, BlackoutWindow>>,
+    pub janitor_report: Mutex<Option<JanitorReport>>,
+    pub integrity_report: Mutex<Option<IntegrityReport>>,
+    pub route_denylist: Mutex<RouteDenylist>,
+    pub validation_policy: Mutex<ValidationPolicy>,
+    pub prices_updated_at: Mutex<Option<DateTime<Utc>>>,
+    pub usage: Mutex<HashMap<String, ApiUsage>>,
+    pub usage_quotas: Mutex<UsageQuotas>,
+    pub api_keys: Mutex<HashMap<String, ApiKeyRecord>>,
+    pub wallet_owners: Mutex<HashMap<String, String>>,
+    pub wallet_labels: Mutex<HashMap<String, String>>,
+    pub wallet_added_at: Mutex<HashMap<String, DateTime<Utc>>>,
+    pub tenant_limits: Mutex<TenantLimitsConfig>,
+    pub tenant_windows: Mutex<HashMap<String, RateWindow>>,
+    pub rate_limits: Mutex<RateLimitConfig>,
+    // Request buckets by client and route pattern
+    pub rate_buckets: Mutex<HashMap<(String, String), RequestBucket>>,
+    // Recent order creations of each API key, and keys whose creations are quarantined
+    pub order_creations: Mutex<HashMap<String, VecDeque<DateTime<Utc>>>>,
+    pub creation_quarantines: Mutex<HashMap<String, CreationQuarantine>>,
+    // Wallets whose automatic executions and new orders are suspended, by public key
+    pub frozen_wallets: Mutex<HashMap<String, WalletFreeze>>,
+    pub tenant_master_key: [u8; 32],
+    // Server key execution receipts are signed with
+    pub receipt_signer: Keypair,
+    // Price sources in the order tokens without a preference try them
+    pub price_providers: Vec<Box<dyn crate::price::PriceProvider>>,
+    pub rpc_client: RpcClient,
+    pub events: broadcast::Sender<StreamEvent>,
+    pub started_at: DateTime<Utc>,
 }
 
 impl AppState {
@@ -22,7 +96,64 @@ impl AppState {
         Self {
             wallets: Mutex::new(HashMap::new()),
             limit_orders: Mutex::new(HashMap::new()),
+            dca_orders: Mutex::new(HashMap::new()),
+            order_events: Mutex::new(HashMap::new()),
+            execution_receipts: Mutex::new(HashMap::new()),
             token_prices: Mutex::new(HashMap::new()),
+            price_meta: Mutex::new(HashMap::new()),
+            price_history: Mutex::new(HashMap::new()),
+            execution_queue: Mutex::new(Vec::new()),
+            execution_pass: tokio::sync::Mutex::new(()),
+            execution_latencies: Mutex::new(VecDeque::new()),
+            slippage_samples: Mutex::new(VecDeque::new()),
+            pause_reasons: Mutex::new(Vec::new()),
+            portfolio_history: Mutex::new(Vec::new()),
+            liquidation_policy: Mutex::new(None),
+            source_prices: Mutex::new(HashMap::new()),
+            price_guards: Mutex::new(HashMap::new()),
+            provider_preferences: Mutex::new(HashMap::new()),
+            watchlists: Mutex::new(HashMap::new()),
+            paper_accounts: Mutex::new(HashMap::new()),
+            depth_cache: Mutex::new(HashMap::new()),
+            upstream_cache: Mutex::new(HashMap::new()),
+            ledger: Mutex::new(Vec::new()),
+            cost_basis: Mutex::new(HashMap::new()),
+            trade_pnl: Mutex::new(Vec::new()),
+            notifier: Mutex::new(NotifierState::default()),
+            preferences: Mutex::new(Preferences::default()),
+            engine_health: Mutex::new(EngineHealth::default()),
+            balance_alerts: Mutex::new(HashMap::new()),
+            funding_alerts: Mutex::new(HashMap::new()),
+            perp_markets: Mutex::new(HashMap::new()),
+            sol_reserve_policies: Mutex::new(HashMap::new()),
+            archived_orders: Mutex::new(HashMap::new()),
+            unconfirmed_executions: Mutex::new(HashMap::new()),
+            exposure_limits: Mutex::new(HashMap::new()),
+            blackout_windows: Mutex::new(HashMap::new()),
+            janitor_report: Mutex::new(None),
+            integrity_report: Mutex::new(None),
+            route_denylist: Mutex::new(RouteDenylist::default()),
+            validation_policy: Mutex::new(ValidationPolicy::default()),
+            prices_updated_at: Mutex::new(None),
+            usage: Mutex::new(HashMap::new()),
+            usage_quotas: Mutex::new(UsageQuotas::default()),
+            api_keys: Mutex::new(crate::auth::load_env_keys()),
+            wallet_owners: Mutex::new(HashMap::new()),
+            wallet_labels: Mutex::new(HashMap::new()),
+            wallet_added_at: Mutex::new(HashMap::new()),
+            tenant_limits: Mutex::new(TenantLimitsConfig::default()),
+            tenant_windows: Mutex::new(HashMap::new()),
+            rate_limits: Mutex::new(RateLimitConfig::default()),
+            rate_buckets: Mutex::new(HashMap::new()),
+            order_creations: Mutex::new(HashMap::new()),
+            creation_quarantines: Mutex::new(HashMap::new()),
+            frozen_wallets: Mutex::new(HashMap::new()),
+            tenant_master_key: crate::tenant::load_master_key(),
+            receipt_signer: crate::receipts::load_signing_key(),
+            price_providers: crate::price::default_providers(),
+            rpc_client: crate::wallet::new_rpc_client(),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            started_at: Utc::now(),
         }
     }
 }
@@ -33,16 +164,33 @@ pub struct Wallet {
     pub pubkey: Pubkey,
 }
 
+// Keypair is not Clone, so copy it through its byte representation. This lets
+// handlers take a wallet out of the lock before awaiting on RPC calls.
+impl Clone for Wallet {
+    fn clone(&self) -> Self {
+        Self {
+            keypair: Keypair::from_bytes(&self.keypair.to_bytes())
+                .expect("keypair bytes round-trip"),
+            pubkey: self.pubkey,
+        }
+    }
+}
+
 // Token Balance for the API response
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct TokenBalance {
     pub mint: String,
     pub symbol: String,
     pub amount: f64,
+    // Exact amount in base units, string encoded so large balances survive JSON parsers
+    #[serde(default)]
+    pub raw_amount: String,
+    #[serde(default)]
+    pub decimals: u8,
 }
 
 // Token Price for the API response
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct TokenPrice {
     pub mint: String,
     pub symbol: String,
@@ -50,32 +198,337 @@ pub struct TokenPrice {
     pub last_updated: DateTime<Utc>,
 }
 
+// Where a cached price came from and when
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct PriceMeta {
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Cached price of a token at one moment, kept for backtraces
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct PricePoint {
+    pub price_usd: f64,
+    pub source: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Price of a single mint with its source and freshness
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MintPrice {
+    pub mint: String,
+    pub symbol: String,
+    pub price_usd: f64,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+    pub age_secs: i64,
+    // Whether the price was served from cache rather than fetched for this request
+    pub cached: bool,
+}
+
+// Price an external feed pushes for a token
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct PushedPrice {
+    pub mint: String,
+    pub price_usd: f64,
+}
+
+// Batch of prices pushed by an external feed
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct PricePushRequest {
+    pub prices: Vec<PushedPrice>,
+}
+
+// How prices from several sources are combined for trigger evaluation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum PriceAggregation {
+    Median,
+    Weighted,
+}
+
+// Per-token price manipulation guard
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PriceGuardConfig {
+    pub mint: String,
+    pub aggregation: PriceAggregation,
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+    pub max_divergence_percent: Option<f64>,
+}
+
+// Order in which price providers are tried for a token, before the remaining ones
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ProviderPreference {
+    pub mint: String,
+    pub providers: Vec<String>,
+}
+
+// Available price providers, their default order and the per-token preferences
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PriceProviders {
+    pub default_order: Vec<String>,
+    pub preferences: Vec<ProviderPreference>,
+}
+
+// Latest price one source reported for a token, and when it was current
+#[derive(Clone, Debug)]
+pub struct SourcePrice {
+    pub price_usd: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Spread between price sources for a token
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PriceDivergence {
+    pub mint: String,
+    pub symbol: String,
+    pub sources: HashMap<String, f64>,
+    pub aggregated_price: Option<f64>,
+    pub divergence_percent: f64,
+    pub guarded: bool,
+}
+
 // Swap request
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug, ToSchema)]
 pub struct SwapRequest {
     pub source_token: String,
     pub target_token: String,
+    #[serde(default)]
     pub amount: f64,
+    // Swap this share of the wallet's source token balance instead of a fixed amount, in percent
+    #[serde(default)]
+    pub amount_percent: Option<f64>,
+    // Swap this many US dollars worth of the source token instead of a fixed amount
+    #[serde(default)]
+    pub amount_usd: Option<f64>,
     pub slippage: Option<f64>,
+    // Wallet to swap from, defaults to the first imported wallet
+    #[serde(alias = "wallet")]
+    pub wallet_pubkey: Option<String>,
+    // Fill the swap from its quote against the wallet's paper balances instead of sending it
+    #[serde(default)]
+    pub dry_run: bool,
+    // Priority fee to pay, the JUPITER_PRIORITY_FEE_LAMPORTS setting or Jupiter's choice unless set
+    #[serde(default)]
+    pub priority_fee: Option<PriorityFee>,
+    // Send the swap as a tipped Jito bundle instead of through the RPC node, out of reach of sandwich bots
+    #[serde(default)]
+    pub mev_protection: bool,
+}
+
+// How much priority fee a swap pays. Low, auto and high bid the 25th, 50th and 90th percentile
+// of the fees recently paid to write the route's pool accounts; custom is a flat lamport amount.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityFee {
+    Auto,
+    Low,
+    High,
+    Custom(u64),
 }
 
 // Swap response
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Clone, Debug, ToSchema)]
 pub struct SwapResponse {
     pub transaction_signature: String,
     pub source_amount: f64,
+    pub source_amount_raw: String,
     pub target_amount: f64,
+    pub target_amount_raw: String,
     pub fee: f64,
+    pub fee_lamports: String,
+    pub fee_details: TransactionFee,
     pub success: bool,
     pub timestamp: DateTime<Utc>,
+    // DEX labels excluded after the first quote routed through the route denylist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub route_exclusions: Vec<String>,
+    #[serde(default)]
+    pub latency: ExecutionLatency,
+    // Filled against paper balances, nothing was sent
+    #[serde(default)]
+    pub simulated: bool,
+    // Target tokens the wallet received according to the confirmed transaction, where
+    // `target_amount` is what the quote promised
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filled_target_amount: Option<f64>,
+}
+
+// When each phase of an execution happened, to see where the engine spends its time
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct ExecutionLatency {
+    pub trigger_detected_at: Option<DateTime<Utc>>,
+    pub quote_received_at: Option<DateTime<Utc>>,
+    pub signed_at: Option<DateTime<Utc>>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    // Unset when the transaction was not confirmed in time
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+// Latency percentiles of one stretch of the execution path, in milliseconds
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PhaseLatency {
+    pub phase: String,
+    pub samples: usize,
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+}
+
+// Latency percentiles over the most recent order executions
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct LatencyMetrics {
+    pub executions: usize,
+    pub phases: Vec<PhaseLatency>,
+}
+
+// Fees paid by a transaction, taken from the confirmed transaction when available
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct TransactionFee {
+    pub network_fee_sol: f64,
+    pub priority_fee_sol: f64,
+    // Aggregator platform fee, charged in the output token
+    pub platform_fee: f64,
+    pub platform_fee_mint: Option<String>,
+    pub rent_sol: f64,
+    // Tip paid to the Jito block engine for a MEV-protected swap
+    #[serde(default)]
+    pub jito_tip_sol: f64,
+    pub total_sol: f64,
+    pub total_usd: Option<f64>,
+    pub confirmed: bool,
+}
+
+// One hop of a multi-leg swap
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SwapRouteLeg {
+    pub target_token: String,
+    pub slippage: Option<f64>,
+}
+
+// Multi-leg swap request, e.g. A -> B -> C as sequential swaps
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SwapRouteRequest {
+    pub source_token: String,
+    pub amount: f64,
+    pub legs: Vec<SwapRouteLeg>,
+}
+
+// Result of a multi-leg swap, including partial completion
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SwapRouteResponse {
+    pub completed_legs: Vec<SwapResponse>,
+    pub failed_leg: Option<usize>,
+    pub error: Option<String>,
+    // Signature of the leg the route stopped at because it was sent but not confirmed in time.
+    // Its funds may be in either token until it is seen landing or failing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unconfirmed_signature: Option<String>,
+    pub complete: bool,
+    pub final_token: String,
+    pub final_amount: f64,
+}
+
+// Price an order's trigger is evaluated against. The perp mark price is smoother than
+// spot and less prone to wicks on thin spot liquidity.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerSource {
+    #[default]
+    Spot,
+    PerpMark,
+}
+
+// What an order does once its trigger fires
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderAction {
+    // Swap the source token into the target token
+    #[default]
+    Swap,
+    // Keep the spot position and open a short of equivalent notional on Drift instead
+    Hedge(HedgeAction),
+    // Move the order's amount of the source token to a safe wallet
+    Transfer(TransferAction),
+    // Swap everything the wallet holds into a stable token
+    ConvertToStable(ConvertToStableAction),
+    // Sell the wallet's whole balance of the source token at trigger time, whatever the order's amount
+    SafeExit,
+    // Only notify that the trigger fired, without trading
+    NotifyOnly,
+}
+
+impl OrderAction {
+    // Whether the action spends the order wallet's funds, so the balances are checked up front
+    pub fn uses_wallet_funds(&self) -> bool {
+        !matches!(self, OrderAction::Hedge(_) | OrderAction::NotifyOnly)
+    }
+
+    // Whether the action sells whatever the wallet holds when it runs, so the order's amount is not checked up front
+    pub fn sells_whole_balance(&self) -> bool {
+        matches!(self, OrderAction::SafeExit)
+    }
+}
+
+// Destination of a transfer action
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct TransferAction {
+    pub destination: String,
+}
+
+// Stable token a convert-to-stable action swaps into
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, ToSchema)]
+pub struct ConvertToStableAction {
+    // USDC unless set
+    #[serde(default)]
+    pub stable_token: Option<String>,
+}
+
+// Short opened by a hedge action
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, ToSchema)]
+pub struct HedgeAction {
+    // Drift perp market to short, defaults to the market tracking the order's target token
+    #[serde(default)]
+    pub market: Option<String>,
+    // Share of the order's notional to hedge, all of it unless set
+    #[serde(default)]
+    pub size_percent: Option<f64>,
+}
+
+impl fmt::Display for OrderAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderAction::Swap => write!(f, "swap"),
+            OrderAction::Hedge(_) => write!(f, "hedge"),
+            OrderAction::Transfer(_) => write!(f, "transfer"),
+            OrderAction::ConvertToStable(_) => write!(f, "convert to stable"),
+            OrderAction::SafeExit => write!(f, "safe exit"),
+            OrderAction::NotifyOnly => write!(f, "notify only"),
+        }
+    }
 }
 
 // Order types
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
 pub enum OrderType {
     Buy,
     Sell,
     StopLoss,
+    TakeProfit,
+    // Buys once the price rises to the target, for breakout entries
+    StopBuy,
+    DeadMansSwitch,
+}
+
+impl OrderType {
+    // Orders that spend the source token balance when they execute
+    pub fn is_sell_side(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Sell | OrderType::StopLoss | OrderType::TakeProfit | OrderType::DeadMansSwitch
+        )
+    }
 }
 
 // Add Display implementation for OrderType
@@ -85,72 +538,2212 @@ impl fmt::Display for OrderType {
             OrderType::Buy => write!(f, "Buy"),
             OrderType::Sell => write!(f, "Sell"),
             OrderType::StopLoss => write!(f, "Stop Loss"),
+            OrderType::TakeProfit => write!(f, "Take Profit"),
+            OrderType::StopBuy => write!(f, "Stop Buy"),
+            OrderType::DeadMansSwitch => write!(f, "Dead Man's Switch"),
         }
     }
 }
 
 // Order status
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
 pub enum OrderStatus {
     Active,
+    PendingFunds,
+    Executing,
     Completed,
     Cancelled,
+    Expired,
     Failed,
+    // A transient failure is waiting out its backoff before the order executes again
+    Retrying,
+    // The swap was sent but not confirmed in time; its signature is polled until it lands or fails
+    Unconfirmed,
+}
+
+impl OrderStatus {
+    // Valid lifecycle transitions. Executing can fall back to Active when an
+    // execution is abandoned before anything was sent, and Failed orders can be retried.
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (Active, Executing)
+                | (Active, PendingFunds)
+                | (Active, Cancelled)
+                | (Active, Expired)
+                | (PendingFunds, Active)
+                | (PendingFunds, Cancelled)
+                | (PendingFunds, Expired)
+                | (Executing, Completed)
+                | (Executing, Failed)
+                | (Executing, Active)
+                | (Executing, Retrying)
+                | (Executing, Unconfirmed)
+                | (Unconfirmed, Completed)
+                | (Unconfirmed, Failed)
+                | (Retrying, Executing)
+                | (Retrying, Cancelled)
+                | (Retrying, Expired)
+                | (Failed, Active)
+                | (Failed, Cancelled)
+        )
+    }
+
+    // Statuses an order never leaves
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderStatus::Completed | OrderStatus::Cancelled | OrderStatus::Expired)
+    }
+}
+
+// Rejected order status change
+#[derive(Debug, Clone)]
+pub struct InvalidTransition {
+    pub order_id: String,
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid status transition for order {}: {:?} -> {:?}",
+            self.order_id, self.from, self.to
+        )
+    }
 }
 
+impl std::error::Error for InvalidTransition {}
+
 // Limit order request
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct LimitOrderRequest {
     pub source_token: String,
     pub target_token: String,
+    #[serde(default)]
     pub amount: f64,
+    // Size the order as this share of the wallet's source token balance when it triggers, in percent
+    #[serde(default)]
+    pub amount_percent: Option<f64>,
+    // Size the order as this many US dollars worth of the token it trades, converted when it triggers
+    #[serde(default)]
+    pub amount_usd: Option<f64>,
     pub price_target: f64,
     pub order_type: OrderType,
     pub expiry_time: Option<DateTime<Utc>>,
     pub slippage: Option<f64>,
+    pub priority: Option<i32>,
+    pub heartbeat_interval_secs: Option<u64>,
+    pub strategy: Option<String>,
+    // Price the trigger is evaluated against, the spot price unless set
+    #[serde(default)]
+    pub trigger_source: TriggerSource,
+    // Token whose price triggers the order, the target token unless set
+    #[serde(default)]
+    pub trigger_mint: Option<String>,
+    // Token the trigger price is quoted in, USD unless set. With SOL, "BONK/SOL below X" triggers on BONK per SOL.
+    #[serde(default)]
+    pub quote_mint: Option<String>,
+    // What to do once triggered, a swap unless set
+    #[serde(default)]
+    pub action: OrderAction,
+    // Most the order may pay in network and priority fees plus price impact, in USD
+    #[serde(default)]
+    pub max_fee_usd: Option<f64>,
+    // How often the trigger is checked, the monitor interval unless set
+    #[serde(default)]
+    pub check_interval_secs: Option<u64>,
+    // Execute against paper balances instead of sending transactions
+    #[serde(default)]
+    pub dry_run: bool,
+    // Priority fee the order's swaps pay once it triggers
+    #[serde(default)]
+    pub priority_fee: Option<PriorityFee>,
+    // Send the order's swaps as Jito bundles
+    #[serde(default)]
+    pub mev_protection: bool,
+    // Rest a Buy or Sell order on the Jupiter limit order program as a maker order instead of polling it
+    #[serde(default)]
+    pub passive: bool,
+    // Wallet the order trades from, defaults to the first imported wallet
+    pub wallet_pubkey: Option<String>,
+    // Create the order even if its price target breaks the validation policy
+    #[serde(default)]
+    pub force: bool,
+    // Times a transient execution failure is retried before the order fails, ORDER_MAX_RETRIES unless set
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    // Wait before the first retry, doubled for each one after it, ORDER_RETRY_BACKOFF_SECS unless set
+    #[serde(default)]
+    pub retry_backoff_secs: Option<u64>,
+    // API key the order is metered against, taken from the request headers
+    #[serde(skip)]
+    pub api_key: Option<String>,
 }
 
 // Limit order response
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct LimitOrder {
     pub id: String,
     pub source_token: String,
     pub target_token: String,
+    // Estimated at creation for orders sized with amount_percent or amount_usd, and set when they trigger
     pub amount: f64,
+    #[serde(default)]
+    pub amount_percent: Option<f64>,
+    #[serde(default)]
+    pub amount_usd: Option<f64>,
     pub price_target: f64,
     pub order_type: OrderType,
     pub status: OrderStatus,
+    #[serde(default)]
+    pub amount_raw: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub expiry_time: Option<DateTime<Utc>>,
     pub slippage: f64,
     pub transaction_signature: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub heartbeat_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub fee_estimate: Option<FeeBreakdown>,
+    #[serde(default)]
+    pub validation: Option<OrderValidation>,
+    #[serde(default)]
+    pub fee: Option<TransactionFee>,
+    #[serde(default)]
+    pub failure: Option<OrderFailure>,
+    // Retries of transient execution failures: allowed, made so far and when the next one is due.
+    // Orders stored before retries existed fail on the first error, as they did then.
+    #[serde(default)]
+    pub max_retries: u32,
+    #[serde(default)]
+    pub retry_backoff_secs: u64,
+    #[serde(default)]
+    pub retry_count: u32,
+    #[serde(default)]
+    pub next_retry_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub strategy: Option<String>,
+    #[serde(default)]
+    pub execution: Option<ExecutionReport>,
+    // When each phase of the execution happened
+    #[serde(default)]
+    pub latency: Option<ExecutionLatency>,
+    // Decisions taken on the order's behalf, such as requoting around denylisted routes
+    #[serde(default)]
+    pub audit_trail: Vec<OrderAuditEntry>,
+    #[serde(default)]
+    pub trigger_source: TriggerSource,
+    #[serde(default)]
+    pub trigger_mint: Option<String>,
+    #[serde(default)]
+    pub quote_mint: Option<String>,
+    #[serde(default)]
+    pub action: OrderAction,
+    #[serde(default)]
+    pub max_fee_usd: Option<f64>,
+    #[serde(default)]
+    pub check_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub priority_fee: Option<PriorityFee>,
+    #[serde(default)]
+    pub mev_protection: bool,
+    // On-chain order of a passive order and the fills synced back from it
+    #[serde(default)]
+    pub passive: Option<PassiveOrder>,
+    // Wallet the order was created for; execution fails if it is no longer imported
+    #[serde(default)]
+    pub wallet_pubkey: String,
+    // API key (tenant) that owns the order and is metered for its notional, never returned to clients
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+// Recurring buy: swap `amount_per_buy` of the source token every `interval_secs` until `total_budget` is spent
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DcaOrderRequest {
+    pub source_token: String,
+    pub target_token: String,
+    pub amount_per_buy: f64,
+    pub interval_secs: u64,
+    pub total_budget: f64,
+    pub slippage: Option<f64>,
+    // Wallet the buys trade from, defaults to the first imported wallet
+    pub wallet_pubkey: Option<String>,
+    #[serde(skip)]
+    pub api_key: Option<String>,
+}
+
+// DCA order status
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum DcaStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+// One executed buy of a DCA order
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct DcaFill {
+    pub source_amount: f64,
+    pub target_amount: f64,
+    // Source tokens paid per target token received
+    pub price: f64,
+    pub transaction_signature: String,
+    pub fee: TransactionFee,
+    pub executed_at: DateTime<Utc>,
+}
+
+// Recurring buy order and the fills it made so far
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct DcaOrder {
+    pub id: String,
+    pub source_token: String,
+    pub target_token: String,
+    pub amount_per_buy: f64,
+    pub interval_secs: u64,
+    pub total_budget: f64,
+    pub slippage: f64,
+    pub status: DcaStatus,
+    // Source tokens spent and target tokens received across all fills
+    pub spent: f64,
+    pub received: f64,
+    pub fills: Vec<DcaFill>,
+    pub next_buy_at: DateTime<Utc>,
+    // Why the last buy failed, cleared by the next fill; failed buys are retried at the next interval
+    pub last_error: Option<String>,
+    pub wallet_pubkey: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+// What an order actually got when it executed, valued at the prices of the moment
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ExecutionReport {
+    pub source_amount: f64,
+    pub target_amount: f64,
+    pub expected_target_amount: f64,
+    pub slippage_percent: f64,
+    pub input_value_usd: f64,
+    pub executed_at: DateTime<Utc>,
+    // USD prices the tokens were valued at when the order executed
+    #[serde(default)]
+    pub source_price_usd: Option<f64>,
+    #[serde(default)]
+    pub target_price_usd: Option<f64>,
+}
+
+// Maker order a passive limit order rests as on the Jupiter limit order program. Amounts are raw
+// units of the input (source) and output (target) tokens.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PassiveOrder {
+    pub order_account: String,
+    pub in_amount_raw: u64,
+    pub out_amount_raw: u64,
+    pub filled_in_raw: u64,
+    pub filled_out_raw: u64,
+    pub create_signature: String,
+    pub cancel_signature: Option<String>,
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+impl LimitOrder {
+    // Move the order to a new status, rejecting transitions the lifecycle does not allow
+    pub fn transition(&mut self, next: OrderStatus) -> Result<(), InvalidTransition> {
+        if !self.status.can_transition_to(&next) {
+            return Err(InvalidTransition {
+                order_id: self.id.clone(),
+                from: self.status.clone(),
+                to: next,
+            });
+        }
+
+        self.status = next;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    // Token whose price triggers the order
+    pub fn trigger_mint(&self) -> &str {
+        self.trigger_mint.as_deref().unwrap_or(&self.target_token)
+    }
+}
+
+// Normalized reason an order execution failed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+pub enum FailureCause {
+    InsufficientBalance,
+    InsufficientFees,
+    SlippageExceeded,
+    RouteNotFound,
+    RpcTimeout,
+    ExposureLimit,
+    QuotaExceeded,
+    Unknown,
+}
+
+impl FailureCause {
+    // Map an execution error message to its cause
+    pub fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("insufficient sol") || message.contains("transaction fees") {
+            FailureCause::InsufficientFees
+        } else if message.contains("insufficient balance") {
+            FailureCause::InsufficientBalance
+        } else if message.contains("exposure limit") {
+            FailureCause::ExposureLimit
+        } else if message.contains("quota") {
+            FailureCause::QuotaExceeded
+        } else if message.contains("slippage") || message.contains("0x1771") {
+            // 0x1771 is the Jupiter program's slippage tolerance exceeded error
+            FailureCause::SlippageExceeded
+        } else if message.contains("route") {
+            FailureCause::RouteNotFound
+        } else if message.contains("timed out") || message.contains("timeout") || message.contains("failed to send") {
+            FailureCause::RpcTimeout
+        } else {
+            FailureCause::Unknown
+        }
+    }
+
+    // What the operator can do about failures with this cause
+    pub fn remediation(&self) -> Remediation {
+        let mut remediation = Remediation::default();
+        match self {
+            FailureCause::InsufficientBalance => remediation.add_funds = true,
+            FailureCause::InsufficientFees => remediation.top_up_sol = true,
+            FailureCause::SlippageExceeded => {
+                remediation.increase_slippage = true;
+                remediation.retry = true;
+            }
+            FailureCause::RouteNotFound => remediation.check_token_pair = true,
+            FailureCause::ExposureLimit => remediation.raise_exposure_limit = true,
+            FailureCause::QuotaExceeded => remediation.raise_quota = true,
+            FailureCause::RpcTimeout | FailureCause::Unknown => remediation.retry = true,
+        }
+        remediation
+    }
+
+    // Failures that leave no swap in flight and may not happen again: RPC timeouts before sending
+    // and slippage from a moving price, which fails the landed transaction. Missing funds, limits
+    // and missing routes fail fast, and unclassified failures are never retried since the swap may
+    // already have been sent.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, FailureCause::RpcTimeout | FailureCause::SlippageExceeded)
+    }
+}
+
+// Order swap that was sent but not confirmed in time. Its ledger entry, metered notional and
+// receipt wait until a poll of its signature sees it land.
+#[derive(Clone, Debug)]
+pub struct UnconfirmedExecution {
+    pub order_id: String,
+    pub wallet: String,
+    pub swap_request: SwapRequest,
+    pub swap_result: SwapResponse,
+    // USD value of the swap, metered against the owner's notional quota once it lands
+    pub notional_usd: f64,
+    pub trigger_price: f64,
+    pub triggered_at: DateTime<Utc>,
+}
+
+// Why an order failed
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct OrderFailure {
+    pub cause: FailureCause,
+    pub message: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl OrderFailure {
+    pub fn new(message: &str) -> Self {
+        Self {
+            cause: FailureCause::classify(message),
+            message: message.to_string(),
+            failed_at: Utc::now(),
+        }
+    }
+}
+
+// Suggested fixes for a group of failed orders
+#[derive(Serialize, Clone, Debug, Default, ToSchema)]
+pub struct Remediation {
+    pub add_funds: bool,
+    pub top_up_sol: bool,
+    pub increase_slippage: bool,
+    pub check_token_pair: bool,
+    pub raise_exposure_limit: bool,
+    pub raise_quota: bool,
+    pub retry: bool,
+}
+
+// Failed orders sharing a cause
+#[derive(Serialize, Debug, ToSchema)]
+pub struct FailedOrderGroup {
+    pub cause: FailureCause,
+    pub count: usize,
+    pub remediation: Remediation,
+    pub orders: Vec<LimitOrder>,
+}
+
+// Inputs used to validate an order, returned on success and failure alike
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct OrderValidation {
+    pub wallet: Option<String>,
+    pub balances: Vec<TokenBalance>,
+    pub fee_estimate: Option<FeeBreakdown>,
+    pub prices: HashMap<String, f64>,
+    pub required_source_amount: Option<f64>,
+}
+
+// SOL needed to execute an order, split into network fee and token account rent
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct FeeBreakdown {
+    pub network_fee_sol: f64,
+    pub ata_rent_sol: f64,
+    pub total_sol: f64,
+}
+
+// Triggered order waiting for execution in the current monitor pass
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct QueuedOrder {
+    pub position: usize,
+    pub order_id: String,
+    pub order_type: OrderType,
+    pub priority: i32,
+    pub notional_usd: f64,
+    pub trigger_price: f64,
+    pub queued_at: DateTime<Utc>,
+}
+
+// Query for simulating an order at hypothetical prices, e.g. `prices=10,12,15`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WhatIfQuery {
+    pub prices: String,
+}
+
+// Outcome of an order at one hypothetical target token price
+#[derive(Serialize, Debug, ToSchema)]
+pub struct WhatIfOutcome {
+    pub price: f64,
+    pub would_trigger: bool,
+    pub input_amount: f64,
+    pub estimated_output: f64,
+    pub minimum_output: f64,
+    pub output_value_usd: f64,
+    pub fee_sol: f64,
+    pub fee_usd: f64,
+    pub net_proceeds_usd: f64,
+}
+
+// Estimated result of executing an order at past prices, before fees and price impact
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BacktraceOutcome {
+    pub input_amount: f64,
+    pub estimated_output: f64,
+    pub minimum_output: f64,
+    pub output_value_usd: f64,
+}
+
+// Stretch of price history during which an order would have triggered
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BacktraceTrigger {
+    pub triggered_at: DateTime<Utc>,
+    pub trigger_price: f64,
+    // Last sample of the stretch still past the target
+    pub until: DateTime<Utc>,
+    pub samples: usize,
+    // Missing when the history has no price for the source or target token at that moment
+    pub estimated_outcome: Option<BacktraceOutcome>,
+}
+
+// Every moment in the stored price history an order would have triggered
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OrderBacktrace {
+    pub order_id: String,
+    pub order_type: OrderType,
+    pub price_target: f64,
+    pub trigger_mint: String,
+    pub quote_mint: Option<String>,
+    pub samples: usize,
+    pub history_from: Option<DateTime<Utc>>,
+    pub history_to: Option<DateTime<Utc>>,
+    pub triggers: Vec<BacktraceTrigger>,
+}
+
+// Query for quoted depth between two tokens
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DepthQuery {
+    pub input: String,
+    pub output: String,
+    pub sizes: Option<String>,
+}
+
+// Quote for one input size on the depth ladder
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct DepthLevel {
+    pub input_amount: f64,
+    pub output_amount: f64,
+    pub effective_price: f64,
+    pub price_impact_percent: f64,
+}
+
+// Approximate depth curve built from quotes at increasing sizes
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct DepthResponse {
+    pub input: String,
+    pub output: String,
+    pub levels: Vec<DepthLevel>,
+    pub fetched_at: DateTime<Utc>,
 }
 
 // Import wallet request
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct ImportWalletRequest {
     pub private_key: Option<String>,
     pub mnemonic: Option<String>,
+    // Also store the wallet in the encrypted keystore under this passphrase
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    // Only derive the wallet and look up its balances, without storing the key
+    #[serde(default)]
+    pub preview: bool,
+    // Name to tell the wallet apart from the others
+    #[serde(default)]
+    pub label: Option<String>,
+    // Several keys imported in one call, instead of `private_key` or `mnemonic`
+    #[serde(default)]
+    pub wallets: Option<Vec<ImportWalletEntry>>,
+    // Whether a batch with an invalid entry imports nothing or every valid entry
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+// One key of a batch import
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ImportWalletEntry {
+    pub private_key: Option<String>,
+    pub mnemonic: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+// How a batch import treats invalid entries
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    // Import nothing unless every entry can be imported
+    #[default]
+    Atomic,
+    // Import every entry that can be imported
+    BestEffort,
+}
+
+// Outcome of one entry of a batch import
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ImportWalletResult {
+    pub index: usize,
+    pub label: Option<String>,
+    // Unset if the key could not be read
+    pub pubkey: Option<String>,
+    pub imported: bool,
+    pub error: Option<String>,
+}
+
+// Outcome of a batch import
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BatchImportResponse {
+    pub mode: ImportMode,
+    pub imported: usize,
+    pub failed: usize,
+    pub results: Vec<ImportWalletResult>,
+}
+
+// Wallet an import would add, so the key can be checked before it is stored
+#[derive(Serialize, ToSchema)]
+pub struct WalletPreview {
+    pub pubkey: String,
+    // Unset if the balances could not be fetched
+    pub balances: Option<Vec<TokenBalance>>,
+}
+
+// Request to decrypt a stored wallet into memory
+#[derive(Deserialize, ToSchema)]
+pub struct UnlockWalletRequest {
+    pub pubkey: String,
+    pub passphrase: String,
+}
+
+// Request to drop a wallet from memory; the passphrase is needed if it is not stored yet
+#[derive(Deserialize, ToSchema)]
+pub struct LockWalletRequest {
+    pub pubkey: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+// Wallet to forget, cancelling its open orders
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RemoveWalletRequest {
+    pub pubkey: String,
+}
+
+// What removing a wallet cancelled and deleted
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RemovedWallet {
+    pub pubkey: String,
+    pub cancelled_orders: Vec<String>,
+    pub cancelled_dca_orders: Vec<String>,
+    pub removed_from_keystore: bool,
+}
+
+// Wallet whose secret key to export, with the configured export password
+#[derive(Deserialize, ToSchema)]
+pub struct ExportWalletRequest {
+    pub pubkey: String,
+    pub password: String,
+}
+
+// Exported base58 secret key, in the format `/import_wallet` accepts
+#[derive(Serialize, ToSchema)]
+pub struct ExportedWallet {
+    pub pubkey: String,
+    pub private_key: String,
 }
 
 // Response for wallet creation
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CreateWalletResponse {
     pub pubkey: String,
     pub mnemonic: String,
 }
 
+// Optional wallet to act on, e.g. `?wallet=<pubkey>`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WalletQuery {
+    pub wallet: Option<String>,
+}
+
+// An imported wallet as listed to the caller
+#[derive(Serialize, Debug, ToSchema)]
+pub struct WalletSummary {
+    pub pubkey: String,
+    pub label: Option<String>,
+    pub added_at: Option<DateTime<Utc>>,
+    // Used when a request does not name a wallet
+    pub default: bool,
+    // Automatic executions and new orders are suspended
+    pub frozen: bool,
+}
+
 // API responses
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    // Machine-readable error code, see `error::ApiError`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+// Heartbeat request for dead man's switch orders (all active ones if no id is given)
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct HeartbeatRequest {
+    pub order_id: Option<String>,
 }
 
 // Cancel limit order request
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CancelOrderRequest {
     pub order_id: String,
-} 
\ No newline at end of file
+}
+
+// Spot token holding valued at the current price
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PortfolioPosition {
+    pub mint: String,
+    pub symbol: String,
+    pub amount: f64,
+    pub price_usd: f64,
+    pub value_usd: f64,
+}
+
+// Native stake account where the wallet is the withdraw authority
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct StakePosition {
+    pub stake_account: String,
+    pub state: String,
+    pub voter: Option<String>,
+    pub amount_sol: f64,
+    pub value_usd: f64,
+}
+
+// Liquidity pool position valued from the pool reserves
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct LpPosition {
+    pub pool: String,
+    pub lp_mint: String,
+    pub lp_amount: f64,
+    pub pool_share: f64,
+    pub token_a_mint: String,
+    pub token_a_amount: f64,
+    pub token_b_mint: String,
+    pub token_b_amount: f64,
+    pub value_usd: f64,
+}
+
+// Portfolio response including spot, staked and LP positions
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PortfolioResponse {
+    pub wallet: String,
+    pub spot: Vec<PortfolioPosition>,
+    pub staked: Vec<StakePosition>,
+    pub liquidity: Vec<LpPosition>,
+    pub spot_value_usd: f64,
+    pub staked_value_usd: f64,
+    pub liquidity_value_usd: f64,
+    pub total_value_usd: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Spot holdings of one token across the caller's wallets. The 24 hour change is what the price
+// move since then made on the current amount, unknown while the price history is shorter.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct PortfolioToken {
+    pub mint: String,
+    pub symbol: String,
+    pub amount: f64,
+    pub price_usd: f64,
+    pub value_usd: f64,
+    pub price_24h_ago_usd: Option<f64>,
+    pub change_24h_usd: Option<f64>,
+    pub change_24h_percent: Option<f64>,
+}
+
+// Value of the caller's wallets together, with the breakdown of each wallet
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PortfolioValuation {
+    pub tokens: Vec<PortfolioToken>,
+    pub wallets: Vec<PortfolioResponse>,
+    // Wallets whose holdings could not be loaded, left out of the totals
+    pub failed_wallets: Vec<PortfolioWalletError>,
+    pub spot_value_usd: f64,
+    pub staked_value_usd: f64,
+    pub liquidity_value_usd: f64,
+    pub total_value_usd: f64,
+    pub change_24h_usd: Option<f64>,
+    pub change_24h_percent: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+// A wallet left out of a portfolio valuation, with why
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PortfolioWalletError {
+    pub wallet: String,
+    pub error: String,
+}
+
+// How well one token holding of a wallet is protected by active stops
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RiskPosition {
+    pub wallet: String,
+    pub mint: String,
+    pub symbol: String,
+    pub balance: f64,
+    pub price_usd: f64,
+    pub value_usd: f64,
+    // Active stops selling this holding
+    pub stop_orders: Vec<String>,
+    pub covered_amount: f64,
+    pub covered_usd: f64,
+    pub uncovered_amount: f64,
+    pub uncovered_usd: f64,
+    // Nearest stop trigger on this token's own price, and how far the price must fall to reach it
+    pub nearest_trigger_price: Option<f64>,
+    pub nearest_trigger_distance_percent: Option<f64>,
+    // Least the stops would receive if they all sold now, at quoted liquidity and their slippage
+    pub worst_case_proceeds_usd: Option<f64>,
+    pub quote_error: Option<String>,
+}
+
+// Funds at risk across the caller's wallets
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RiskSummary {
+    pub positions: Vec<RiskPosition>,
+    pub total_value_usd: f64,
+    pub covered_usd: f64,
+    pub uncovered_usd: f64,
+    pub worst_case_proceeds_usd: f64,
+    pub generated_at: DateTime<Utc>,
+}
+
+// Point in the portfolio value history
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PortfolioSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub total_value_usd: f64,
+}
+
+// Vacation mode: liquidate to a stable asset if the portfolio drops too fast
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct LiquidationPolicy {
+    pub enabled: bool,
+    // Wallets whose combined value is watched and whose tokens are liquidated
+    pub wallets: Vec<String>,
+    pub drawdown_percent: f64,
+    pub window_hours: f64,
+    pub tokens: Vec<String>,
+    pub stable_token: String,
+    pub slippage: Option<f64>,
+    #[serde(default)]
+    pub triggered_at: Option<DateTime<Utc>>,
+}
+
+// Transfer of a token from the wallet to another address
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct TransferRequest {
+    pub token: String,
+    pub amount: f64,
+    pub destination: String,
+}
+
+// Kind of movement recorded in the ledger
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum LedgerEntryKind {
+    Trade,
+    InternalTransfer,
+    ExternalTransfer,
+}
+
+// Ledger entry for a trade or transfer
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct LedgerEntry {
+    pub id: String,
+    pub kind: LedgerEntryKind,
+    pub wallet: String,
+    pub counterparty: Option<String>,
+    pub source_token: String,
+    pub source_amount: f64,
+    pub target_token: Option<String>,
+    pub target_amount: Option<f64>,
+    pub order_id: Option<String>,
+    pub transaction_signature: String,
+    pub timestamp: DateTime<Utc>,
+    // USD prices of the tokens when the entry was recorded
+    #[serde(default)]
+    pub source_price_usd: Option<f64>,
+    #[serde(default)]
+    pub target_price_usd: Option<f64>,
+}
+
+// Tokens of one kind a wallet got through the API and what they cost in USD, at their average
+// cost. Amounts the wallet had before or got elsewhere are not tracked.
+#[derive(Serialize, Clone, Debug, Default, ToSchema)]
+pub struct CostBasis {
+    pub amount: f64,
+    pub cost_usd: f64,
+    pub realized_usd: f64,
+}
+
+// Profit of one trade: what the sold tokens fetched against their cost basis. The realized profit
+// is unknown when none of the sold amount was tracked or the trade could not be valued.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct TradePnl {
+    pub wallet: String,
+    pub order_id: Option<String>,
+    pub source_token: String,
+    pub source_amount: f64,
+    pub source_price_usd: Option<f64>,
+    pub target_token: String,
+    pub target_amount: f64,
+    pub target_price_usd: Option<f64>,
+    pub value_usd: Option<f64>,
+    pub cost_basis_usd: Option<f64>,
+    pub realized_usd: Option<f64>,
+    pub fee_usd: f64,
+    pub transaction_signature: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Profit on one token a wallet holds through the API
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TokenPnl {
+    pub wallet: String,
+    pub mint: String,
+    pub symbol: String,
+    pub amount: f64,
+    pub average_cost_usd: Option<f64>,
+    pub cost_basis_usd: f64,
+    pub price_usd: Option<f64>,
+    pub value_usd: Option<f64>,
+    pub realized_usd: f64,
+    pub unrealized_usd: Option<f64>,
+}
+
+// Profit of an order's execution, and what selling did against holding the tokens until now
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OrderPnl {
+    pub order_id: String,
+    pub order_type: Option<OrderType>,
+    pub trade: TradePnl,
+    // Value now of what the order received less the value now of what it sold. Positive when
+    // the order came out ahead of holding on.
+    pub vs_holding_usd: Option<f64>,
+}
+
+// Realized and unrealized profit of the caller's wallets
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PnlReport {
+    pub tokens: Vec<TokenPnl>,
+    pub orders: Vec<OrderPnl>,
+    pub realized_usd: f64,
+    pub unrealized_usd: f64,
+    pub fees_usd: f64,
+    pub total_usd: f64,
+    // What stop loss orders saved against holding on, summed over their executions
+    pub stop_loss_vs_holding_usd: f64,
+    pub generated_at: DateTime<Utc>,
+}
+
+// Events the notifier can deliver
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, ToSchema)]
+pub enum NotificationEvent {
+    PriceNearTrigger,
+    OrderExecuted,
+    OrderFailed,
+    TradingPaused,
+    EngineDegraded,
+    EngineRecovered,
+    LowBalance,
+    WalletDeposit,
+    WalletWithdrawal,
+    FundingRate,
+    BlackoutTrigger,
+    StaleState,
+    RunawayClient,
+    WalletFrozen,
+}
+
+// Notification severity, lowest first
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+impl NotificationEvent {
+    // Default severity of each event
+    pub fn severity(&self) -> Severity {
+        match self {
+            NotificationEvent::OrderExecuted => Severity::Info,
+            NotificationEvent::PriceNearTrigger => Severity::Warning,
+            NotificationEvent::OrderFailed => Severity::Critical,
+            NotificationEvent::TradingPaused => Severity::Critical,
+            NotificationEvent::EngineDegraded => Severity::Critical,
+            NotificationEvent::EngineRecovered => Severity::Info,
+            NotificationEvent::LowBalance => Severity::Error,
+            NotificationEvent::WalletDeposit => Severity::Info,
+            NotificationEvent::WalletWithdrawal => Severity::Warning,
+            NotificationEvent::FundingRate => Severity::Warning,
+            NotificationEvent::BlackoutTrigger => Severity::Warning,
+            NotificationEvent::StaleState => Severity::Warning,
+            NotificationEvent::RunawayClient => Severity::Error,
+            NotificationEvent::WalletFrozen => Severity::Critical,
+        }
+    }
+}
+
+// Per-channel delivery limits, severity threshold and the events the channel receives. Without
+// an event list a channel receives every event, except Telegram which defaults to order fills
+// and failures.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct ChannelPolicy {
+    pub rate_limit_per_minute: Option<u32>,
+    pub min_severity: Option<Severity>,
+    #[serde(default)]
+    pub events: Option<Vec<NotificationEvent>>,
+}
+
+// Daily window (UTC hours) during which only critical events are delivered immediately
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct QuietHours {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl QuietHours {
+    // Whether the given UTC hour falls in the window, which may wrap past midnight
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+// Per-event delivery settings; events with a digest interval are batched into one message
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct EventPolicy {
+    pub digest_interval_secs: Option<u64>,
+}
+
+// Notification throttling and digest configuration
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub channels: HashMap<String, ChannelPolicy>,
+    #[serde(default)]
+    pub events: HashMap<NotificationEvent, EventPolicy>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    // UTC hour at which info-level events without their own digest interval are sent as one
+    // daily digest instead of immediately
+    #[serde(default)]
+    pub daily_digest_hour: Option<u32>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        // Near-trigger warnings are the noisy ones, so digest them every 5 minutes by default
+        let mut events = HashMap::new();
+        events.insert(
+            NotificationEvent::PriceNearTrigger,
+            EventPolicy { digest_interval_secs: Some(300) },
+        );
+
+        Self {
+            channels: HashMap::new(),
+            events,
+            quiet_hours: None,
+            daily_digest_hour: None,
+        }
+    }
+}
+
+// Messages waiting for a digest, with the time each was raised
+pub type PendingDigest = Vec<(DateTime<Utc>, String)>;
+
+// Runtime notifier state: config and pending digests of each tenant under its settings key,
+// recent sends per channel, template overrides by locale and name, and the locale each API key
+// picked
+#[derive(Default)]
+pub struct NotifierState {
+    pub configs: HashMap<String, NotificationConfig>,
+    pub pending: HashMap<(String, NotificationEvent), PendingDigest>,
+    pub sent: HashMap<String, VecDeque<DateTime<Utc>>>,
+    pub dropped: HashMap<String, u64>,
+    pub templates: BTreeMap<(String, String), String>,
+    pub locales: HashMap<String, String>,
+}
+
+// Notification message template, built in or an operator override
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct NotificationTemplate {
+    pub name: String,
+    pub locale: String,
+    pub template: String,
+    pub overridden: bool,
+}
+
+// Operator override of a notification template, in minijinja syntax
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct NotificationTemplateRequest {
+    pub name: String,
+    pub locale: String,
+    pub template: String,
+}
+
+// Notification language of an API key, and the one messages are actually rendered in
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct NotificationLocale {
+    pub locale: Option<String>,
+    pub effective_locale: String,
+}
+
+// Language to render the caller's notifications in, null to use the server default
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct NotificationLocaleRequest {
+    pub locale: Option<String>,
+}
+
+// Operator preferences applied to responses
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct Preferences {
+    pub timezone: Option<String>,
+    // Default language of notifications, for API keys that did not pick one
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+// Optional timezone for rendering timestamps, e.g. `?tz=+05:30`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimezoneQuery {
+    pub tz: Option<String>,
+}
+
+// How the watchdog reacts to failing upstream dependencies
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct WatchdogConfig {
+    pub failure_threshold: u32,
+    pub pause_on_degraded: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            pause_on_degraded: true,
+        }
+    }
+}
+
+// Result of the latest canary checks against one upstream dependency
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct DependencyHealth {
+    pub consecutive_failures: u32,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_checked: Option<DateTime<Utc>>,
+}
+
+// Engine health as seen by the watchdog
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct EngineHealth {
+    pub degraded: bool,
+    pub degraded_since: Option<DateTime<Utc>>,
+    pub dependencies: HashMap<String, DependencyHealth>,
+    pub config: WatchdogConfig,
+}
+
+// Virtual balances a wallet trades against in simulation, seeded from its on-chain balances
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PaperAccount {
+    pub wallet: String,
+    // Amount held by mint
+    pub balances: HashMap<String, f64>,
+    pub fills: Vec<PaperFill>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Simulated swap, filled at its Jupiter quote
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PaperFill {
+    pub signature: String,
+    pub source_token: String,
+    pub target_token: String,
+    pub source_amount: f64,
+    pub target_amount: f64,
+    pub fee_sol: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Request to set a wallet's paper balance of one token
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct PaperBalanceRequest {
+    pub wallet_pubkey: String,
+    pub mint: String,
+    pub amount: f64,
+}
+
+// Request to add a token to the caller's watchlist
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct WatchlistRequest {
+    pub mint: String,
+}
+
+// Token on a watchlist; its price is kept current whether or not a wallet holds it
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct WatchlistEntry {
+    pub mint: String,
+    pub symbol: String,
+    pub added_at: DateTime<Utc>,
+    // Latest cached USD price, filled in when the watchlist is read
+    #[serde(default)]
+    pub price_usd: Option<f64>,
+}
+
+// Request to be alerted when a wallet balance drops below a threshold
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BalanceAlertRequest {
+    pub wallet: String,
+    pub mint: String,
+    pub threshold: f64,
+}
+
+// Balance threshold alert; it fires once when crossed and re-arms when the balance recovers
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct BalanceAlert {
+    pub id: String,
+    pub wallet: String,
+    pub mint: String,
+    pub threshold: f64,
+    pub triggered: bool,
+    pub last_balance: Option<f64>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Side of the threshold a funding rate alert fires on
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingCondition {
+    Above,
+    Below,
+}
+
+// Request to create a funding rate alert on a Drift perp market
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct FundingRateAlertRequest {
+    pub market: String,
+    pub condition: FundingCondition,
+    // Hourly funding rate in percent, positive when longs pay shorts
+    pub threshold_percent: f64,
+}
+
+// Funding rate alert; like balance alerts it fires once when crossed and re-arms afterwards
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct FundingRateAlert {
+    pub id: String,
+    pub market: String,
+    pub condition: FundingCondition,
+    pub threshold_percent: f64,
+    pub triggered: bool,
+    pub last_rate_percent: Option<f64>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    // API key (tenant) that created the alert, never returned to clients
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+// Mark price and funding of a Drift perp market
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PerpMarket {
+    pub market: String,
+    pub mark_price: f64,
+    pub oracle_price: Option<f64>,
+    // Latest hourly funding rate in percent, positive when longs pay shorts
+    pub funding_rate_percent: Option<f64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Keep a SOL buffer for fees by swapping a funding token into SOL when it runs low
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SolReservePolicy {
+    pub enabled: bool,
+    pub min_sol: f64,
+    pub target_sol: f64,
+    pub funding_token: String,
+    pub slippage: Option<f64>,
+}
+
+// Query for failed orders, e.g. `since=2024-01-01T00:00:00Z`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FailedOrdersQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub tz: Option<String>,
+}
+
+// Filters and adjustments for retrying failed orders in bulk
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct RetryFailedRequest {
+    #[serde(default)]
+    pub order_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub cause: Option<FailureCause>,
+    #[serde(default)]
+    pub order_type: Option<OrderType>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    // New slippage for every retried order, in percent
+    #[serde(default)]
+    pub slippage: Option<f64>,
+}
+
+// Failed order that was not reactivated
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SkippedRetry {
+    pub order_id: String,
+    pub reason: String,
+}
+
+// Outcome of a bulk retry
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RetryFailedResponse {
+    pub retried: Vec<LimitOrder>,
+    pub skipped: Vec<SkippedRetry>,
+}
+
+// Order in which listed orders are returned, newest first by default
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSort {
+    #[default]
+    CreatedDesc,
+    CreatedAsc,
+    UpdatedDesc,
+    UpdatedAsc,
+    PriceTargetDesc,
+    PriceTargetAsc,
+}
+
+// Query for listing orders, e.g. `archived=true&tz=+02:00` or
+// `status=Active&token=<mint>&sort=created_asc&limit=50&offset=100`
+#[derive(Deserialize, Debug, Default, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListOrdersQuery {
+    #[serde(default)]
+    pub archived: bool,
+    pub tz: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub order_type: Option<OrderType>,
+    // Orders trading or triggering on this mint
+    pub token: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: OrderSort,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+// What to do with an automated buy that would breach an exposure limit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, ToSchema)]
+pub enum ExposureAction {
+    Reject,
+    Downsize,
+}
+
+// One decision recorded on an order's audit trail
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct OrderAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub event: String,
+    pub detail: String,
+}
+
+// Step of an order's lifecycle recorded in its history
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum OrderEventKind {
+    Created,
+    Triggered,
+    Executing,
+    Completed,
+    Failed,
+    Reactivated,
+    PendingFunds,
+    Cancelled,
+    Expired,
+    Retrying,
+    Unconfirmed,
+}
+
+impl OrderEventKind {
+    // Event recorded when an order moves to a status
+    pub fn for_status(status: &OrderStatus) -> Self {
+        match status {
+            OrderStatus::Active => OrderEventKind::Reactivated,
+            OrderStatus::PendingFunds => OrderEventKind::PendingFunds,
+            OrderStatus::Executing => OrderEventKind::Executing,
+            OrderStatus::Completed => OrderEventKind::Completed,
+            OrderStatus::Cancelled => OrderEventKind::Cancelled,
+            OrderStatus::Expired => OrderEventKind::Expired,
+            OrderStatus::Failed => OrderEventKind::Failed,
+            OrderStatus::Retrying => OrderEventKind::Retrying,
+            OrderStatus::Unconfirmed => OrderEventKind::Unconfirmed,
+        }
+    }
+}
+
+// One entry of an order's history, kept apart from the order so it survives archiving
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct OrderEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: OrderEventKind,
+    // Status of the order after the event
+    pub status: OrderStatus,
+    pub reason: Option<String>,
+    // Price the trigger was checked against, for triggered orders
+    pub trigger_price: Option<f64>,
+    // Cached USD prices of the order's tokens at the time
+    pub prices: HashMap<String, f64>,
+}
+
+// What an order executed, as attested by the server
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ReceiptContents {
+    pub order_id: String,
+    pub order_type: OrderType,
+    pub action: OrderAction,
+    pub source_token: String,
+    pub target_token: String,
+    pub amount: f64,
+    pub price_target: f64,
+    pub slippage: f64,
+    pub wallet_pubkey: String,
+    pub api_key: Option<String>,
+    // Price the trigger fired at
+    pub trigger_price: f64,
+    // Amounts received against what the prices of the moment promised
+    pub execution: Option<ExecutionReport>,
+    pub fee: Option<TransactionFee>,
+    pub transaction_signature: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub triggered_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub issued_at: DateTime<Utc>,
+}
+
+// Execution receipt signed with the server key, so neither side can alter it afterwards
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ExecutionReceipt {
+    pub receipt: ReceiptContents,
+    // Base58 public key of the server key that signed the receipt
+    pub signer: String,
+    // Base58 ed25519 signature of the compact JSON of `receipt`, fields in the order shown
+    pub signature: String,
+}
+
+// Upstream payload kept by the caching proxy, in memory and on disk
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct CachedPayload {
+    pub url: String,
+    pub etag: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    pub body: String,
+}
+
+// State of one cached upstream payload
+#[derive(Serialize, Debug, ToSchema)]
+pub struct UpstreamCacheEntry {
+    pub name: String,
+    pub url: String,
+    pub ttl_secs: u64,
+    pub cached: bool,
+    pub etag: Option<String>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub size_bytes: usize,
+}
+
+// Cached payload to refresh, every one when no name is given
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct UpstreamCacheRefreshRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+// Token from Jupiter's token list
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct TokenInfo {
+    pub address: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+}
+
+// Filter for the token list, matched against symbol, name and mint, e.g. `?search=bonk`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokenListQuery {
+    pub search: Option<String>,
+}
+
+// AMM programs and DEX labels swaps must not route through
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct RouteDenylist {
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    // Labels of the denied programs together with the denied labels, resolved when the list is set
+    #[serde(default)]
+    pub excluded_labels: Vec<String>,
+}
+
+// Creation time checks of an order's price target against the current price. Orders created
+// with `force` skip them.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(default)]
+pub struct ValidationPolicy {
+    // Stop losses must trigger below the current price
+    pub stop_loss_below_market: bool,
+    // Take profits must trigger above the current price
+    pub take_profit_above_market: bool,
+    // Stop buys must trigger above the current price. Policies saved before stop buys existed
+    // leave it out, and get the check.
+    #[serde(default = "default_true")]
+    pub stop_buy_above_market: bool,
+    // Buy targets must be below the current price
+    pub buy_below_market: bool,
+    // Sell targets must be above the current price
+    pub sell_above_market: bool,
+    // Least distance between the target and the current price, in percent
+    pub min_distance_percent: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            stop_loss_below_market: true,
+            take_profit_above_market: true,
+            stop_buy_above_market: true,
+            buy_below_market: false,
+            sell_above_market: false,
+            min_distance_percent: 0.0,
+        }
+    }
+}
+
+// Maximum share of the portfolio a token may reach through automated buys
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ExposureLimit {
+    pub mint: String,
+    pub max_portfolio_percent: f64,
+    pub action: ExposureAction,
+}
+
+// Period during which automatic execution is suspended, e.g. a program upgrade or a token unlock
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct BlackoutWindow {
+    pub id: String,
+    pub name: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    // Tokens the window covers, every token when empty
+    pub mints: Vec<String>,
+    // Orders whose trigger fired during the window and were left active
+    pub suppressed_orders: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    // Tenant whose orders the window covers, every tenant's when unset
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+// Request to schedule a blackout window
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BlackoutWindowRequest {
+    pub name: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    #[serde(default)]
+    pub mints: Vec<String>,
+}
+
+// Kind of stale or orphaned state the janitor found
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    // Open order whose wallet is no longer imported
+    OrphanedWalletOrder,
+    // Active order on a token without a fresh price
+    StalePrice,
+    // Order held in Executing past the timeout
+    StuckExecuting,
+    // Execution queue entry without an order waiting to execute
+    OrphanedQueueEntry,
+}
+
+// One anomaly and the state it was found in
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub order_id: Option<String>,
+    pub wallet: Option<String>,
+    pub mint: Option<String>,
+    pub detail: String,
+    // When the state went stale, if known
+    pub since: Option<DateTime<Utc>>,
+}
+
+// Result of a janitor scan
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct JanitorReport {
+    pub anomalies: Vec<Anomaly>,
+    pub generated_at: DateTime<Utc>,
+}
+
+// What reconciling an anomaly did
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ReconciledAnomaly {
+    pub anomaly: Anomaly,
+    pub resolved: bool,
+    pub action: String,
+}
+
+// Outcome of one startup integrity check
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct IntegrityCheck {
+    pub name: String,
+    pub passed: bool,
+    pub problems: Vec<String>,
+}
+
+// State integrity verified at startup. Any failed check boots the server in read-only safe mode.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct IntegrityReport {
+    pub safe_mode: bool,
+    pub checks: Vec<IntegrityCheck>,
+    pub checked_at: DateTime<Utc>,
+}
+
+// Time window for strategy statistics
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StrategyStatsQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+// Execution quality and outcome of all orders tagged with one strategy
+#[derive(Serialize, Debug, ToSchema)]
+pub struct StrategyStats {
+    pub strategy: String,
+    pub orders: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub cancelled: usize,
+    pub fill_rate: f64,
+    pub average_slippage_percent: f64,
+    // Value of what the executed orders received minus what they gave up, both at current prices
+    pub pnl_usd: f64,
+    pub fees_usd: f64,
+    pub fee_drag_percent: f64,
+}
+
+// Quote a swap was sent at against what the wallet received, kept for slippage analytics
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct SlippageSample {
+    pub transaction_signature: String,
+    pub wallet: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub source_token: String,
+    pub target_token: String,
+    pub source_amount: f64,
+    // USD value of the source amount when the swap executed, unset without a price
+    pub notional_usd: Option<f64>,
+    pub quoted_amount: f64,
+    pub filled_amount: f64,
+    // Shortfall of the fill against the quote, negative when it filled better than quoted
+    pub slippage_bps: f64,
+    // Slippage tolerance the swap was sent with
+    pub tolerance_bps: f64,
+    pub priority_fee_sol: f64,
+    pub executed_at: DateTime<Utc>,
+}
+
+// Filters of the slippage statistics
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlippageStatsQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    // Only swaps trading this mint
+    pub token: Option<String>,
+    // Timezone the time of day buckets are in, UTC by default
+    pub tz: Option<String>,
+}
+
+// Slippage distribution of one group of executions, in basis points
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SlippageBucket {
+    // Mint, hour of the day or order size range the executions share
+    pub bucket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub executions: usize,
+    pub mean_bps: f64,
+    pub p50_bps: f64,
+    pub p90_bps: f64,
+    pub p99_bps: f64,
+    pub max_bps: f64,
+    // Executions whose slippage used up more than half of their tolerance
+    pub near_tolerance: usize,
+    pub average_priority_fee_sol: f64,
+}
+
+// Quote against fill statistics of recent executions
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SlippageStats {
+    pub executions: usize,
+    pub overall: Option<SlippageBucket>,
+    // A swap counts towards both of its tokens
+    pub by_token: Vec<SlippageBucket>,
+    pub by_hour: Vec<SlippageBucket>,
+    pub by_size: Vec<SlippageBucket>,
+}
+
+// Public, sanitized operational status: no positions, balances or order details
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PublicStatus {
+    pub status: String,
+    pub uptime_secs: i64,
+    pub degraded: bool,
+    pub trading_paused: bool,
+    pub active_orders: usize,
+    pub price_update_age_secs: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Quote request, amount in UI units of the source token
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct QuoteRequest {
+    pub source_token: String,
+    pub target_token: String,
+    pub amount: f64,
+    pub slippage: Option<f64>,
+}
+
+// What a swap would get at the current quote, amounts in UI units
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SwapQuotePreview {
+    pub source_token: String,
+    pub target_token: String,
+    pub input_amount: f64,
+    pub expected_output: f64,
+    // Least output the swap accepts at the requested slippage
+    pub minimum_output: f64,
+    // Target tokens per source token at the expected output
+    pub price: f64,
+    pub price_impact_percent: f64,
+    pub slippage: f64,
+    pub route_labels: Vec<String>,
+    // Denylisted DEXes the first quote routed through, before requoting without them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub route_exclusions: Vec<String>,
+    pub quoted_at: DateTime<Utc>,
+}
+
+// Commands accepted on the WebSocket channel
+#[derive(Deserialize, Debug)]
+#[serde(tag = "command", content = "params", rename_all = "snake_case")]
+pub enum WsCommand {
+    CreateOrder(Box<LimitOrderRequest>),
+    CancelOrder(CancelOrderRequest),
+    Quote(QuoteRequest),
+}
+
+// WebSocket command frame: `{"id": "...", "command": "...", "params": {...}}`
+#[derive(Deserialize, Debug)]
+pub struct WsRequest {
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub command: WsCommand,
+}
+
+// Reply to a WebSocket command, carrying the command's correlation id
+#[derive(Serialize, Debug)]
+pub struct WsResponse {
+    pub id: Option<String>,
+    pub success: bool,
+    pub data: Option<serde_json::Value>,
+    pub error: Option<String>,
+    // Machine-readable code of the error, as in HTTP error bodies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+// Monthly limits for an API key, unset fields are unlimited
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct UsageQuota {
+    pub max_requests: Option<u64>,
+    pub max_notional_usd: Option<f64>,
+}
+
+// Quota applied to every API key, with per-key overrides
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct UsageQuotas {
+    #[serde(default)]
+    pub default: UsageQuota,
+    #[serde(default)]
+    pub keys: HashMap<String, UsageQuota>,
+}
+
+// Requests made and notional executed by an API key in one calendar month (UTC)
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct ApiUsage {
+    pub period: String,
+    pub requests: u64,
+    pub notional_usd: f64,
+}
+
+// Usage of the calling API key against its quota
+#[derive(Serialize, Debug, ToSchema)]
+pub struct UsageReport {
+    pub api_key: String,
+    pub usage: ApiUsage,
+    pub quota: UsageQuota,
+}
+
+// Which quota or tenant limit was hit
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum QuotaKind {
+    Requests,
+    NotionalUsd,
+    RequestsPerMinute,
+    ActiveOrders,
+}
+
+// Error returned when an API key has used up a monthly quota or hit a tenant limit
+#[derive(Debug, Clone)]
+pub struct QuotaExceeded {
+    pub kind: QuotaKind,
+    pub used: f64,
+    pub limit: f64,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            QuotaKind::Requests => write!(f, "Monthly request quota exceeded: {} of {} requests used", self.used, self.limit),
+            QuotaKind::NotionalUsd => write!(
+                f,
+                "Monthly notional quota exceeded: ${:.2} of ${:.2} used",
+                self.used, self.limit
+            ),
+            QuotaKind::RequestsPerMinute => write!(f, "Rate limit exceeded: {} of {} requests this minute", self.used, self.limit),
+            QuotaKind::ActiveOrders => write!(f, "Active order limit reached: {} of {} orders", self.used, self.limit),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+// Error returned when a swap's fees at a fresh quote exceed the order's fee budget
+#[derive(Debug, Clone)]
+pub struct FeeBudgetExceeded {
+    pub max_fee_usd: f64,
+    pub network_fee_usd: f64,
+    pub priority_fee_usd: f64,
+    pub price_impact_usd: f64,
+    pub total_usd: f64,
+}
+
+impl fmt::Display for FeeBudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Fees of ${:.4} (network ${:.4}, priority ${:.4}, price impact ${:.4}) exceed the budget of ${:.4}",
+            self.total_usd, self.network_fee_usd, self.priority_fee_usd, self.price_impact_usd, self.max_fee_usd
+        )
+    }
+}
+
+impl std::error::Error for FeeBudgetExceeded {}
+
+// Registered API key of one user. Only a hash of the key is kept.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub label: String,
+    // First characters of the key, to tell keys apart in listings
+    pub key_prefix: String,
+    #[serde(skip_serializing, default)]
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    // Set on subaccount keys, which act on one wallet with a subset of operations
+    #[serde(default)]
+    pub scope: Option<KeyScope>,
+}
+
+// Operation a subaccount key can be granted
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiOperation {
+    ViewPrices,
+    ViewBalances,
+    ViewOrders,
+    CreateOrders,
+    CancelOrders,
+    Swap,
+}
+
+// What a subaccount key may do: the granted operations, on one wallet only
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct KeyScope {
+    pub wallet: String,
+    pub operations: Vec<ApiOperation>,
+    // Order types the key may create, any when empty
+    #[serde(default)]
+    pub order_types: Vec<OrderType>,
+    // Tenant the key acts for, the owner of its wallet. Never returned, it is that tenant's own key.
+    #[serde(skip_serializing, default)]
+    pub tenant: String,
+}
+
+// Request to create an API key for a new user, or a subaccount key when scoped
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub label: String,
+    #[serde(default)]
+    pub scope: Option<KeyScope>,
+}
+
+// Newly created API key, the only time the key itself is returned
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub record: ApiKeyRecord,
+}
+
+// Limits for one tenant in hosted mode, unset fields are unlimited
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct TenantLimits {
+    pub max_requests_per_minute: Option<u32>,
+    pub max_active_orders: Option<usize>,
+    // Triggered orders executed per monitor pass, the rest wait for the next pass
+    pub max_executions_per_pass: Option<usize>,
+}
+
+// Limits applied to every tenant, with per-tenant overrides keyed by API key
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct TenantLimitsConfig {
+    #[serde(default)]
+    pub default: TenantLimits,
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantLimits>,
+}
+
+// API key refused new orders after creating them faster than any person would
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct CreationQuarantine {
+    pub id: String,
+    // First characters of the key, to tell keys apart in listings
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub api_key: String,
+    // Orders the key created within the window before it was quarantined
+    pub creations: usize,
+    pub window_secs: i64,
+    pub quarantined_at: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+// Error returned when a quarantined API key tries to create an order
+#[derive(Debug, Clone)]
+pub struct CreationQuarantined {
+    pub creations: usize,
+    pub window_secs: i64,
+    pub until: DateTime<Utc>,
+}
+
+impl From<&CreationQuarantine> for CreationQuarantined {
+    fn from(quarantine: &CreationQuarantine) -> Self {
+        Self {
+            creations: quarantine.creations,
+            window_secs: quarantine.window_secs,
+            until: quarantine.until,
+        }
+    }
+}
+
+impl fmt::Display for CreationQuarantined {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Order creation is quarantined until {} after this API key created {} orders in {} seconds",
+            self.until, self.creations, self.window_secs
+        )
+    }
+}
+
+impl std::error::Error for CreationQuarantined {}
+
+// Wallet put on hold by its owner, for when its key may be compromised or a strategy misbehaves.
+// Its orders stay as they are, but none of them executes and no new one is accepted until it is
+// unfrozen.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct WalletFreeze {
+    pub pubkey: String,
+    pub reason: Option<String>,
+    pub frozen_at: DateTime<Utc>,
+    // Orders that were waiting to retry a failed execution, cancelled by the freeze
+    pub cancelled_retries: Vec<String>,
+}
+
+// Request to freeze a wallet, the body is optional
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct FreezeWalletRequest {
+    pub reason: Option<String>,
+}
+
+// Error returned when an order is created for a frozen wallet
+#[derive(Debug, Clone)]
+pub struct WalletFrozen {
+    pub pubkey: String,
+    pub frozen_at: DateTime<Utc>,
+}
+
+impl fmt::Display for WalletFrozen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Wallet {} is frozen since {}, unfreeze it to create orders", self.pubkey, self.frozen_at)
+    }
+}
+
+impl std::error::Error for WalletFrozen {}
+
+// Requests a tenant made in the current one minute window
+#[derive(Clone, Debug)]
+pub struct RateWindow {
+    pub started_at: DateTime<Utc>,
+    pub requests: u32,
+}
+
+// Requests one client may make to a route: up to `burst` at once (`per_minute` if unset),
+// refilled at `per_minute`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RouteBudget {
+    pub per_minute: u32,
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+// Per-client request budgets by route pattern, such as `/get_prices` or `/orders/:id`. Clients
+// are their registered API key, or their IP address without one. Routes not listed use the
+// default budget, if any.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub default: Option<RouteBudget>,
+    #[serde(default)]
+    pub routes: HashMap<String, RouteBudget>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        // Routes that call Jupiter on every request are the ones a single client can abuse
+        let routes = ["/get_prices", "/get_quote", "/swap_route", "/depth"]
+            .into_iter()
+            .map(|route| (route.to_string(), RouteBudget { per_minute: 60, burst: None }))
+            .collect();
+
+        Self { default: None, routes }
+    }
+}
+
+// Requests a client has left on a route, as of the last time it was refilled
+#[derive(Clone, Debug)]
+pub struct RequestBucket {
+    pub tokens: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Event streamed to WebSocket subscribers
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    // An order was created or changed status
+    OrderUpdated { order: LimitOrder },
+    // Prices that changed in the latest update, by mint
+    PricesUpdated {
+        prices: HashMap<String, f64>,
+        updated_at: DateTime<Utc>,
+    },
+    // Funds moved in or out of a tracked wallet outside of this server
+    WalletActivity { activity: WalletActivity },
+    // Something held an order back without failing it, such as fees over its budget
+    OrderWarning { order: LimitOrder, warning: String },
+}
+
+// Whether funds came into or left a wallet
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityDirection {
+    Deposit,
+    Withdrawal,
+}
+
+// Kind of an on-chain transaction as seen from the wallet
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Swap,
+    Deposit,
+    Withdrawal,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Success,
+    Failed,
+}
+
+// Change of one token balance of the wallet in a transaction, negative when tokens left it
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct TokenChange {
+    pub mint: String,
+    pub symbol: String,
+    pub amount: f64,
+}
+
+// On-chain transaction of a wallet, normalized for display
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct WalletTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub kind: TransactionKind,
+    pub status: TransactionStatus,
+    pub error: Option<String>,
+    // Network fee, when the wallet paid it
+    pub fee_sol: f64,
+    pub changes: Vec<TokenChange>,
+    // Programs the transaction called, by name when well known
+    pub programs: Vec<String>,
+    // Whether the transaction was made through this API and has a ledger entry
+    pub via_api: bool,
+}
+
+// Page of a wallet's transaction history, e.g. `?wallet=<pubkey>&limit=50&before=<signature>`
+#[derive(Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionsQuery {
+    pub wallet: Option<String>,
+    pub limit: Option<usize>,
+    pub before: Option<String>,
+}
+
+// External deposit or withdrawal detected on a tracked wallet
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct WalletActivity {
+    pub wallet: String,
+    pub direction: ActivityDirection,
+    pub mint: String,
+    pub symbol: String,
+    pub amount: f64,
+    // Owner of the account on the other side of the move, if it could be identified
+    pub counterparty: Option<String>,
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: DateTime<Utc>,
+}
@@ -0,0 +1,307 @@
+use crate::models::{AppState, NotificationConfig, NotificationEvent, NotifierState, Severity};
+use crate::{templates, tenant};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use reqwest::Client;
+use std::sync::Arc;
+use tokio::time;
+use tracing::{error, info, warn};
+
+// Channel names used in the notification config
+pub const CHANNEL_LOG: &str = "log";
+pub const CHANNEL_WEBHOOK: &str = "webhook";
+pub const CHANNEL_TELEGRAM: &str = "telegram";
+
+// Telegram bot posting notifications to one chat; the bot token is read from the secrets backend
+const TELEGRAM_BOT_TOKEN_SECRET: &str = "TELEGRAM_BOT_TOKEN";
+const TELEGRAM_CHAT_ID_ENV: &str = "TELEGRAM_CHAT_ID";
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+// Events sent to Telegram unless its channel policy lists others: executed orders, which
+// include triggered stop losses, and failed executions
+const TELEGRAM_DEFAULT_EVENTS: &[NotificationEvent] = &[NotificationEvent::OrderExecuted, NotificationEvent::OrderFailed];
+
+// How often pending digests are checked
+const DIGEST_FLUSH_INTERVAL_SECS: u64 = 10;
+
+// Channels that are currently configured
+fn enabled_channels() -> Vec<&'static str> {
+    let mut channels = vec![CHANNEL_LOG];
+    if std::env::var("NOTIFY_WEBHOOK_URL").is_ok() {
+        channels.push(CHANNEL_WEBHOOK);
+    }
+    if crate::secrets::get_secret(TELEGRAM_BOT_TOKEN_SECRET).is_some() && std::env::var(TELEGRAM_CHAT_ID_ENV).is_ok() {
+        channels.push(CHANNEL_TELEGRAM);
+    }
+    channels
+}
+
+// Whether non-critical events are being held back right now
+fn in_quiet_hours(config: &NotificationConfig, now: DateTime<Utc>) -> bool {
+    config
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|quiet_hours| quiet_hours.contains(now.hour()))
+}
+
+// Whether an event waits for the daily digest: info-level events without a digest interval of
+// their own, once a digest hour is set
+fn in_daily_digest(config: &NotificationConfig, event: &NotificationEvent) -> bool {
+    config.daily_digest_hour.is_some()
+        && event.severity() == Severity::Info
+        && config.events.get(event).and_then(|policy| policy.digest_interval_secs).is_none()
+}
+
+// Config under a settings key; tenants that never set one get the default
+fn config_for(notifier: &NotifierState, key: &str) -> NotificationConfig {
+    notifier.configs.get(key).cloned().unwrap_or_default()
+}
+
+// Notification config of a tenant
+pub fn get_config(app_state: &AppState, tenant: &str) -> NotificationConfig {
+    let notifier = app_state.notifier.lock().unwrap();
+    config_for(&notifier, &tenant::settings_key(tenant))
+}
+
+// Replace the notification config of a tenant
+pub fn set_config(app_state: &AppState, tenant: &str, config: NotificationConfig) -> NotificationConfig {
+    let mut notifier = app_state.notifier.lock().unwrap();
+    notifier.configs.insert(tenant::settings_key(tenant), config.clone());
+    info!("Notification config updated");
+    config
+}
+
+// Notify about an engine-wide event, either immediately or as part of its digest
+pub async fn notify(app_state: &AppState, event: NotificationEvent, message: String) {
+    notify_with_details(app_state, event, None, message, None).await;
+}
+
+// Notify about an event with its message rendered from a template in the language of the
+// tenant it concerns. The template variables go to the webhook payload as details.
+pub async fn notify_event(
+    app_state: &AppState,
+    event: NotificationEvent,
+    tenant: Option<&str>,
+    template: &str,
+    vars: serde_json::Value,
+) {
+    let message = templates::render(app_state, tenant, template, &vars);
+    notify_with_details(app_state, event, tenant, message, Some(vars)).await;
+}
+
+// Notify about an event under the config of the tenant it concerns, engine-wide events using the
+// operator's, attaching structured details to the webhook payload. Digests only carry the message.
+pub async fn notify_with_details(
+    app_state: &AppState,
+    event: NotificationEvent,
+    tenant: Option<&str>,
+    message: String,
+    details: Option<serde_json::Value>,
+) {
+    let key = tenant::settings_key(tenant.unwrap_or_default());
+    let digested = {
+        let mut notifier = app_state.notifier.lock().unwrap();
+        let config = config_for(&notifier, &key);
+        let digest_interval = config.events.get(&event).and_then(|policy| policy.digest_interval_secs);
+
+        // During quiet hours everything but critical events waits for the next digest flush
+        let quiet = in_quiet_hours(&config, Utc::now()) && event.severity() < Severity::Critical;
+
+        if digest_interval.is_some() || quiet || in_daily_digest(&config, &event) {
+            notifier
+                .pending
+                .entry((key.clone(), event.clone()))
+                .or_default()
+                .push((Utc::now(), message.clone()));
+            true
+        } else {
+            false
+        }
+    };
+
+    if !digested {
+        dispatch(app_state, &key, &event, &message, details.as_ref()).await;
+    }
+}
+
+// Reserve a send slot on a channel, respecting the tenant's event toggles and severity threshold
+// and the channel's per-minute rate limit
+fn take_send_slot(app_state: &AppState, key: &str, channel: &str, event: &NotificationEvent, now: DateTime<Utc>) -> bool {
+    let mut notifier = app_state.notifier.lock().unwrap();
+    let policy = config_for(&notifier, key).channels.get(channel).cloned().unwrap_or_default();
+
+    let subscribed = match &policy.events {
+        Some(events) => events.contains(event),
+        None => channel != CHANNEL_TELEGRAM || TELEGRAM_DEFAULT_EVENTS.contains(event),
+    };
+    if !subscribed {
+        return false;
+    }
+
+    // Channels only receive events at or above their severity threshold
+    if policy.min_severity.is_some_and(|min_severity| event.severity() < min_severity) {
+        return false;
+    }
+
+    let limit = policy.rate_limit_per_minute;
+
+    let sent = notifier.sent.entry(channel.to_string()).or_default();
+    while sent.front().is_some_and(|sent_at| now - *sent_at >= Duration::minutes(1)) {
+        sent.pop_front();
+    }
+
+    if let Some(limit) = limit {
+        if sent.len() >= limit as usize {
+            *notifier.dropped.entry(channel.to_string()).or_insert(0) += 1;
+            return false;
+        }
+    }
+
+    notifier.sent.entry(channel.to_string()).or_default().push_back(now);
+    true
+}
+
+// Deliver a message to every enabled channel that has capacity
+async fn dispatch(
+    app_state: &AppState,
+    key: &str,
+    event: &NotificationEvent,
+    message: &str,
+    details: Option<&serde_json::Value>,
+) {
+    let now = Utc::now();
+
+    for channel in enabled_channels() {
+        if !take_send_slot(app_state, key, channel, event, now) {
+            warn!("Notification to {} skipped by channel policy: {:?}", channel, event);
+            continue;
+        }
+
+        let result = match channel {
+            CHANNEL_WEBHOOK => send_webhook(event, message, details).await,
+            CHANNEL_TELEGRAM => send_telegram(message).await,
+            _ => {
+                info!("[notification] {:?}: {}", event, message);
+                Ok(())
+            }
+        };
+
+        if let Err(err) = result {
+            error!("Failed to deliver notification to {}: {}", channel, err);
+        }
+    }
+}
+
+// Post the notification as JSON to the configured webhook
+async fn send_webhook(event: &NotificationEvent, message: &str, details: Option<&serde_json::Value>) -> anyhow::Result<()> {
+    let url = std::env::var("NOTIFY_WEBHOOK_URL")?;
+
+    let mut payload = serde_json::json!({
+        "event": event,
+        "message": message,
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+    if let Some(details) = details {
+        payload["details"] = details.clone();
+    }
+
+    Client::new()
+        .post(&url)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+// Send the message to the configured Telegram chat through the bot API
+async fn send_telegram(message: &str) -> anyhow::Result<()> {
+    let token = crate::secrets::get_secret(TELEGRAM_BOT_TOKEN_SECRET)
+        .ok_or_else(|| anyhow::anyhow!("{} is not set", TELEGRAM_BOT_TOKEN_SECRET))?;
+    let chat_id = std::env::var(TELEGRAM_CHAT_ID_ENV)?;
+
+    // The token is part of the URL, so errors are reported by status only
+    let response = Client::new()
+        .post(format!("{}/bot{}/sendMessage", TELEGRAM_API_URL, token))
+        .json(&serde_json::json!({
+            "chat_id": chat_id,
+            "text": message,
+            "disable_web_page_preview": true,
+        }))
+        .send()
+        .await
+        .map_err(|err| anyhow::anyhow!("Telegram request failed: {}", err.without_url()))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Telegram answered {}: {}", status, body));
+    }
+
+    Ok(())
+}
+
+// Take the digests whose interval has elapsed since their first pending event, along with the
+// settings key of the tenant they belong to. Daily digests are due at the digest hour once their
+// first event is older than that hour's start.
+fn take_due_digests(app_state: &AppState, now: DateTime<Utc>) -> Vec<(String, NotificationEvent, Vec<String>)> {
+    let mut notifier = app_state.notifier.lock().unwrap();
+    let configs = notifier.configs.clone();
+    let mut due = Vec::new();
+
+    for ((key, event), pending) in notifier.pending.iter_mut() {
+        let config = configs.get(key).cloned().unwrap_or_default();
+
+        // Held back until quiet hours end
+        if in_quiet_hours(&config, now) && event.severity() < Severity::Critical {
+            continue;
+        }
+
+        let first = match pending.first() {
+            Some((first, _)) => *first,
+            None => continue,
+        };
+
+        let is_due = match config.daily_digest_hour {
+            Some(hour) if in_daily_digest(&config, event) => {
+                let hour_start = now.date_naive().and_hms_opt(now.hour(), 0, 0).map(|start| start.and_utc());
+                now.hour() == hour && hour_start.map(|start| first < start).unwrap_or(false)
+            }
+            _ => {
+                let interval = config
+                    .events
+                    .get(event)
+                    .and_then(|policy| policy.digest_interval_secs)
+                    .unwrap_or(0);
+                now - first >= Duration::seconds(interval as i64)
+            }
+        };
+
+        if is_due {
+            let messages = pending.drain(..).map(|(_, message)| message).collect();
+            due.push((key.clone(), event.clone(), messages));
+        }
+    }
+
+    due
+}
+
+// Background task flushing digests as a single message per event type
+pub async fn run_digest_flusher(app_state: Arc<AppState>) {
+    info!("Starting notification digest task");
+
+    loop {
+        time::sleep(time::Duration::from_secs(DIGEST_FLUSH_INTERVAL_SECS)).await;
+
+        for (key, event, messages) in take_due_digests(&app_state, Utc::now()) {
+            // Digests of a tenant go out in its language, the operator's in the default one
+            let tenant = Some(key.as_str()).filter(|key| !key.is_empty());
+            let vars = serde_json::json!({
+                "count": messages.len(),
+                "event": format!("{:?}", event),
+                "messages": messages,
+            });
+            let digest = templates::render(&app_state, tenant, "digest", &vars);
+            dispatch(&app_state, &key, &event, &digest, None).await;
+        }
+    }
+}
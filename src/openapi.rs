@@ -0,0 +1,196 @@
+use crate::api;
+use crate::metering;
+use axum::{extract::Json, response::IntoResponse};
+use utoipa::openapi::{
+    security::{ApiKey, ApiKeyValue, SecurityRequirement, SecurityScheme},
+    ContentBuilder, Ref, RefOr, ResponseBuilder,
+};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+// OpenAPI document of the HTTP API, generated from the handler annotations in `api`. Swagger UI
+// in `static/docs.html` renders it.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Solana Wallet API"),
+    paths(
+        api::get_public_status,
+        api::generate_wallet,
+        api::import_wallet,
+        api::unlock_wallet,
+        api::lock_wallet,
+        api::remove_wallet,
+        api::export_wallet,
+        api::list_wallets,
+        api::freeze_wallet,
+        api::unfreeze_wallet,
+        api::get_balances,
+        api::get_mint_price,
+        api::get_prices,
+        api::swap_token,
+        api::set_limit_order,
+        api::list_limit_orders,
+        api::archive_order,
+        api::get_order_history,
+        api::get_order_receipt,
+        api::cancel_limit_order,
+        api::create_dca_order,
+        api::list_dca_orders,
+        api::cancel_dca_order,
+        api::get_portfolio,
+        api::get_execution_queue,
+        api::heartbeat,
+        api::get_liquidation_policy,
+        api::set_liquidation_policy,
+        api::get_sol_reserve_policy,
+        api::set_sol_reserve_policy,
+        api::get_risk,
+        api::get_portfolio_history,
+        api::resume_trading,
+        api::list_price_guards,
+        api::set_price_guard,
+        api::remove_price_guard,
+        api::list_price_providers,
+        api::set_provider_preference,
+        api::remove_provider_preference,
+        api::get_latency_metrics,
+        api::get_price_divergence,
+        api::push_prices,
+        api::what_if_order,
+        api::backtrace_order,
+        api::get_quote,
+        api::get_depth,
+        api::swap_route,
+        api::transfer,
+        api::get_ledger,
+        api::get_transactions,
+        api::get_pnl,
+        api::get_notification_config,
+        api::set_notification_config,
+        api::get_notification_templates,
+        api::set_notification_template,
+        api::remove_notification_template,
+        api::get_notification_locale,
+        api::set_notification_locale,
+        api::get_preferences,
+        api::set_preferences,
+        api::get_engine_health,
+        api::set_watchdog_config,
+        api::list_balance_alerts,
+        api::create_balance_alert,
+        api::remove_balance_alert,
+        api::list_paper_accounts,
+        api::set_paper_balance,
+        api::reset_paper_account,
+        api::get_watchlist,
+        api::add_to_watchlist,
+        api::remove_from_watchlist,
+        api::list_funding_alerts,
+        api::create_funding_alert,
+        api::remove_funding_alert,
+        api::get_perp_market,
+        api::list_failed_orders,
+        api::retry_failed_orders,
+        api::list_exposure_limits,
+        api::set_exposure_limit,
+        api::remove_exposure_limit,
+        api::list_blackout_windows,
+        api::create_blackout_window,
+        api::remove_blackout_window,
+        api::get_route_denylist,
+        api::set_route_denylist,
+        api::get_token_list,
+        api::get_upstream_cache,
+        api::refresh_upstream_cache,
+        api::get_janitor_report,
+        api::get_integrity_report,
+        api::reconcile_janitor,
+        api::get_validation_policy,
+        api::set_validation_policy,
+        api::get_strategy_stats,
+        api::get_slippage_stats,
+        api::get_usage,
+        api::get_usage_quotas,
+        api::set_usage_quotas,
+        api::list_api_keys,
+        api::create_api_key,
+        api::revoke_api_key,
+        api::get_tenant_limits,
+        api::set_tenant_limits,
+        api::get_rate_limits,
+        api::set_rate_limits,
+        api::list_order_quarantines,
+        api::lift_order_quarantine
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&ApiKeyAuth, &ErrorResponses),
+    tags(
+        (name = "status", description = "Public engine status"),
+        (name = "wallets", description = "Wallet generation, import and keystore"),
+        (name = "prices", description = "Prices, price guards, providers and watchlist"),
+        (name = "trading", description = "Swaps, quotes, routes and transfers"),
+        (name = "orders", description = "Limit orders and their history"),
+        (name = "dca", description = "Dollar cost averaging orders"),
+        (name = "portfolio", description = "Portfolio value, history and ledger"),
+        (name = "risk", description = "Risk summary and trading limits"),
+        (name = "engine", description = "Execution engine state"),
+        (name = "alerts", description = "Balance and funding rate alerts"),
+        (name = "paper", description = "Paper trading accounts"),
+        (name = "account", description = "API keys, usage, notifications and preferences"),
+        (name = "admin", description = "Operations reserved to the admin API key"),
+    )
+)]
+pub struct ApiDoc;
+
+// Body of every error response, see `error::ApiError` for the codes
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorResponse {
+    #[schema(example = false)]
+    success: bool,
+    #[schema(example = "Order not found: 5f0c...")]
+    error: String,
+    #[schema(example = "order_not_found")]
+    code: String,
+}
+
+// Requests are authenticated with the API key header
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(metering::API_KEY_HEADER))),
+        );
+        openapi.security = Some(vec![SecurityRequirement::new("api_key", Vec::<String>::new())]);
+    }
+}
+
+// Every operation can fail with the structured error body, so it is documented once as the
+// default response rather than on each handler
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let response = ResponseBuilder::new()
+            .description("Error with a machine-readable code")
+            .content(
+                "application/json",
+                ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorResponse"))).build(),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [&mut item.get, &mut item.post, &mut item.put, &mut item.patch, &mut item.delete];
+            for operation in operations.into_iter().flatten() {
+                operation.responses.responses.insert("default".to_string(), RefOr::T(response.clone()));
+            }
+        }
+    }
+}
+
+// Handler for the OpenAPI document
+pub async fn openapi_json() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
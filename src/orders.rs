@@ -1,72 +1,172 @@
-use crate::models::{AppState, LimitOrder, LimitOrderRequest, OrderStatus, OrderType, SwapRequest};
+use crate::models::{AppState, BacktraceOutcome, BacktraceTrigger, ExecutionLatency, FailedOrderGroup, FailureCause, LimitOrder, LimitOrderRequest, ListOrdersQuery, NotificationEvent, OrderAuditEntry, OrderEvent, OrderBacktrace, OrderEventKind, OrderFailure, OrderSort, OrderStatus, OrderValidation, OrderType, PricePoint, RetryFailedRequest, RetryFailedResponse, SkippedRetry, StrategyStats, TokenBalance, TriggerSource, UnconfirmedExecution, ValidationPolicy, WhatIfOutcome};
+use crate::blackout;
+use crate::creation_guard;
+use crate::drift;
+use crate::actions::{self, ActionResult, UnconfirmedSwap};
+use crate::engine;
+use crate::events;
+use crate::freeze;
+use crate::jupiter_limit;
+use crate::notify;
 use crate::price;
 use crate::swap;
+use crate::tenant;
 use anyhow::{anyhow, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::time;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 use rand;
 
-// Create a new limit order
+// Distance from the trigger price, in percent, at which a warning is sent
+const NEAR_TRIGGER_PERCENT: f64 = 2.0;
+
+// Finished orders stay in the hot order map this long before being archived
+const ARCHIVE_RETENTION_DAYS: i64 = 7;
+
+// How often finished orders are archived
+const ARCHIVE_INTERVAL_SECS: u64 = 3600;
+
+// Seconds between trigger checks for orders without their own interval
+const MONITOR_INTERVAL_ENV: &str = "MONITOR_INTERVAL_SECS";
+const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 30;
+
+// Fastest an order may be checked, so tight stops cannot hammer the price APIs
+const MIN_CHECK_INTERVAL_SECS: u64 = 2;
+
+// Retries of transient execution failures for orders that do not set their own
+const MAX_RETRIES_ENV: &str = "ORDER_MAX_RETRIES";
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const RETRY_BACKOFF_ENV: &str = "ORDER_RETRY_BACKOFF_SECS";
+const DEFAULT_RETRY_BACKOFF_SECS: u64 = 10;
+
+// Longest wait between two retries, however many came before
+const MAX_RETRY_BACKOFF_SECS: u64 = 3600;
+
+// A transaction can no longer land once its blockhash expires, about a minute after signing, so
+// an unconfirmed swap still unknown to the cluster this long after sending never landed
+const UNCONFIRMED_MAX_AGE_SECS: i64 = 600;
+
+// Create a new limit order, recording the wallet, balances, fees and prices the validation used
 pub async fn create_limit_order(
     app_state: Arc<AppState>,
-    order_request: LimitOrderRequest,
+    mut order_request: LimitOrderRequest,
+    validation: &mut OrderValidation,
 ) -> Result<LimitOrder> {
     let now = Utc::now();
     let id = Uuid::new_v4().to_string();
     
-    // Validate wallet has enough tokens for the swap
-    let wallets = app_state.wallets.lock().unwrap();
-    if wallets.is_empty() {
-        return Err(anyhow!("No wallets found to execute order"));
+    // Dead man's switch orders need a heartbeat interval to count down from
+    if order_request.order_type == OrderType::DeadMansSwitch
+        && order_request.heartbeat_interval_secs.unwrap_or(0) == 0
+    {
+        return Err(anyhow!("Dead man's switch orders require a heartbeat_interval_secs greater than zero"));
     }
     
-    // Just use the first wallet for now
-    // In a real app, this would be tied to the user who created the order
-    let wallet = wallets.values().next().unwrap();
+    if order_request.max_fee_usd.is_some_and(|max_fee| max_fee <= 0.0) {
+        return Err(anyhow!("max_fee_usd must be greater than zero"));
+    }
+    if order_request.check_interval_secs.is_some_and(|interval| interval < MIN_CHECK_INTERVAL_SECS) {
+        return Err(anyhow!("check_interval_secs must be at least {}", MIN_CHECK_INTERVAL_SECS));
+    }
+    if order_request.retry_backoff_secs == Some(0) {
+        return Err(anyhow!("retry_backoff_secs must be greater than zero"));
+    }
     
-    // Estimate transaction fees
-    let estimated_fee = crate::wallet::estimate_transaction_fees().await
-        .unwrap_or(0.01); // Default to 0.01 SOL if estimation fails
+    // In hosted mode tenants are capped on active orders and only see their own wallets
+    let tenant = order_request.api_key.clone().unwrap_or_else(|| crate::metering::ANONYMOUS_KEY.to_string());
+    tenant::check_active_orders(&app_state, &tenant)?;
+    
+    // A key creating orders in a loop is quarantined before it floods the engine
+    creation_guard::check_creation(&app_state, &tenant).await?;
+    
+    // Hedges need a perp market to short and a venue to trade it on, transfers a valid destination
+    actions::validate(&order_request.action, &order_request.order_type, &order_request.target_token)?;
+    if order_request.passive {
+        jupiter_limit::validate(&order_request)?;
+    }
+    let passive = order_request.passive;
+    
+    // A pair trigger needs two different tokens, a token priced in itself is always 1
+    let trigger_mint = order_request.trigger_mint.as_deref().unwrap_or(&order_request.target_token);
+    if order_request.quote_mint.as_deref() == Some(trigger_mint) {
+        return Err(anyhow!("quote_mint must differ from the token the order triggers on"));
+    }
+    
+    // Perp triggers need a market tracking the trigger token and a fresh mark to validate against
+    if order_request.trigger_source == TriggerSource::PerpMark {
+        let (market, _) = drift::perp_market_for_mint(trigger_mint).ok_or_else(|| {
+            anyhow!(
+                "No Drift perp market tracks {}, use the spot trigger source",
+                crate::wallet::KnownTokens::get_symbol(trigger_mint)
+            )
+        })?;
+        drift::refresh_markets(&app_state, &[market.to_string()]).await?;
+    }
+    
+    // The order is bound to this wallet for its whole lifetime
+    let wallet = crate::wallet::find_tenant_wallet(&app_state, &tenant, order_request.wallet_pubkey.as_deref())?;
+    validation.wallet = Some(wallet.pubkey.to_string());
+    freeze::check_order(&app_state, &wallet.pubkey.to_string())?;
+    
+    // Estimate transaction fees, including rent if the target token account has to be created
+    let fee_estimate = crate::wallet::estimate_order_fees(&app_state.rpc_client, &wallet.pubkey, &order_request.target_token).await;
+    let estimated_fee = fee_estimate.total_sol;
+    validation.fee_estimate = Some(fee_estimate.clone());
+    
+    // Take one balance snapshot so every check below sees the same balances
+    let balances = crate::wallet::get_token_balances(&app_state.rpc_client, &wallet).await?;
+    validation.balances = balances.clone();
+    
+    // Orders sized in US dollars or as a share of the balance are checked at today's prices and
+    // balance, and resized when they trigger
+    if let Some(amount_usd) = order_request.amount_usd {
+        crate::utils::validate_amount_usd(amount_usd, order_request.amount, order_request.amount_percent)?;
+        let amount_mint = if order_request.order_type.is_sell_side() { &order_request.source_token } else { &order_request.target_token };
+        order_request.amount = actions::amount_from_usd(&app_state, amount_mint, amount_usd)?;
+    } else if let Some(amount_percent) = order_request.amount_percent {
+        crate::utils::validate_amount_percent(amount_percent, order_request.amount)?;
+        order_request.amount = actions::amount_from_percent(
+            &app_state,
+            &order_request.order_type,
+            &order_request.source_token,
+            &order_request.target_token,
+            &balances,
+            amount_percent,
+            order_request.slippage.unwrap_or(0.5),
+        )?;
+        if order_request.amount <= 0.0 && order_request.action.uses_wallet_funds() {
+            return Err(anyhow!(
+                "The wallet holds no {} to size the order from",
+                crate::wallet::KnownTokens::get_symbol(&order_request.source_token)
+            ));
+        }
+    } else if order_request.action.uses_wallet_funds() {
+        crate::utils::validate_amount(order_request.amount)?;
+    }
     
-    info!("Estimated transaction fee for limit order: {} SOL", estimated_fee);
+    info!(
+        "Estimated fees for limit order: {} SOL network fee + {} SOL token account rent",
+        fee_estimate.network_fee_sol, fee_estimate.ata_rent_sol
+    );
     
     // Check token balance based on order type
-    if order_request.order_type == OrderType::Sell || order_request.order_type == OrderType::StopLoss {
-        // For sell and stop loss orders, check if the wallet has enough of the source token
-        let has_balance = crate::wallet::has_sufficient_balance(
-            wallet, 
+    if order_request.order_type.is_sell_side() {
+        // For sell side orders, check if the wallet has enough of the source token
+        validation.required_source_amount = Some(order_request.amount);
+        let has_balance = crate::wallet::balance_covers(
+            &balances, 
             &order_request.source_token, 
             order_request.amount
-        ).await?;
+        );
         
-        if !has_balance {
-            let order_type_str = if order_request.order_type == OrderType::Sell { "sell" } else { "stop loss" };
+        // Hedges and notify-only orders never spend the wallet's funds, safe exits sell whatever is there
+        if !has_balance && order_request.action.uses_wallet_funds() && !order_request.action.sells_whole_balance() {
+            let order_type_str = order_request.order_type.to_string().to_lowercase();
             return Err(anyhow!("Insufficient balance to create {} order. Please add funds.", order_type_str));
         }
-        
-        // For stop loss orders, validate that the price target makes sense
-        if order_request.order_type == OrderType::StopLoss {
-            // Get current price of the target token
-            let current_price = price::get_token_price(&app_state, &order_request.target_token)
-                .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
-            
-            // For stop loss, the price target should be below the current price
-            if order_request.price_target >= current_price {
-                return Err(anyhow!(
-                    "Invalid stop loss price: {} is not below the current price {}. Stop loss should be set below current price.",
-                    order_request.price_target,
-                    current_price
-                ));
-            }
-            
-            info!(
-                "Creating stop loss order with target price {} (current price: {})",
-                order_request.price_target, current_price
-            );
-        }
     } else {
         // For buy orders, we need to calculate the estimated cost in the source token
         // Get current price of the target token
@@ -76,10 +176,13 @@ pub async fn create_limit_order(
         // Get current price of the source token
         let source_price = price::get_token_price(&app_state, &order_request.source_token)
             .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
+        validation.prices.insert(order_request.target_token.clone(), target_price);
+        validation.prices.insert(order_request.source_token.clone(), source_price);
         
         // Calculate estimated amount needed in source token
         let price_ratio = if source_price > 0.0 { target_price / source_price } else { 0.0 };
         let estimated_source_amount = order_request.amount * price_ratio * (1.0 + order_request.slippage.unwrap_or(0.5) / 100.0);
+        validation.required_source_amount = Some(estimated_source_amount);
         
         info!(
             "Buy order calculation: Target price: ${}, Source price: ${}, Price ratio: {}, Estimated source amount needed: {}",
@@ -87,13 +190,13 @@ pub async fn create_limit_order(
         );
         
         // Check if the wallet has enough of the source token for the estimated cost
-        let has_enough_source = crate::wallet::has_sufficient_balance(
-            wallet,
+        let has_enough_source = crate::wallet::balance_covers(
+            &balances,
             &order_request.source_token,
             estimated_source_amount
-        ).await?;
+        );
         
-        if !has_enough_source {
+        if !has_enough_source && order_request.action.uses_wallet_funds() {
             return Err(anyhow!(
                 "Insufficient balance of {} to create buy order. Estimated amount needed: {} (based on current price: ${})",
                 crate::wallet::KnownTokens::get_symbol(&order_request.source_token),
@@ -103,30 +206,104 @@ pub async fn create_limit_order(
         }
         
         // Also ensure they have some SOL for transaction fees
-        let has_sol = crate::wallet::has_sufficient_balance(
-            wallet,
+        let has_sol = crate::wallet::balance_covers(
+            &balances,
             "So11111111111111111111111111111111111111112",
             estimated_fee
-        ).await?;
+        );
+        
+        if !has_sol && order_request.action.uses_wallet_funds() {
+            return Err(anyhow!(
+                "Insufficient SOL balance for transaction fees. Need at least {} SOL ({} SOL network fee + {} SOL token account rent).",
+                estimated_fee, fee_estimate.network_fee_sol, fee_estimate.ata_rent_sol
+            ));
+        }
+    }
+    
+    // Check the price target against the market as the validation policy asks, unless forced
+    let policy = get_validation_policy(&app_state);
+    let mut policy_violations = Vec::new();
+    if policy_applies(&policy, &order_request.order_type) {
+        let current_price = reference_price(&app_state, &order_request)?;
+        validation.prices.insert(trigger_mint.to_string(), current_price);
+        policy_violations = price_policy_violations(&policy, &order_request.order_type, order_request.price_target, current_price);
         
-        if !has_sol {
-            return Err(anyhow!("Insufficient SOL balance for transaction fees. Need at least {} SOL.", estimated_fee));
+        info!(
+            "Creating {} order with target price {} (current price: {})",
+            order_request.order_type, order_request.price_target, current_price
+        );
+    }
+    
+    if !policy_violations.is_empty() {
+        if !order_request.force {
+            return Err(anyhow!("{} Set force to create the order anyway.", policy_violations.join(" ")));
         }
+        warn!("Creating order despite the validation policy: {}", policy_violations.join(" "));
     }
     
-    let limit_order = LimitOrder {
+    // Sell side amounts are in the source token, buy amounts in the target token
+    let amount_mint = if order_request.order_type.is_sell_side() {
+        &order_request.source_token
+    } else {
+        &order_request.target_token
+    };
+    let amount_raw = crate::wallet::KnownTokens::get_decimals(amount_mint)
+        .ok()
+        .map(|decimals| crate::utils::raw_amount_string(order_request.amount, decimals as u8));
+    
+    // Dead man's switch countdowns start when the order is created
+    let last_heartbeat = if order_request.order_type == OrderType::DeadMansSwitch { Some(now) } else { None };
+    
+    let mut limit_order = LimitOrder {
         id: id.clone(),
         source_token: order_request.source_token,
         target_token: order_request.target_token,
         amount: order_request.amount,
+        amount_percent: order_request.amount_percent,
+        amount_usd: order_request.amount_usd,
         price_target: order_request.price_target,
         order_type: order_request.order_type,
         status: OrderStatus::Active,
+        amount_raw,
         created_at: now,
         updated_at: now,
         expiry_time: order_request.expiry_time,
         slippage: order_request.slippage.unwrap_or(0.5),
         transaction_signature: None,
+        priority: order_request.priority.unwrap_or(0),
+        heartbeat_interval_secs: order_request.heartbeat_interval_secs,
+        last_heartbeat,
+        fee_estimate: Some(fee_estimate),
+        validation: Some(validation.clone()),
+        fee: None,
+        failure: None,
+        max_retries: order_request.max_retries.unwrap_or_else(default_max_retries),
+        retry_backoff_secs: order_request.retry_backoff_secs.unwrap_or_else(default_retry_backoff_secs),
+        retry_count: 0,
+        next_retry_at: None,
+        strategy: order_request.strategy,
+        execution: None,
+        latency: None,
+        audit_trail: policy_violations
+            .into_iter()
+            .map(|violation| OrderAuditEntry {
+                timestamp: now,
+                event: "validation_forced".to_string(),
+                detail: violation,
+            })
+            .collect(),
+        trigger_source: order_request.trigger_source,
+        trigger_mint: order_request.trigger_mint,
+        quote_mint: order_request.quote_mint,
+        action: order_request.action,
+        max_fee_usd: order_request.max_fee_usd,
+        check_interval_secs: order_request.check_interval_secs,
+        dry_run: order_request.dry_run,
+        priority_fee: order_request.priority_fee,
+        mev_protection: order_request.mev_protection,
+        passive: None,
+        wallet_pubkey: wallet.pubkey.to_string(),
+        api_key: order_request.api_key,
     };
     
     info!("Creating new {:?} limit order {} to swap {} {} for {} at price {}",
@@ -137,346 +314,1667 @@ pub async fn create_limit_order(
            crate::wallet::KnownTokens::get_symbol(&limit_order.target_token),
            limit_order.price_target);
     
+    // Passive orders rest on chain from the start and are never polled
+    if passive {
+        limit_order.passive = Some(jupiter_limit::place_order(&app_state, &wallet, &limit_order).await?);
+    }
+    
     // Add the order to app state
     let mut orders = app_state.limit_orders.lock().unwrap();
     orders.insert(id, limit_order.clone());
+    events::publish_order(&app_state, &limit_order);
+    record_event(&app_state, &limit_order, OrderEventKind::Created, None, None);
+    creation_guard::record_creation(&app_state, &tenant);
     
     Ok(limit_order)
 }
 
-// Get all limit orders
-pub fn get_limit_orders(app_state: Arc<AppState>) -> Vec<LimitOrder> {
-    let orders = app_state.limit_orders.lock().unwrap();
-    orders.values().cloned().collect()
+// Current validation policy
+pub fn get_validation_policy(app_state: &AppState) -> ValidationPolicy {
+    let policy = app_state.validation_policy.lock().unwrap();
+    policy.clone()
 }
 
-// Cancel a limit order
-pub fn cancel_limit_order(app_state: Arc<AppState>, order_id: &str) -> Result<LimitOrder> {
-    let mut orders = app_state.limit_orders.lock().unwrap();
+// Validate and store the validation policy
+pub fn set_validation_policy(app_state: &AppState, policy: ValidationPolicy) -> Result<ValidationPolicy> {
+    if !(0.0..100.0).contains(&policy.min_distance_percent) {
+        return Err(anyhow!("min_distance_percent must be at least 0 and below 100"));
+    }
     
-    if let Some(mut order) = orders.get(order_id).cloned() {
-        // Only cancel active orders
-        if order.status == OrderStatus::Active {
-            order.status = OrderStatus::Cancelled;
-            order.updated_at = Utc::now();
-            orders.insert(order_id.to_string(), order.clone());
-            
-            info!("Cancelled limit order {}", order_id);
-            Ok(order)
-        } else {
-            Err(anyhow!("Cannot cancel an order that is not active (current status: {:?})", order.status))
+    let mut current = app_state.validation_policy.lock().unwrap();
+    *current = policy.clone();
+    info!("Validation policy updated: {:?}", policy);
+    Ok(policy)
+}
+
+// Whether the policy checks the price target of this order type at all
+fn policy_applies(policy: &ValidationPolicy, order_type: &OrderType) -> bool {
+    let direction_checked = match order_type {
+        OrderType::StopLoss => policy.stop_loss_below_market,
+        OrderType::TakeProfit => policy.take_profit_above_market,
+        OrderType::StopBuy => policy.stop_buy_above_market,
+        OrderType::Buy => policy.buy_below_market,
+        OrderType::Sell => policy.sell_above_market,
+        // Dead man's switches ignore their price target
+        OrderType::DeadMansSwitch => return false,
+    };
+    direction_checked || policy.min_distance_percent > 0.0
+}
+
+// Rules of the validation policy a price target breaks at the current price
+fn price_policy_violations(policy: &ValidationPolicy, order_type: &OrderType, price_target: f64, current_price: f64) -> Vec<String> {
+    let mut violations = Vec::new();
+    
+    let direction = match order_type {
+        OrderType::StopLoss if policy.stop_loss_below_market && price_target >= current_price => Some(format!(
+            "Invalid stop loss price: {} is not below the current price {}. Stop loss should be set below current price.",
+            price_target, current_price
+        )),
+        OrderType::TakeProfit if policy.take_profit_above_market && price_target <= current_price => Some(format!(
+            "Invalid take profit price: {} is not above the current price {}. Take profit should be set above current price.",
+            price_target, current_price
+        )),
+        OrderType::StopBuy if policy.stop_buy_above_market && price_target <= current_price => Some(format!(
+            "Invalid stop buy price: {} is not above the current price {}. Stop buy should be set above current price.",
+            price_target, current_price
+        )),
+        OrderType::Buy if policy.buy_below_market && price_target >= current_price => Some(format!(
+            "Invalid buy price: {} is not below the current price {}, the order would fill immediately.",
+            price_target, current_price
+        )),
+        OrderType::Sell if policy.sell_above_market && price_target <= current_price => Some(format!(
+            "Invalid sell price: {} is not above the current price {}, the order would fill immediately.",
+            price_target, current_price
+        )),
+        _ => None,
+    };
+    violations.extend(direction);
+    
+    if current_price > 0.0 && policy.min_distance_percent > 0.0 {
+        let distance_percent = (price_target - current_price).abs() / current_price * 100.0;
+        if distance_percent < policy.min_distance_percent {
+            violations.push(format!(
+                "Price target {} is {:.2}% from the current price {}, closer than the minimum of {}%.",
+                price_target, distance_percent, current_price, policy.min_distance_percent
+            ));
         }
-    } else {
-        Err(anyhow!("Order not found: {}", order_id))
     }
+    
+    violations
 }
 
-// Check if an order should be executed
-fn should_execute_order(order: &LimitOrder, current_price: f64) -> bool {
-    match order.order_type {
-        OrderType::Buy => {
-            // Buy when the price is below or equal to the target price
-            current_price <= order.price_target
-        }
-        OrderType::Sell => {
-            // Sell when the price is above or equal to the target price
-            current_price >= order.price_target
+// Price a new order's target is checked against, from the order's trigger source
+fn reference_price(app_state: &AppState, order_request: &LimitOrderRequest) -> Result<f64> {
+    let trigger_mint = order_request.trigger_mint.as_deref().unwrap_or(&order_request.target_token);
+    let price = match order_request.trigger_source {
+        TriggerSource::Spot => price::get_token_price(app_state, trigger_mint)
+            .map_err(|e| anyhow!("Failed to get price for trigger token: {}", e))?,
+        TriggerSource::PerpMark => drift::get_mark_price(app_state, trigger_mint)
+            .map_err(|e| anyhow!("Failed to get perp mark price for trigger token: {}", e))?,
+    };
+    
+    match &order_request.quote_mint {
+        Some(quote_mint) => {
+            let quote_price = price::get_token_price(app_state, quote_mint)
+                .map_err(|e| anyhow!("Failed to get price for quote token: {}", e))?;
+            if quote_price <= 0.0 {
+                return Err(anyhow!("Price of quote token {} is zero", quote_mint));
+            }
+            Ok(price / quote_price)
         }
-        OrderType::StopLoss => {
-            // Stop loss triggers when the price drops to or below the target price
-            current_price <= order.price_target
+        None => Ok(price),
+    }
+}
+
+// Get all limit orders the tenant may see
+pub fn get_limit_orders(app_state: Arc<AppState>, tenant: &str, query: &ListOrdersQuery) -> Vec<LimitOrder> {
+    let orders = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.values().filter(|order| tenant::can_access_order(tenant, order)).cloned().collect::<Vec<_>>()
+    };
+    filter_orders(orders, query)
+}
+
+// Whether an order passes the listing filters of the query
+fn matches_query(order: &LimitOrder, query: &ListOrdersQuery) -> bool {
+    if matches!(&query.status, Some(status) if order.status != *status) {
+        return false;
+    }
+    if matches!(&query.order_type, Some(order_type) if order.order_type != *order_type) {
+        return false;
+    }
+    if matches!(query.created_after, Some(created_after) if order.created_at <= created_after) {
+        return false;
+    }
+    match query.token.as_deref() {
+        Some(token) => {
+            order.source_token == token
+                || order.target_token == token
+                || order.trigger_mint() == token
+                || order.quote_mint.as_deref() == Some(token)
         }
+        None => true,
     }
 }
 
-// Execute a limit order
-async fn execute_order(app_state: Arc<AppState>, order: LimitOrder) -> Result<LimitOrder> {
-    // Get the wallet
-    let wallets = app_state.wallets.lock().unwrap();
-    if wallets.is_empty() {
-        return Err(anyhow!("No wallets found to execute order"));
+// Filter, sort and page a listing of orders by the query
+pub fn filter_orders(orders: Vec<LimitOrder>, query: &ListOrdersQuery) -> Vec<LimitOrder> {
+    let mut orders = orders.into_iter().filter(|order| matches_query(order, query)).collect::<Vec<_>>();
+    
+    match query.sort {
+        OrderSort::CreatedDesc => orders.sort_by_key(|order| std::cmp::Reverse(order.created_at)),
+        OrderSort::CreatedAsc => orders.sort_by_key(|order| order.created_at),
+        OrderSort::UpdatedDesc => orders.sort_by_key(|order| std::cmp::Reverse(order.updated_at)),
+        OrderSort::UpdatedAsc => orders.sort_by_key(|order| order.updated_at),
+        OrderSort::PriceTargetDesc => orders.sort_by(|a, b| b.price_target.total_cmp(&a.price_target)),
+        OrderSort::PriceTargetAsc => orders.sort_by(|a, b| a.price_target.total_cmp(&b.price_target)),
     }
     
-    // Just use the first wallet for now
-    // In a real app, this would be tied to the user who created the order
-    let wallet = wallets.values().next().unwrap();
+    orders
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+// Archived orders the tenant may see, moved out of the hot order map once finished
+pub fn get_archived_orders(app_state: &AppState, tenant: &str) -> Vec<LimitOrder> {
+    let archived = app_state.archived_orders.lock().unwrap();
+    archived.values().filter(|order| tenant::can_access_order(tenant, order)).cloned().collect()
+}
+
+// Whether an order is finished and can be archived
+fn is_archivable(order: &LimitOrder) -> bool {
+    order.status.is_terminal() || order.status == OrderStatus::Failed
+}
+
+// Soft-delete a finished order by moving it to the archive
+pub fn archive_order(app_state: &AppState, tenant: &str, order_id: &str) -> Result<LimitOrder> {
+    let mut orders = app_state.limit_orders.lock().unwrap();
     
-    // Estimate transaction fees
-    let estimated_fee = crate::wallet::estimate_transaction_fees().await
-        .unwrap_or(0.01); // Default to 0.01 SOL if estimation fails
+    match orders.get(order_id).filter(|order| tenant::can_access_order(tenant, order)) {
+        Some(order) if !is_archivable(order) => {
+            return Err(anyhow!("Only finished orders can be archived (current status: {:?})", order.status));
+        }
+        Some(_) => {}
+        None => return Err(anyhow!("Order not found: {}", order_id)),
+    }
     
-    info!("Estimated transaction fee for order execution: {} SOL", estimated_fee);
+    let order = orders.remove(order_id).unwrap();
+    app_state.archived_orders.lock().unwrap().insert(order.id.clone(), order.clone());
     
-    // Get current prices for calculation
-    let target_price = price::get_token_price(&app_state, &order.target_token)
-        .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
+    info!("Archived order {}", order_id);
+    Ok(order)
+}
+
+// Archive every finished order last updated before the cutoff, returning how many were moved
+pub fn archive_orders_before(app_state: &AppState, cutoff: DateTime<Utc>) -> usize {
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    let expired_ids = orders
+        .values()
+        .filter(|order| is_archivable(order) && order.updated_at < cutoff)
+        .map(|order| order.id.clone())
+        .collect::<Vec<_>>();
     
-    // Double-check balance before executing based on order type
-    if order.order_type == OrderType::Sell || order.order_type == OrderType::StopLoss {
-        // For sell and stop loss orders, check if the wallet still has enough of the source token
-        let has_balance = crate::wallet::has_sufficient_balance(
-            wallet, 
-            &order.source_token, 
-            order.amount
-        ).await?;
-        
-        if !has_balance {
-            // Mark the order as failed due to insufficient balance
-            let mut orders = app_state.limit_orders.lock().unwrap();
-            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
-                updated_order.status = OrderStatus::Failed;
-                updated_order.updated_at = Utc::now();
-                orders.insert(order.id.clone(), updated_order.clone());
-                
-                let order_type_str = if order.order_type == OrderType::Sell { "Sell" } else { "Stop loss" };
-                error!("{} order {} failed: Insufficient balance of {} to execute", 
-                       order_type_str, order.id, crate::wallet::KnownTokens::get_symbol(&order.source_token));
-                
-                return Ok(updated_order);
-            }
-            return Err(anyhow!("Insufficient balance to execute sell order"));
-        }
-    } else {
-        // For buy orders, we need to calculate the estimated cost in the source token
-        // Get current price of the source token
-        let source_price = price::get_token_price(&app_state, &order.source_token)
-            .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
-        
-        // Calculate estimated amount needed in source token using current prices
-        let price_ratio = if source_price > 0.0 { target_price / source_price } else { 0.0 };
-        let estimated_source_amount = order.amount * price_ratio * (1.0 + order.slippage / 100.0);
-        
-        info!(
-            "Buy order execution calculation: Target price: ${}, Source price: ${}, Price ratio: {}, Estimated source amount needed: {}",
-            target_price, source_price, price_ratio, estimated_source_amount
-        );
-        
-        // Check if the wallet has enough of the source token for the estimated cost
-        let has_enough_source = crate::wallet::has_sufficient_balance(
-            wallet,
-            &order.source_token,
-            estimated_source_amount
-        ).await?;
-        
-        if !has_enough_source {
-            // Mark the order as failed due to insufficient balance
-            let mut orders = app_state.limit_orders.lock().unwrap();
-            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
-                updated_order.status = OrderStatus::Failed;
-                updated_order.updated_at = Utc::now();
-                orders.insert(order.id.clone(), updated_order.clone());
-                
-                let order_type_str = if order.order_type == OrderType::Buy { "Buy" } else { "Stop loss" };
-                error!(
-                    "{} order {} failed: Insufficient balance of {} to execute. Needed: {}, Current price: ${}",
-                    order_type_str, order.id, 
-                    crate::wallet::KnownTokens::get_symbol(&order.source_token),
-                    estimated_source_amount,
-                    source_price
-                );
-                
-                return Ok(updated_order);
-            }
-            return Err(anyhow!("Insufficient balance to execute buy order"));
+    let mut archived = app_state.archived_orders.lock().unwrap();
+    for id in &expired_ids {
+        if let Some(order) = orders.remove(id) {
+            archived.insert(id.clone(), order);
         }
+    }
+    
+    expired_ids.len()
+}
+
+// Background task archiving finished orders past the retention window
+pub async fn run_order_archiver(app_state: Arc<AppState>) {
+    info!("Starting order archiver task, retention {} days", ARCHIVE_RETENTION_DAYS);
+    
+    loop {
+        time::sleep(time::Duration::from_secs(ARCHIVE_INTERVAL_SECS)).await;
         
-        // Also ensure they have some SOL for transaction fees
-        let has_sol = crate::wallet::has_sufficient_balance(
-            wallet,
-            "So11111111111111111111111111111111111111112",
-            estimated_fee
-        ).await?;
-        
-        if !has_sol {
-            // Mark the order as failed due to insufficient SOL
-            let mut orders = app_state.limit_orders.lock().unwrap();
-            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
-                updated_order.status = OrderStatus::Failed;
-                updated_order.updated_at = Utc::now();
-                orders.insert(order.id.clone(), updated_order.clone());
-                
-                error!("Order {} failed: Insufficient SOL for transaction fees. Need at least {} SOL", 
-                       order.id, estimated_fee);
-                
-                return Ok(updated_order);
-            }
-            return Err(anyhow!("Insufficient SOL for transaction fees"));
+        let cutoff = Utc::now() - chrono::Duration::days(ARCHIVE_RETENTION_DAYS);
+        let archived = archive_orders_before(&app_state, cutoff);
+        if archived > 0 {
+            info!("Archived {} finished orders older than {}", archived, cutoff);
         }
     }
+}
+
+// Compare order outcomes per strategy tag over orders created in the window, archived ones included
+pub fn get_strategy_stats(
+    app_state: &AppState,
+    tenant: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Vec<StrategyStats> {
+    let mut all_orders = get_archived_orders(app_state, tenant);
+    {
+        let orders = app_state.limit_orders.lock().unwrap();
+        all_orders.extend(orders.values().filter(|order| tenant::can_access_order(tenant, order)).cloned());
+    }
     
-    // Create swap request
-    let swap_request = SwapRequest {
-        source_token: order.source_token.clone(),
-        target_token: order.target_token.clone(),
-        amount: order.amount,
-        slippage: Some(order.slippage),
-    };
+    let mut by_strategy: HashMap<String, Vec<LimitOrder>> = HashMap::new();
+    for order in all_orders {
+        if since.is_some_and(|since| order.created_at < since) || until.is_some_and(|until| order.created_at > until) {
+            continue;
+        }
+        let strategy = order.strategy.clone().unwrap_or_else(|| "untagged".to_string());
+        by_strategy.entry(strategy).or_default().push(order);
+    }
     
-    info!("Executing limit order {} - {:?} order for {} {} at price target {}",
-           order.id,
-           order.order_type,
-           order.amount,
-           crate::wallet::KnownTokens::get_symbol(&order.source_token),
-           order.price_target);
-    
-    // Execute swap
-    match swap::execute_swap(wallet, &swap_request).await {
-        Ok(swap_result) => {
-            // Update order
-            let mut orders = app_state.limit_orders.lock().unwrap();
-            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
-                updated_order.status = OrderStatus::Completed;
-                updated_order.updated_at = Utc::now();
-                updated_order.transaction_signature = Some(swap_result.transaction_signature.clone());
-                
-                orders.insert(order.id.clone(), updated_order.clone());
-                
-                info!(
-                    "Successfully executed limit order {}: {} -> {} for {} at price {}. Signature: {}",
-                    order.id, 
-                    crate::wallet::KnownTokens::get_symbol(&order.source_token), 
-                    crate::wallet::KnownTokens::get_symbol(&order.target_token), 
-                    order.amount, 
-                    order.price_target,
-                    swap_result.transaction_signature
-                );
-                
-                Ok(updated_order)
+    let mut stats = by_strategy
+        .into_iter()
+        .map(|(strategy, orders)| {
+            let count = |status: OrderStatus| orders.iter().filter(|order| order.status == status).count();
+            let completed = count(OrderStatus::Completed);
+            let failed = count(OrderStatus::Failed);
+            let expired = count(OrderStatus::Expired);
+            let cancelled = count(OrderStatus::Cancelled);
+            
+            // Cancelled orders were withdrawn by the user, so they do not count against the fill rate
+            let attempted = completed + failed + expired;
+            let fill_rate = if attempted > 0 { completed as f64 / attempted as f64 } else { 0.0 };
+            
+            let executions = orders
+                .iter()
+                .filter_map(|order| order.execution.as_ref().map(|execution| (order, execution)))
+                .collect::<Vec<_>>();
+            
+            let average_slippage_percent = if executions.is_empty() {
+                0.0
             } else {
-                Err(anyhow!("Order not found after execution: {}", order.id))
+                executions.iter().map(|(_, execution)| execution.slippage_percent).sum::<f64>() / executions.len() as f64
+            };
+            
+            let pnl_usd = executions
+                .iter()
+                .map(|(order, execution)| {
+                    let source_price = price::get_token_price(app_state, &order.source_token).unwrap_or(0.0);
+                    let target_price = price::get_token_price(app_state, &order.target_token).unwrap_or(0.0);
+                    execution.target_amount * target_price - execution.source_amount * source_price
+                })
+                .sum();
+            
+            let fees_usd: f64 = executions
+                .iter()
+                .filter_map(|(order, _)| order.fee.as_ref().and_then(|fee| fee.total_usd))
+                .sum();
+            let traded_usd: f64 = executions.iter().map(|(_, execution)| execution.input_value_usd).sum();
+            let fee_drag_percent = if traded_usd > 0.0 { fees_usd / traded_usd * 100.0 } else { 0.0 };
+            
+            StrategyStats {
+                strategy,
+                orders: orders.len(),
+                completed,
+                failed,
+                expired,
+                cancelled,
+                fill_rate,
+                average_slippage_percent,
+                pnl_usd,
+                fees_usd,
+                fee_drag_percent,
             }
+        })
+        .collect::<Vec<_>>();
+    
+    stats.sort_by(|a, b| a.strategy.cmp(&b.strategy));
+    stats
+}
+
+// Failed orders grouped by cause, largest group first, optionally only those failed since a time
+pub fn get_failed_orders(app_state: &AppState, tenant: &str, since: Option<DateTime<Utc>>) -> Vec<FailedOrderGroup> {
+    let orders = app_state.limit_orders.lock().unwrap();
+    let mut groups: HashMap<FailureCause, Vec<LimitOrder>> = HashMap::new();
+    
+    let failed = orders
+        .values()
+        .filter(|order| order.status == OrderStatus::Failed && tenant::can_access_order(tenant, order));
+    for order in failed {
+        // Orders that failed before failures were recorded only have their update time
+        let failed_at = order.failure.as_ref().map_or(order.updated_at, |failure| failure.failed_at);
+        if since.is_some_and(|since| failed_at < since) {
+            continue;
         }
-        Err(err) => {
-            error!("Failed to execute order {}: {}", order.id, err);
-            
-            // Mark order as failed
-            let mut orders = app_state.limit_orders.lock().unwrap();
-            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
-                updated_order.status = OrderStatus::Failed;
-                updated_order.updated_at = Utc::now();
-                
-                orders.insert(order.id.clone(), updated_order.clone());
-                
-                Ok(updated_order)
-            } else {
-                Err(anyhow!("Order not found after failed execution: {}", order.id))
+        
+        let cause = order.failure.as_ref().map_or(FailureCause::Unknown, |failure| failure.cause);
+        groups.entry(cause).or_default().push(order.clone());
+    }
+    
+    let mut groups = groups
+        .into_iter()
+        .map(|(cause, mut orders)| {
+            orders.sort_by_key(|order| std::cmp::Reverse(order.updated_at));
+            FailedOrderGroup {
+                cause,
+                count: orders.len(),
+                remediation: cause.remediation(),
+                orders,
             }
+        })
+        .collect::<Vec<_>>();
+    
+    groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+    groups
+}
+
+// Whether a failed order matches the bulk retry filters
+fn matches_retry_filter(order: &LimitOrder, request: &RetryFailedRequest) -> bool {
+    if order.status != OrderStatus::Failed {
+        return false;
+    }
+    if let Some(order_ids) = &request.order_ids {
+        if !order_ids.contains(&order.id) {
+            return false;
+        }
+    }
+    if let Some(cause) = request.cause {
+        if order.failure.as_ref().map_or(FailureCause::Unknown, |failure| failure.cause) != cause {
+            return false;
+        }
+    }
+    if let Some(order_type) = &request.order_type {
+        if &order.order_type != order_type {
+            return false;
+        }
+    }
+    if let Some(since) = request.since {
+        let failed_at = order.failure.as_ref().map_or(order.updated_at, |failure| failure.failed_at);
+        if failed_at < since {
+            return false;
         }
     }
+    true
 }
 
-// Background task to monitor limit orders
-pub async fn monitor_limit_orders(app_state: Arc<AppState>) {
-    info!("Starting limit order monitor task");
+// Source token an order will spend when it executes, at current prices
+fn required_source_amount(app_state: &AppState, order: &LimitOrder, slippage: f64) -> Result<f64> {
+    let target_price = price::get_token_price(app_state, &order.target_token)
+        .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
     
-    // Wait a bit on startup to make sure everything is initialized
-    time::sleep(time::Duration::from_secs(5)).await;
+    if order.order_type.is_sell_side() {
+        return Ok(order.amount);
+    }
     
-    loop {
-        // Sleep for a few seconds to avoid hammering the APIs
-        time::sleep(time::Duration::from_secs(30)).await;
+    let source_price = price::get_token_price(app_state, &order.source_token)
+        .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
+    let price_ratio = if source_price > 0.0 { target_price / source_price } else { 0.0 };
+    Ok(order.amount * price_ratio * (1.0 + slippage / 100.0))
+}
+
+// Reactivate failed orders matching the filters after re-validating prices and balances.
+// Balances are reserved across the batch so the retried orders can all be covered together.
+pub async fn retry_failed_orders(app_state: Arc<AppState>, tenant: &str, request: RetryFailedRequest) -> Result<RetryFailedResponse> {
+    if let Some(slippage) = request.slippage {
+        crate::utils::validate_slippage(slippage)?;
+    }
+    
+    let candidates = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        let mut candidates = orders
+            .values()
+            .filter(|order| tenant::can_access_order(tenant, order) && matches_retry_filter(order, &request))
+            .cloned()
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.created_at.cmp(&b.created_at)));
+        candidates
+    };
+    
+    if candidates.is_empty() {
+        return Ok(RetryFailedResponse { retried: Vec::new(), skipped: Vec::new() });
+    }
+    
+    price::update_prices(app_state.clone()).await?;
+    
+    // Balances and reservations are tracked per wallet since each order spends from its own
+    let mut balances_by_wallet: HashMap<String, Vec<TokenBalance>> = HashMap::new();
+    let mut reserved: HashMap<(String, String), f64> = HashMap::new();
+    let mut retried = Vec::new();
+    let mut skipped = Vec::new();
+    
+    for order in candidates {
+        let slippage = request.slippage.unwrap_or(order.slippage);
         
-        // Skip if no wallets are available
-        {
-            let wallets = app_state.wallets.lock().unwrap();
-            if wallets.is_empty() {
-                continue;
+        if !balances_by_wallet.contains_key(&order.wallet_pubkey) {
+            let balances = match crate::wallet::find_wallet(&app_state, Some(&order.wallet_pubkey)) {
+                Ok(wallet) => crate::wallet::get_token_balances(&app_state.rpc_client, &wallet).await,
+                Err(err) => Err(err),
+            };
+            match balances {
+                Ok(balances) => {
+                    balances_by_wallet.insert(order.wallet_pubkey.clone(), balances);
+                }
+                Err(err) => {
+                    skipped.push(SkippedRetry { order_id: order.id.clone(), reason: err.to_string() });
+                    continue;
+                }
             }
         }
+        let balances = &balances_by_wallet[&order.wallet_pubkey];
+        let reservation_key = (order.wallet_pubkey.clone(), order.source_token.clone());
         
-        // Update token prices
-        if let Err(err) = price::update_prices(app_state.clone()).await {
-            error!("Failed to update prices: {}", err);
+        let required = match required_source_amount(&app_state, &order, slippage) {
+            Ok(required) => required,
+            Err(err) => {
+                skipped.push(SkippedRetry { order_id: order.id.clone(), reason: err.to_string() });
+                continue;
+            }
+        };
+        
+        let already_reserved = reserved.get(&reservation_key).copied().unwrap_or(0.0);
+        if !crate::wallet::balance_covers(balances, &order.source_token, already_reserved + required) {
+            skipped.push(SkippedRetry {
+                order_id: order.id.clone(),
+                reason: format!(
+                    "Insufficient balance of {}: need {}",
+                    crate::wallet::KnownTokens::get_symbol(&order.source_token),
+                    required
+                ),
+            });
             continue;
         }
         
-        // Get active orders
-        let orders = {
-            let orders_lock = app_state.limit_orders.lock().unwrap();
-            orders_lock
-                .values()
-                .filter(|order| order.status == OrderStatus::Active)
-                .cloned()
-                .collect::<Vec<_>>()
+        let mut orders = app_state.limit_orders.lock().unwrap();
+        let stored = match orders.get_mut(&order.id) {
+            Some(stored) => stored,
+            None => {
+                skipped.push(SkippedRetry { order_id: order.id.clone(), reason: "Order not found".to_string() });
+                continue;
+            }
         };
         
-        if !orders.is_empty() {
-            info!("Checking {} active limit orders", orders.len());
+        // The order may have been cancelled while we were validating
+        if let Err(err) = stored.transition(OrderStatus::Active) {
+            skipped.push(SkippedRetry { order_id: order.id.clone(), reason: err.to_string() });
+            continue;
         }
         
-        for order in orders {
-            // Check if the order has expired
-            if let Some(expiry_time) = order.expiry_time {
-                if Utc::now() > expiry_time {
-                    info!("Order {} has expired, cancelling", order.id);
-                    if let Err(err) = cancel_limit_order(app_state.clone(), &order.id) {
-                        error!("Failed to cancel expired order {}: {}", order.id, err);
-                    }
-                    continue;
-                }
-            }
-            
-            // Get the current price of the target token
-            match price::get_token_price(&app_state, &order.target_token) {
-                Ok(current_price) => {
-                    let should_execute = should_execute_order(&order, current_price);
-                    
-                    // Add debug logging based on order type
-                    match order.order_type {
-                        OrderType::Buy => {
-                            if current_price <= order.price_target {
-                                info!("Buy order {} triggered - current price {} <= target {}", 
-                                       order.id, current_price, order.price_target);
-                            } else {
-                                // Only log occasionally to avoid spamming the logs
-                                if rand::random::<u8>() < 5 { // ~2% chance
-                                    info!("Buy order {} waiting - current price {} > target {}", 
-                                          order.id, current_price, order.price_target);
-                                }
-                            }
-                        }
-                        OrderType::Sell => {
-                            if current_price >= order.price_target {
-                                info!("Sell order {} triggered - current price {} >= target {}", 
-                                       order.id, current_price, order.price_target);
-                            } else {
-                                // Only log occasionally to avoid spamming the logs
-                                if rand::random::<u8>() < 5 { // ~2% chance
-                                    info!("Sell order {} waiting - current price {} < target {}", 
-                                          order.id, current_price, order.price_target);
-                                }
-                            }
-                        }
-                        OrderType::StopLoss => {
-                            if current_price <= order.price_target {
-                                info!("Stop loss order {} triggered - current price {} <= target {}", 
-                                       order.id, current_price, order.price_target);
-                            } else {
-                                // Only log occasionally to avoid spamming the logs
-                                if rand::random::<u8>() < 5 { // ~2% chance
-                                    info!("Stop loss order {} waiting - current price {} > target {}", 
-                                          order.id, current_price, order.price_target);
-                                }
+        stored.slippage = slippage;
+        stored.failure = None;
+        *reserved.entry(reservation_key).or_insert(0.0) += required;
+        events::publish_order(&app_state, stored);
+        record_event(&app_state, stored, OrderEventKind::Reactivated, Some(format!("Retried with {}% slippage", slippage)), None);
+        retried.push(stored.clone());
+    }
+    
+    info!("Retried {} failed orders, skipped {}", retried.len(), skipped.len());
+    Ok(RetryFailedResponse { retried, skipped })
+}
+
+// Move a stored order to a new status through the lifecycle state machine
+pub fn set_order_status(app_state: &AppState, order_id: &str, next: OrderStatus) -> Result<LimitOrder> {
+    transition_order(app_state, order_id, next, None)
+}
+
+// Move a stored order to a new status, recording why in its history
+pub fn transition_order(app_state: &AppState, order_id: &str, next: OrderStatus, reason: Option<String>) -> Result<LimitOrder> {
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    let order = orders
+        .get_mut(order_id)
+        .ok_or_else(|| anyhow!("Order not found: {}", order_id))?;
+    
+    order.transition(next)?;
+    events::publish_order(app_state, order);
+    record_event(app_state, order, OrderEventKind::for_status(&order.status), reason, None);
+    Ok(order.clone())
+}
+
+// Record a step of an order's lifecycle in its history, with the cached prices of its tokens
+pub fn record_event(
+    app_state: &AppState,
+    order: &LimitOrder,
+    kind: OrderEventKind,
+    reason: Option<String>,
+    trigger_price: Option<f64>,
+) {
+    let mut mints = vec![order.source_token.as_str(), order.target_token.as_str(), order.trigger_mint()];
+    mints.extend(order.quote_mint.as_deref());
+    
+    let prices = mints
+        .into_iter()
+        .filter_map(|mint| price::get_token_price(app_state, mint).ok().map(|price| (mint.to_string(), price)))
+        .collect();
+    
+    let event = OrderEvent {
+        timestamp: Utc::now(),
+        kind,
+        status: order.status.clone(),
+        reason,
+        trigger_price,
+        prices,
+    };
+    
+    let mut order_events = app_state.order_events.lock().unwrap();
+    order_events.entry(order.id.clone()).or_default().push(event);
+}
+
+// History of an order the tenant may see, archived or not, oldest event first
+pub fn get_order_history(app_state: &AppState, tenant: &str, order_id: &str) -> Result<Vec<OrderEvent>> {
+    let visible = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        let archived = app_state.archived_orders.lock().unwrap();
+        orders
+            .get(order_id)
+            .or_else(|| archived.get(order_id))
+            .is_some_and(|order| tenant::can_access_order(tenant, order))
+    };
+    if !visible {
+        return Err(anyhow!("Order not found: {}", order_id));
+    }
+    
+    let order_events = app_state.order_events.lock().unwrap();
+    Ok(order_events.get(order_id).cloned().unwrap_or_default())
+}
+
+// Record a decision taken on an order's behalf on its audit trail
+pub fn record_audit(app_state: &AppState, order_id: &str, event: &str, detail: String) {
+    info!("Order {} {}: {}", order_id, event, detail);
+    
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    if let Some(order) = orders.get_mut(order_id) {
+        order.audit_trail.push(OrderAuditEntry {
+            timestamp: Utc::now(),
+            event: event.to_string(),
+            detail,
+        });
+    }
+}
+
+// Cancel a limit order owned by the tenant. A passive order is cancelled on chain first.
+pub async fn cancel_limit_order(app_state: Arc<AppState>, tenant: &str, order_id: &str) -> Result<LimitOrder> {
+    // Another tenant's order is reported exactly like one that does not exist
+    let order = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.get(order_id).filter(|order| tenant::can_access_order(tenant, order)).cloned()
+    };
+    let order = order.ok_or_else(|| anyhow!("Order not found: {}", order_id))?;
+    
+    if order.status.can_transition_to(&OrderStatus::Cancelled) {
+        if let Some(signature) = jupiter_limit::cancel_passive_order(&app_state, &order).await? {
+            let mut orders = app_state.limit_orders.lock().unwrap();
+            if let Some(passive) = orders.get_mut(order_id).and_then(|order| order.passive.as_mut()) {
+                passive.cancel_signature = Some(signature);
+            }
+        }
+    }
+    
+    let order = transition_order(&app_state, order_id, OrderStatus::Cancelled, Some("Cancelled by the owner".to_string()))?;
+    info!("Cancelled limit order {}", order_id);
+    Ok(order)
+}
+
+// Cancel every order of a wallet that could still execute, before the wallet is removed.
+// Refused while one of them is executing, so no swap is left without its wallet halfway.
+pub fn cancel_wallet_orders(app_state: &AppState, pubkey: &str) -> Result<Vec<String>> {
+    let open = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        let wallet_orders = orders.values().filter(|order| order.wallet_pubkey == pubkey);
+        if let Some(executing) = wallet_orders.clone().find(|order| order.status == OrderStatus::Executing) {
+            return Err(anyhow!("Order {} of wallet {} is executing, try again once it finishes", executing.id, pubkey));
+        }
+        // The funds of a passive order sit with the program until it is cancelled with the wallet's key
+        if let Some(resting) = wallet_orders.clone().find(|order| order.status == OrderStatus::Active && order.passive.is_some()) {
+            return Err(anyhow!("Passive order {} of wallet {} is resting on chain, cancel it first", resting.id, pubkey));
+        }
+        wallet_orders
+            .filter(|order| order.status.can_transition_to(&OrderStatus::Cancelled))
+            .map(|order| order.id.clone())
+            .collect::<Vec<_>>()
+    };
+    
+    for order_id in &open {
+        transition_order(app_state, order_id, OrderStatus::Cancelled, Some("Wallet removed".to_string()))?;
+    }
+    
+    info!("Cancelled {} orders of removed wallet {}", open.len(), pubkey);
+    Ok(open)
+}
+
+fn should_execute_order(order: &LimitOrder, current_price: f64) -> bool {
+    match order.order_type {
+        OrderType::Buy => {
+            // Buy when the price is below or equal to the target price
+            current_price <= order.price_target
+        }
+        OrderType::Sell => {
+            // Sell when the price is above or equal to the target price
+            current_price >= order.price_target
+        }
+        OrderType::StopLoss => {
+            // Stop loss triggers when the price drops to or below the target price
+            current_price <= order.price_target
+        }
+        OrderType::TakeProfit => {
+            // Take profit triggers when the price rises to or above the target price
+            current_price >= order.price_target
+        }
+        OrderType::StopBuy => {
+            // Stop buy triggers when the price breaks out to or above the target price
+            current_price >= order.price_target
+        }
+        OrderType::DeadMansSwitch => {
+            // Dead man's switch ignores the price and fires when the heartbeat lapses
+            heartbeat_expired(order, Utc::now())
+        }
+    }
+}
+
+// Check if the price is within NEAR_TRIGGER_PERCENT of a price based trigger
+fn is_near_trigger(order: &LimitOrder, current_price: f64) -> bool {
+    if order.order_type == OrderType::DeadMansSwitch || order.price_target <= 0.0 {
+        return false;
+    }
+    
+    (current_price - order.price_target).abs() / order.price_target * 100.0 <= NEAR_TRIGGER_PERCENT
+}
+
+// Check whether a dead man's switch order has missed its heartbeat
+fn heartbeat_expired(order: &LimitOrder, now: chrono::DateTime<Utc>) -> bool {
+    match (order.last_heartbeat, order.heartbeat_interval_secs) {
+        (Some(last_heartbeat), Some(interval)) => {
+            now > last_heartbeat + chrono::Duration::seconds(interval as i64)
+        }
+        _ => false,
+    }
+}
+
+// Reset the countdown on the tenant's active dead man's switch orders
+pub fn record_heartbeat(app_state: Arc<AppState>, tenant: &str, order_id: Option<&str>) -> Result<Vec<LimitOrder>> {
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    let now = Utc::now();
+    
+    if let Some(order_id) = order_id {
+        match orders.get(order_id).filter(|order| tenant::can_access_order(tenant, order)) {
+            Some(order) if order.order_type != OrderType::DeadMansSwitch => {
+                return Err(anyhow!("Order {} is not a dead man's switch order", order_id));
+            }
+            Some(order) if order.status != OrderStatus::Active => {
+                return Err(anyhow!("Order {} is not active (current status: {:?})", order_id, order.status));
+            }
+            Some(_) => {}
+            None => return Err(anyhow!("Order not found: {}", order_id)),
+        }
+    }
+    
+    let mut refreshed = Vec::new();
+    
+    for order in orders.values_mut().filter(|order| tenant::can_access_order(tenant, order)) {
+        let matches_id = order_id.is_none_or(|id| order.id == id);
+        
+        if matches_id && order.order_type == OrderType::DeadMansSwitch && order.status == OrderStatus::Active {
+            order.last_heartbeat = Some(now);
+            order.updated_at = now;
+            refreshed.push(order.clone());
+        }
+    }
+    
+    info!("Heartbeat received, refreshed {} dead man's switch orders", refreshed.len());
+    Ok(refreshed)
+}
+
+// Simulate whether an order would trigger at hypothetical target prices and what it would yield,
+// using a fresh quote for the order size scaled to each price
+pub async fn simulate_order_outcomes(
+    app_state: Arc<AppState>,
+    tenant: &str,
+    order_id: &str,
+    prices: &[f64],
+) -> Result<Vec<WhatIfOutcome>> {
+    let order = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.get(order_id).filter(|order| tenant::can_access_order(tenant, order)).cloned()
+    }
+    .ok_or_else(|| anyhow!("Order not found: {}", order_id))?;
+    
+    // Hypothetical prices are target token USD prices, which pair triggers are not compared against
+    if order.trigger_mint() != order.target_token || order.quote_mint.is_some() {
+        return Err(anyhow!("What-if simulation only supports orders triggered on the target token's USD price"));
+    }
+    
+    let target_price = price::get_token_price(&app_state, &order.target_token)
+        .map_err(|e| anyhow!("Failed to get price for target token: {}", e))?;
+    let source_price = price::get_token_price(&app_state, &order.source_token)
+        .map_err(|e| anyhow!("Failed to get price for source token: {}", e))?;
+    let sol_price = price::get_token_price(&app_state, "So11111111111111111111111111111111111111112")
+        .unwrap_or(0.0);
+    
+    // Sell side orders spend `amount` of the source token, buys need enough source to get `amount`
+    let quote_input = if order.order_type.is_sell_side() {
+        order.amount
+    } else if source_price > 0.0 {
+        order.amount * target_price / source_price
+    } else {
+        return Err(anyhow!("Source token price is zero"));
+    };
+    
+    let source_decimals = crate::wallet::KnownTokens::get_decimals(&order.source_token)?;
+    let target_decimals = crate::wallet::KnownTokens::get_decimals(&order.target_token)?;
+    
+    let quote = swap::get_swap_quote(
+        &order.source_token,
+        &order.target_token,
+        (quote_input * 10f64.powi(source_decimals)) as u64,
+        order.slippage,
+    )
+    .await?;
+    
+    let quoted_in = quote.in_amount_units()? as f64 / 10f64.powi(source_decimals);
+    let quoted_out = quote.out_amount_units()? as f64 / 10f64.powi(target_decimals);
+    
+    if quoted_in <= 0.0 || quoted_out <= 0.0 {
+        return Err(anyhow!("Quote returned an empty route"));
+    }
+    
+    // Target tokens received per source token at the current price, including price impact
+    let current_rate = quoted_out / quoted_in;
+    
+    let fee_sol = crate::wallet::estimate_transaction_fees(&app_state.rpc_client).await
+        .unwrap_or(0.01); // Default to 0.01 SOL if estimation fails
+    let fee_usd = fee_sol * sol_price;
+    
+    let outcomes = prices
+        .iter()
+        .map(|&price| {
+            // Assume the source token holds its value while the target moves to `price`
+            let rate = current_rate * target_price / price;
+            let (input_amount, estimated_output) = if order.order_type.is_sell_side() {
+                (order.amount, order.amount * rate)
+            } else {
+                (order.amount / rate, order.amount)
+            };
+            let minimum_output = estimated_output * (1.0 - order.slippage / 100.0);
+            let output_value_usd = minimum_output * price;
+            
+            WhatIfOutcome {
+                price,
+                would_trigger: should_execute_order(&order, price),
+                input_amount,
+                estimated_output,
+                minimum_output,
+                output_value_usd,
+                fee_sol,
+                fee_usd,
+                net_proceeds_usd: output_value_usd - fee_usd,
+            }
+        })
+        .collect();
+    
+    Ok(outcomes)
+}
+
+// Latest price in a history at or before `at`
+fn price_at(history: &[PricePoint], at: DateTime<Utc>) -> Option<f64> {
+    let index = history.partition_point(|point| point.timestamp <= at);
+    index.checked_sub(1).map(|index| history[index].price_usd)
+}
+
+// What the order would have traded at past prices, assuming the swap filled at the USD price ratio
+fn backtrace_outcome(order: &LimitOrder, source_price: f64, target_price: f64) -> Option<BacktraceOutcome> {
+    if source_price <= 0.0 || target_price <= 0.0 {
+        return None;
+    }
+    
+    // Sell side orders spend `amount` of the source token, buys receive `amount` of the target
+    let (input_amount, estimated_output) = if order.order_type.is_sell_side() {
+        (order.amount, order.amount * source_price / target_price)
+    } else {
+        (order.amount * target_price / source_price, order.amount)
+    };
+    let minimum_output = estimated_output * (1.0 - order.slippage / 100.0);
+    
+    Some(BacktraceOutcome {
+        input_amount,
+        estimated_output,
+        minimum_output,
+        output_value_usd: minimum_output * target_price,
+    })
+}
+
+// Replay an order's trigger against the stored price history, reporting each stretch of history
+// during which it would have triggered with its target as it is now
+pub fn backtrace_order(app_state: &AppState, tenant: &str, order_id: &str) -> Result<OrderBacktrace> {
+    let order = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.get(order_id).filter(|order| tenant::can_access_order(tenant, order)).cloned()
+    }
+    .ok_or_else(|| anyhow!("Order not found: {}", order_id))?;
+    
+    if order.order_type == OrderType::DeadMansSwitch {
+        return Err(anyhow!("Dead man's switch orders trigger on missed heartbeats, not on prices"));
+    }
+    if order.trigger_source != TriggerSource::Spot {
+        return Err(anyhow!("Only spot price history is stored, perp mark triggers cannot be backtraced"));
+    }
+    
+    let trigger_history = price::get_price_history(app_state, order.trigger_mint());
+    let quote_history = order.quote_mint.as_deref().map(|mint| price::get_price_history(app_state, mint));
+    let source_history = price::get_price_history(app_state, &order.source_token);
+    let target_history = price::get_price_history(app_state, &order.target_token);
+    
+    // Pair triggers divide by the quote token's price at the same moment, like live evaluation
+    let series = trigger_history
+        .iter()
+        .filter_map(|point| match &quote_history {
+            Some(quote_history) => price_at(quote_history, point.timestamp)
+                .filter(|quote_price| *quote_price > 0.0)
+                .map(|quote_price| (point.timestamp, point.price_usd / quote_price)),
+            None => Some((point.timestamp, point.price_usd)),
+        })
+        .collect::<Vec<_>>();
+    
+    let mut triggers: Vec<BacktraceTrigger> = Vec::new();
+    let mut triggering = false;
+    for (timestamp, trigger_price) in &series {
+        if !should_execute_order(&order, *trigger_price) {
+            triggering = false;
+            continue;
+        }
+        
+        // Consecutive samples past the target are one trigger
+        if triggering {
+            if let Some(trigger) = triggers.last_mut() {
+                trigger.until = *timestamp;
+                trigger.samples += 1;
+            }
+            continue;
+        }
+        triggering = true;
+        
+        let estimated_outcome = match (price_at(&source_history, *timestamp), price_at(&target_history, *timestamp)) {
+            (Some(source_price), Some(target_price)) => backtrace_outcome(&order, source_price, target_price),
+            _ => None,
+        };
+        triggers.push(BacktraceTrigger {
+            triggered_at: *timestamp,
+            trigger_price: *trigger_price,
+            until: *timestamp,
+            samples: 1,
+            estimated_outcome,
+        });
+    }
+    
+    Ok(OrderBacktrace {
+        order_id: order.id.clone(),
+        order_type: order.order_type.clone(),
+        price_target: order.price_target,
+        trigger_mint: order.trigger_mint().to_string(),
+        quote_mint: order.quote_mint.clone(),
+        samples: series.len(),
+        history_from: series.first().map(|(timestamp, _)| *timestamp),
+        history_to: series.last().map(|(timestamp, _)| *timestamp),
+        triggers,
+    })
+}
+
+// Execute a limit order, holding it in Executing while the swap is in flight
+async fn execute_order(app_state: Arc<AppState>, order: LimitOrder, trigger_price: f64, detected_at: DateTime<Utc>) -> Result<LimitOrder> {
+    let reason = if order.status == OrderStatus::Retrying {
+        format!("Retry {} of {} after a transient failure", order.retry_count, order.max_retries)
+    } else if order.order_type == OrderType::DeadMansSwitch {
+        "Heartbeat missed".to_string()
+    } else {
+        format!("Price {} reached target {}", trigger_price, order.price_target)
+    };
+    record_event(&app_state, &order, OrderEventKind::Triggered, Some(reason), Some(trigger_price));
+    let triggered_at = Utc::now();
+    
+    set_order_status(&app_state, &order.id, OrderStatus::Executing)?;
+    
+    let result = run_order_execution(app_state.clone(), order.clone(), trigger_price, triggered_at, detected_at).await;
+    
+    match &result {
+        // Completed, failed with a recorded cause, or waiting for its swap to confirm
+        Ok(order) => {
+            if order.status == OrderStatus::Completed {
+                if let Err(err) = crate::receipts::issue_receipt(&app_state, order, trigger_price, triggered_at) {
+                    error!("Failed to issue execution receipt for order {}: {}", order.id, err);
+                }
+            }
+            events::publish_order(&app_state, order)
+        }
+        // Errors here happen before a swap is sent, so the order goes back to waiting for its trigger
+        Err(err) => {
+            let reason = Some(format!("Execution abandoned before sending: {}", err));
+            if let Err(err) = transition_order(&app_state, &order.id, OrderStatus::Active, reason) {
+                error!("Failed to reactivate order {}: {}", order.id, err);
+            }
+        }
+    }
+    
+    result
+}
+
+// Run the action of an order that is already Executing
+async fn run_order_execution(
+    app_state: Arc<AppState>,
+    order: LimitOrder,
+    trigger_price: f64,
+    triggered_at: DateTime<Utc>,
+    detected_at: DateTime<Utc>,
+) -> Result<LimitOrder> {
+    // Execute from the wallet the order was created for, never silently from another one
+    let wallet = match crate::wallet::find_wallet(&app_state, Some(&order.wallet_pubkey)) {
+        Ok(wallet) => wallet,
+        Err(err) => {
+            let reason = format!("Wallet {} for order {} was removed", order.wallet_pubkey, order.id);
+            let mut orders = app_state.limit_orders.lock().unwrap();
+            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
+                updated_order.transition(OrderStatus::Failed)?;
+                updated_order.failure = Some(OrderFailure::new(&reason));
+                orders.insert(order.id.clone(), updated_order.clone());
+                record_event(&app_state, &updated_order, OrderEventKind::Failed, Some(reason.clone()), None);
+                
+                error!("Order {} failed: {}", order.id, reason);
+                return Ok(updated_order);
+            }
+            return Err(err);
+        }
+    };
+    let wallet = &wallet;
+    
+    // Orders sized as a share of the balance trade that share of what the wallet holds now
+    let mut order = order;
+    if let Some(reason) = actions::resolve_amount_percent(&app_state, wallet, &mut order).await? {
+        let mut orders = app_state.limit_orders.lock().unwrap();
+        if let Some(mut updated_order) = orders.get(&order.id).cloned() {
+            updated_order.transition(OrderStatus::Failed)?;
+            updated_order.failure = Some(OrderFailure::new(&reason));
+            orders.insert(order.id.clone(), updated_order.clone());
+            record_event(&app_state, &updated_order, OrderEventKind::Failed, Some(reason.clone()), None);
+            
+            error!("Order {} failed: {}", order.id, reason);
+            return Ok(updated_order);
+        }
+        return Err(anyhow!("Order not found after sizing it: {}", order.id));
+    }
+    
+    // Orders sized in US dollars trade that much at the prices they triggered at
+    actions::resolve_amount_usd(&app_state, &mut order)?;
+    
+    // Orders placed with an API key count against its monthly notional quota
+    if let Some(api_key) = &order.api_key {
+        let amount_mint = if order.order_type.is_sell_side() { &order.source_token } else { &order.target_token };
+        let notional_usd = order.amount * price::get_token_price(&app_state, amount_mint).unwrap_or(0.0);
+        
+        if let Err(err) = crate::metering::check_notional(&app_state, api_key, notional_usd) {
+            let mut orders = app_state.limit_orders.lock().unwrap();
+            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
+                updated_order.transition(OrderStatus::Failed)?;
+                updated_order.failure = Some(OrderFailure::new(&err.to_string()));
+                orders.insert(order.id.clone(), updated_order.clone());
+                record_event(&app_state, &updated_order, OrderEventKind::Failed, Some(err.to_string()), None);
+                
+                error!("Order {} failed: {}", order.id, err);
+                return Ok(updated_order);
+            }
+            return Err(err.into());
+        }
+    }
+    
+    // Run what the order does once triggered
+    let outcome = match actions::execute(&app_state, wallet, &order).await? {
+        ActionResult::Completed(outcome) => *outcome,
+        ActionResult::Unconfirmed(swap) => {
+            return mark_unconfirmed(&app_state, &order, *swap, trigger_price, triggered_at, detected_at);
+        }
+        ActionResult::Failed(reason) => {
+            if let Some(retrying) = schedule_retry(&app_state, &order.id, &reason)? {
+                return Ok(retrying);
+            }
+            let mut orders = app_state.limit_orders.lock().unwrap();
+            if let Some(mut updated_order) = orders.get(&order.id).cloned() {
+                updated_order.transition(OrderStatus::Failed)?;
+                updated_order.failure = Some(OrderFailure::new(&reason));
+                orders.insert(order.id.clone(), updated_order.clone());
+                record_event(&app_state, &updated_order, OrderEventKind::Failed, Some(reason.clone()), None);
+                
+                error!("Order {} failed: {}", order.id, reason);
+                return Ok(updated_order);
+            }
+            return Err(anyhow!("Order not found after failed execution: {}", order.id));
+        }
+    };
+    
+    // Update order
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    if let Some(mut updated_order) = orders.get(&order.id).cloned() {
+        updated_order.transition(OrderStatus::Completed)?;
+        updated_order.next_retry_at = None;
+        updated_order.execution = outcome.execution;
+        updated_order.latency = outcome.latency.map(|latency| ExecutionLatency {
+            trigger_detected_at: Some(detected_at),
+            ..latency
+        });
+        if let Some(latency) = &updated_order.latency {
+            crate::latency::record_latency(&app_state, latency.clone());
+        }
+        updated_order.transaction_signature = outcome.transaction_signature.clone();
+        updated_order.fee = outcome.fee;
+        
+        if let Some(api_key) = &order.api_key {
+            crate::metering::record_notional(&app_state, api_key, outcome.notional_usd);
+        }
+        
+        orders.insert(order.id.clone(), updated_order.clone());
+        let reason = outcome.transaction_signature.as_ref().map(|signature| format!("Signature: {}", signature));
+        record_event(&app_state, &updated_order, OrderEventKind::Completed, reason, None);
+        
+        info!(
+            "Successfully executed limit order {} ({}). Signature: {}",
+            order.id, order.action, outcome.transaction_signature.as_deref().unwrap_or("none")
+        );
+        
+        Ok(updated_order)
+    } else {
+        Err(anyhow!("Order not found after execution: {}", order.id))
+    }
+}
+
+// Hold an order whose swap was sent but not confirmed in time until its signature is seen
+// landing or failing. Only what the action already saw confirmed is metered now.
+fn mark_unconfirmed(
+    app_state: &AppState,
+    order: &LimitOrder,
+    swap: UnconfirmedSwap,
+    trigger_price: f64,
+    triggered_at: DateTime<Utc>,
+    detected_at: DateTime<Utc>,
+) -> Result<LimitOrder> {
+    let outcome = swap.outcome;
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    let mut updated_order = orders
+        .get(&order.id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Order not found after execution: {}", order.id))?;
+    
+    updated_order.transition(OrderStatus::Unconfirmed)?;
+    updated_order.next_retry_at = None;
+    updated_order.execution = outcome.execution;
+    updated_order.latency = outcome.latency.map(|latency| ExecutionLatency {
+        trigger_detected_at: Some(detected_at),
+        ..latency
+    });
+    updated_order.transaction_signature = outcome.transaction_signature;
+    updated_order.fee = outcome.fee;
+    
+    if let Some(api_key) = &order.api_key {
+        crate::metering::record_notional(app_state, api_key, outcome.notional_usd);
+    }
+    
+    orders.insert(order.id.clone(), updated_order.clone());
+    let signature = swap.swap_result.transaction_signature.clone();
+    record_event(app_state, &updated_order, OrderEventKind::Unconfirmed, Some(format!("Signature: {}", signature)), None);
+    
+    app_state.unconfirmed_executions.lock().unwrap().insert(
+        order.id.clone(),
+        UnconfirmedExecution {
+            order_id: order.id.clone(),
+            wallet: order.wallet_pubkey.clone(),
+            swap_request: swap.swap_request,
+            swap_result: swap.swap_result,
+            notional_usd: swap.notional_usd,
+            trigger_price,
+            triggered_at,
+        },
+    );
+    
+    warn!("Order {} is waiting for swap {} to confirm", order.id, signature);
+    Ok(updated_order)
+}
+
+// Poll the signatures of order swaps that were not confirmed in time, completing the orders whose
+// swap landed and failing those whose swap failed or can no longer land
+async fn confirm_unconfirmed_orders(app_state: &AppState) {
+    let pending = {
+        let unconfirmed = app_state.unconfirmed_executions.lock().unwrap();
+        unconfirmed.values().cloned().collect::<Vec<_>>()
+    };
+    
+    for mut execution in pending {
+        let signature = execution.swap_result.transaction_signature.clone();
+        let confirmation = swap::recheck_swap(
+            &app_state.rpc_client,
+            &execution.wallet,
+            &execution.swap_request.target_token,
+            &mut execution.swap_result,
+        )
+        .await;
+        
+        let result = match confirmation {
+            Ok(swap::Confirmation::Landed) => complete_unconfirmed(app_state, &execution).await,
+            Ok(swap::Confirmation::Failed(err)) => {
+                let reason = format!("Swap transaction {} failed on chain: {}", signature, err);
+                fail_unconfirmed(app_state, &execution, &reason).await
+            }
+            Ok(_) if Utc::now() - execution.swap_result.timestamp > chrono::Duration::seconds(UNCONFIRMED_MAX_AGE_SECS) => {
+                let reason = format!("Swap transaction {} never landed", signature);
+                fail_unconfirmed(app_state, &execution, &reason).await
+            }
+            Ok(_) => continue,
+            // The outcome is still unknown, so the order keeps waiting
+            Err(err) => {
+                warn!("Failed to check swap {} of order {}: {}", signature, execution.order_id, err);
+                continue;
+            }
+        };
+        
+        app_state.unconfirmed_executions.lock().unwrap().remove(&execution.order_id);
+        if let Err(err) = result {
+            error!("Failed to settle unconfirmed order {}: {}", execution.order_id, err);
+        }
+    }
+}
+
+// Complete an order whose unconfirmed swap landed, booking it only now
+async fn complete_unconfirmed(app_state: &AppState, execution: &UnconfirmedExecution) -> Result<()> {
+    let completed = {
+        let mut orders = app_state.limit_orders.lock().unwrap();
+        let order = orders
+            .get_mut(&execution.order_id)
+            .ok_or_else(|| anyhow!("Order not found: {}", execution.order_id))?;
+        order.transition(OrderStatus::Completed)?;
+        if let Some(latency) = order.latency.as_mut() {
+            latency.confirmed_at = execution.swap_result.latency.confirmed_at;
+            crate::latency::record_latency(app_state, latency.clone());
+        }
+        if let Some(api_key) = &order.api_key {
+            crate::metering::record_notional(app_state, api_key, execution.notional_usd);
+        }
+        let reason = Some(format!("Signature: {}", execution.swap_result.transaction_signature));
+        record_event(app_state, order, OrderEventKind::Completed, reason, None);
+        order.clone()
+    };
+    
+    actions::book_swap(app_state, &execution.wallet, &execution.order_id, &execution.swap_request, &execution.swap_result);
+    if let Err(err) = crate::receipts::issue_receipt(app_state, &completed, execution.trigger_price, execution.triggered_at) {
+        error!("Failed to issue execution receipt for order {}: {}", completed.id, err);
+    }
+    events::publish_order(app_state, &completed);
+    info!("Swap of order {} confirmed. Signature: {}", completed.id, execution.swap_result.transaction_signature);
+    notify_executed(app_state, &completed, execution.trigger_price).await;
+    Ok(())
+}
+
+// Fail an order whose unconfirmed swap failed on chain or never landed
+async fn fail_unconfirmed(app_state: &AppState, execution: &UnconfirmedExecution, reason: &str) -> Result<()> {
+    let failed = {
+        let mut orders = app_state.limit_orders.lock().unwrap();
+        let order = orders
+            .get_mut(&execution.order_id)
+            .ok_or_else(|| anyhow!("Order not found: {}", execution.order_id))?;
+        order.transition(OrderStatus::Failed)?;
+        order.failure = Some(OrderFailure::new(reason));
+        record_event(app_state, order, OrderEventKind::Failed, Some(reason.to_string()), None);
+        order.clone()
+    };
+    
+    error!("Order {} failed: {}", failed.id, reason);
+    events::publish_order(app_state, &failed);
+    notify_failed(app_state, &failed).await;
+    Ok(())
+}
+
+// Seconds between trigger checks for orders without their own interval
+pub fn monitor_interval_secs() -> u64 {
+    std::env::var(MONITOR_INTERVAL_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MONITOR_INTERVAL_SECS)
+        .max(MIN_CHECK_INTERVAL_SECS)
+}
+
+// Retries allowed to orders that do not set their own
+fn default_max_retries() -> u32 {
+    std::env::var(MAX_RETRIES_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+}
+
+// Wait before the first retry for orders that do not set their own
+fn default_retry_backoff_secs() -> u64 {
+    std::env::var(RETRY_BACKOFF_ENV)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_RETRY_BACKOFF_SECS)
+}
+
+// Wait before the given retry, starting at the order's backoff and doubling for each retry after it
+fn retry_backoff(order: &LimitOrder, retry: u32) -> chrono::Duration {
+    let factor = 1u64 << retry.saturating_sub(1).min(16);
+    let secs = order.retry_backoff_secs.saturating_mul(factor).min(MAX_RETRY_BACKOFF_SECS);
+    chrono::Duration::seconds(secs as i64)
+}
+
+// Put an execution that failed transiently on hold until its backoff passes, if it has retries left.
+// Returns None when the order should fail instead.
+fn schedule_retry(app_state: &AppState, order_id: &str, reason: &str) -> Result<Option<LimitOrder>> {
+    let cause = FailureCause::classify(reason);
+    let mut orders = app_state.limit_orders.lock().unwrap();
+    let order = match orders.get_mut(order_id) {
+        Some(order) if cause.is_transient() && order.retry_count < order.max_retries => order,
+        _ => return Ok(None),
+    };
+    
+    order.transition(OrderStatus::Retrying)?;
+    order.retry_count += 1;
+    order.next_retry_at = Some(Utc::now() + retry_backoff(order, order.retry_count));
+    order.failure = Some(OrderFailure::new(reason));
+    
+    let detail = format!(
+        "{}, retry {} of {} at {}",
+        reason,
+        order.retry_count,
+        order.max_retries,
+        order.next_retry_at.map(|at| at.to_rfc3339()).unwrap_or_default()
+    );
+    warn!("Order {} failed transiently: {}", order.id, detail);
+    events::publish_order(app_state, order);
+    record_event(app_state, order, OrderEventKind::Retrying, Some(detail), None);
+    Ok(Some(order.clone()))
+}
+
+// Orders waiting to retry whose backoff has passed, with the price they execute at. Orders that
+// expired while waiting are expired instead.
+fn due_retries(app_state: &AppState, now: DateTime<Utc>) -> Vec<(LimitOrder, f64)> {
+    let retrying = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.status == OrderStatus::Retrying)
+            .filter(|order| matches!(order.next_retry_at, Some(at) if at <= now))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    
+    let mut due = Vec::new();
+    for order in retrying {
+        if freeze::cancel_frozen_retry(app_state, &order) {
+            continue;
+        }
+        if let Some(expiry_time) = order.expiry_time.filter(|expiry_time| now > *expiry_time) {
+            let reason = Some(format!("Expired at {} while waiting to retry", expiry_time));
+            if let Err(err) = transition_order(app_state, &order.id, OrderStatus::Expired, reason) {
+                error!("Failed to expire order {}: {}", order.id, err);
+            }
+            continue;
+        }
+        
+        // The trigger already fired, so the retry goes ahead at whatever the price is now
+        let price = if order.order_type == OrderType::DeadMansSwitch {
+            price::get_token_price(app_state, &order.target_token)
+        } else {
+            price::get_order_trigger_price(app_state, &order)
+        };
+        due.push((order, price.unwrap_or(0.0)));
+    }
+    due
+}
+
+// Seconds between trigger checks for an order
+fn check_interval_secs(order: &LimitOrder, monitor_interval: u64) -> u64 {
+    order.check_interval_secs.unwrap_or(monitor_interval)
+}
+
+// How long the monitor sleeps: the shortest check interval of any active order
+fn next_tick_secs(app_state: &AppState, monitor_interval: u64) -> u64 {
+    let orders = app_state.limit_orders.lock().unwrap();
+    orders
+        .values()
+        .filter(|order| order.status == OrderStatus::Active)
+        .map(|order| check_interval_secs(order, monitor_interval))
+        .fold(monitor_interval, u64::min)
+}
+
+// Whether an order's check interval has passed since it was last checked
+fn check_due(order: &LimitOrder, last_checked: Option<&DateTime<Utc>>, monitor_interval: u64, now: DateTime<Utc>) -> bool {
+    last_checked.is_none_or(|last_checked| {
+        // Half a second of slack so an order is not skipped for waking up marginally early
+        now - *last_checked + chrono::Duration::milliseconds(500)
+            >= chrono::Duration::seconds(check_interval_secs(order, monitor_interval) as i64)
+    })
+}
+
+// Background task to monitor limit orders
+pub async fn monitor_limit_orders(app_state: Arc<AppState>) {
+    let monitor_interval = monitor_interval_secs();
+    info!("Starting limit order monitor task, checking every {} seconds", monitor_interval);
+    
+    // Wait a bit on startup to make sure everything is initialized
+    time::sleep(time::Duration::from_secs(5)).await;
+    
+    // When each active order was last checked
+    let mut last_checked: HashMap<String, DateTime<Utc>> = HashMap::new();
+    
+    loop {
+        // Wake up as often as the fastest order needs, which is the monitor interval unless one asks for less
+        let tick = next_tick_secs(&app_state, monitor_interval);
+        time::sleep(time::Duration::from_secs(tick)).await;
+        
+        // Skip if no wallets are available
+        {
+            let wallets = app_state.wallets.lock().unwrap();
+            if wallets.is_empty() {
+                continue;
+            }
+        }
+        
+        // Update token prices
+        if let Err(err) = price::update_prices(app_state.clone()).await {
+            error!("Failed to update prices: {}", err);
+            continue;
+        }
+        
+        // Orders on a market that failed to refresh see a stale mark and wait for the next pass
+        if let Err(err) = drift::update_perp_prices(&app_state).await {
+            error!("Failed to update perp prices: {}", err);
+        }
+        
+        // Get active orders due for a check
+        let now = Utc::now();
+        let orders = {
+            let orders_lock = app_state.limit_orders.lock().unwrap();
+            last_checked.retain(|id, _| orders_lock.get(id).is_some_and(|order| order.status == OrderStatus::Active));
+            orders_lock
+                .values()
+                .filter(|order| order.status == OrderStatus::Active)
+                .filter(|order| check_due(order, last_checked.get(&order.id), monitor_interval, now))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for order in &orders {
+            last_checked.insert(order.id.clone(), now);
+        }
+        
+        if !orders.is_empty() {
+            info!("Checking {} active limit orders", orders.len());
+        }
+        
+        // Swaps sent earlier without a confirmation may have landed since
+        confirm_unconfirmed_orders(&app_state).await;
+        
+        // Orders triggered in this pass and retries now due, executed afterwards in priority order
+        let mut triggered = evaluate_orders(&app_state, orders, true).await;
+        triggered.extend(due_retries(&app_state, now));
+        run_triggered_orders(&app_state, triggered).await;
+    }
+}
+
+// Check orders against the cached prices, returning the triggered ones with their trigger price.
+// Expired orders are expired on the way.
+async fn evaluate_orders(app_state: &AppState, orders: Vec<LimitOrder>, notify_near_trigger: bool) -> Vec<(LimitOrder, f64)> {
+    let mut triggered = Vec::new();
+    
+    for order in orders {
+        // Passive orders are filled by takers on chain, their fills are synced instead
+        if order.passive.is_some() {
+            continue;
+        }
+        
+        // Check if the order has expired
+        if let Some(expiry_time) = order.expiry_time {
+            if Utc::now() > expiry_time {
+                info!("Order {} has expired", order.id);
+                let reason = Some(format!("Expired at {}", expiry_time));
+                if let Err(err) = transition_order(app_state, &order.id, OrderStatus::Expired, reason) {
+                    error!("Failed to expire order {}: {}", order.id, err);
+                }
+                continue;
+            }
+        }
+        
+        // Dead man's switch orders fire on a missed heartbeat, not on price
+        if order.order_type == OrderType::DeadMansSwitch {
+            if heartbeat_expired(&order, Utc::now()) {
+                info!("Dead man's switch order {} triggered - heartbeat missed", order.id);
+                let current_price = price::get_token_price(app_state, &order.target_token).unwrap_or(0.0);
+                triggered.push((order, current_price));
+            }
+            continue;
+        }
+        
+        // Get the trigger price of the trigger token (aggregated if the token is guarded,
+        // or the perp mark price if the order asked for it), in the quote token for pair triggers
+        match price::get_order_trigger_price(app_state, &order) {
+            Ok(current_price) => {
+                let should_execute = should_execute_order(&order, current_price);
+                
+                // Add debug logging based on order type
+                match order.order_type {
+                    OrderType::Buy => {
+                        if current_price <= order.price_target {
+                            info!("Buy order {} triggered - current price {} <= target {}", 
+                                   order.id, current_price, order.price_target);
+                        } else {
+                            // Only log occasionally to avoid spamming the logs
+                            if rand::random::<u8>() < 5 { // ~2% chance
+                                info!("Buy order {} waiting - current price {} > target {}", 
+                                      order.id, current_price, order.price_target);
+                            }
+                        }
+                    }
+                    OrderType::Sell => {
+                        if current_price >= order.price_target {
+                            info!("Sell order {} triggered - current price {} >= target {}", 
+                                   order.id, current_price, order.price_target);
+                        } else {
+                            // Only log occasionally to avoid spamming the logs
+                            if rand::random::<u8>() < 5 { // ~2% chance
+                                info!("Sell order {} waiting - current price {} < target {}", 
+                                      order.id, current_price, order.price_target);
+                            }
+                        }
+                    }
+                    OrderType::StopLoss => {
+                        if current_price <= order.price_target {
+                            info!("Stop loss order {} triggered - current price {} <= target {}", 
+                                   order.id, current_price, order.price_target);
+                        } else {
+                            // Only log occasionally to avoid spamming the logs
+                            if rand::random::<u8>() < 5 { // ~2% chance
+                                info!("Stop loss order {} waiting - current price {} > target {}", 
+                                      order.id, current_price, order.price_target);
+                            }
+                        }
+                    }
+                    OrderType::TakeProfit => {
+                        if current_price >= order.price_target {
+                            info!("Take profit order {} triggered - current price {} >= target {}", 
+                                   order.id, current_price, order.price_target);
+                        } else {
+                            // Only log occasionally to avoid spamming the logs
+                            if rand::random::<u8>() < 5 { // ~2% chance
+                                info!("Take profit order {} waiting - current price {} < target {}", 
+                                      order.id, current_price, order.price_target);
                             }
                         }
                     }
-                    
-                    if should_execute {
-                        // Clone the order before moving it to execute_order
-                        let order_to_execute = order.clone();
-                        
-                        // Execute the order
-                        if let Err(err) = execute_order(app_state.clone(), order_to_execute).await {
-                            error!("Failed to execute order {}: {}", order.id, err);
+                    OrderType::StopBuy => {
+                        if current_price >= order.price_target {
+                            info!("Stop buy order {} triggered - current price {} >= target {}", 
+                                   order.id, current_price, order.price_target);
+                        } else {
+                            // Only log occasionally to avoid spamming the logs
+                            if rand::random::<u8>() < 5 { // ~2% chance
+                                info!("Stop buy order {} waiting - current price {} < target {}", 
+                                      order.id, current_price, order.price_target);
+                            }
                         }
                     }
+                    OrderType::DeadMansSwitch => {
+                        // Handled above, before the price lookup
+                    }
+                }
+                
+                if should_execute {
+                    triggered.push((order, current_price));
+                } else if notify_near_trigger && is_near_trigger(&order, current_price) {
+                    let vars = serde_json::json!({
+                        "order_type": order.order_type,
+                        "order_id": order.id,
+                        "price": current_price,
+                        "target": order.price_target,
+                    });
+                    notify::notify_event(app_state, NotificationEvent::PriceNearTrigger, order.api_key.as_deref(), "price_near_trigger", vars).await;
+                }
+            }
+            Err(err) => {
+                error!("Failed to get price for token {}: {}", order.target_token, err);
+            }
+        }
+    }
+    
+    triggered
+}
+
+// Execute triggered orders in priority order
+async fn run_triggered_orders(app_state: &Arc<AppState>, triggered: Vec<(LimitOrder, f64)>) {
+    if triggered.is_empty() {
+        return;
+    }
+    
+    // One pass at a time, re-reading each order below so one a previous pass ran is skipped
+    let _pass = app_state.execution_pass.lock().await;
+    
+    // Leave triggered orders active while trading is paused
+    if let Some(reason) = engine::trading_paused(app_state) {
+        info!("Trading paused ({}), not executing {} triggered orders", reason, triggered.len());
+        return;
+    }
+    
+    // Orders covered by a blackout window stay active and only warn
+    let triggered = blackout::suppress_triggers(app_state, triggered).await;
+    let triggered = freeze::suppress_triggers(app_state, triggered);
+    if triggered.is_empty() {
+        return;
+    }
+    
+    // Build and publish the execution queue for this pass
+    let queue = engine::build_execution_queue(app_state, triggered);
+    info!("Executing {} triggered orders in priority order", queue.len());
+    engine::set_execution_queue(app_state, queue.clone());
+    
+    for queued in queue {
+        // Re-read the order so one cancelled or whose wallet was frozen while queued is skipped
+        let order = {
+            let orders_lock = app_state.limit_orders.lock().unwrap();
+            orders_lock.get(&queued.order_id).cloned()
+        };
+        let order = order.filter(|order| !freeze::is_frozen(app_state, &order.wallet_pubkey));
+        
+        if let Some(order) = order.filter(|order| matches!(order.status, OrderStatus::Active | OrderStatus::Retrying)) {
+            let (order_type, api_key) = (order.order_type.clone(), order.api_key.clone());
+            match execute_order(app_state.clone(), order, queued.trigger_price, queued.queued_at).await {
+                Ok(executed) if executed.status == OrderStatus::Completed => {
+                    notify_executed(app_state, &executed, queued.trigger_price).await;
                 }
+                // Transient failures are only reported once the retries run out, and unconfirmed
+                // swaps once their outcome is known
+                Ok(executed) if matches!(executed.status, OrderStatus::Retrying | OrderStatus::Unconfirmed) => {}
+                Ok(executed) => notify_failed(app_state, &executed).await,
                 Err(err) => {
-                    error!("Failed to get price for token {}: {}", order.target_token, err);
+                    error!("Failed to execute order {}: {}", queued.order_id, err);
+                    let vars = serde_json::json!({
+                        "order_type": order_type,
+                        "order_id": queued.order_id,
+                        "error": err.to_string(),
+                    });
+                    notify::notify_event(app_state, NotificationEvent::OrderFailed, api_key.as_deref(), "order_failed", vars).await;
                 }
             }
+        } else {
+            info!("Skipping queued order {}: no longer active", queued.order_id);
+        }
+        
+        engine::remove_from_queue(app_state, &queued.order_id);
+    }
+}
+
+// Notify the owner of an order that it executed, with the rate its swap filled at
+async fn notify_executed(app_state: &AppState, executed: &LimitOrder, trigger_price: f64) {
+    let mut vars = serde_json::json!({
+        "order_type": executed.order_type,
+        "order_id": executed.id,
+        "action": executed.action.to_string(),
+    });
+    let template = match &executed.transaction_signature {
+        Some(signature) => {
+            vars["signature"] = signature.clone().into();
+            vars["price"] = trigger_price.into();
+            // Rate the fill came in at, in target tokens per source token
+            if let Some(execution) = executed.execution.as_ref().filter(|execution| execution.source_amount > 0.0) {
+                vars["source_amount"] = execution.source_amount.into();
+                vars["target_amount"] = execution.target_amount.into();
+                vars["fill_price"] = (execution.target_amount / execution.source_amount).into();
+                vars["source_symbol"] = crate::wallet::KnownTokens::get_symbol(&executed.source_token).into();
+                vars["target_symbol"] = crate::wallet::KnownTokens::get_symbol(&executed.target_token).into();
+            }
+            "order_executed"
+        }
+        None => {
+            vars["price"] = trigger_price.into();
+            "order_triggered"
+        }
+    };
+    notify::notify_event(app_state, NotificationEvent::OrderExecuted, executed.api_key.as_deref(), template, vars).await;
+}
+
+// Notify the owner of an order that it failed
+async fn notify_failed(app_state: &AppState, failed: &LimitOrder) {
+    let vars = serde_json::json!({
+        "order_type": failed.order_type,
+        "order_id": failed.id,
+    });
+    notify::notify_event(app_state, NotificationEvent::OrderFailed, failed.api_key.as_deref(), "order_failed", vars).await;
+}
+
+// Whether an order triggers on the spot price of one of the mints
+fn triggers_on(order: &LimitOrder, mints: &[String]) -> bool {
+    order.order_type != OrderType::DeadMansSwitch
+        && order.trigger_source == TriggerSource::Spot
+        && (mints.iter().any(|mint| mint == order.trigger_mint())
+            || order.quote_mint.as_ref().is_some_and(|quote_mint| mints.contains(quote_mint)))
+}
+
+// Tokens whose spot price active orders trigger on, including the quote side of pair triggers
+pub fn spot_trigger_mints(app_state: &AppState) -> Vec<String> {
+    let orders = app_state.limit_orders.lock().unwrap();
+    let mut mints = Vec::new();
+    for order in orders.values() {
+        if order.status != OrderStatus::Active
+            || order.order_type == OrderType::DeadMansSwitch
+            || order.trigger_source != TriggerSource::Spot
+        {
+            continue;
+        }
+        for mint in std::iter::once(order.trigger_mint().to_string()).chain(order.quote_mint.clone()) {
+            if !mints.contains(&mint) {
+                mints.push(mint);
+            }
         }
     }
+    mints
+}
+
+// Check the active orders triggering on freshly pushed prices right away, rather than on the next monitor pass
+pub async fn check_orders_on_prices(app_state: &Arc<AppState>, mints: &[String]) {
+    let orders = {
+        let orders_lock = app_state.limit_orders.lock().unwrap();
+        orders_lock
+            .values()
+            .filter(|order| order.status == OrderStatus::Active && triggers_on(order, mints))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    if orders.is_empty() {
+        return;
+    }
+    
+    // Near-trigger notices stay with the monitor's pace, pushes arrive several times a second
+    let triggered = evaluate_orders(app_state, orders, false).await;
+    run_triggered_orders(app_state, triggered).await;
 }
 
 // Public version of should_execute_order for testing purposes
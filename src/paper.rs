@@ -0,0 +1,121 @@
+use crate::models::{AppState, PaperAccount, PaperBalanceRequest, PaperFill, Wallet};
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+// Set to "true" to fill every swap against paper balances instead of sending it
+const SIMULATION_MODE_ENV: &str = "SIMULATION_MODE";
+
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// Whether the server trades on paper only
+pub fn simulation_mode() -> bool {
+    std::env::var(SIMULATION_MODE_ENV).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+// Open a paper account for the wallet on first use, starting from its on-chain balances
+async fn ensure_account(app_state: &AppState, wallet: &Wallet) {
+    let pubkey = wallet.pubkey.to_string();
+    if app_state.paper_accounts.lock().unwrap().contains_key(&pubkey) {
+        return;
+    }
+
+    let balances = match wallet::get_token_balances(&app_state.rpc_client, wallet).await {
+        Ok(balances) => balances.into_iter().map(|balance| (balance.mint, balance.amount)).collect(),
+        Err(err) => {
+            warn!("Failed to read balances of wallet {}, its paper account starts empty: {}", pubkey, err);
+            HashMap::new()
+        }
+    };
+
+    let mut accounts = app_state.paper_accounts.lock().unwrap();
+    accounts.entry(pubkey.clone()).or_insert_with(|| {
+        info!("Opened paper account for wallet {}", pubkey);
+        PaperAccount {
+            wallet: pubkey.clone(),
+            balances,
+            fills: Vec::new(),
+            created_at: Utc::now(),
+        }
+    });
+}
+
+// Book a simulated swap on the wallet's paper account, refusing it if the balances do not cover it
+pub async fn apply_fill(app_state: &AppState, wallet: &Wallet, fill: PaperFill) -> Result<()> {
+    ensure_account(app_state, wallet).await;
+
+    let mut accounts = app_state.paper_accounts.lock().unwrap();
+    let account = accounts
+        .get_mut(&wallet.pubkey.to_string())
+        .ok_or_else(|| anyhow!("Paper account of wallet {} was reset", wallet.pubkey))?;
+
+    let held = |mint: &str| account.balances.get(mint).copied().unwrap_or(0.0);
+    let sol_needed = fill.fee_sol + if fill.source_token == SOL_MINT { fill.source_amount } else { 0.0 };
+    if held(&fill.source_token) < fill.source_amount {
+        return Err(anyhow!(
+            "Insufficient paper balance of {}: {} held, {} needed",
+            KnownTokens::get_symbol(&fill.source_token),
+            held(&fill.source_token),
+            fill.source_amount
+        ));
+    }
+    if held(SOL_MINT) < sol_needed {
+        return Err(anyhow!("Insufficient paper SOL balance for transaction fees. Need at least {} SOL.", sol_needed));
+    }
+
+    *account.balances.entry(fill.source_token.clone()).or_insert(0.0) -= fill.source_amount;
+    *account.balances.entry(SOL_MINT.to_string()).or_insert(0.0) -= fill.fee_sol;
+    *account.balances.entry(fill.target_token.clone()).or_insert(0.0) += fill.target_amount;
+
+    info!(
+        "Paper swap {} {} -> {} {} for wallet {}",
+        fill.source_amount,
+        KnownTokens::get_symbol(&fill.source_token),
+        fill.target_amount,
+        KnownTokens::get_symbol(&fill.target_token),
+        account.wallet
+    );
+    account.fills.push(fill);
+    Ok(())
+}
+
+// Paper accounts of the wallets the tenant may use
+pub fn get_paper_accounts(app_state: &AppState, tenant: &str) -> Vec<PaperAccount> {
+    let accounts = app_state.paper_accounts.lock().unwrap();
+    accounts
+        .values()
+        .filter(|account| tenant::can_use_wallet(app_state, tenant, &account.wallet))
+        .cloned()
+        .collect()
+}
+
+// Set the paper balance of one token, opening the wallet's account first if needed
+pub async fn set_paper_balance(app_state: &AppState, tenant: &str, request: PaperBalanceRequest) -> Result<PaperAccount> {
+    if request.amount < 0.0 {
+        return Err(anyhow!("amount must not be negative"));
+    }
+    let wallet = wallet::find_tenant_wallet(app_state, tenant, Some(&request.wallet_pubkey))?;
+    ensure_account(app_state, &wallet).await;
+
+    let mut accounts = app_state.paper_accounts.lock().unwrap();
+    let account = accounts
+        .get_mut(&request.wallet_pubkey)
+        .ok_or_else(|| anyhow!("Paper account of wallet {} was reset", request.wallet_pubkey))?;
+    account.balances.insert(request.mint.clone(), request.amount);
+
+    info!("Set paper balance of {} to {} for wallet {}", KnownTokens::get_symbol(&request.mint), request.amount, request.wallet_pubkey);
+    Ok(account.clone())
+}
+
+// Drop a wallet's paper account; the next simulated swap starts over from its on-chain balances
+pub fn reset_paper_account(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<PaperAccount> {
+    if !tenant::can_use_wallet(app_state, tenant, pubkey) {
+        return Err(anyhow!("No paper account for wallet {}", pubkey));
+    }
+
+    let mut accounts = app_state.paper_accounts.lock().unwrap();
+    accounts.remove(pubkey).ok_or_else(|| anyhow!("No paper account for wallet {}", pubkey))
+}
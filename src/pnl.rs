@@ -0,0 +1,204 @@
+use crate::models::{AppState, CostBasis, LedgerEntry, LedgerEntryKind, OrderPnl, OrderType, PnlReport, TokenPnl, TradePnl};
+use crate::price;
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::Result;
+use chrono::Utc;
+
+// Tracked amounts left below this after a sale are rounding, and the position is closed
+const DUST_AMOUNT: f64 = 1e-9;
+
+// USD value of a trade: what the wallet received at the target token's price, or what it gave at
+// the source token's when the target has no price. Slippage thus counts against the sold token.
+fn trade_value(entry: &LedgerEntry) -> Option<f64> {
+    let received = entry
+        .target_price_usd
+        .zip(entry.target_amount)
+        .map(|(price_usd, amount)| price_usd * amount)
+        .filter(|value| *value > 0.0);
+    received.or_else(|| {
+        entry
+            .source_price_usd
+            .map(|price_usd| price_usd * entry.source_amount)
+            .filter(|value| *value > 0.0)
+    })
+}
+
+// Take an amount out of a position at its average cost. Returns how much of it was tracked and
+// what that part cost.
+fn take_out(position: &mut CostBasis, amount: f64) -> (f64, f64) {
+    let taken = amount.min(position.amount).max(0.0);
+    let cost_usd = if position.amount > 0.0 { position.cost_usd * taken / position.amount } else { 0.0 };
+    position.amount -= taken;
+    position.cost_usd -= cost_usd;
+    if position.amount <= DUST_AMOUNT {
+        position.amount = 0.0;
+        position.cost_usd = 0.0;
+    }
+    (taken, cost_usd)
+}
+
+// Book a trade from the ledger. The sold tokens leave their position at its average cost and
+// realize their share of the trade's value; the bought ones join theirs at that value.
+pub fn record_trade(app_state: &AppState, entry: &LedgerEntry, fee_usd: f64) {
+    let target_token = match &entry.target_token {
+        Some(target_token) => target_token.clone(),
+        None => return,
+    };
+    let target_amount = entry.target_amount.unwrap_or(0.0);
+    let value_usd = trade_value(entry);
+
+    let (sold, cost_usd, realized_usd) = {
+        let mut book = app_state.cost_basis.lock().unwrap();
+        let source = book.entry((entry.wallet.clone(), entry.source_token.clone())).or_default();
+        let (sold, cost_usd) = take_out(source, entry.source_amount);
+        let realized_usd = value_usd
+            .filter(|_| sold > 0.0)
+            .map(|value_usd| value_usd * sold / entry.source_amount - cost_usd);
+        source.realized_usd += realized_usd.unwrap_or(0.0);
+
+        if let Some(value_usd) = value_usd {
+            let target = book.entry((entry.wallet.clone(), target_token.clone())).or_default();
+            target.amount += target_amount;
+            target.cost_usd += value_usd;
+        }
+        (sold, cost_usd, realized_usd)
+    };
+
+    app_state.trade_pnl.lock().unwrap().push(TradePnl {
+        wallet: entry.wallet.clone(),
+        order_id: entry.order_id.clone(),
+        source_token: entry.source_token.clone(),
+        source_amount: entry.source_amount,
+        source_price_usd: entry.source_price_usd,
+        target_token,
+        target_amount,
+        target_price_usd: entry.target_price_usd,
+        value_usd,
+        cost_basis_usd: (sold > 0.0).then_some(cost_usd),
+        realized_usd,
+        fee_usd,
+        transaction_signature: entry.transaction_signature.clone(),
+        timestamp: entry.timestamp,
+    });
+}
+
+// Carry the cost basis of transferred tokens: over to the receiving wallet for an internal
+// transfer, out of the books for an external one
+pub fn record_transfer(app_state: &AppState, entry: &LedgerEntry) {
+    let mut book = app_state.cost_basis.lock().unwrap();
+    let (moved, cost_usd) = match book.get_mut(&(entry.wallet.clone(), entry.source_token.clone())) {
+        Some(position) => take_out(position, entry.source_amount),
+        None => return,
+    };
+
+    if let (LedgerEntryKind::InternalTransfer, Some(destination)) = (&entry.kind, &entry.counterparty) {
+        if moved > 0.0 {
+            let position = book.entry((destination.clone(), entry.source_token.clone())).or_default();
+            position.amount += moved;
+            position.cost_usd += cost_usd;
+        }
+    }
+}
+
+// Value now of what a trade received less the value now of what it sold
+fn vs_holding(app_state: &AppState, trade: &TradePnl) -> Option<f64> {
+    let source_price = price::get_token_price(app_state, &trade.source_token).ok()?;
+    let target_price = price::get_token_price(app_state, &trade.target_token).ok()?;
+    Some(trade.target_amount * target_price - trade.source_amount * source_price)
+}
+
+// Type of the limit order behind a trade, archived or not. DCA fills have none.
+fn order_type(app_state: &AppState, order_id: &str) -> Option<OrderType> {
+    let order_type = app_state.limit_orders.lock().unwrap().get(order_id).map(|order| order.order_type.clone());
+    order_type.or_else(|| {
+        app_state
+            .archived_orders
+            .lock()
+            .unwrap()
+            .get(order_id)
+            .map(|order| order.order_type.clone())
+    })
+}
+
+// Realized and unrealized profit of the caller's wallets, or of one with `wallet`, on the tokens
+// they traded through the API, valued at the cached prices
+pub fn get_pnl(app_state: &AppState, tenant: &str, wallet: Option<&str>) -> Result<PnlReport> {
+    let only = wallet
+        .map(|pubkey| wallet::find_tenant_wallet(app_state, tenant, Some(pubkey)))
+        .transpose()?
+        .map(|wallet| wallet.pubkey.to_string());
+    let visible = |owner: &str| match &only {
+        Some(only) => only == owner,
+        None => tenant::can_use_wallet(app_state, tenant, owner),
+    };
+
+    let positions = {
+        let book = app_state.cost_basis.lock().unwrap();
+        book.iter()
+            .filter(|((owner, _), position)| visible(owner) && (position.amount > 0.0 || position.realized_usd != 0.0))
+            .map(|(key, position)| (key.clone(), position.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut tokens = positions
+        .into_iter()
+        .map(|((owner, mint), position)| {
+            let price_usd = price::get_token_price(app_state, &mint).ok();
+            let value_usd = price_usd.map(|price_usd| price_usd * position.amount);
+            TokenPnl {
+                wallet: owner,
+                symbol: KnownTokens::get_symbol(&mint),
+                mint,
+                amount: position.amount,
+                average_cost_usd: (position.amount > 0.0).then(|| position.cost_usd / position.amount),
+                cost_basis_usd: position.cost_usd,
+                price_usd,
+                value_usd,
+                realized_usd: position.realized_usd,
+                unrealized_usd: value_usd.map(|value_usd| value_usd - position.cost_usd),
+            }
+        })
+        .collect::<Vec<_>>();
+    tokens.sort_by(|a, b| (&a.wallet, &a.symbol, &a.mint).cmp(&(&b.wallet, &b.symbol, &b.mint)));
+
+    let trades = {
+        let trade_pnl = app_state.trade_pnl.lock().unwrap();
+        trade_pnl.iter().filter(|trade| visible(&trade.wallet)).cloned().collect::<Vec<_>>()
+    };
+    let fees_usd: f64 = trades.iter().map(|trade| trade.fee_usd).sum();
+
+    // Newest first
+    let orders = trades
+        .into_iter()
+        .rev()
+        .filter_map(|trade| {
+            let order_id = trade.order_id.clone()?;
+            Some(OrderPnl {
+                order_type: order_type(app_state, &order_id),
+                vs_holding_usd: vs_holding(app_state, &trade),
+                order_id,
+                trade,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let realized_usd: f64 = tokens.iter().map(|token| token.realized_usd).sum();
+    let unrealized_usd: f64 = tokens.iter().filter_map(|token| token.unrealized_usd).sum();
+    let stop_loss_vs_holding_usd = orders
+        .iter()
+        .filter(|order| order.order_type == Some(OrderType::StopLoss))
+        .filter_map(|order| order.vs_holding_usd)
+        .sum();
+
+    Ok(PnlReport {
+        tokens,
+        orders,
+        realized_usd,
+        unrealized_usd,
+        fees_usd,
+        total_usd: realized_usd + unrealized_usd - fees_usd,
+        stop_loss_vs_holding_usd,
+        generated_at: Utc::now(),
+    })
+}
@@ -0,0 +1,352 @@
+use crate::models::{AppState, LpPosition, PortfolioPosition, PortfolioResponse, PortfolioToken, PortfolioValuation, PortfolioWalletError, StakePosition, Wallet};
+use crate::price;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use solana_client::{
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::{error, info};
+
+// Native SOL mint, used to price stake accounts
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// Offsets into the stake account layout (Meta: discriminant, rent reserve, staker, withdrawer, lockup)
+const STAKE_WITHDRAWER_OFFSET: usize = 44;
+const STAKE_VOTER_OFFSET: usize = 124;
+
+// A liquidity pool whose LP token we know how to value
+struct KnownLpPool {
+    name: &'static str,
+    lp_mint: &'static str,
+    token_a_mint: &'static str,
+    token_a_vault: &'static str,
+    token_b_mint: &'static str,
+    token_b_vault: &'static str,
+}
+
+// Common LP positions we look for in every wallet
+const KNOWN_LP_POOLS: &[KnownLpPool] = &[
+    KnownLpPool {
+        name: "Raydium SOL-USDC",
+        lp_mint: "8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu",
+        token_a_mint: "So11111111111111111111111111111111111111112",
+        token_a_vault: "DQyrAcCrDXQ7NeoqGgDCZwBvWDcYmFCjSb9JtteuvPpz",
+        token_b_mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+        token_b_vault: "HLmqeL62xR1QoZ1HKKbXRrdN1p3phKpxRMb2VVopvBBz",
+    },
+];
+
+// Look up a cached USD price, treating unknown tokens as worthless
+fn price_or_zero(app_state: &AppState, mint: &str) -> f64 {
+    match price::get_token_price(app_state, mint) {
+        Ok(price) => price,
+        Err(_) => {
+            error!("No price available for {}, valuing at $0", mint);
+            0.0
+        }
+    }
+}
+
+// Human readable name for the stake account state discriminant
+fn stake_state_name(discriminant: u32) -> &'static str {
+    match discriminant {
+        0 => "uninitialized",
+        1 => "initialized",
+        2 => "delegated",
+        3 => "rewards_pool",
+        _ => "unknown",
+    }
+}
+
+// Find native stake accounts the wallet can withdraw from
+pub async fn get_stake_positions(app_state: &AppState, owner: &Pubkey) -> Result<Vec<StakePosition>> {
+    let client = &app_state.rpc_client;
+
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            STAKE_WITHDRAWER_OFFSET,
+            &owner.to_bytes(),
+        ))]),
+        ..Default::default()
+    };
+
+    let accounts = client
+        .get_program_accounts_with_config(&solana_sdk::stake::program::id(), config)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch stake accounts: {}", e))?;
+
+    let sol_price = price_or_zero(app_state, SOL_MINT);
+    let mut positions = Vec::new();
+
+    for (address, account) in accounts {
+        let data = &account.data;
+        let discriminant = if data.len() >= 4 {
+            u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+        } else {
+            0
+        };
+
+        // Only delegated stake accounts carry a vote account
+        let voter = if discriminant == 2 && data.len() >= STAKE_VOTER_OFFSET + 32 {
+            Pubkey::try_from(&data[STAKE_VOTER_OFFSET..STAKE_VOTER_OFFSET + 32])
+                .ok()
+                .map(|pubkey| pubkey.to_string())
+        } else {
+            None
+        };
+
+        let amount_sol = crate::utils::lamports_to_sol(account.lamports);
+
+        positions.push(StakePosition {
+            stake_account: address.to_string(),
+            state: stake_state_name(discriminant).to_string(),
+            voter,
+            amount_sol,
+            value_usd: amount_sol * sol_price,
+        });
+    }
+
+    Ok(positions)
+}
+
+// Value an LP holding as its share of both pool reserves
+fn value_lp_share(
+    lp_amount: f64,
+    lp_supply: f64,
+    reserve_a: f64,
+    price_a: f64,
+    reserve_b: f64,
+    price_b: f64,
+) -> (f64, f64, f64, f64) {
+    if lp_supply <= 0.0 {
+        return (0.0, 0.0, 0.0, 0.0);
+    }
+
+    let share = lp_amount / lp_supply;
+    let amount_a = reserve_a * share;
+    let amount_b = reserve_b * share;
+
+    (share, amount_a, amount_b, amount_a * price_a + amount_b * price_b)
+}
+
+// Find positions in the known LP pools held by the wallet
+pub async fn get_lp_positions(app_state: &AppState, owner: &Pubkey) -> Result<Vec<LpPosition>> {
+    let client = &app_state.rpc_client;
+    let mut positions = Vec::new();
+
+    for pool in KNOWN_LP_POOLS {
+        let lp_mint = Pubkey::from_str(pool.lp_mint)?;
+        let lp_account = wallet::get_token_account(owner, &lp_mint);
+
+        // A missing token account simply means no position in this pool
+        let lp_amount = match client.get_token_account_balance(&lp_account).await {
+            Ok(balance) => balance.ui_amount.unwrap_or(0.0),
+            Err(_) => continue,
+        };
+
+        if lp_amount <= 0.0 {
+            continue;
+        }
+
+        let lp_supply = client.get_token_supply(&lp_mint).await?.ui_amount.unwrap_or(0.0);
+        let reserve_a = client
+            .get_token_account_balance(&Pubkey::from_str(pool.token_a_vault)?)
+            .await?
+            .ui_amount
+            .unwrap_or(0.0);
+        let reserve_b = client
+            .get_token_account_balance(&Pubkey::from_str(pool.token_b_vault)?)
+            .await?
+            .ui_amount
+            .unwrap_or(0.0);
+
+        let (pool_share, token_a_amount, token_b_amount, value_usd) = value_lp_share(
+            lp_amount,
+            lp_supply,
+            reserve_a,
+            price_or_zero(app_state, pool.token_a_mint),
+            reserve_b,
+            price_or_zero(app_state, pool.token_b_mint),
+        );
+
+        positions.push(LpPosition {
+            pool: pool.name.to_string(),
+            lp_mint: pool.lp_mint.to_string(),
+            lp_amount,
+            pool_share,
+            token_a_mint: pool.token_a_mint.to_string(),
+            token_a_amount,
+            token_b_mint: pool.token_b_mint.to_string(),
+            token_b_amount,
+            value_usd,
+        });
+    }
+
+    Ok(positions)
+}
+
+// Build the full portfolio for a wallet: spot, staked and LP positions
+pub async fn get_portfolio(app_state: &AppState, wallet: &Wallet) -> Result<PortfolioResponse> {
+    let balances = wallet::get_token_balances(&app_state.rpc_client, wallet).await?;
+
+    let spot = balances
+        .into_iter()
+        .map(|balance| {
+            let price_usd = price_or_zero(app_state, &balance.mint);
+            PortfolioPosition {
+                value_usd: balance.amount * price_usd,
+                symbol: KnownTokens::get_symbol(&balance.mint),
+                mint: balance.mint,
+                amount: balance.amount,
+                price_usd,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Staked and LP lookups are best effort so one failing RPC call does not hide the spot holdings
+    let staked = get_stake_positions(app_state, &wallet.pubkey).await.unwrap_or_else(|e| {
+        error!("Failed to load stake positions: {}", e);
+        Vec::new()
+    });
+    let liquidity = get_lp_positions(app_state, &wallet.pubkey).await.unwrap_or_else(|e| {
+        error!("Failed to load LP positions: {}", e);
+        Vec::new()
+    });
+
+    let spot_value_usd: f64 = spot.iter().map(|p| p.value_usd).sum();
+    let staked_value_usd: f64 = staked.iter().map(|p| p.value_usd).sum();
+    let liquidity_value_usd: f64 = liquidity.iter().map(|p| p.value_usd).sum();
+    let total_value_usd = spot_value_usd + staked_value_usd + liquidity_value_usd;
+
+    info!(
+        "Portfolio for {}: spot ${:.2}, staked ${:.2}, liquidity ${:.2}",
+        wallet.pubkey, spot_value_usd, staked_value_usd, liquidity_value_usd
+    );
+
+    Ok(PortfolioResponse {
+        wallet: wallet.pubkey.to_string(),
+        spot,
+        staked,
+        liquidity,
+        spot_value_usd,
+        staked_value_usd,
+        liquidity_value_usd,
+        total_value_usd,
+        timestamp: Utc::now(),
+    })
+}
+
+// Change of a value over the last 24 hours from the token's price move, with the price back then
+fn change_24h(app_state: &AppState, mint: &str, amount: f64, price_usd: f64) -> Option<(f64, f64)> {
+    let price_24h_ago = price::get_price_at(app_state, mint, Utc::now() - Duration::hours(24))?;
+    Some((price_24h_ago, amount * (price_usd - price_24h_ago)))
+}
+
+// The caller's wallets to value, or the one given with `wallet`
+pub fn portfolio_wallets(app_state: &AppState, tenant: &str, wallet: Option<&str>) -> Result<Vec<Wallet>> {
+    let wallets = match wallet {
+        Some(pubkey) => vec![wallet::find_tenant_wallet(app_state, tenant, Some(pubkey))?],
+        None => wallet::list_wallets(app_state, tenant)
+            .into_iter()
+            .filter_map(|summary| wallet::find_wallet(app_state, Some(&summary.pubkey)).ok())
+            .collect(),
+    };
+    if wallets.is_empty() {
+        return Err(anyhow!("No wallet imported"));
+    }
+
+    Ok(wallets)
+}
+
+// Value of wallets together. Spot holdings are summed per token across wallets, and the 24 hour
+// change covers spot and staked holdings whose price is known from a day ago. LP positions are
+// left out of the change. A wallet whose balances cannot be loaded is listed in `failed_wallets`
+// and left out of the totals; only when no wallet can be loaded is the valuation an error.
+pub async fn get_portfolio_valuation(app_state: &AppState, wallets: &[Wallet]) -> Result<PortfolioValuation> {
+    let mut portfolios = Vec::new();
+    let mut failed_wallets = Vec::new();
+    let mut last_error = None;
+    for wallet in wallets {
+        match get_portfolio(app_state, wallet).await {
+            Ok(portfolio) => portfolios.push(portfolio),
+            Err(err) => {
+                error!("Failed to load portfolio of {}: {}", wallet.pubkey, err);
+                failed_wallets.push(PortfolioWalletError {
+                    wallet: wallet.pubkey.to_string(),
+                    error: err.to_string(),
+                });
+                last_error = Some(err);
+            }
+        }
+    }
+    if portfolios.is_empty() {
+        return Err(last_error.unwrap_or_else(|| anyhow!("No wallet to value")));
+    }
+
+    let mut holdings: BTreeMap<String, PortfolioPosition> = BTreeMap::new();
+    for position in portfolios.iter().flat_map(|portfolio| &portfolio.spot) {
+        holdings
+            .entry(position.mint.clone())
+            .and_modify(|holding| {
+                holding.amount += position.amount;
+                holding.value_usd += position.value_usd;
+            })
+            .or_insert_with(|| position.clone());
+    }
+
+    let mut tokens = holdings
+        .into_values()
+        .filter(|holding| holding.amount > 0.0)
+        .map(|holding| {
+            let change = change_24h(app_state, &holding.mint, holding.amount, holding.price_usd);
+            PortfolioToken {
+                price_24h_ago_usd: change.map(|(price_24h_ago, _)| price_24h_ago),
+                change_24h_usd: change.map(|(_, change)| change),
+                change_24h_percent: change
+                    .filter(|(price_24h_ago, _)| *price_24h_ago > 0.0)
+                    .map(|(price_24h_ago, _)| (holding.price_usd - price_24h_ago) / price_24h_ago * 100.0),
+                mint: holding.mint,
+                symbol: holding.symbol,
+                amount: holding.amount,
+                price_usd: holding.price_usd,
+                value_usd: holding.value_usd,
+            }
+        })
+        .collect::<Vec<_>>();
+    tokens.sort_by(|a, b| b.value_usd.partial_cmp(&a.value_usd).unwrap_or(std::cmp::Ordering::Equal));
+
+    let staked_sol: f64 = portfolios.iter().flat_map(|portfolio| &portfolio.staked).map(|position| position.amount_sol).sum();
+    let staked_change = change_24h(app_state, SOL_MINT, staked_sol, price_or_zero(app_state, SOL_MINT))
+        .filter(|_| staked_sol > 0.0)
+        .map(|(_, change)| change);
+    let changes = tokens.iter().filter_map(|token| token.change_24h_usd).chain(staked_change).collect::<Vec<_>>();
+
+    let spot_value_usd: f64 = portfolios.iter().map(|portfolio| portfolio.spot_value_usd).sum();
+    let staked_value_usd: f64 = portfolios.iter().map(|portfolio| portfolio.staked_value_usd).sum();
+    let liquidity_value_usd: f64 = portfolios.iter().map(|portfolio| portfolio.liquidity_value_usd).sum();
+    let total_value_usd = spot_value_usd + staked_value_usd + liquidity_value_usd;
+
+    let change_24h_usd = (!changes.is_empty()).then(|| changes.iter().sum::<f64>());
+    let change_24h_percent = change_24h_usd
+        .map(|change| (change, total_value_usd - change))
+        .filter(|(_, value_24h_ago)| *value_24h_ago > 0.0)
+        .map(|(change, value_24h_ago)| change / value_24h_ago * 100.0);
+
+    Ok(PortfolioValuation {
+        tokens,
+        wallets: portfolios,
+        failed_wallets,
+        spot_value_usd,
+        staked_value_usd,
+        liquidity_value_usd,
+        total_value_usd,
+        change_24h_usd,
+        change_24h_percent,
+        timestamp: Utc::now(),
+    })
+}
@@ -1,11 +1,15 @@
-use crate::models::TokenPrice;
+use crate::models::{MintPrice, PriceAggregation, PriceDivergence, PriceGuardConfig, PriceMeta, PricePoint, PriceProviders, ProviderPreference, PushedPrice, SourcePrice, TokenPrice, TriggerSource};
 use anyhow::{anyhow, Result};
-use chrono::Utc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize};
+use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::{error, info};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
 
 // Jupiter API URLs for price data
 const JUPITER_PRICE_API_URL: &str = "https://price.jup.ag/v4/price";
@@ -13,6 +17,152 @@ const JUPITER_PRICE_API_URL: &str = "https://price.jup.ag/v4/price";
 // CoinGecko API for fallback
 const COINGECKO_API_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
 
+// Birdeye price API, only queried when an API key is configured
+const BIRDEYE_PRICE_API_URL: &str = "https://public-api.birdeye.so/defi/price";
+const BIRDEYE_API_KEY_ENV: &str = "BIRDEYE_API_KEY";
+
+// USDC, the quote token pool prices are derived against
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+// Cached prices younger than this are served without fetching, by `/get_prices` and single
+// mint lookups
+const PRICE_CACHE_TTL_ENV: &str = "PRICE_CACHE_TTL_SECS";
+const DEFAULT_PRICE_CACHE_TTL_SECS: i64 = 60;
+
+// Per-source prices older than this are left out of medians, guard aggregates and divergence
+const SOURCE_PRICE_TTL_ENV: &str = "SOURCE_PRICE_TTL_SECS";
+const DEFAULT_SOURCE_PRICE_TTL_SECS: i64 = 120;
+
+// How long and how many cached prices are kept per token for backtraces
+const PRICE_HISTORY_RETENTION_HOURS: i64 = 7 * 24;
+const MAX_PRICE_HISTORY_POINTS: usize = 20_000;
+
+// Price source names used for per-source prices and guard weights
+pub const SOURCE_JUPITER: &str = "jupiter";
+pub const SOURCE_COINGECKO: &str = "coingecko";
+pub const SOURCE_BIRDEYE: &str = "birdeye";
+pub const SOURCE_POOL: &str = "pool";
+pub const SOURCE_PYTH: &str = "pyth";
+pub const SOURCE_MOCK: &str = "mock";
+pub const SOURCE_EXTERNAL: &str = "external";
+
+// A source of USD token prices
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    // Source name used in price metadata, guard weights and provider preferences
+    fn name(&self) -> &'static str;
+    
+    // Prices of the given mints; mints the provider has no price for are left out
+    async fn get_prices(&self, mints: &[String]) -> Result<Vec<TokenPrice>>;
+}
+
+// Jupiter's price API as a price provider
+pub struct JupiterProvider;
+
+#[async_trait]
+impl PriceProvider for JupiterProvider {
+    fn name(&self) -> &'static str {
+        SOURCE_JUPITER
+    }
+    
+    async fn get_prices(&self, mints: &[String]) -> Result<Vec<TokenPrice>> {
+        get_prices_from_jupiter(mints).await
+    }
+}
+
+// CoinGecko as a price provider, for the tokens it has an id for
+pub struct CoinGeckoProvider;
+
+#[async_trait]
+impl PriceProvider for CoinGeckoProvider {
+    fn name(&self) -> &'static str {
+        SOURCE_COINGECKO
+    }
+    
+    async fn get_prices(&self, mints: &[String]) -> Result<Vec<TokenPrice>> {
+        get_coingecko_prices_for_mints(mints).await
+    }
+}
+
+// Price provider serving prices set in memory, for tests and offline runs. Clones share their prices,
+// so a test can keep one to move prices after handing another to the app state.
+#[derive(Clone)]
+pub struct MockPriceProvider {
+    name: &'static str,
+    prices: Arc<Mutex<HashMap<String, f64>>>,
+    failing: Arc<AtomicBool>,
+}
+
+impl MockPriceProvider {
+    pub fn new() -> Self {
+        Self::named(SOURCE_MOCK)
+    }
+    
+    // A mock under another name, to stand in for several providers at once
+    pub fn named(name: &'static str) -> Self {
+        Self {
+            name,
+            prices: Arc::new(Mutex::new(HashMap::new())),
+            failing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    
+    pub fn set_price(&self, mint: &str, price_usd: f64) {
+        self.prices.lock().unwrap().insert(mint.to_string(), price_usd);
+    }
+    
+    // Make every request fail, as an unreachable API would
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+}
+
+impl Default for MockPriceProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PriceProvider for MockPriceProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+    
+    async fn get_prices(&self, mints: &[String]) -> Result<Vec<TokenPrice>> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(anyhow!("Mock price provider is failing"));
+        }
+        
+        let prices = self.prices.lock().unwrap();
+        Ok(mints
+            .iter()
+            .filter_map(|mint| {
+                prices.get(mint).map(|price_usd| TokenPrice {
+                    mint: mint.clone(),
+                    symbol: crate::wallet::KnownTokens::get_symbol(mint),
+                    price_usd: *price_usd,
+                    last_updated: Utc::now(),
+                })
+            })
+            .collect())
+    }
+}
+
+// Providers the server starts with, in the order tokens without a preference try them
+pub fn default_providers() -> Vec<Box<dyn PriceProvider>> {
+    vec![
+        Box::new(JupiterProvider),
+        Box::new(crate::pyth::PythProvider),
+        Box::new(CoinGeckoProvider),
+    ]
+}
+
+// Names of the configured providers, in their default order
+fn provider_names(app_state: &crate::models::AppState) -> Vec<&'static str> {
+    app_state.price_providers.iter().map(|provider| provider.name()).collect()
+}
+
 // Jupiter price response structures
 #[derive(Deserialize, Debug)]
 struct JupiterPriceResponse {
@@ -40,6 +190,18 @@ struct CoinGeckoTokenData {
     usd: f64,
 }
 
+// Birdeye price response structure
+#[derive(Deserialize, Debug)]
+struct BirdeyePriceResponse {
+    success: bool,
+    data: Option<BirdeyePriceData>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BirdeyePriceData {
+    value: f64,
+}
+
 // Token mapping for CoinGecko IDs
 fn get_coingecko_id(symbol: &str) -> Option<&'static str> {
     match symbol.to_uppercase().as_str() {
@@ -129,61 +291,745 @@ pub async fn get_prices_from_coingecko(symbols: &[String]) -> Result<Vec<TokenPr
     Ok(prices)
 }
 
+// Age after which a cached price is fetched again on request
+pub fn price_cache_ttl_secs() -> i64 {
+    std::env::var(PRICE_CACHE_TTL_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_PRICE_CACHE_TTL_SECS)
+}
+
+// Age after which a source's price for a token no longer counts
+fn source_price_ttl_secs() -> i64 {
+    std::env::var(SOURCE_PRICE_TTL_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SOURCE_PRICE_TTL_SECS)
+}
+
 // Update prices in the app state
 pub async fn update_prices(app_state: Arc<crate::models::AppState>) -> Result<()> {
-    // Get list of mints from all wallets
-    let tokens = {
-        let wallets = app_state.wallets.lock().unwrap();
+    refresh_prices(app_state, None).await
+}
+
+// Update only the tracked prices older than the cache TTL, leaving fresh ones as they are
+pub async fn refresh_stale_prices(app_state: Arc<crate::models::AppState>) -> Result<()> {
+    refresh_prices(app_state, Some(price_cache_ttl_secs())).await
+}
+
+// Fetch the prices of every tracked token, or with a maximum age only of those older than it
+async fn refresh_prices(app_state: Arc<crate::models::AppState>, max_age_secs: Option<i64>) -> Result<()> {
+    // Keep serving the cached prices as if every source had stopped updating
+    #[cfg(feature = "chaos")]
+    if crate::chaos::prices_stale() {
+        return Ok(());
+    }
+    
+    // Watched tokens are tracked whether or not a wallet holds them
+    let mut tokens = crate::watchlist::watched_mints(&app_state);
+    
+    // Guarded tokens always need fresh prices from every source
+    let guarded_mints = {
+        let guards = app_state.price_guards.lock().unwrap();
+        guards.keys().cloned().collect::<Vec<_>>()
+    };
+    
+    for mint in &guarded_mints {
+        if !tokens.contains(mint) {
+            tokens.push(mint.clone());
+        }
+    }
+    
+    // Tokens active orders trigger on, including the two sides of pair triggers
+    let order_mints = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| order.status == crate::models::OrderStatus::Active)
+            .flat_map(|order| {
+                std::iter::once(order.trigger_mint().to_string()).chain(order.quote_mint.clone())
+            })
+            .collect::<Vec<_>>()
+    };
+    
+    for mint in &order_mints {
+        if !tokens.contains(mint) {
+            tokens.push(mint.clone());
+        }
+    }
+    
+    let (tokens, guarded_mints) = match max_age_secs {
+        Some(max_age_secs) => {
+            let now = Utc::now();
+            let meta = app_state.price_meta.lock().unwrap();
+            let stale = |mint: &String| {
+                meta.get(mint)
+                    .map(|meta| (now - meta.updated_at).num_seconds() > max_age_secs)
+                    .unwrap_or(true)
+            };
+            (
+                tokens.into_iter().filter(|mint| stale(mint)).collect::<Vec<_>>(),
+                guarded_mints.into_iter().filter(|mint| stale(mint)).collect::<Vec<_>>(),
+            )
+        }
+        None => (tokens, guarded_mints),
+    };
+    if max_age_secs.is_some() && tokens.is_empty() {
+        return Ok(());
+    }
+    
+    // Prices that moved, streamed to WebSocket subscribers once the update is done
+    let mut changed = HashMap::new();
+    
+    // Each token goes to its preferred provider first and fails over to the next ones
+    let prices = fetch_with_failover(&app_state, &tokens).await?;
+    let answered = prices
+        .iter()
+        .map(|(price, source)| (price.mint.clone(), *source))
+        .collect::<Vec<_>>();
+    for (price, source) in &prices {
+        if cache_price(&app_state, &price.mint, price.price_usd, source) {
+            changed.insert(price.mint.clone(), price.price_usd);
+        }
+    }
+    info!("Updated {} token prices", prices.len());
+    
+    *app_state.prices_updated_at.lock().unwrap() = Some(Utc::now());
+    crate::events::publish_prices(&app_state, changed);
+    
+    // Collect a second opinion for guarded tokens from every provider that has not priced them yet
+    for provider in &app_state.price_providers {
+        let mints = guarded_mints
+            .iter()
+            .filter(|mint| !answered.contains(&((*mint).clone(), provider.name())))
+            .cloned()
+            .collect::<Vec<_>>();
+        if mints.is_empty() {
+            continue;
+        }
         
-        if wallets.is_empty() {
-            // Default to SOL if no wallets
-            vec!["So11111111111111111111111111111111111111112".to_string()]
-        } else {
-            // Get unique tokens from all wallets
-            let mut tokens = Vec::new();
-            
-            for (_, _) in wallets.iter() {
-                // This would require async in the lock, so in a real app
-                // we might use a different approach to avoid deadlocks
-                // For now, just use default tokens
-                tokens.push("So11111111111111111111111111111111111111112".to_string());
-                tokens.push("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string()); // USDC
-            }
-            
-            tokens
+        match provider.get_prices(&mints).await {
+            Ok(prices) => record_source_prices(&app_state, provider.name(), &prices),
+            Err(e) => error!("Failed to get guard prices from {}: {}", provider.name(), e),
         }
+    }
+    
+    Ok(())
+}
+
+// Providers to try for a token: its preference first, then the remaining ones in default order
+fn provider_order(app_state: &crate::models::AppState, mint: &str) -> Vec<String> {
+    let mut order = {
+        let preferences = app_state.provider_preferences.lock().unwrap();
+        preferences.get(mint).map(|preference| preference.providers.clone()).unwrap_or_default()
     };
     
-    // Try Jupiter first
-    match get_prices_from_jupiter(&tokens).await {
-        Ok(prices) => {
-            let mut price_map = app_state.token_prices.lock().unwrap();
-            for price in prices {
-                price_map.insert(price.mint.clone(), price.price_usd);
+    for provider in provider_names(app_state) {
+        if !order.iter().any(|name| name == provider) {
+            order.push(provider.to_string());
+        }
+    }
+    order
+}
+
+// Fetch prices for the tokens. Tokens are batched per provider at each step of their order,
+// and a token moves on to its next provider when the current one fails or has no price for it.
+async fn fetch_with_failover(
+    app_state: &crate::models::AppState,
+    tokens: &[String],
+) -> Result<Vec<(TokenPrice, &'static str)>> {
+    // Nothing watched and no active orders
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let providers = &app_state.price_providers;
+    let orders = tokens
+        .iter()
+        .map(|mint| (mint.clone(), provider_order(app_state, mint)))
+        .collect::<HashMap<_, _>>();
+    
+    let mut remaining = tokens.to_vec();
+    let mut fetched = Vec::new();
+    let mut errors = Vec::new();
+    
+    for step in 0..providers.len() {
+        for provider in providers {
+            let mints = remaining
+                .iter()
+                .filter(|mint| orders[*mint].get(step).map(String::as_str) == Some(provider.name()))
+                .cloned()
+                .collect::<Vec<_>>();
+            if mints.is_empty() {
+                continue;
             }
-            info!("Updated prices from Jupiter");
-            return Ok(());
-        }
-        Err(e) => {
-            error!("Failed to get prices from Jupiter: {}", e);
-            // Fall back to CoinGecko
-            let symbols = vec!["SOL".to_string(), "USDC".to_string()];
-            match get_prices_from_coingecko(&symbols).await {
+            
+            match provider.get_prices(&mints).await {
                 Ok(prices) => {
-                    let mut price_map = app_state.token_prices.lock().unwrap();
+                    let prices = prices.into_iter().filter(|price| mints.contains(&price.mint)).collect::<Vec<_>>();
+                    record_source_prices(app_state, provider.name(), &prices);
                     for price in prices {
-                        price_map.insert(price.mint.clone(), price.price_usd);
+                        remaining.retain(|mint| *mint != price.mint);
+                        fetched.push((price, provider.name()));
                     }
-                    info!("Updated prices from CoinGecko");
-                    return Ok(());
                 }
                 Err(e) => {
-                    error!("Failed to get prices from CoinGecko: {}", e);
-                    return Err(anyhow!("Failed to update prices from all sources"));
+                    error!("Failed to get prices from {}: {}", provider.name(), e);
+                    errors.push(format!("{}: {}", provider.name(), e));
                 }
             }
         }
     }
+    
+    if fetched.is_empty() {
+        return Err(anyhow!("Failed to update prices from all sources ({})", errors.join("; ")));
+    }
+    if !remaining.is_empty() {
+        warn!("No price provider has a price for {}", remaining.join(", "));
+    }
+    
+    Ok(fetched)
+}
+
+// Available providers and the per-token preferences
+pub fn get_price_providers(app_state: &crate::models::AppState) -> PriceProviders {
+    let preferences = app_state.provider_preferences.lock().unwrap();
+    PriceProviders {
+        default_order: provider_names(app_state).iter().map(|name| name.to_string()).collect(),
+        preferences: preferences.values().cloned().collect(),
+    }
+}
+
+// Store the order providers are tried in for a token
+pub fn set_provider_preference(app_state: &crate::models::AppState, preference: ProviderPreference) -> Result<ProviderPreference> {
+    preference.mint.parse::<solana_sdk::pubkey::Pubkey>().map_err(|_| anyhow!("Invalid mint: {}", preference.mint))?;
+    if preference.providers.is_empty() {
+        return Err(anyhow!("At least one provider is required"));
+    }
+    
+    let available = provider_names(app_state);
+    let mut seen = Vec::new();
+    for provider in &preference.providers {
+        if !available.contains(&provider.as_str()) {
+            return Err(anyhow!(
+                "Unknown price provider: {} (available: {})",
+                provider,
+                available.join(", ")
+            ));
+        }
+        if seen.contains(&provider) {
+            return Err(anyhow!("Provider {} is listed twice", provider));
+        }
+        seen.push(provider);
+    }
+    
+    let mut preferences = app_state.provider_preferences.lock().unwrap();
+    preferences.insert(preference.mint.clone(), preference.clone());
+    
+    info!("Price providers for {}: {}", preference.mint, preference.providers.join(", "));
+    Ok(preference)
+}
+
+// Remove a token's provider preference, returning it to the default order
+pub fn remove_provider_preference(app_state: &crate::models::AppState, mint: &str) -> Result<ProviderPreference> {
+    let mut preferences = app_state.provider_preferences.lock().unwrap();
+    preferences
+        .remove(mint)
+        .ok_or_else(|| anyhow!("No provider preference configured for {}", mint))
+}
+
+// Cache a token's USD price along with its source, returning whether the price changed
+fn cache_price(app_state: &crate::models::AppState, mint: &str, price_usd: f64, source: &str) -> bool {
+    let now = Utc::now();
+    app_state.price_meta.lock().unwrap().insert(mint.to_string(), PriceMeta {
+        source: source.to_string(),
+        updated_at: now,
+    });
+    record_price_point(app_state, mint, PricePoint {
+        price_usd,
+        source: source.to_string(),
+        timestamp: now,
+    });
+    
+    let mut price_map = app_state.token_prices.lock().unwrap();
+    price_map.insert(mint.to_string(), price_usd) != Some(price_usd)
+}
+
+// Append a cached price to the token's history, dropping points past the retention period
+fn record_price_point(app_state: &crate::models::AppState, mint: &str, point: PricePoint) {
+    let cutoff = point.timestamp - chrono::Duration::hours(PRICE_HISTORY_RETENTION_HOURS);
+    let mut history = app_state.price_history.lock().unwrap();
+    let points = history.entry(mint.to_string()).or_default();
+    while matches!(points.front(), Some(oldest) if oldest.timestamp < cutoff) || points.len() >= MAX_PRICE_HISTORY_POINTS {
+        points.pop_front();
+    }
+    points.push_back(point);
+}
+
+// Cached prices of a token over the retention period, oldest first
+pub fn get_price_history(app_state: &crate::models::AppState, mint: &str) -> Vec<PricePoint> {
+    let history = app_state.price_history.lock().unwrap();
+    history.get(mint).map(|points| points.iter().cloned().collect()).unwrap_or_default()
+}
+
+// Last cached price of a token at or before a time, if the history goes back that far
+pub fn get_price_at(app_state: &crate::models::AppState, mint: &str, at: DateTime<Utc>) -> Option<f64> {
+    let history = app_state.price_history.lock().unwrap();
+    history
+        .get(mint)?
+        .iter()
+        .rev()
+        .find(|point| point.timestamp <= at)
+        .map(|point| point.price_usd)
+}
+
+// Cache prices a provider pushed, streaming the ones that moved to WebSocket subscribers
+pub fn record_pushed_prices(app_state: &crate::models::AppState, source: &'static str, prices: &[TokenPrice]) {
+    record_source_prices(app_state, source, prices);
+    
+    let mut changed = HashMap::new();
+    for price in prices {
+        if cache_price(app_state, &price.mint, price.price_usd, source) {
+            changed.insert(price.mint.clone(), price.price_usd);
+        }
+    }
+    crate::events::publish_prices(app_state, changed);
+}
+
+// Merge prices pushed by an external feed with the other sources. Each token's cached price
+// becomes the median of the latest fresh price from every source, the pushed one included. A
+// pushed price expires like any source's once the feed stops pushing.
+pub fn push_external_prices(app_state: &crate::models::AppState, pushed: &[PushedPrice]) -> Result<Vec<MintPrice>> {
+    if pushed.is_empty() {
+        return Err(anyhow!("No prices to push"));
+    }
+    for price in pushed {
+        Pubkey::from_str(&price.mint).map_err(|_| anyhow!("Invalid token mint: {}", price.mint))?;
+        if !price.price_usd.is_finite() || price.price_usd <= 0.0 {
+            return Err(anyhow!("Price for {} must be a positive number", price.mint));
+        }
+    }
+    
+    let now = Utc::now();
+    let prices = pushed
+        .iter()
+        .map(|price| TokenPrice {
+            mint: price.mint.clone(),
+            symbol: crate::wallet::KnownTokens::get_symbol(&price.mint),
+            price_usd: price.price_usd,
+            last_updated: now,
+        })
+        .collect::<Vec<_>>();
+    record_source_prices(app_state, SOURCE_EXTERNAL, &prices);
+    
+    let mut changed = HashMap::new();
+    let mut merged = Vec::new();
+    for price in prices {
+        let sources = token_source_prices(app_state, &price.mint);
+        let price_usd = median_price(&sources.values().cloned().collect::<Vec<_>>()).unwrap_or(price.price_usd);
+        if cache_price(app_state, &price.mint, price_usd, SOURCE_EXTERNAL) {
+            changed.insert(price.mint.clone(), price_usd);
+        }
+        merged.push(MintPrice {
+            mint: price.mint,
+            symbol: price.symbol,
+            price_usd,
+            source: SOURCE_EXTERNAL.to_string(),
+            updated_at: now,
+            age_secs: 0,
+            cached: false,
+        });
+    }
+    info!("Merged {} externally pushed prices", merged.len());
+    crate::events::publish_prices(app_state, changed);
+    
+    Ok(merged)
+}
+
+// Price of one token from Birdeye
+async fn get_price_from_birdeye(mint: &str) -> Result<f64> {
+    let api_key = crate::secrets::get_secret(BIRDEYE_API_KEY_ENV)
+        .ok_or_else(|| anyhow!("{} is not set", BIRDEYE_API_KEY_ENV))?;
+    
+    let response = Client::new()
+        .get(BIRDEYE_PRICE_API_URL)
+        .query(&[("address", mint)])
+        .header("X-API-KEY", api_key)
+        .header("x-chain", "solana")
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<BirdeyePriceResponse>()
+        .await?;
+    
+    match response.data {
+        Some(data) if response.success => Ok(data.value),
+        _ => Err(anyhow!("Birdeye has no price for {}", mint)),
+    }
+}
+
+// Price of one token implied by routing a whole token into USDC through the pools Jupiter uses
+async fn get_price_from_pool(app_state: &crate::models::AppState, mint: &str) -> Result<f64> {
+    if mint == USDC_MINT {
+        return Err(anyhow!("USDC is the quote token of pool prices"));
+    }
+    
+    // Unknown mints carry their decimals on chain
+    let decimals = match crate::wallet::KnownTokens::get_decimals(mint) {
+        Ok(decimals) => decimals,
+        Err(_) => {
+            let pubkey = mint.parse().map_err(|_| anyhow!("Invalid mint: {}", mint))?;
+            app_state.rpc_client.get_token_supply(&pubkey).await?.decimals as i32
+        }
+    };
+    
+    let quote = crate::swap::get_swap_quote(mint, USDC_MINT, 10u64.pow(decimals as u32), 0.5).await?;
+    Ok(quote.out_amount_units()? as f64 / 1e6)
+}
+
+// Fetch one token's price across the source chain: its providers in order, then Birdeye, then pools
+async fn fetch_mint_price(app_state: &crate::models::AppState, mint: &str) -> Result<(f64, &'static str)> {
+    let mut errors = Vec::new();
+    
+    for name in provider_order(app_state, mint) {
+        let provider = match app_state.price_providers.iter().find(|provider| provider.name() == name) {
+            Some(provider) => provider,
+            None => continue,
+        };
+        match provider.get_prices(&[mint.to_string()]).await {
+            Ok(prices) => match prices.iter().find(|price| price.mint == mint) {
+                Some(price) => return Ok((price.price_usd, provider.name())),
+                None => errors.push(format!("{}: no price", provider.name())),
+            },
+            Err(e) => errors.push(format!("{}: {}", provider.name(), e)),
+        }
+    }
+    
+    match get_price_from_birdeye(mint).await {
+        Ok(price) => return Ok((price, SOURCE_BIRDEYE)),
+        Err(e) => errors.push(format!("{}: {}", SOURCE_BIRDEYE, e)),
+    }
+    
+    match get_price_from_pool(app_state, mint).await {
+        Ok(price) => return Ok((price, SOURCE_POOL)),
+        Err(e) => errors.push(format!("{}: {}", SOURCE_POOL, e)),
+    }
+    
+    Err(anyhow!("No price source has a price for {} ({})", mint, errors.join("; ")))
+}
+
+// Cached prices with the time each was last fetched
+pub fn cached_prices(app_state: &crate::models::AppState) -> Vec<TokenPrice> {
+    let fallback = (*app_state.prices_updated_at.lock().unwrap()).unwrap_or(app_state.started_at);
+    let price_map = app_state.token_prices.lock().unwrap();
+    let meta = app_state.price_meta.lock().unwrap();
+    
+    let mut prices = price_map
+        .iter()
+        .map(|(mint, price_usd)| TokenPrice {
+            mint: mint.clone(),
+            symbol: crate::wallet::KnownTokens::get_symbol(mint),
+            price_usd: *price_usd,
+            last_updated: meta.get(mint).map(|meta| meta.updated_at).unwrap_or(fallback),
+        })
+        .collect::<Vec<_>>();
+    prices.sort_by(|a, b| a.symbol.cmp(&b.symbol).then_with(|| a.mint.cmp(&b.mint)));
+    prices
+}
+
+// Price of a single mint, served from cache while fresh and fetched on demand otherwise.
+// A stale cached price is still returned, with its age, if every source fails.
+pub async fn get_mint_price(app_state: &crate::models::AppState, mint: &str) -> Result<MintPrice> {
+    mint.parse::<solana_sdk::pubkey::Pubkey>().map_err(|_| anyhow!("Invalid mint: {}", mint))?;
+    
+    let cached = {
+        let price_map = app_state.token_prices.lock().unwrap();
+        let meta = app_state.price_meta.lock().unwrap();
+        price_map.get(mint).map(|price| (*price, meta.get(mint).cloned()))
+    };
+    
+    let now = Utc::now();
+    let cached_price = |price_usd: f64, meta: Option<PriceMeta>| {
+        let (source, updated_at) = match meta {
+            Some(meta) => (meta.source, meta.updated_at),
+            None => ("unknown".to_string(), (*app_state.prices_updated_at.lock().unwrap()).unwrap_or(app_state.started_at)),
+        };
+        MintPrice {
+            mint: mint.to_string(),
+            symbol: crate::wallet::KnownTokens::get_symbol(mint),
+            price_usd,
+            source,
+            updated_at,
+            age_secs: (now - updated_at).num_seconds(),
+            cached: true,
+        }
+    };
+    
+    if let Some((price_usd, Some(meta))) = &cached {
+        if (now - meta.updated_at).num_seconds() <= price_cache_ttl_secs() {
+            return Ok(cached_price(*price_usd, Some(meta.clone())));
+        }
+    }
+    
+    match fetch_mint_price(app_state, mint).await {
+        Ok((price_usd, source)) => {
+            cache_price(app_state, mint, price_usd, source);
+            Ok(MintPrice {
+                mint: mint.to_string(),
+                symbol: crate::wallet::KnownTokens::get_symbol(mint),
+                price_usd,
+                source: source.to_string(),
+                updated_at: Utc::now(),
+                age_secs: 0,
+                cached: false,
+            })
+        }
+        Err(err) => match cached {
+            Some((price_usd, meta)) => {
+                error!("Serving stale price for {}: {}", mint, err);
+                Ok(cached_price(price_usd, meta))
+            }
+            None => Err(err),
+        },
+    }
+}
+
+// Get CoinGecko prices keyed by mint rather than CoinGecko id
+pub async fn get_coingecko_prices_for_mints(mints: &[String]) -> Result<Vec<TokenPrice>> {
+    let symbols = mints
+        .iter()
+        .map(|mint| crate::wallet::KnownTokens::get_symbol(mint))
+        .collect::<Vec<_>>();
+    
+    let prices = get_prices_from_coingecko(&symbols).await?;
+    
+    // CoinGecko answers with its own ids, so map them back to the requested mints
+    let mut result = Vec::new();
+    for (mint, symbol) in mints.iter().zip(symbols.iter()) {
+        if let Some(id) = get_coingecko_id(symbol) {
+            if let Some(price) = prices.iter().find(|p| p.mint == id) {
+                result.push(TokenPrice {
+                    mint: mint.clone(),
+                    symbol: symbol.clone(),
+                    price_usd: price.price_usd,
+                    last_updated: price.last_updated,
+                });
+            }
+        }
+    }
+    
+    Ok(result)
+}
+
+// Remember the latest price each source reported for a token, with the time it was current
+fn record_source_prices(app_state: &crate::models::AppState, source: &str, prices: &[TokenPrice]) {
+    let mut source_prices = app_state.source_prices.lock().unwrap();
+    for price in prices {
+        source_prices.entry(price.mint.clone()).or_default().insert(
+            source.to_string(),
+            SourcePrice {
+                price_usd: price.price_usd,
+                updated_at: price.last_updated,
+            },
+        );
+    }
+}
+
+// Prices of a token from the sources that reported it within the TTL. A source that stopped
+// reporting, or a feed that stopped pushing, drops out instead of pinning the aggregate.
+fn fresh_source_prices(sources: &HashMap<String, SourcePrice>, now: DateTime<Utc>) -> HashMap<String, f64> {
+    let cutoff = now - chrono::Duration::seconds(source_price_ttl_secs());
+    sources
+        .iter()
+        .filter(|(_, price)| price.updated_at >= cutoff)
+        .map(|(source, price)| (source.clone(), price.price_usd))
+        .collect()
+}
+
+// Fresh prices of one token by source
+fn token_source_prices(app_state: &crate::models::AppState, mint: &str) -> HashMap<String, f64> {
+    let source_prices = app_state.source_prices.lock().unwrap();
+    source_prices
+        .get(mint)
+        .map(|sources| fresh_source_prices(sources, Utc::now()))
+        .unwrap_or_default()
+}
+
+// Median of a set of prices
+fn median_price(prices: &[f64]) -> Option<f64> {
+    if prices.is_empty() {
+        return None;
+    }
+    
+    let mut sorted = prices.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+// Combine source prices according to a guard config
+fn aggregate_price(guard: &PriceGuardConfig, sources: &HashMap<String, f64>) -> Option<f64> {
+    match guard.aggregation {
+        PriceAggregation::Median => {
+            median_price(&sources.values().cloned().collect::<Vec<_>>())
+        }
+        PriceAggregation::Weighted => {
+            // Sources without an explicit weight count once
+            let (weighted_sum, total_weight) = sources.iter().fold((0.0, 0.0), |(sum, total), (source, price)| {
+                let weight = guard.weights.get(source).cloned().unwrap_or(1.0);
+                (sum + price * weight, total + weight)
+            });
+            
+            if total_weight > 0.0 {
+                Some(weighted_sum / total_weight)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// Largest distance of any source from the reference price, in percent
+fn divergence_percent(sources: &HashMap<String, f64>, reference: f64) -> f64 {
+    if reference <= 0.0 {
+        return 0.0;
+    }
+    
+    sources
+        .values()
+        .map(|price| (price - reference).abs() / reference * 100.0)
+        .fold(0.0, f64::max)
+}
+
+// Store the price guard for a token
+pub fn set_price_guard(app_state: &crate::models::AppState, guard: PriceGuardConfig) -> Result<PriceGuardConfig> {
+    if guard.weights.values().any(|weight| *weight < 0.0) {
+        return Err(anyhow!("Source weights cannot be negative"));
+    }
+    if let Some(max_divergence) = guard.max_divergence_percent {
+        if max_divergence <= 0.0 {
+            return Err(anyhow!("max_divergence_percent must be greater than zero"));
+        }
+    }
+    
+    let mut guards = app_state.price_guards.lock().unwrap();
+    guards.insert(guard.mint.clone(), guard.clone());
+    
+    info!("Price guard set for {}: {:?}", guard.mint, guard.aggregation);
+    Ok(guard)
+}
+
+// Remove the price guard for a token
+pub fn remove_price_guard(app_state: &crate::models::AppState, mint: &str) -> Result<PriceGuardConfig> {
+    let mut guards = app_state.price_guards.lock().unwrap();
+    guards
+        .remove(mint)
+        .ok_or_else(|| anyhow!("No price guard configured for {}", mint))
+}
+
+// Price used to decide whether an order triggers
+pub fn get_trigger_price(app_state: &crate::models::AppState, token_mint: &str) -> Result<f64> {
+    let guard = {
+        let guards = app_state.price_guards.lock().unwrap();
+        guards.get(token_mint).cloned()
+    };
+    
+    // Unguarded tokens use the primary feed directly
+    let guard = match guard {
+        Some(guard) => guard,
+        None => return get_token_price(app_state, token_mint),
+    };
+    
+    let sources = token_source_prices(app_state, token_mint);
+    
+    let price = aggregate_price(&guard, &sources)
+        .ok_or_else(|| anyhow!("No fresh source prices available for guarded token {}", token_mint))?;
+    
+    if let Some(max_divergence) = guard.max_divergence_percent {
+        let divergence = divergence_percent(&sources, price);
+        if divergence > max_divergence {
+            return Err(anyhow!(
+                "Price sources for {} diverge by {:.2}% (max {}%), refusing to evaluate triggers",
+                token_mint, divergence, max_divergence
+            ));
+        }
+    }
+    
+    Ok(price)
+}
+
+// Price used to decide whether an order triggers, from the source the order chose
+pub fn get_order_trigger_price(app_state: &crate::models::AppState, order: &crate::models::LimitOrder) -> Result<f64> {
+    let price = match order.trigger_source {
+        TriggerSource::Spot => get_trigger_price(app_state, order.trigger_mint())?,
+        TriggerSource::PerpMark => crate::drift::get_mark_price(app_state, order.trigger_mint())?,
+    };
+    
+    // Pair triggers divide by the quote token's USD price, so "BONK/SOL" is BONK per SOL
+    match &order.quote_mint {
+        Some(quote_mint) => {
+            let quote_price = get_trigger_price(app_state, quote_mint)?;
+            if quote_price <= 0.0 {
+                return Err(anyhow!("Price of quote token {} is zero", quote_mint));
+            }
+            Ok(price / quote_price)
+        }
+        None => Ok(price),
+    }
+}
+
+// Divergence between price sources for every token we have fresh source prices for
+pub fn get_price_divergence(app_state: &crate::models::AppState) -> Vec<PriceDivergence> {
+    let now = Utc::now();
+    let source_prices = app_state.source_prices.lock().unwrap().clone();
+    let guards = app_state.price_guards.lock().unwrap().clone();
+    
+    source_prices
+        .into_iter()
+        .map(|(mint, sources)| (mint, fresh_source_prices(&sources, now)))
+        .filter(|(_, sources)| !sources.is_empty())
+        .map(|(mint, sources)| {
+            let guard = guards.get(&mint);
+            let aggregated_price = match guard {
+                Some(guard) => aggregate_price(guard, &sources),
+                None => median_price(&sources.values().cloned().collect::<Vec<_>>()),
+            };
+            
+            PriceDivergence {
+                symbol: crate::wallet::KnownTokens::get_symbol(&mint),
+                divergence_percent: aggregated_price
+                    .map(|price| divergence_percent(&sources, price))
+                    .unwrap_or(0.0),
+                guarded: guard.is_some(),
+                mint,
+                sources,
+                aggregated_price,
+            }
+        })
+        .collect()
+}
+
+// Value a transaction fee in USD at cached prices, leaving it unset if a price is missing
+pub fn value_fee(app_state: &crate::models::AppState, fee: &mut crate::models::TransactionFee) {
+    let sol_price = match get_token_price(app_state, "So11111111111111111111111111111111111111112") {
+        Ok(price) => price,
+        Err(_) => return,
+    };
+    
+    let platform_fee_usd = match &fee.platform_fee_mint {
+        Some(mint) => match get_token_price(app_state, mint) {
+            Ok(price) => fee.platform_fee * price,
+            Err(_) => return,
+        },
+        None => 0.0,
+    };
+    
+    fee.total_usd = Some(fee.total_sol * sol_price + platform_fee_usd);
 }
 
 // Get current price for a specific token
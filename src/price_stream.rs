@@ -0,0 +1,101 @@
+use crate::models::AppState;
+use crate::orders;
+use crate::price;
+use crate::pyth;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::{sync::mpsc, time};
+use tracing::{info, warn};
+
+// How often the streamed tokens are compared with the ones active orders trigger on
+const STREAM_RESCAN_SECS: u64 = 15;
+
+// Wait before reconnecting after the stream drops
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+// Price batches buffered between the stream and the order checks
+const STREAM_BUFFER: usize = 64;
+
+// Set to "false" to rely on the monitor's polling alone
+const PRICE_STREAM_ENV: &str = "PRICE_STREAM";
+
+// Whether pushed prices are enabled
+pub fn enabled() -> bool {
+    std::env::var(PRICE_STREAM_ENV).map_or(true, |value| !(value == "0" || value.eq_ignore_ascii_case("false")))
+}
+
+// Tokens active orders trigger on that Pyth pushes prices for
+fn streamable_mints(app_state: &AppState) -> Vec<String> {
+    let mut mints = orders::spot_trigger_mints(app_state)
+        .into_iter()
+        .filter(|mint| pyth::has_feed(mint))
+        .collect::<Vec<_>>();
+    mints.sort();
+    mints
+}
+
+// Background task checking orders on every price Pyth pushes. The limit order monitor keeps
+// polling every order on its interval, so orders still trigger while the stream is down.
+pub async fn run_price_stream(app_state: Arc<AppState>) {
+    if !enabled() {
+        info!("Price stream disabled, orders are checked by polling only");
+        return;
+    }
+    info!("Starting price stream task");
+
+    loop {
+        let mints = streamable_mints(&app_state);
+        if mints.is_empty() {
+            time::sleep(time::Duration::from_secs(STREAM_RESCAN_SECS)).await;
+            continue;
+        }
+
+        info!("Streaming Pyth prices for {} tokens", mints.len());
+        match follow_stream(&app_state, &mints).await {
+            Ok(()) => info!("Orders now trigger on different tokens, restarting the price stream"),
+            Err(err) => {
+                warn!("Price stream dropped, polling continues: {}", err);
+                time::sleep(time::Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+            }
+        }
+    }
+}
+
+// Apply pushed prices until the stream breaks or the tokens to stream change
+async fn follow_stream(app_state: &Arc<AppState>, mints: &[String]) -> Result<()> {
+    let (sender, mut receiver) = mpsc::channel(STREAM_BUFFER);
+    let stream_mints = mints.to_vec();
+    let stream = tokio::spawn(async move { pyth::stream_prices_from_pyth(&stream_mints, sender).await });
+
+    let mut last_rescan = time::Instant::now();
+    let rescan_interval = time::Duration::from_secs(STREAM_RESCAN_SECS);
+
+    loop {
+        match time::timeout(rescan_interval, receiver.recv()).await {
+            Ok(Some(prices)) => {
+                // Keep serving the cached prices as if every source had stopped updating
+                #[cfg(feature = "chaos")]
+                if crate::chaos::prices_stale() {
+                    continue;
+                }
+
+                price::record_pushed_prices(app_state, price::SOURCE_PYTH, &prices);
+                let updated = prices.into_iter().map(|price| price.mint).collect::<Vec<_>>();
+                orders::check_orders_on_prices(app_state, &updated).await;
+            }
+            // The sender is gone, so the stream ended; its result says why
+            Ok(None) => break,
+            Err(_) => {}
+        }
+
+        if last_rescan.elapsed() >= rescan_interval {
+            last_rescan = time::Instant::now();
+            if streamable_mints(app_state) != mints {
+                stream.abort();
+                return Ok(());
+            }
+        }
+    }
+
+    stream.await?
+}
@@ -0,0 +1,187 @@
+use crate::models::TokenPrice;
+use crate::price::PriceProvider;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+// Pyth Hermes endpoint serving the latest parsed price updates
+const HERMES_LATEST_PRICE_URL: &str = "https://hermes.pyth.network/v2/updates/price/latest";
+
+// Pyth Hermes endpoint pushing parsed price updates as server-sent events
+const HERMES_PRICE_STREAM_URL: &str = "https://hermes.pyth.network/v2/updates/price/stream";
+
+// Extra feeds as comma separated mint=feed_id pairs, for tokens the built-in table does not know
+const PYTH_FEED_IDS_ENV: &str = "PYTH_FEED_IDS";
+
+// Pyth USD price feeds of the tokens the server knows
+const KNOWN_FEEDS: &[(&str, &str)] = &[
+    // SOL/USD
+    ("So11111111111111111111111111111111111111112", "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"),
+    // USDC/USD
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a"),
+    // BONK/USD
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "72b021217ca3fe68922a19aaf990109cb9d84e9ad004b4d2025ad6f529314419"),
+];
+
+// Hermes response structures
+#[derive(Deserialize, Debug)]
+struct HermesResponse {
+    parsed: Vec<HermesPriceUpdate>,
+}
+
+#[derive(Deserialize, Debug)]
+struct HermesPriceUpdate {
+    id: String,
+    price: HermesPrice,
+}
+
+#[derive(Deserialize, Debug)]
+struct HermesPrice {
+    price: String,
+    expo: i32,
+    publish_time: i64,
+}
+
+// Feed ids by mint, the environment overriding the built-in table
+fn feed_ids() -> HashMap<String, String> {
+    let mut feeds = KNOWN_FEEDS
+        .iter()
+        .map(|(mint, feed)| (mint.to_string(), feed.to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let configured = std::env::var(PYTH_FEED_IDS_ENV).unwrap_or_default();
+    for pair in configured.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        if let Some((mint, feed)) = pair.split_once('=') {
+            feeds.insert(mint.trim().to_string(), feed.trim().trim_start_matches("0x").to_lowercase());
+        }
+    }
+
+    feeds
+}
+
+// Feeds of the mints that have one, mapped back to their mint
+fn mints_by_feed(mints: &[String]) -> Result<HashMap<String, String>> {
+    let feeds = feed_ids();
+    let mints_by_feed = mints
+        .iter()
+        .filter_map(|mint| feeds.get(mint).map(|feed| (feed.clone(), mint.clone())))
+        .collect::<HashMap<_, _>>();
+
+    if mints_by_feed.is_empty() {
+        return Err(anyhow!("No Pyth price feed for the requested tokens"));
+    }
+    Ok(mints_by_feed)
+}
+
+// Hermes query selecting the feeds, with parsed prices
+fn feed_query(mints_by_feed: &HashMap<String, String>) -> Vec<(&str, &str)> {
+    let mut query = mints_by_feed
+        .keys()
+        .map(|feed| ("ids[]", feed.as_str()))
+        .collect::<Vec<_>>();
+    query.push(("parsed", "true"));
+    query
+}
+
+// USD prices of the updates for the requested feeds
+fn parse_updates(updates: Vec<HermesPriceUpdate>, mints_by_feed: &HashMap<String, String>) -> Result<Vec<TokenPrice>> {
+    let mut prices = Vec::new();
+    for update in updates {
+        let mint = match mints_by_feed.get(update.id.trim_start_matches("0x")) {
+            Some(mint) => mint,
+            None => continue,
+        };
+        let mantissa = update
+            .price
+            .price
+            .parse::<i64>()
+            .map_err(|_| anyhow!("Invalid Pyth price for feed {}: {}", update.id, update.price.price))?;
+
+        prices.push(TokenPrice {
+            mint: mint.clone(),
+            symbol: crate::wallet::KnownTokens::get_symbol(mint),
+            price_usd: mantissa as f64 * 10f64.powi(update.price.expo),
+            last_updated: Utc.timestamp_opt(update.price.publish_time, 0).single().unwrap_or_else(Utc::now),
+        });
+    }
+
+    Ok(prices)
+}
+
+// Whether Pyth has a price feed for the mint
+pub fn has_feed(mint: &str) -> bool {
+    feed_ids().contains_key(mint)
+}
+
+// Prices of the given mints from Pyth. Mints without a known feed are left out.
+pub async fn get_prices_from_pyth(mints: &[String]) -> Result<Vec<TokenPrice>> {
+    let mints_by_feed = mints_by_feed(mints)?;
+
+    let response = Client::new()
+        .get(HERMES_LATEST_PRICE_URL)
+        .query(&feed_query(&mints_by_feed))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<HermesResponse>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse Pyth Hermes response: {}", e))?;
+
+    parse_updates(response.parsed, &mints_by_feed)
+}
+
+// Follow Hermes' server-sent price updates for the mints, sending each batch of prices on `updates`.
+// Returns once the stream ends or the receiver is dropped; an error means the stream broke.
+pub async fn stream_prices_from_pyth(mints: &[String], updates: mpsc::Sender<Vec<TokenPrice>>) -> Result<()> {
+    let mints_by_feed = mints_by_feed(mints)?;
+
+    let mut response = Client::new()
+        .get(HERMES_PRICE_STREAM_URL)
+        .query(&feed_query(&mints_by_feed))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // Events are `data: <json>` lines; chunks can end mid-line, so keep the unfinished tail
+    let mut buffer = String::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+        while let Some(end) = buffer.find('\n') {
+            let line = buffer[..end].trim().to_string();
+            buffer.drain(..=end);
+
+            let data = match line.strip_prefix("data:") {
+                Some(data) => data.trim(),
+                None => continue,
+            };
+            let event = serde_json::from_str::<HermesResponse>(data)
+                .map_err(|e| anyhow!("Failed to parse Pyth Hermes stream event: {}", e))?;
+
+            let prices = parse_updates(event.parsed, &mints_by_feed)?;
+            if !prices.is_empty() && updates.send(prices).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(anyhow!("Pyth Hermes price stream closed"))
+}
+
+// Pyth Hermes as a price provider
+pub struct PythProvider;
+
+#[async_trait]
+impl PriceProvider for PythProvider {
+    fn name(&self) -> &'static str {
+        crate::price::SOURCE_PYTH
+    }
+
+    async fn get_prices(&self, mints: &[String]) -> Result<Vec<TokenPrice>> {
+        get_prices_from_pyth(mints).await
+    }
+}
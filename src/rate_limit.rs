@@ -0,0 +1,155 @@
+#[cfg(feature = "server")]
+use crate::auth;
+#[cfg(feature = "server")]
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::metering;
+use crate::models::{AppState, RateLimitConfig, RequestBucket, RouteBudget};
+#[cfg(feature = "server")]
+use crate::tenant;
+#[cfg(feature = "server")]
+use crate::utils;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "server")]
+use axum::{
+    extract::{ConnectInfo, MatchedPath},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use std::net::SocketAddr;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use tracing::info;
+#[cfg(feature = "server")]
+use tracing::warn;
+
+// Set to "true" behind a reverse proxy, so clients are told apart by the first X-Forwarded-For
+// address instead of the proxy's. Left unset otherwise, as clients can put anything in the header.
+#[cfg(feature = "server")]
+const TRUST_PROXY_ENV: &str = "RATE_LIMIT_TRUST_PROXY";
+
+// Buckets kept before idle ones are dropped. A bucket idle for longer has refilled under any
+// budget whose burst is at most an hour's worth, and a full bucket is the same as no bucket.
+const MAX_BUCKETS: usize = 10_000;
+const BUCKET_IDLE_SECS: i64 = 3600;
+
+// Current rate limit configuration
+pub fn get_config(app_state: &AppState) -> RateLimitConfig {
+    app_state.rate_limits.lock().unwrap().clone()
+}
+
+// Replace the rate limit configuration. Buckets of routes whose budget changed start over.
+pub fn set_config(app_state: &AppState, config: RateLimitConfig) -> Result<RateLimitConfig> {
+    let budgets = config.default.iter().map(|budget| ("default", budget));
+    for (route, budget) in budgets.chain(config.routes.iter().map(|(route, budget)| (route.as_str(), budget))) {
+        if budget.per_minute == 0 || budget.burst == Some(0) {
+            return Err(anyhow!("Budget of {} must allow at least one request", route));
+        }
+    }
+
+    let previous = std::mem::replace(&mut *app_state.rate_limits.lock().unwrap(), config.clone());
+    app_state.rate_buckets.lock().unwrap().retain(|(_, route), _| {
+        let budget_of = |config: &RateLimitConfig| config.routes.get(route).or(config.default.as_ref()).cloned();
+        budget_of(&previous) == budget_of(&config)
+    });
+
+    info!("Rate limits updated ({} route budgets)", config.routes.len());
+    Ok(config)
+}
+
+// Budget of a route pattern, if it is limited
+fn budget_for(app_state: &AppState, route: &str) -> Option<RouteBudget> {
+    let config = app_state.rate_limits.lock().unwrap();
+    config.routes.get(route).or(config.default.as_ref()).cloned()
+}
+
+// Take a request from the client's bucket for the route. Returns how many seconds until one is
+// available when the bucket is empty.
+pub fn take_request(app_state: &AppState, client: &str, route: &str, now: DateTime<Utc>) -> Result<(), f64> {
+    let budget = match budget_for(app_state, route) {
+        Some(budget) => budget,
+        None => return Ok(()),
+    };
+    let capacity = budget.burst.unwrap_or(budget.per_minute) as f64;
+    let refill_per_sec = budget.per_minute as f64 / 60.0;
+
+    let mut buckets = app_state.rate_buckets.lock().unwrap();
+    if buckets.len() >= MAX_BUCKETS {
+        buckets.retain(|_, bucket| (now - bucket.updated_at).num_seconds() < BUCKET_IDLE_SECS);
+    }
+
+    let bucket = buckets
+        .entry((client.to_string(), route.to_string()))
+        .or_insert(RequestBucket { tokens: capacity, updated_at: now });
+    let elapsed = (now - bucket.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+    bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+    bucket.updated_at = now;
+
+    if bucket.tokens < 1.0 {
+        return Err((1.0 - bucket.tokens) / refill_per_sec);
+    }
+    bucket.tokens -= 1.0;
+    Ok(())
+}
+
+// Client a request is counted against: its API key if the key is registered, so made up keys
+// don't each get a budget of their own, and its address otherwise
+#[cfg(feature = "server")]
+fn client_of<B>(app_state: &AppState, request: &Request<B>) -> String {
+    let key = metering::api_key_from_headers(request.headers());
+    if key != metering::ANONYMOUS_KEY && (auth::validate_key(app_state, &key) || (tenant::hosted_mode() && tenant::is_admin(&key))) {
+        return format!("key:{}", key);
+    }
+
+    let trust_proxy = std::env::var(TRUST_PROXY_ENV)
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|address| address.trim().to_string())
+        .filter(|address| trust_proxy && !address.is_empty());
+    let address = forwarded.or_else(|| {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip().to_string())
+    });
+    format!("ip:{}", address.unwrap_or_else(|| "unknown".to_string()))
+}
+
+// Middleware refusing requests beyond the client's budget for the route with a 429
+#[cfg(feature = "server")]
+pub async fn enforce_rate_limits<B>(request: Request<B>, next: Next<B>) -> Response {
+    let app_state = match request.extensions().get::<Arc<AppState>>().cloned() {
+        Some(app_state) => app_state,
+        None => return next.run(request).await,
+    };
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(route) => route.as_str().to_string(),
+        None => return next.run(request).await,
+    };
+
+    let client = client_of(&app_state, &request);
+    if let Err(wait_secs) = take_request(&app_state, &client, &route, Utc::now()) {
+        let retry_after = wait_secs.ceil().max(1.0) as u64;
+        // Only the start of an API key is logged
+        let shown = if client.starts_with("key:") { format!("{}...", client.get(..12).unwrap_or(&client)) } else { client.clone() };
+        warn!("Rate limited {} on {}, retry in {}s", shown, route, retry_after);
+
+        let message = format!("Too many requests to {}, retry in {} seconds", route, retry_after);
+        let mut response = ApiError::RateLimited(message)
+            .into_api_response()
+            .with_format(utils::ResponseFormat::from_headers(request.headers()))
+            .into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    next.run(request).await
+}
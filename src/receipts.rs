@@ -0,0 +1,95 @@
+use crate::models::{AppState, ExecutionReceipt, LimitOrder, ReceiptContents};
+use crate::orders;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+// Base58 encoded 64 byte keypair receipts are signed with, in the Solana CLI's secret key layout
+const SIGNING_KEY_ENV: &str = "RECEIPT_SIGNING_KEY";
+
+// Load the receipt signing key, falling back to a random one that does not survive restarts
+pub fn load_signing_key() -> Keypair {
+    let configured = crate::secrets::get_secret(SIGNING_KEY_ENV)
+        .and_then(|encoded| bs58::decode(encoded.trim()).into_vec().ok())
+        .and_then(|bytes| Keypair::from_bytes(&bytes).ok());
+
+    match configured {
+        Some(keypair) => keypair,
+        None => {
+            if crate::tenant::hosted_mode() {
+                warn!("{} is not set to a base58 encoded keypair, signing receipts with an ephemeral key", SIGNING_KEY_ENV);
+            }
+            Keypair::new()
+        }
+    }
+}
+
+// Bytes a receipt's signature covers
+fn signed_bytes(contents: &ReceiptContents) -> Result<Vec<u8>> {
+    serde_json::to_vec(contents).map_err(|e| anyhow!("Failed to serialize receipt: {}", e))
+}
+
+// Sign and store the receipt of a completed order
+pub fn issue_receipt(
+    app_state: &AppState,
+    order: &LimitOrder,
+    trigger_price: f64,
+    triggered_at: DateTime<Utc>,
+) -> Result<ExecutionReceipt> {
+    let contents = ReceiptContents {
+        order_id: order.id.clone(),
+        order_type: order.order_type.clone(),
+        action: order.action.clone(),
+        source_token: order.source_token.clone(),
+        target_token: order.target_token.clone(),
+        amount: order.amount,
+        price_target: order.price_target,
+        slippage: order.slippage,
+        wallet_pubkey: order.wallet_pubkey.clone(),
+        api_key: order.api_key.clone(),
+        trigger_price,
+        execution: order.execution.clone(),
+        fee: order.fee.clone(),
+        transaction_signature: order.transaction_signature.clone(),
+        created_at: order.created_at,
+        triggered_at,
+        completed_at: order.updated_at,
+        issued_at: Utc::now(),
+    };
+
+    let signature = app_state.receipt_signer.sign_message(&signed_bytes(&contents)?);
+    let receipt = ExecutionReceipt {
+        receipt: contents,
+        signer: app_state.receipt_signer.pubkey().to_string(),
+        signature: signature.to_string(),
+    };
+
+    let mut receipts = app_state.execution_receipts.lock().unwrap();
+    receipts.insert(order.id.clone(), receipt.clone());
+    info!("Issued execution receipt for order {}", order.id);
+    Ok(receipt)
+}
+
+// Whether a receipt's signature matches its contents and signer
+pub fn verify_receipt(receipt: &ExecutionReceipt) -> Result<bool> {
+    let signer = Pubkey::from_str(&receipt.signer).map_err(|_| anyhow!("Invalid receipt signer: {}", receipt.signer))?;
+    let signature = Signature::from_str(&receipt.signature).map_err(|_| anyhow!("Invalid receipt signature"))?;
+    Ok(signature.verify(signer.as_ref(), &signed_bytes(&receipt.receipt)?))
+}
+
+// Receipt of an order the tenant may see
+pub fn get_receipt(app_state: &AppState, tenant: &str, order_id: &str) -> Result<ExecutionReceipt> {
+    // Visibility follows the order's history, which outlives archiving
+    orders::get_order_history(app_state, tenant, order_id)?;
+
+    let receipts = app_state.execution_receipts.lock().unwrap();
+    receipts
+        .get(order_id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Order {} has no execution receipt", order_id))
+}
@@ -0,0 +1,135 @@
+use crate::engine;
+use crate::freeze;
+use crate::ledger;
+use crate::models::{AppState, SolReservePolicy, SwapRequest};
+use crate::price;
+use crate::swap;
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::{anyhow, Result};
+use tracing::{error, info, warn};
+
+// Native SOL mint
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// Extra funding token swapped to cover slippage and price movement
+const FUNDING_BUFFER: f64 = 1.02;
+
+// Validate and store the tenant's SOL reserve policy
+pub fn set_policy(app_state: &AppState, tenant: &str, policy: SolReservePolicy) -> Result<SolReservePolicy> {
+    if policy.min_sol <= 0.0 {
+        return Err(anyhow!("min_sol must be greater than zero"));
+    }
+    if policy.target_sol < policy.min_sol {
+        return Err(anyhow!("target_sol must be at least min_sol"));
+    }
+    if policy.funding_token == SOL_MINT {
+        return Err(anyhow!("The funding token cannot be SOL"));
+    }
+
+    let mut policies = app_state.sol_reserve_policies.lock().unwrap();
+    policies.insert(tenant::settings_key(tenant), policy.clone());
+
+    info!(
+        "SOL reserve policy set: top up to {} SOL from {} below {} SOL",
+        policy.target_sol, KnownTokens::get_symbol(&policy.funding_token), policy.min_sol
+    );
+    Ok(policy)
+}
+
+// The tenant's current SOL reserve policy
+pub fn get_policy(app_state: &AppState, tenant: &str) -> Option<SolReservePolicy> {
+    let policies = app_state.sol_reserve_policies.lock().unwrap();
+    policies.get(&tenant::settings_key(tenant)).cloned()
+}
+
+// Top up every wallet whose SOL balance fell below its owner's reserve. A policy only ever
+// spends the funding token of the wallets its tenant may use.
+pub async fn maintain_sol_reserve(app_state: &AppState) {
+    let policies = {
+        let policies = app_state.sol_reserve_policies.lock().unwrap();
+        policies
+            .iter()
+            .filter(|(_, policy)| policy.enabled)
+            .map(|(owner, policy)| (owner.clone(), policy.clone()))
+            .collect::<Vec<_>>()
+    };
+    if policies.is_empty() {
+        return;
+    }
+
+    if let Some(reason) = engine::trading_paused(app_state) {
+        warn!("Skipping SOL reserve top-up, trading is paused: {}", reason);
+        return;
+    }
+
+    for (owner, policy) in policies {
+        let wallets = tenant::tenant_wallets(app_state, &owner)
+            .into_iter()
+            .filter_map(|pubkey| wallet::find_wallet(app_state, Some(&pubkey)).ok())
+            .collect::<Vec<_>>();
+
+        for trading_wallet in wallets {
+            if freeze::is_frozen(app_state, &trading_wallet.pubkey.to_string()) {
+                continue;
+            }
+            if let Err(err) = top_up_wallet(app_state, &policy, &trading_wallet).await {
+                error!("Failed to top up SOL reserve for {}: {}", trading_wallet.pubkey, err);
+            }
+        }
+    }
+}
+
+// Swap enough of the funding token to bring one wallet back to the target SOL balance
+async fn top_up_wallet(app_state: &AppState, policy: &SolReservePolicy, trading_wallet: &crate::models::Wallet) -> Result<()> {
+    let balances = wallet::get_token_balances(&app_state.rpc_client, trading_wallet).await?;
+    let sol_balance = balances
+        .iter()
+        .find(|balance| balance.mint == SOL_MINT)
+        .map(|balance| balance.amount)
+        .unwrap_or(0.0);
+
+    if sol_balance >= policy.min_sol {
+        return Ok(());
+    }
+
+    let sol_price = price::get_token_price(app_state, SOL_MINT)?;
+    let funding_price = price::get_token_price(app_state, &policy.funding_token)?;
+    let sol_needed = policy.target_sol - sol_balance;
+    let amount = sol_needed * sol_price / funding_price * FUNDING_BUFFER;
+
+    if !wallet::balance_covers(&balances, &policy.funding_token, amount) {
+        return Err(anyhow!(
+            "Not enough {} to top up {} SOL (need {})",
+            KnownTokens::get_symbol(&policy.funding_token), sol_needed, amount
+        ));
+    }
+
+    warn!(
+        "Wallet {} holds {} SOL, below the {} SOL reserve. Swapping {} {} into SOL",
+        trading_wallet.pubkey, sol_balance, policy.min_sol, amount, KnownTokens::get_symbol(&policy.funding_token)
+    );
+
+    let swap_request = SwapRequest {
+        source_token: policy.funding_token.clone(),
+        target_token: SOL_MINT.to_string(),
+        amount,
+        amount_percent: None,
+        amount_usd: None,
+        slippage: policy.slippage,
+        wallet_pubkey: Some(trading_wallet.pubkey.to_string()),
+        dry_run: false,
+        priority_fee: None,
+        mev_protection: false,
+    };
+
+    let mut result = swap::execute_swap(app_state, trading_wallet, &swap_request).await?;
+    price::value_fee(app_state, &mut result.fee_details);
+    ledger::record_trade(app_state, &trading_wallet.pubkey.to_string(), &swap_request, &result, None);
+
+    info!(
+        "Topped up {} with {} SOL. Signature: {}",
+        trading_wallet.pubkey, result.target_amount, result.transaction_signature
+    );
+    Ok(())
+}
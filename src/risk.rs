@@ -0,0 +1,161 @@
+use crate::models::{AppState, LimitOrder, OrderAction, OrderStatus, OrderType, RiskPosition, RiskSummary, TokenBalance, TriggerSource};
+use crate::price;
+use crate::swap;
+use crate::tenant;
+use crate::wallet::{self, KnownTokens};
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+// Orders protecting a holding: stops, including dead man's switches, that sell it when they fire
+fn is_protective_stop(order: &LimitOrder) -> bool {
+    matches!(order.status, OrderStatus::Active | OrderStatus::PendingFunds | OrderStatus::Retrying)
+        && matches!(order.order_type, OrderType::StopLoss | OrderType::DeadMansSwitch)
+        && matches!(order.action, OrderAction::Swap | OrderAction::SafeExit | OrderAction::ConvertToStable(_))
+}
+
+// Token a stop sells into
+fn proceeds_token(order: &LimitOrder) -> String {
+    match &order.action {
+        OrderAction::ConvertToStable(convert) => convert.stable_token.clone().unwrap_or_else(|| USDC_MINT.to_string()),
+        _ => order.target_token.clone(),
+    }
+}
+
+// Least USD a stop would receive selling `amount` right now
+async fn worst_case_proceeds(app_state: &AppState, order: &LimitOrder, mint: &str, amount: f64) -> Result<f64> {
+    let output = proceeds_token(order);
+    let input_decimals = KnownTokens::get_decimals(mint)?;
+    let output_decimals = KnownTokens::get_decimals(&output)?;
+
+    let units = (amount * 10f64.powi(input_decimals)) as u64;
+    let (quote, _) = swap::get_allowed_quote(app_state, mint, &output, units, order.slippage, None).await?;
+    let min_out = quote.min_out_amount_units()? as f64 / 10f64.powi(output_decimals);
+
+    Ok(min_out * price::get_token_price(app_state, &output)?)
+}
+
+// Summarize one holding against the stops selling it
+async fn assess_position(app_state: &AppState, wallet: &str, holding: &TokenBalance, stops: &[LimitOrder]) -> RiskPosition {
+    let price_usd = price::get_token_price(app_state, &holding.mint).unwrap_or(0.0);
+
+    // Each stop covers its amount, a safe exit the whole balance; together they cover at most the balance
+    let mut remaining = holding.amount;
+    let mut covered_by = Vec::new();
+    for stop in stops {
+        let amount = match stop.action {
+            OrderAction::SafeExit => remaining,
+            _ => stop.amount.min(remaining),
+        };
+        remaining -= amount;
+        covered_by.push((stop, amount));
+    }
+    let covered_amount = holding.amount - remaining;
+
+    let nearest_trigger_price = stops
+        .iter()
+        .filter(|stop| {
+            stop.order_type == OrderType::StopLoss
+                && stop.trigger_source == TriggerSource::Spot
+                && stop.quote_mint.is_none()
+                && stop.trigger_mint() == holding.mint
+        })
+        .map(|stop| stop.price_target)
+        .reduce(f64::max);
+    let nearest_trigger_distance_percent = nearest_trigger_price
+        .filter(|_| price_usd > 0.0)
+        .map(|target| (price_usd - target) / price_usd * 100.0);
+
+    let mut proceeds = 0.0;
+    let mut quote_error = None;
+    for (stop, amount) in covered_by.into_iter().filter(|(_, amount)| *amount > 0.0) {
+        match worst_case_proceeds(app_state, stop, &holding.mint, amount).await {
+            Ok(usd) => proceeds += usd,
+            Err(err) => {
+                warn!("Could not quote stop {} for the risk summary: {}", stop.id, err);
+                quote_error = Some(err.to_string());
+            }
+        }
+    }
+
+    RiskPosition {
+        wallet: wallet.to_string(),
+        mint: holding.mint.clone(),
+        symbol: holding.symbol.clone(),
+        balance: holding.amount,
+        price_usd,
+        value_usd: holding.amount * price_usd,
+        stop_orders: stops.iter().map(|stop| stop.id.clone()).collect(),
+        covered_amount,
+        covered_usd: covered_amount * price_usd,
+        uncovered_amount: remaining,
+        uncovered_usd: remaining * price_usd,
+        nearest_trigger_price,
+        nearest_trigger_distance_percent,
+        worst_case_proceeds_usd: if quote_error.is_none() { Some(proceeds) } else { None },
+        quote_error,
+    }
+}
+
+// Per wallet and token, how much of the caller's holdings active stops protect, optionally for one wallet only
+pub async fn get_risk_summary(app_state: &AppState, tenant: &str, wallet: Option<&str>) -> Result<RiskSummary> {
+    let wallets = match wallet {
+        Some(pubkey) => vec![wallet::find_tenant_wallet(app_state, tenant, Some(pubkey))?],
+        None => tenant::tenant_wallets(app_state, tenant)
+            .into_iter()
+            .filter_map(|pubkey| wallet::find_wallet(app_state, Some(&pubkey)).ok())
+            .collect(),
+    };
+
+    let orders = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders.values().filter(|order| is_protective_stop(order)).cloned().collect::<Vec<_>>()
+    };
+
+    let mut positions = Vec::new();
+    for owner in wallets {
+        let pubkey = owner.pubkey.to_string();
+        let balances = wallet::get_token_balances(&app_state.rpc_client, &owner).await?;
+
+        // Stops on tokens the wallet no longer holds still show up, with nothing to cover
+        let mut holdings = balances
+            .into_iter()
+            .map(|balance| (balance.mint.clone(), balance))
+            .collect::<BTreeMap<_, _>>();
+        for order in orders.iter().filter(|order| order.wallet_pubkey == pubkey) {
+            holdings.entry(order.source_token.clone()).or_insert_with(|| TokenBalance {
+                mint: order.source_token.clone(),
+                symbol: KnownTokens::get_symbol(&order.source_token),
+                amount: 0.0,
+                raw_amount: "0".to_string(),
+                decimals: KnownTokens::get_decimals(&order.source_token).unwrap_or(0) as u8,
+            });
+        }
+
+        for holding in holdings.values() {
+            let mut stops = orders
+                .iter()
+                .filter(|order| order.wallet_pubkey == pubkey && order.source_token == holding.mint)
+                .cloned()
+                .collect::<Vec<_>>();
+            if holding.amount <= 0.0 && stops.is_empty() {
+                continue;
+            }
+            // The stops closest to the price fire first, so they are counted first
+            stops.sort_by(|a, b| b.price_target.partial_cmp(&a.price_target).unwrap_or(std::cmp::Ordering::Equal));
+            positions.push(assess_position(app_state, &pubkey, holding, &stops).await);
+        }
+    }
+
+    Ok(RiskSummary {
+        total_value_usd: positions.iter().map(|position| position.value_usd).sum(),
+        covered_usd: positions.iter().map(|position| position.covered_usd).sum(),
+        uncovered_usd: positions.iter().map(|position| position.uncovered_usd).sum(),
+        worst_case_proceeds_usd: positions.iter().filter_map(|position| position.worst_case_proceeds_usd).sum(),
+        positions,
+        generated_at: Utc::now(),
+    })
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+// Backend master secrets and API credentials are read from: `env` (the default) or `file`
+const SECRETS_BACKEND_ENV: &str = "SECRETS_BACKEND";
+
+// Directory the file backend reads secrets from, one file named after each secret
+const SECRETS_DIR_ENV: &str = "SECRETS_DIR";
+const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+
+// Backend chosen at startup
+static PROVIDER: OnceLock<Box<dyn SecretsProvider>> = OnceLock::new();
+
+// Where master secrets and API credentials such as the tenant master key, the receipt signing key
+// and API keys come from. Backends only hand out values; parsing and fallbacks stay with the callers.
+pub trait SecretsProvider: Send + Sync {
+    // Backend name, for logs
+    fn name(&self) -> &'static str;
+
+    // Value of a secret, or None when the backend does not hold it
+    fn get_secret(&self, name: &str) -> Result<Option<String>>;
+}
+
+// Secrets read from environment variables of the same name, as before backends existed
+pub struct EnvSecrets;
+
+impl SecretsProvider for EnvSecrets {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn get_secret(&self, name: &str) -> Result<Option<String>> {
+        Ok(std::env::var(name).ok())
+    }
+}
+
+// Secrets read from files in a directory, such as Docker or Kubernetes secrets mounted at
+// /run/secrets. Files are read on every lookup, so rotated secrets apply without a restart.
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretsProvider for FileSecrets {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get_secret(&self, name: &str) -> Result<Option<String>> {
+        // Secret names are environment variable style, so a name cannot escape the directory
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow!("Invalid secret name: {}", name));
+        }
+
+        let path = self.dir.join(name);
+        match fs::read_to_string(&path) {
+            // Files written by editors and `echo` end with a newline that is not part of the secret
+            Ok(value) => Ok(Some(value.trim_end_matches(['\r', '\n']).to_string())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(anyhow!("Failed to read secret {}: {}", path.display(), err)),
+        }
+    }
+}
+
+// Backend configured through SECRETS_BACKEND
+fn configured_provider() -> Result<Box<dyn SecretsProvider>> {
+    let backend = std::env::var(SECRETS_BACKEND_ENV).unwrap_or_default();
+    match backend.trim().to_ascii_lowercase().as_str() {
+        "" | "env" => Ok(Box::new(EnvSecrets)),
+        "file" => {
+            let dir = std::env::var(SECRETS_DIR_ENV).unwrap_or_else(|_| DEFAULT_SECRETS_DIR.to_string());
+            Ok(Box::new(FileSecrets::new(dir)))
+        }
+        "vault" | "aws_kms" => Err(anyhow!("The {} secrets backend is not available yet, use env or file", backend)),
+        _ => Err(anyhow!("Unknown {}: {} (expected env or file)", SECRETS_BACKEND_ENV, backend)),
+    }
+}
+
+// Choose the secrets backend, failing on a backend that is unknown or not available so the server
+// does not start without its secrets. Call before anything reads a secret.
+pub fn init() -> Result<()> {
+    let provider = configured_provider()?;
+    info!("Reading secrets from the {} backend", provider.name());
+    let _ = PROVIDER.set(provider);
+    Ok(())
+}
+
+// Backend secrets are read from. Binaries that skip `init` read the environment.
+pub fn provider() -> &'static dyn SecretsProvider {
+    PROVIDER
+        .get_or_init(|| {
+            configured_provider().unwrap_or_else(|err| {
+                warn!("{}, reading secrets from the environment", err);
+                Box::new(EnvSecrets)
+            })
+        })
+        .as_ref()
+}
+
+// Value of a secret from the configured backend. Backend failures are logged and treated as missing.
+pub fn get_secret(name: &str) -> Option<String> {
+    match provider().get_secret(name) {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Failed to read secret {} from the {} backend: {}", name, provider().name(), err);
+            None
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::models::{AppState, SlippageBucket, SlippageSample, SlippageStats, SlippageStatsQuery, SwapRequest, SwapResponse};
+use crate::price;
+use crate::tenant;
+use crate::utils;
+use crate::wallet::KnownTokens;
+use anyhow::Result;
+use chrono::{FixedOffset, Timelike};
+use std::collections::BTreeMap;
+
+// Executions kept for the statistics, oldest dropped first
+const MAX_SLIPPAGE_SAMPLES: usize = 5000;
+
+// Upper bounds of the order size buckets in USD, with their labels. Swaps without a price for
+// their source token go in their own bucket.
+const SIZE_BUCKETS: &[(f64, &str)] = &[
+    (100.0, "<$100"),
+    (1_000.0, "$100-$1k"),
+    (10_000.0, "$1k-$10k"),
+    (100_000.0, "$10k-$100k"),
+    (f64::INFINITY, ">=$100k"),
+];
+const UNPRICED_BUCKET: &str = "unpriced";
+
+// Keep the quote against the fill of a confirmed swap. Swaps whose fill could not be read from
+// the transaction are left out rather than counted at their quote.
+pub fn record_execution(app_state: &AppState, wallet: &str, request: &SwapRequest, response: &SwapResponse) {
+    let filled_amount = match response.filled_target_amount {
+        Some(filled_amount) if response.success && !response.simulated && response.target_amount > 0.0 => filled_amount,
+        _ => return,
+    };
+
+    let notional_usd = price::get_token_price(app_state, &request.source_token)
+        .ok()
+        .map(|price| price * response.source_amount);
+    let api_key = app_state.wallet_owners.lock().unwrap().get(wallet).cloned();
+
+    let sample = SlippageSample {
+        transaction_signature: response.transaction_signature.clone(),
+        wallet: wallet.to_string(),
+        api_key,
+        source_token: request.source_token.clone(),
+        target_token: request.target_token.clone(),
+        source_amount: response.source_amount,
+        notional_usd,
+        quoted_amount: response.target_amount,
+        filled_amount,
+        slippage_bps: (response.target_amount - filled_amount) / response.target_amount * 10_000.0,
+        tolerance_bps: request.slippage.unwrap_or(0.5) * 100.0,
+        priority_fee_sol: response.fee_details.priority_fee_sol,
+        executed_at: response.timestamp,
+    };
+
+    let mut samples = app_state.slippage_samples.lock().unwrap();
+    if samples.len() >= MAX_SLIPPAGE_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize(bucket: String, symbol: Option<String>, samples: &[&SlippageSample]) -> SlippageBucket {
+    let mut slippages = samples.iter().map(|sample| sample.slippage_bps).collect::<Vec<_>>();
+    slippages.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let executions = samples.len() as f64;
+
+    SlippageBucket {
+        bucket,
+        symbol,
+        executions: samples.len(),
+        mean_bps: slippages.iter().sum::<f64>() / executions,
+        p50_bps: percentile(&slippages, 50.0),
+        p90_bps: percentile(&slippages, 90.0),
+        p99_bps: percentile(&slippages, 99.0),
+        max_bps: slippages[slippages.len() - 1],
+        near_tolerance: samples.iter().filter(|sample| sample.slippage_bps > sample.tolerance_bps / 2.0).count(),
+        average_priority_fee_sol: samples.iter().map(|sample| sample.priority_fee_sol).sum::<f64>() / executions,
+    }
+}
+
+fn size_bucket(sample: &SlippageSample) -> &'static str {
+    match sample.notional_usd {
+        Some(notional_usd) => SIZE_BUCKETS
+            .iter()
+            .find(|(upper, _)| notional_usd < *upper)
+            .map(|(_, label)| *label)
+            .unwrap_or(UNPRICED_BUCKET),
+        None => UNPRICED_BUCKET,
+    }
+}
+
+// Slippage of the tenant's recent executions overall and by token, hour of the day and order size
+pub fn get_slippage_stats(app_state: &AppState, tenant: &str, query: &SlippageStatsQuery) -> Result<SlippageStats> {
+    let offset = match query.tz.as_deref() {
+        Some(tz) => utils::parse_timezone(tz)?,
+        None => FixedOffset::east_opt(0).unwrap(),
+    };
+
+    let all_samples = app_state.slippage_samples.lock().unwrap().clone();
+    let samples = all_samples
+        .iter()
+        .filter(|sample| tenant::is_admin(tenant) || tenant::can_access(tenant, sample.api_key.as_deref()))
+        .filter(|sample| query.since.map(|since| sample.executed_at >= since).unwrap_or(true))
+        .filter(|sample| query.until.map(|until| sample.executed_at < until).unwrap_or(true))
+        .filter(|sample| match &query.token {
+            Some(token) => &sample.source_token == token || &sample.target_token == token,
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    if samples.is_empty() {
+        return Ok(SlippageStats {
+            executions: 0,
+            overall: None,
+            by_token: Vec::new(),
+            by_hour: Vec::new(),
+            by_size: Vec::new(),
+        });
+    }
+
+    let mut by_token: BTreeMap<&str, Vec<&SlippageSample>> = BTreeMap::new();
+    let mut by_hour: BTreeMap<u32, Vec<&SlippageSample>> = BTreeMap::new();
+    let mut by_size: BTreeMap<usize, Vec<&SlippageSample>> = BTreeMap::new();
+    for sample in samples.iter().copied() {
+        by_token.entry(sample.source_token.as_str()).or_default().push(sample);
+        by_token.entry(sample.target_token.as_str()).or_default().push(sample);
+        by_hour.entry(sample.executed_at.with_timezone(&offset).hour()).or_default().push(sample);
+
+        // Keyed by position so the buckets come out smallest first, unpriced last
+        let label = size_bucket(sample);
+        let position = SIZE_BUCKETS.iter().position(|(_, bucket)| *bucket == label).unwrap_or(SIZE_BUCKETS.len());
+        by_size.entry(position).or_default().push(sample);
+    }
+
+    // Busiest tokens first
+    let mut by_token = by_token
+        .into_iter()
+        .map(|(mint, samples)| summarize(mint.to_string(), Some(KnownTokens::get_symbol(mint)), &samples))
+        .collect::<Vec<_>>();
+    by_token.sort_by_key(|bucket| std::cmp::Reverse(bucket.executions));
+
+    Ok(SlippageStats {
+        executions: samples.len(),
+        overall: Some(summarize("all".to_string(), None, &samples)),
+        by_token,
+        by_hour: by_hour
+            .into_iter()
+            .map(|(hour, samples)| summarize(format!("{:02}:00", hour), None, &samples))
+            .collect(),
+        by_size: by_size
+            .into_iter()
+            .map(|(position, samples)| {
+                let label = SIZE_BUCKETS.get(position).map(|(_, label)| *label).unwrap_or(UNPRICED_BUCKET);
+                summarize(label.to_string(), None, &samples)
+            })
+            .collect(),
+    })
+}
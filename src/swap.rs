@@ -1,20 +1,68 @@
-use crate::models::{SwapRequest, SwapResponse, Wallet};
+use crate::models::{AppState, DepthLevel, DepthResponse, ExecutionLatency, FeeBudgetExceeded, PaperFill, PriorityFee, QuoteRequest, SwapQuotePreview, SwapRequest, SwapResponse, SwapRouteRequest, SwapRouteResponse, TransactionFee, Wallet};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
-use tracing::info;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance};
+use tracing::{info, warn};
 use solana_sdk::{
-    transaction::Transaction,
+    address_lookup_table::state::AddressLookupTable,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
     commitment_config::CommitmentConfig,
 };
 
-// Jupiter API URLs
-const JUPITER_QUOTE_API_URL: &str = "https://quote-api.jup.ag/v4/quote";
-const JUPITER_SWAP_API_URL: &str = "https://quote-api.jup.ag/v4/swap";
+// Jupiter v6 API URLs
+const JUPITER_QUOTE_API_URL: &str = "https://quote-api.jup.ag/v6/quote";
+const JUPITER_SWAP_API_URL: &str = "https://quote-api.jup.ag/v6/swap";
 
-// Jupiter quote response
+// Priority fee Jupiter adds to swap transactions, in lamports; Jupiter picks one when unset
+const PRIORITY_FEE_ENV: &str = "JUPITER_PRIORITY_FEE_LAMPORTS";
+
+// Percentile of recent prioritization fees each strategy bids
+const LOW_PRIORITY_FEE_PERCENTILE: usize = 25;
+const AUTO_PRIORITY_FEE_PERCENTILE: usize = 50;
+const HIGH_PRIORITY_FEE_PERCENTILE: usize = 90;
+
+// Default input sizes for the depth ladder, in input token units
+pub const DEFAULT_DEPTH_SIZES: [f64; 4] = [0.1, 1.0, 10.0, 100.0];
+
+// Most sizes one depth ladder quotes, each one is a Jupiter request
+pub const MAX_DEPTH_SIZES: usize = 10;
+
+// How long a depth ladder is served from cache
+const DEPTH_CACHE_TTL_SECS: i64 = 15;
+
+// Base fee charged per signature, anything above it is priority fee
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+// How many times to look for the confirmed transaction before falling back to the estimate
+const FEE_CONFIRMATION_ATTEMPTS: u32 = 10;
+
+// Native SOL mint, received as lamports rather than into a token account
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// Commitment swaps are confirmed at: processed, confirmed (default) or finalized
+const CONFIRMATION_COMMITMENT_ENV: &str = "SWAP_CONFIRMATION_COMMITMENT";
+
+// How long to wait for a sent swap to reach that commitment, and how often to check
+const CONFIRMATION_TIMEOUT_SECS: u64 = 90;
+const CONFIRMATION_POLL_MS: u64 = 1000;
+
+// How many times an expired transaction is re-signed with a fresh blockhash
+const MAX_BLOCKHASH_REFRESHES: u32 = 2;
+
+// Accounts a transaction may lock on mainnet, counting the ones loaded from lookup tables
+const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+// Account cap Jupiter routes within when a swap transaction fails preflight
+const REQUOTE_MAX_ACCOUNTS: usize = 32;
+
+// Jupiter quote response. Fields we do not read are kept so the quote can be passed back to
+// /swap exactly as it was received.
 #[derive(Deserialize, Serialize, Debug)]
 pub struct JupiterQuoteResponse {
     #[serde(rename = "inputMint")]
@@ -29,12 +77,65 @@ pub struct JupiterQuoteResponse {
     route_plan: Vec<JupiterRoutePlan>,
     #[serde(rename = "otherAmountThreshold")]
     other_amount_threshold: String,
+    #[serde(rename = "platformFee", default)]
+    platform_fee: Option<JupiterPlatformFee>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct JupiterPlatformFee {
+    amount: String,
+    #[serde(rename = "feeBps")]
+    fee_bps: u16,
+}
+
+impl JupiterQuoteResponse {
+    // Quoted input amount in base units
+    pub fn in_amount_units(&self) -> Result<u64> {
+        self.in_amount
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid inAmount in quote: {}", e))
+    }
+    
+    // Quoted output amount in base units
+    pub fn out_amount_units(&self) -> Result<u64> {
+        self.out_amount
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid outAmount in quote: {}", e))
+    }
+    
+    // Least output the swap accepts at the quoted slippage, in base units
+    pub fn min_out_amount_units(&self) -> Result<u64> {
+        self.other_amount_threshold
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid otherAmountThreshold in quote: {}", e))
+    }
+    
+    // Price impact of the route in percent; Jupiter reports it as a fraction in a string
+    pub fn price_impact_percent(&self) -> f64 {
+        self.other
+            .get("priceImpactPct")
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.parse::<f64>().ok())
+            .map_or(0.0, |fraction| fraction * 100.0)
+    }
+    
+    // DEX label of every hop of the route
+    pub fn route_labels(&self) -> Vec<String> {
+        self.route_plan
+            .iter()
+            .map(|hop| hop.swap_info.label.clone())
+            .collect()
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct JupiterRoutePlan {
     #[serde(rename = "swapInfo")]
     swap_info: JupiterSwapInfo,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -46,17 +147,34 @@ pub struct JupiterSwapInfo {
     #[serde(rename = "outputMint")]
     output_mint: String,
     label: String,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
-// Jupiter swap request
+// Jupiter swap request, embedding the quote it executes
 #[derive(Serialize, Debug)]
-struct JupiterSwapRequest {
+struct JupiterSwapRequest<'a> {
     #[serde(rename = "quoteResponse")]
-    quote_response: String,
+    quote_response: &'a JupiterQuoteResponse,
     #[serde(rename = "userPublicKey")]
     user_public_key: String,
-    #[serde(rename = "wrapUnwrapSOL")]
-    wrap_unwrap_sol: bool,
+    #[serde(rename = "wrapAndUnwrapSol")]
+    wrap_and_unwrap_sol: bool,
+    #[serde(rename = "dynamicComputeUnitLimit")]
+    dynamic_compute_unit_limit: bool,
+    #[serde(flatten)]
+    priority_fee: JupiterPriorityFee,
+}
+
+// Priority fee settings of a Jupiter swap request, only one of which is sent
+#[derive(Serialize, Debug, Default)]
+struct JupiterPriorityFee {
+    // A lamport amount, or "auto" to let Jupiter choose
+    #[serde(rename = "prioritizationFeeLamports", skip_serializing_if = "Option::is_none")]
+    prioritization_fee_lamports: Option<serde_json::Value>,
+    // Price per compute unit, Jupiter adds the ComputeBudget instruction setting it
+    #[serde(rename = "computeUnitPriceMicroLamports", skip_serializing_if = "Option::is_none")]
+    compute_unit_price_micro_lamports: Option<u64>,
 }
 
 // Jupiter swap response
@@ -64,15 +182,345 @@ struct JupiterSwapRequest {
 struct JupiterSwapResponse {
     #[serde(rename = "swapTransaction")]
     swap_transaction: String,
+    #[serde(rename = "lastValidBlockHeight", default)]
+    last_valid_block_height: Option<u64>,
+    #[serde(rename = "prioritizationFeeLamports", default)]
+    prioritization_fee_lamports: Option<u64>,
+}
+
+// Slippage percentage in basis points, as Jupiter expects it
+fn slippage_bps(slippage_percent: f64) -> u64 {
+    (slippage_percent * 100.0).round().max(0.0) as u64
+}
+
+// Priority fee strategy of a swap that does not set one
+fn default_priority_fee() -> PriorityFee {
+    match std::env::var(PRIORITY_FEE_ENV).ok().and_then(|value| value.parse::<u64>().ok()) {
+        Some(lamports) => PriorityFee::Custom(lamports),
+        None => PriorityFee::Auto,
+    }
+}
+
+// Value at a percentile of fees sorted in ascending order
+fn fee_percentile(sorted_fees: &[u64], percentile: usize) -> u64 {
+    let index = (sorted_fees.len() - 1) * percentile / 100;
+    sorted_fees[index]
+}
+
+// Priority fee to ask Jupiter for. Percentile strategies are tuned to what recent transactions paid
+// to write the route's pool accounts, and left to Jupiter when the RPC node has no samples.
+async fn priority_fee_settings(
+    rpc_client: &RpcClient,
+    strategy: Option<PriorityFee>,
+    quote: &JupiterQuoteResponse,
+) -> JupiterPriorityFee {
+    let percentile = match strategy.unwrap_or_else(default_priority_fee) {
+        PriorityFee::Custom(lamports) => {
+            return JupiterPriorityFee {
+                prioritization_fee_lamports: Some(serde_json::Value::from(lamports)),
+                ..JupiterPriorityFee::default()
+            };
+        }
+        PriorityFee::Low => LOW_PRIORITY_FEE_PERCENTILE,
+        PriorityFee::Auto => AUTO_PRIORITY_FEE_PERCENTILE,
+        PriorityFee::High => HIGH_PRIORITY_FEE_PERCENTILE,
+    };
+    let jupiter_auto = JupiterPriorityFee {
+        prioritization_fee_lamports: Some(serde_json::Value::from("auto")),
+        ..JupiterPriorityFee::default()
+    };
+
+    let accounts = quote
+        .route_plan
+        .iter()
+        .filter_map(|hop| hop.swap_info.amm_key.parse::<Pubkey>().ok())
+        .collect::<Vec<_>>();
+    let mut fees = match rpc_client.get_recent_prioritization_fees(&accounts).await {
+        Ok(samples) => samples.into_iter().map(|sample| sample.prioritization_fee).collect::<Vec<_>>(),
+        Err(err) => {
+            warn!("Failed to fetch recent prioritization fees, leaving the priority fee to Jupiter: {}", err);
+            return jupiter_auto;
+        }
+    };
+    if fees.is_empty() {
+        return jupiter_auto;
+    }
+    fees.sort_unstable();
+
+    let micro_lamports = fee_percentile(&fees, percentile);
+    info!(
+        "Bidding {} micro-lamports per compute unit, the {}th percentile of {} recent prioritization fees",
+        micro_lamports,
+        percentile,
+        fees.len()
+    );
+    JupiterPriorityFee {
+        compute_unit_price_micro_lamports: Some(micro_lamports),
+        ..JupiterPriorityFee::default()
+    }
+}
+
+// Commitment a swap must reach before it is reported as successful
+fn confirmation_commitment() -> CommitmentConfig {
+    match std::env::var(CONFIRMATION_COMMITMENT_ENV).as_deref() {
+        Ok("processed") => CommitmentConfig::processed(),
+        Ok("finalized") => CommitmentConfig::finalized(),
+        _ => CommitmentConfig::confirmed(),
+    }
+}
+
+// Where a sent transaction ended up
+pub enum Confirmation {
+    // Reached the configured commitment
+    Landed,
+    // Landed, but an instruction failed
+    Failed(TransactionError),
+    // Not seen at the configured commitment before the timeout; it may still land
+    Pending,
+    // Its blockhash expired without it landing, so it never will
+    Expired,
+}
+
+// Poll a signature until it reaches the commitment, fails, expires or the timeout passes. The
+// transaction is already sent, so RPC errors while polling only leave its outcome unknown: they
+// are polled through and end as Pending, never as an error that could get the swap sent again.
+async fn await_confirmation(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+    last_valid_block_height: Option<u64>,
+) -> Confirmation {
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(CONFIRMATION_TIMEOUT_SECS);
+    
+    loop {
+        match rpc_client.get_signature_statuses(&[*signature]).await {
+            Ok(statuses) => {
+                if let Some(Some(status)) = statuses.value.first() {
+                    if let Some(err) = &status.err {
+                        return Confirmation::Failed(err.clone());
+                    }
+                    if status.satisfies_commitment(commitment) {
+                        return Confirmation::Landed;
+                    }
+                }
+                
+                if let Some(last_valid_block_height) = last_valid_block_height {
+                    match rpc_client.get_block_height().await {
+                        Ok(block_height) if block_height > last_valid_block_height => return Confirmation::Expired,
+                        Ok(_) => {}
+                        Err(err) => warn!("Failed to fetch the block height while confirming {}: {}", signature, err),
+                    }
+                }
+            }
+            Err(err) => warn!("Failed to fetch the status of transaction {}: {}", signature, err),
+        }
+        
+        if tokio::time::Instant::now() >= deadline {
+            return Confirmation::Pending;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(CONFIRMATION_POLL_MS)).await;
+    }
+}
+
+// Look up a sent swap that was not confirmed in time, searching the transaction history since
+// it may have landed long ago. Once it has, the response is marked successful and gets the amount
+// the wallet received when the balance changes show it.
+pub async fn recheck_swap(
+    rpc_client: &RpcClient,
+    owner: &str,
+    target_mint: &str,
+    swap_result: &mut SwapResponse,
+) -> Result<Confirmation> {
+    let signature = swap_result.transaction_signature.parse::<Signature>()?;
+    let statuses = rpc_client.get_signature_statuses_with_history(&[signature]).await?.value;
+    let status = match statuses.into_iter().next().flatten() {
+        Some(status) => status,
+        None => return Ok(Confirmation::Pending),
+    };
+    if let Some(err) = status.err {
+        return Ok(Confirmation::Failed(err));
+    }
+    if !status.satisfies_commitment(confirmation_commitment()) {
+        return Ok(Confirmation::Pending);
+    }
+    
+    swap_result.success = true;
+    swap_result.latency.confirmed_at = Some(Utc::now());
+    if let Some((_, filled_target_amount)) = fetch_confirmed_swap(rpc_client, &signature, owner, target_mint).await {
+        swap_result.filled_target_amount = filled_target_amount;
+    }
+    Ok(Confirmation::Landed)
+}
+
+// Sign and send a transaction, then wait for it to land. A transaction whose blockhash is unknown
+// or expired is re-signed with a fresh blockhash and sent again. With MEV protection it is only
+// ever sent to the Jito block engine, never to the RPC node.
+async fn send_and_confirm(
+    rpc_client: &RpcClient,
+    wallet: &Wallet,
+    unsigned: VersionedTransaction,
+    mut last_valid_block_height: Option<u64>,
+    mev_protection: bool,
+    latency: &mut ExecutionLatency,
+) -> Result<(VersionedTransaction, Signature, Confirmation)> {
+    let commitment = confirmation_commitment();
+    let mut message = unsigned.message;
+    
+    for attempt in 0..=MAX_BLOCKHASH_REFRESHES {
+        if attempt > 0 {
+            let (blockhash, last_valid) = rpc_client
+                .get_latest_blockhash_with_commitment(commitment)
+                .await
+                .map_err(|e| anyhow!("Failed to refresh blockhash: {}", e))?;
+            message.set_recent_blockhash(blockhash);
+            last_valid_block_height = Some(last_valid);
+            info!("Re-signing transaction with blockhash {} (attempt {})", blockhash, attempt + 1);
+        }
+        
+        let transaction = VersionedTransaction::try_new(message.clone(), &[&wallet.keypair])
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
+        latency.signed_at = Some(Utc::now());
+        
+        #[cfg(feature = "chaos")]
+        crate::chaos::rpc_fault("send_transaction")?;
+        let signature = if mev_protection {
+            info!("Sending transaction to the Jito block engine");
+            crate::jito::send_bundle(wallet, &transaction).await?;
+            transaction.signatures[0]
+        } else {
+            info!("Sending transaction to the network");
+            match rpc_client.send_transaction(&transaction).await {
+                Ok(signature) => signature,
+                Err(err) if err.get_transaction_error() == Some(TransactionError::BlockhashNotFound) => {
+                    warn!("Blockhash of the swap transaction is not known to the cluster");
+                    continue;
+                }
+                Err(err) => return Err(anyhow!("Failed to send transaction: {}", err)),
+            }
+        };
+        latency.submitted_at = Some(Utc::now());
+        info!("Transaction sent with signature: {}", signature);
+        
+        match await_confirmation(rpc_client, &signature, commitment, last_valid_block_height).await {
+            Confirmation::Expired => {
+                warn!("Blockhash of transaction {} expired before it landed", signature);
+                continue;
+            }
+            confirmation => return Ok((transaction, signature, confirmation)),
+        }
+    }
+    
+    Err(anyhow!("Swap transaction did not land after {} blockhash refreshes", MAX_BLOCKHASH_REFRESHES))
+}
+
+// Fee actually charged by a landed transaction, in lamports, and the target tokens the wallet
+// received from it when its balance changes show them
+async fn fetch_confirmed_swap(rpc_client: &RpcClient, signature: &Signature, owner: &str, target_mint: &str) -> Option<(u64, Option<f64>)> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    
+    for _ in 0..FEE_CONFIRMATION_ATTEMPTS {
+        if let Ok(transaction) = rpc_client.get_transaction_with_config(signature, config).await {
+            if let Some(meta) = transaction.transaction.meta {
+                return Some((meta.fee, received_amount(&meta, owner, target_mint)));
+            }
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    }
+    
+    None
+}
+
+// Tokens of a mint an owner gained in a transaction. SOL also counts the owner's lamports, with
+// the fee added back; swaps pay it from the fee payer, which is the wallet.
+fn received_amount(meta: &UiTransactionStatusMeta, owner: &str, mint: &str) -> Option<f64> {
+    let held = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| -> Option<f64> {
+        let balances: Option<&Vec<UiTransactionTokenBalance>> = balances.as_ref().into();
+        let total = balances?
+            .iter()
+            .filter(|balance| balance.mint == mint)
+            .filter(|balance| Option::<&String>::from(balance.owner.as_ref()).map(|balance_owner| balance_owner == owner).unwrap_or(false))
+            .map(|balance| balance.ui_token_amount.ui_amount.unwrap_or(0.0))
+            .sum();
+        Some(total)
+    };
+    
+    let mut received = held(&meta.post_token_balances)? - held(&meta.pre_token_balances)?;
+    if mint == SOL_MINT {
+        if let (Some(pre), Some(post)) = (meta.pre_balances.first(), meta.post_balances.first()) {
+            let lamports = *post as i128 - *pre as i128 + meta.fee as i128;
+            received += lamports as f64 / 1e9;
+        }
+    }
+    
+    (received > 0.0).then_some(received)
 }
 
-// Get a swap quote from Jupiter Aggregator
+// Split the fees of a swap into network, priority, platform and rent components
+fn build_transaction_fee(
+    confirmed_fee: Option<u64>,
+    signature_count: usize,
+    estimated_fee: f64,
+    rent_sol: f64,
+    jito_tip_lamports: u64,
+    platform_fee: Option<&JupiterPlatformFee>,
+    target_token: &str,
+) -> Result<TransactionFee> {
+    let jito_tip_sol = crate::utils::lamports_to_sol(jito_tip_lamports);
+    let base_fee = LAMPORTS_PER_SIGNATURE * signature_count as u64;
+    let (network_fee_sol, priority_fee_sol) = match confirmed_fee {
+        Some(fee) => (
+            crate::utils::lamports_to_sol(fee.min(base_fee)),
+            crate::utils::lamports_to_sol(fee.saturating_sub(base_fee)),
+        ),
+        None => (estimated_fee, 0.0),
+    };
+    
+    let platform_fee_amount = match platform_fee {
+        Some(platform_fee) => platform_fee.amount.parse::<f64>()? / 10f64.powi(
+            crate::wallet::KnownTokens::get_decimals(target_token)?,
+        ),
+        None => 0.0,
+    };
+    
+    Ok(TransactionFee {
+        network_fee_sol,
+        priority_fee_sol,
+        platform_fee: platform_fee_amount,
+        platform_fee_mint: platform_fee.map(|_| target_token.to_string()),
+        rent_sol,
+        jito_tip_sol,
+        total_sol: network_fee_sol + priority_fee_sol + rent_sol + jito_tip_sol,
+        total_usd: None,
+        confirmed: confirmed_fee.is_some(),
+    })
+}
+
+// Get a swap quote from Jupiter Aggregator, with slippage in percent
 pub async fn get_swap_quote(
     source_token: &str,
     target_token: &str,
     amount: u64,
     slippage: f64,
 ) -> Result<JupiterQuoteResponse> {
+    get_swap_quote_excluding(source_token, target_token, amount, slippage, &[], None).await
+}
+
+// Get a swap quote that avoids the given DEX labels, optionally routed within `max_accounts`
+pub async fn get_swap_quote_excluding(
+    source_token: &str,
+    target_token: &str,
+    amount: u64,
+    slippage: f64,
+    excluded_labels: &[String],
+    max_accounts: Option<usize>,
+) -> Result<JupiterQuoteResponse> {
+    #[cfg(feature = "chaos")]
+    crate::chaos::delay_quote().await;
+    
     let client = Client::new();
     
     // Build URL
@@ -82,14 +530,22 @@ pub async fn get_swap_quote(
         source_token,
         target_token,
         amount,
-        (slippage * 100.0) as u64
+        slippage_bps(slippage)
     );
     
     info!("Getting swap quote from Jupiter: {}", url);
     
+    // Labels contain spaces, so let the query builder encode them
+    let mut request = client.get(&url);
+    if !excluded_labels.is_empty() {
+        request = request.query(&[("excludeDexes", excluded_labels.join(","))]);
+    }
+    if let Some(max_accounts) = max_accounts {
+        request = request.query(&[("maxAccounts", max_accounts)]);
+    }
+    
     // Send request with error handling
-    let response = client
-        .get(&url)
+    let response = request
         .send()
         .await
         .map_err(|e| anyhow!("Failed to send request to Jupiter API: {}", e))?;
@@ -110,25 +566,330 @@ pub async fn get_swap_quote(
     Ok(quote)
 }
 
+// Get a quote whose route avoids the route denylist. A quote routed through a denied DEX is
+// rejected and requoted without the denied DEXes, returning the labels that were hit.
+pub async fn get_allowed_quote(
+    app_state: &AppState,
+    source_token: &str,
+    target_token: &str,
+    amount: u64,
+    slippage: f64,
+    max_accounts: Option<usize>,
+) -> Result<(JupiterQuoteResponse, Vec<String>)> {
+    let excluded = crate::denylist::excluded_labels(app_state);
+    let quote = get_swap_quote_excluding(source_token, target_token, amount, slippage, &[], max_accounts).await?;
+    
+    let denied = crate::denylist::denied_labels(&quote.route_labels(), &excluded);
+    if denied.is_empty() {
+        return Ok((quote, Vec::new()));
+    }
+    
+    warn!(
+        "Rejected quote {} -> {} routed through denylisted {}, requoting without them",
+        crate::wallet::KnownTokens::get_symbol(source_token),
+        crate::wallet::KnownTokens::get_symbol(target_token),
+        denied.join(", ")
+    );
+    
+    let requote = get_swap_quote_excluding(source_token, target_token, amount, slippage, &excluded, max_accounts).await?;
+    let still_denied = crate::denylist::denied_labels(&requote.route_labels(), &excluded);
+    if !still_denied.is_empty() {
+        return Err(anyhow!("No route avoids the denylisted {}", still_denied.join(", ")));
+    }
+    
+    Ok((requote, denied))
+}
+
+// Quote a swap for a confirmation screen, without building, signing or sending a transaction
+pub async fn preview_swap(app_state: &AppState, request: &QuoteRequest) -> Result<SwapQuotePreview> {
+    crate::utils::validate_amount(request.amount)?;
+    let slippage = request.slippage.unwrap_or(0.5);
+    crate::utils::validate_slippage(slippage)?;
+    
+    let source_decimals = crate::wallet::KnownTokens::get_decimals(&request.source_token)?;
+    let target_decimals = crate::wallet::KnownTokens::get_decimals(&request.target_token)?;
+    let amount = crate::utils::ui_amount_to_token_amount(request.amount, source_decimals as u8);
+    
+    // Quote the way an executed swap would, around the route denylist
+    let (quote, route_exclusions) = get_allowed_quote(
+        app_state,
+        &request.source_token,
+        &request.target_token,
+        amount,
+        slippage,
+        None,
+    )
+    .await?;
+    
+    let input_amount = quote.in_amount_units()? as f64 / 10f64.powi(source_decimals);
+    let expected_output = quote.out_amount_units()? as f64 / 10f64.powi(target_decimals);
+    let minimum_output = quote.min_out_amount_units()? as f64 / 10f64.powi(target_decimals);
+    
+    Ok(SwapQuotePreview {
+        source_token: request.source_token.clone(),
+        target_token: request.target_token.clone(),
+        input_amount,
+        expected_output,
+        minimum_output,
+        price: if input_amount > 0.0 { expected_output / input_amount } else { 0.0 },
+        price_impact_percent: quote.price_impact_percent(),
+        slippage,
+        route_labels: quote.route_labels(),
+        route_exclusions,
+        quoted_at: Utc::now(),
+    })
+}
+
+// Quote a ladder of input sizes to approximate depth and price impact
+pub async fn get_depth(
+    app_state: &AppState,
+    input: &str,
+    output: &str,
+    sizes: &[f64],
+) -> Result<DepthResponse> {
+    let sizes = depth_sizes(sizes)?;
+    // The same ladder in any order, or with repeats, shares one entry
+    let cache_key = format!("{}:{}:{:?}", input, output, sizes);
+    
+    // Serve recent ladders from cache to avoid hammering Jupiter
+    {
+        let cache = app_state.depth_cache.lock().unwrap();
+        if let Some(depth) = cache.get(&cache_key) {
+            if Utc::now() - depth.fetched_at < chrono::Duration::seconds(DEPTH_CACHE_TTL_SECS) {
+                return Ok(depth.clone());
+            }
+        }
+    }
+    
+    let input_decimals = crate::wallet::KnownTokens::get_decimals(input)?;
+    let output_decimals = crate::wallet::KnownTokens::get_decimals(output)?;
+    
+    let mut levels: Vec<DepthLevel> = Vec::new();
+    
+    for size in sizes {
+        let amount = (size * 10f64.powi(input_decimals)) as u64;
+        let quote = get_swap_quote(input, output, amount, 0.5).await?;
+        
+        let output_amount = quote.out_amount_units()? as f64 / 10f64.powi(output_decimals);
+        let effective_price = output_amount / size;
+        
+        // Impact is measured against the smallest size on the ladder
+        let price_impact_percent = match levels.first() {
+            Some(best) if best.effective_price > 0.0 => {
+                (best.effective_price - effective_price) / best.effective_price * 100.0
+            }
+            _ => 0.0,
+        };
+        
+        levels.push(DepthLevel {
+            input_amount: size,
+            output_amount,
+            effective_price,
+            price_impact_percent,
+        });
+    }
+    
+    let depth = DepthResponse {
+        input: input.to_string(),
+        output: output.to_string(),
+        levels,
+        fetched_at: Utc::now(),
+    };
+    
+    // Drop expired ladders so requests for ever new sizes don't grow the cache without bound
+    let mut cache = app_state.depth_cache.lock().unwrap();
+    let now = Utc::now();
+    cache.retain(|_, cached| now - cached.fetched_at < chrono::Duration::seconds(DEPTH_CACHE_TTL_SECS));
+    cache.insert(cache_key, depth.clone());
+    
+    Ok(depth)
+}
+
+// Sort and dedupe the sizes of a depth ladder, and cap how many it has
+pub fn depth_sizes(sizes: &[f64]) -> Result<Vec<f64>> {
+    let mut sizes = sizes.to_vec();
+    sizes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sizes.dedup();
+    
+    if sizes.is_empty() {
+        return Err(anyhow!("At least one depth size is required"));
+    }
+    if sizes.len() > MAX_DEPTH_SIZES {
+        return Err(anyhow!("At most {} depth sizes can be quoted at once", MAX_DEPTH_SIZES));
+    }
+    
+    Ok(sizes)
+}
+
+// Ask Jupiter to build the unsigned swap transaction for a quote, with the last block height
+// its blockhash is valid for
+async fn build_swap_transaction(
+    client: &Client,
+    rpc_client: &RpcClient,
+    wallet: &Wallet,
+    quote: &JupiterQuoteResponse,
+    priority_fee: Option<PriorityFee>,
+) -> Result<(VersionedTransaction, Option<u64>, u64)> {
+    let jupiter_swap_request = JupiterSwapRequest {
+        quote_response: quote,
+        user_public_key: wallet.pubkey.to_string(),
+        wrap_and_unwrap_sol: true, // Auto-wrap/unwrap SOL as needed
+        dynamic_compute_unit_limit: true,
+        priority_fee: priority_fee_settings(rpc_client, priority_fee, quote).await,
+    };
+    
+    // Get swap transaction
+    info!("Requesting swap transaction from Jupiter");
+    let swap_response = client
+        .post(JUPITER_SWAP_API_URL)
+        .json(&jupiter_swap_request)
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to request swap transaction: {}", e))?;
+    
+    // Check for HTTP errors
+    if !swap_response.status().is_success() {
+        let status = swap_response.status();
+        let error_text = swap_response.text().await.unwrap_or_else(|_| "Unable to get error details".to_string());
+        return Err(anyhow!("Jupiter API returned error status {}: {}", status, error_text));
+    }
+    
+    let jupiter_swap = swap_response
+        .json::<JupiterSwapResponse>()
+        .await
+        .map_err(|e| anyhow!("Failed to parse swap response: {}", e))?;
+    
+    info!(
+        "Jupiter built the swap with a {} lamport priority fee, valid until block height {}",
+        jupiter_swap.prioritization_fee_lamports.unwrap_or(0),
+        jupiter_swap.last_valid_block_height.map_or("unknown".to_string(), |height| height.to_string())
+    );
+    
+    // Decode the transaction
+    let transaction_data = base64::Engine::decode(
+        &base64::engine::general_purpose::STANDARD,
+        &jupiter_swap.swap_transaction
+    ).map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+    
+    // v6 returns versioned transactions that may use address lookup tables
+    let transaction = bincode::deserialize(&transaction_data)
+        .map_err(|e| anyhow!("Failed to deserialize transaction: {}", e))?;
+    
+    Ok((
+        transaction,
+        jupiter_swap.last_valid_block_height,
+        jupiter_swap.prioritization_fee_lamports.unwrap_or(0),
+    ))
+}
+
+// Check a swap transaction against the cluster's limits before signing it: its serialized size,
+// and the number of accounts it locks once its address lookup tables are resolved
+async fn preflight_transaction(rpc_client: &RpcClient, transaction: &VersionedTransaction) -> Result<()> {
+    // Jupiter leaves zeroed signatures in place, so the size is the size of the signed transaction
+    let size = bincode::serialized_size(transaction)? as usize;
+    if size > PACKET_DATA_SIZE {
+        return Err(anyhow!("transaction is {} bytes, the limit is {}", size, PACKET_DATA_SIZE));
+    }
+    
+    let lookups = transaction.message.address_table_lookups().unwrap_or(&[]);
+    let mut account_count = transaction.message.static_account_keys().len();
+    
+    if !lookups.is_empty() {
+        let table_keys = lookups.iter().map(|lookup| lookup.account_key).collect::<Vec<_>>();
+        let tables = rpc_client
+            .get_multiple_accounts(&table_keys)
+            .await
+            .map_err(|e| anyhow!("failed to load address lookup tables: {}", e))?;
+        
+        for (lookup, account) in lookups.iter().zip(tables) {
+            let account = account.ok_or_else(|| anyhow!("address lookup table {} does not exist", lookup.account_key))?;
+            let table = AddressLookupTable::deserialize(&account.data)
+                .map_err(|e| anyhow!("invalid address lookup table {}: {}", lookup.account_key, e))?;
+            
+            let indexes = lookup.writable_indexes.iter().chain(&lookup.readonly_indexes);
+            if let Some(index) = indexes.clone().find(|index| **index as usize >= table.addresses.len()) {
+                return Err(anyhow!(
+                    "address lookup table {} has no entry {}",
+                    lookup.account_key, index
+                ));
+            }
+            account_count += indexes.count();
+        }
+    }
+    
+    if account_count > MAX_TRANSACTION_ACCOUNTS {
+        return Err(anyhow!("transaction locks {} accounts, the limit is {}", account_count, MAX_TRANSACTION_ACCOUNTS));
+    }
+    
+    Ok(())
+}
+
+// Network and priority fees plus the value lost to price impact, in USD, refused past the budget
+fn check_fee_budget(
+    app_state: &AppState,
+    swap_request: &SwapRequest,
+    quote: &JupiterQuoteResponse,
+    network_fee_sol: f64,
+    priority_fee_lamports: u64,
+    max_fee_usd: f64,
+) -> Result<()> {
+    let sol_price = crate::price::get_token_price(app_state, "So11111111111111111111111111111111111111112")
+        .map_err(|e| anyhow!("Cannot check the fee budget: {}", e))?;
+    let source_price = crate::price::get_token_price(app_state, &swap_request.source_token)
+        .map_err(|e| anyhow!("Cannot check the fee budget: {}", e))?;
+    
+    let network_fee_usd = network_fee_sol * sol_price;
+    let priority_fee_usd = crate::utils::lamports_to_sol(priority_fee_lamports) * sol_price;
+    let price_impact_usd = swap_request.amount * source_price * quote.price_impact_percent() / 100.0;
+    let total_usd = network_fee_usd + priority_fee_usd + price_impact_usd;
+    
+    if total_usd > max_fee_usd {
+        return Err(FeeBudgetExceeded {
+            max_fee_usd,
+            network_fee_usd,
+            priority_fee_usd,
+            price_impact_usd,
+            total_usd,
+        }
+        .into());
+    }
+    
+    info!("Swap fees of ${:.4} are within the budget of ${:.4}", total_usd, max_fee_usd);
+    Ok(())
+}
+
 // Execute a swap using Jupiter Aggregator
 pub async fn execute_swap(
+    app_state: &AppState,
     wallet: &Wallet,
     swap_request: &SwapRequest,
 ) -> Result<SwapResponse> {
+    execute_swap_with_fee_budget(app_state, wallet, swap_request, None).await
+}
+
+// Execute a swap, refusing to send it if its fees at the fresh quote exceed `max_fee_usd`
+pub async fn execute_swap_with_fee_budget(
+    app_state: &AppState,
+    wallet: &Wallet,
+    swap_request: &SwapRequest,
+    max_fee_usd: Option<f64>,
+) -> Result<SwapResponse> {
+    if swap_request.dry_run || crate::paper::simulation_mode() {
+        return simulate_swap(app_state, wallet, swap_request).await;
+    }
+    
     let client = Client::new();
-    let rpc_client = RpcClient::new_with_commitment(
-        crate::wallet::get_rpc_url(),
-        CommitmentConfig::confirmed(),
-    );
+    let rpc_client = &app_state.rpc_client;
     
     // Estimate transaction fees
-    let estimated_fee = crate::wallet::estimate_transaction_fees().await
+    let estimated_fee = crate::wallet::estimate_transaction_fees(rpc_client).await
         .unwrap_or(0.01); // Default to 0.01 SOL if estimation fails
     
     info!("Estimated transaction fee for swap: {} SOL", estimated_fee);
     
     // Check if the wallet has sufficient SOL for transaction fees
     let has_sol = crate::wallet::has_sufficient_balance(
+        rpc_client,
         wallet,
         "So11111111111111111111111111111111111111112",
         estimated_fee
@@ -140,7 +901,8 @@ pub async fn execute_swap(
     
     // Check if the wallet has sufficient balance of the source token
     let has_balance = crate::wallet::has_sufficient_balance(
-        wallet, 
+        rpc_client,
+        wallet,
         &swap_request.source_token,
         swap_request.amount
     ).await?;
@@ -150,93 +912,323 @@ pub async fn execute_swap(
                  crate::wallet::KnownTokens::get_symbol(&swap_request.source_token)));
     }
     
+    // Rent is only paid if the swap has to create the target token account
+    let rent_sol = match crate::wallet::needs_token_account(rpc_client, &wallet.pubkey, &swap_request.target_token).await {
+        Ok(true) => crate::wallet::ATA_RENT_SOL,
+        _ => 0.0,
+    };
+    
     // Convert amount based on decimals
     let source_token_decimals = crate::wallet::KnownTokens::get_decimals(&swap_request.source_token)?;
     let amount_lamports = (swap_request.amount * 10f64.powi(source_token_decimals as i32)) as u64;
     
     // Get slippage or use default
-    let slippage = swap_request.slippage.unwrap_or(0.5) / 100.0; // Convert to percentage
+    let slippage = swap_request.slippage.unwrap_or(0.5);
     
     // Get quote
-    let quote = get_swap_quote(
+    let (mut quote, mut route_exclusions) = get_allowed_quote(
+        app_state,
         &swap_request.source_token,
         &swap_request.target_token,
         amount_lamports,
         slippage,
+        None,
     )
     .await?;
+    let mut latency = ExecutionLatency {
+        quote_received_at: Some(Utc::now()),
+        ..ExecutionLatency::default()
+    };
     
     info!("Got swap quote for {} {} to {}", 
           swap_request.amount, 
           crate::wallet::KnownTokens::get_symbol(&swap_request.source_token),
           crate::wallet::KnownTokens::get_symbol(&swap_request.target_token));
     
-    // Serialize quote to string for the swap request
-    let quote_json = serde_json::to_string(&quote)
-        .map_err(|e| anyhow!("Failed to serialize quote to JSON: {}", e))?;
-    
-    // Build swap request
-    let jupiter_swap_request = JupiterSwapRequest {
-        quote_response: quote_json,
-        user_public_key: wallet.pubkey.to_string(),
-        wrap_unwrap_sol: true, // Auto-wrap/unwrap SOL as needed
-    };
-    
-    // Get swap transaction
-    info!("Requesting swap transaction from Jupiter");
-    let swap_response = client
-        .post(JUPITER_SWAP_API_URL)
-        .json(&jupiter_swap_request)
-        .send()
-        .await
-        .map_err(|e| anyhow!("Failed to request swap transaction: {}", e))?;
+    let (mut unsigned, mut last_valid_block_height, mut priority_fee_lamports) =
+        build_swap_transaction(&client, rpc_client, wallet, &quote, swap_request.priority_fee).await?;
     
-    // Check for HTTP errors
-    if !swap_response.status().is_success() {
-        let status = swap_response.status();
-        let error_text = swap_response.text().await.unwrap_or_else(|_| "Unable to get error details".to_string());
-        return Err(anyhow!("Jupiter API returned error status {}: {}", status, error_text));
+    // A transaction the cluster would reject is requoted over fewer accounts instead of being sent
+    if let Err(err) = preflight_transaction(rpc_client, &unsigned).await {
+        warn!(
+            "Swap transaction failed preflight ({}), requoting within {} accounts",
+            err, REQUOTE_MAX_ACCOUNTS
+        );
+        
+        let (requote, requote_exclusions) = get_allowed_quote(
+            app_state,
+            &swap_request.source_token,
+            &swap_request.target_token,
+            amount_lamports,
+            slippage,
+            Some(REQUOTE_MAX_ACCOUNTS),
+        )
+        .await?;
+        quote = requote;
+        latency.quote_received_at = Some(Utc::now());
+        route_exclusions.extend(requote_exclusions);
+        route_exclusions.sort();
+        route_exclusions.dedup();
+        
+        (unsigned, last_valid_block_height, priority_fee_lamports) = build_swap_transaction(&client, rpc_client, wallet, &quote, swap_request.priority_fee).await?;
+        preflight_transaction(rpc_client, &unsigned)
+            .await
+            .map_err(|e| anyhow!("Swap transaction still fails preflight after requoting: {}", e))?;
     }
     
-    let jupiter_swap = swap_response
-        .json::<JupiterSwapResponse>()
-        .await
-        .map_err(|e| anyhow!("Failed to parse swap response: {}", e))?;
-    
-    // Decode the transaction
-    info!("Decoding and signing transaction");
-    let transaction_data = base64::Engine::decode(
-        &base64::engine::general_purpose::STANDARD,
-        &jupiter_swap.swap_transaction
-    ).map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+    // The Jito tip is paid on top of the priority fee and counts against the budget with it
+    let jito_tip_lamports = if swap_request.mev_protection { crate::jito::tip_lamports() } else { 0 };
     
-    let mut transaction: Transaction = bincode::deserialize(&transaction_data)
-        .map_err(|e| anyhow!("Failed to deserialize transaction: {}", e))?;
+    // The priority fee is only known once Jupiter has built the transaction
+    if let Some(max_fee_usd) = max_fee_usd {
+        check_fee_budget(
+            app_state,
+            swap_request,
+            &quote,
+            estimated_fee,
+            priority_fee_lamports + jito_tip_lamports,
+            max_fee_usd,
+        )?;
+    }
     
-    // Sign the transaction
-    transaction.sign(&[&wallet.keypair], transaction.message.recent_blockhash);
+    // Sign, send and wait for the transaction to land, re-signing it if its blockhash expires
+    let (transaction, signature, confirmation) = send_and_confirm(
+        rpc_client,
+        wallet,
+        unsigned,
+        last_valid_block_height,
+        swap_request.mev_protection,
+        &mut latency,
+    )
+    .await?;
     
-    // Send the transaction
-    info!("Sending transaction to the network");
-    let signature = rpc_client
-        .send_transaction(&transaction)
-        .map_err(|e| anyhow!("Failed to send transaction: {}", e))?;
+    let confirmed = match confirmation {
+        Confirmation::Landed => {
+            latency.confirmed_at = Some(Utc::now());
+            true
+        }
+        Confirmation::Failed(err) => {
+            return Err(anyhow!("Swap transaction {} failed on chain: {}", signature, err));
+        }
+        Confirmation::Expired => {
+            return Err(anyhow!("Swap transaction {} expired before it landed", signature));
+        }
+        Confirmation::Pending => {
+            warn!("Transaction {} not confirmed in time, reporting it as unconfirmed", signature);
+            false
+        }
+    };
     
-    info!("Transaction sent with signature: {}", signature);
+    let (confirmed_fee, filled_target_amount) = if confirmed {
+        fetch_confirmed_swap(rpc_client, &signature, &wallet.pubkey.to_string(), &swap_request.target_token)
+            .await
+            .map(|(fee, filled_target_amount)| (Some(fee), filled_target_amount))
+            .unwrap_or((None, None))
+    } else {
+        (None, None)
+    };
+    if confirmed && confirmed_fee.is_none() {
+        warn!("Fee of transaction {} not available yet, reporting estimated fees", signature);
+    }
+    let fee_details = build_transaction_fee(
+        confirmed_fee,
+        transaction.signatures.len(),
+        estimated_fee,
+        rent_sol,
+        jito_tip_lamports,
+        quote.platform_fee.as_ref(),
+        &swap_request.target_token,
+    )?;
     
     // Parse amounts for response
     let source_amount = swap_request.amount;
     let target_amount = quote.out_amount.parse::<f64>()? / 10f64.powi(
         crate::wallet::KnownTokens::get_decimals(&swap_request.target_token)? as i32,
     );
+    #[cfg(feature = "chaos")]
+    let target_amount = crate::chaos::partial_fill(target_amount);
     
-    // Return the swap results
-    Ok(SwapResponse {
+    let response = SwapResponse {
         transaction_signature: signature.to_string(),
         source_amount,
+        source_amount_raw: amount_lamports.to_string(),
         target_amount,
-        fee: estimated_fee, // Include the estimated transaction fee
-        success: true,
+        target_amount_raw: quote.out_amount.clone(),
+        fee: fee_details.total_sol,
+        fee_lamports: crate::utils::sol_to_lamports(fee_details.total_sol).to_string(),
+        fee_details,
+        success: confirmed,
         timestamp: Utc::now(),
+        route_exclusions,
+        latency,
+        simulated: false,
+        filled_target_amount,
+    };
+    
+    // Keep the quote against the fill for the slippage statistics
+    crate::slippage::record_execution(app_state, &wallet.pubkey.to_string(), swap_request, &response);
+    Ok(response)
+}
+
+// Fill a swap at its Jupiter quote against the wallet's paper balances, without signing or sending anything
+async fn simulate_swap(app_state: &AppState, wallet: &Wallet, swap_request: &SwapRequest) -> Result<SwapResponse> {
+    let source_token_decimals = crate::wallet::KnownTokens::get_decimals(&swap_request.source_token)?;
+    let amount_lamports = (swap_request.amount * 10f64.powi(source_token_decimals)) as u64;
+    let slippage = swap_request.slippage.unwrap_or(0.5);
+    
+    let (quote, route_exclusions) = get_allowed_quote(
+        app_state,
+        &swap_request.source_token,
+        &swap_request.target_token,
+        amount_lamports,
+        slippage,
+        None,
+    )
+    .await?;
+    let latency = ExecutionLatency {
+        quote_received_at: Some(Utc::now()),
+        ..ExecutionLatency::default()
+    };
+    
+    let target_amount = quote.out_amount.parse::<f64>()? / 10f64.powi(
+        crate::wallet::KnownTokens::get_decimals(&swap_request.target_token)?,
+    );
+    let network_fee_sol = crate::utils::lamports_to_sol(LAMPORTS_PER_SIGNATURE);
+    let fee_details = build_transaction_fee(
+        None,
+        1,
+        network_fee_sol,
+        0.0,
+        0,
+        quote.platform_fee.as_ref(),
+        &swap_request.target_token,
+    )?;
+    
+    let signature = format!("simulated-{}", uuid::Uuid::new_v4());
+    let timestamp = Utc::now();
+    crate::paper::apply_fill(app_state, wallet, PaperFill {
+        signature: signature.clone(),
+        source_token: swap_request.source_token.clone(),
+        target_token: swap_request.target_token.clone(),
+        source_amount: swap_request.amount,
+        target_amount,
+        fee_sol: fee_details.total_sol,
+        timestamp,
     })
-}
\ No newline at end of file
+    .await?;
+    
+    Ok(SwapResponse {
+        transaction_signature: signature,
+        source_amount: swap_request.amount,
+        source_amount_raw: amount_lamports.to_string(),
+        target_amount,
+        target_amount_raw: quote.out_amount.clone(),
+        fee: fee_details.total_sol,
+        fee_lamports: crate::utils::sol_to_lamports(fee_details.total_sol).to_string(),
+        fee_details,
+        success: true,
+        timestamp,
+        route_exclusions,
+        latency,
+        simulated: true,
+        filled_target_amount: None,
+    })
+}
+
+// Execute a multi-leg swap as sequential swaps, stopping at the first failed leg
+pub async fn execute_swap_route(
+    app_state: &AppState,
+    wallet: &Wallet,
+    route_request: &SwapRouteRequest,
+) -> Result<SwapRouteResponse> {
+    if route_request.legs.is_empty() {
+        return Err(anyhow!("A route needs at least one leg"));
+    }
+    
+    let mut current_token = route_request.source_token.clone();
+    for leg in &route_request.legs {
+        if leg.target_token == current_token {
+            return Err(anyhow!("Route leg swaps {} into itself", current_token));
+        }
+        current_token = leg.target_token.clone();
+    }
+    
+    let mut completed_legs = Vec::new();
+    let mut current_token = route_request.source_token.clone();
+    let mut current_amount = route_request.amount;
+    
+    for (index, leg) in route_request.legs.iter().enumerate() {
+        let slippage = leg.slippage.unwrap_or(0.5);
+        let swap_request = SwapRequest {
+            source_token: current_token.clone(),
+            target_token: leg.target_token.clone(),
+            amount: current_amount,
+            amount_percent: None,
+            amount_usd: None,
+            slippage: Some(slippage),
+            wallet_pubkey: Some(wallet.pubkey.to_string()),
+            dry_run: false,
+            priority_fee: None,
+            mev_protection: false,
+        };
+        
+        info!(
+            "Route leg {}/{}: {} {} -> {}",
+            index + 1,
+            route_request.legs.len(),
+            current_amount,
+            crate::wallet::KnownTokens::get_symbol(&current_token),
+            crate::wallet::KnownTokens::get_symbol(&leg.target_token)
+        );
+        
+        match execute_swap(app_state, wallet, &swap_request).await {
+            // The next leg would spend tokens this one may never deliver, so the route stops here
+            Ok(result) if !result.success => {
+                warn!("Route leg {} was not confirmed in time, stopping the route", index + 1);
+                return Ok(SwapRouteResponse {
+                    completed_legs,
+                    failed_leg: Some(index),
+                    error: Some(format!(
+                        "Transaction {} was not confirmed in time, its outcome is unknown",
+                        result.transaction_signature
+                    )),
+                    unconfirmed_signature: Some(result.transaction_signature),
+                    complete: false,
+                    final_token: current_token,
+                    final_amount: current_amount,
+                });
+            }
+            Ok(result) => {
+                // Spend what this leg filled, or without a confirmed fill only what it is
+                // guaranteed to have delivered
+                current_amount = result
+                    .filled_target_amount
+                    .unwrap_or(result.target_amount * (1.0 - slippage / 100.0));
+                current_token = leg.target_token.clone();
+                completed_legs.push(result);
+            }
+            Err(err) => {
+                // Stop here; funds stay in the last token we reached
+                return Ok(SwapRouteResponse {
+                    completed_legs,
+                    failed_leg: Some(index),
+                    error: Some(err.to_string()),
+                    unconfirmed_signature: None,
+                    complete: false,
+                    final_token: current_token,
+                    final_amount: current_amount,
+                });
+            }
+        }
+    }
+    
+    Ok(SwapRouteResponse {
+        completed_legs,
+        failed_leg: None,
+        error: None,
+        unconfirmed_signature: None,
+        complete: true,
+        final_token: current_token,
+        final_amount: current_amount,
+    })
+}
@@ -0,0 +1,327 @@
+use crate::models::{AppState, NotificationLocale, NotificationTemplate, NotificationTemplateRequest};
+use anyhow::{anyhow, Result};
+use minijinja::Environment;
+use serde_json::Value;
+use tracing::{info, warn};
+
+// Language every notification has a built in template for, and the last fallback
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Built in notification templates by name and locale
+const BUILTIN_TEMPLATES: &[(&str, &str, &str)] = &[
+    (
+        "price_near_trigger",
+        "en",
+        "{{ order_type | order_type }} order {{ order_id }} is near its trigger: price {{ price }} vs target {{ target }}",
+    ),
+    (
+        "price_near_trigger",
+        "es",
+        "La orden {{ order_type | order_type }} {{ order_id }} está cerca de activarse: precio {{ price }} frente al objetivo {{ target }}",
+    ),
+    (
+        "price_near_trigger",
+        "de",
+        "{{ order_type | order_type }}-Order {{ order_id }} ist kurz vor dem Auslösen: Preis {{ price }} gegenüber Ziel {{ target }}",
+    ),
+    (
+        "order_executed",
+        "en",
+        "{{ order_type | order_type }} order {{ order_id }} executed ({{ action }}){% if fill_price %}: swapped {{ source_amount }} {{ source_symbol }} for {{ target_amount }} {{ target_symbol }} at {{ fill_price | round(6) }} {{ target_symbol }} per {{ source_symbol }}{% endif %}. Signature: {{ signature }}",
+    ),
+    (
+        "order_executed",
+        "es",
+        "Orden {{ order_type | order_type }} {{ order_id }} ejecutada ({{ action }}){% if fill_price %}: {{ source_amount }} {{ source_symbol }} por {{ target_amount }} {{ target_symbol }} a {{ fill_price | round(6) }} {{ target_symbol }} por {{ source_symbol }}{% endif %}. Firma: {{ signature }}",
+    ),
+    (
+        "order_executed",
+        "de",
+        "{{ order_type | order_type }}-Order {{ order_id }} ausgeführt ({{ action }}){% if fill_price %}: {{ source_amount }} {{ source_symbol }} gegen {{ target_amount }} {{ target_symbol }} zu {{ fill_price | round(6) }} {{ target_symbol }} je {{ source_symbol }}{% endif %}. Signatur: {{ signature }}",
+    ),
+    (
+        "order_triggered",
+        "en",
+        "{{ order_type | order_type }} order {{ order_id }} triggered at price {{ price }}, no trade was made ({{ action }})",
+    ),
+    (
+        "order_triggered",
+        "es",
+        "Orden {{ order_type | order_type }} {{ order_id }} activada al precio {{ price }}, no se realizó ninguna operación ({{ action }})",
+    ),
+    (
+        "order_triggered",
+        "de",
+        "{{ order_type | order_type }}-Order {{ order_id }} bei Preis {{ price }} ausgelöst, es wurde nicht gehandelt ({{ action }})",
+    ),
+    (
+        "order_failed",
+        "en",
+        "{{ order_type | order_type }} order {{ order_id }} failed to execute{% if error %}: {{ error }}{% endif %}",
+    ),
+    (
+        "order_failed",
+        "es",
+        "La orden {{ order_type | order_type }} {{ order_id }} no se pudo ejecutar{% if error %}: {{ error }}{% endif %}",
+    ),
+    (
+        "order_failed",
+        "de",
+        "{{ order_type | order_type }}-Order {{ order_id }} konnte nicht ausgeführt werden{% if error %}: {{ error }}{% endif %}",
+    ),
+    (
+        "blackout_trigger",
+        "en",
+        "{{ order_type | order_type }} order {{ order_id }} triggered at price {{ price }} during blackout window \"{{ window }}\" (until {{ until }}), it stays active",
+    ),
+    (
+        "blackout_trigger",
+        "es",
+        "La orden {{ order_type | order_type }} {{ order_id }} se activó al precio {{ price }} durante la ventana de bloqueo \"{{ window }}\" (hasta {{ until }}), sigue activa",
+    ),
+    (
+        "blackout_trigger",
+        "de",
+        "{{ order_type | order_type }}-Order {{ order_id }} wurde bei Preis {{ price }} im Sperrfenster \"{{ window }}\" (bis {{ until }}) ausgelöst und bleibt aktiv",
+    ),
+    (
+        "low_balance",
+        "en",
+        "Wallet {{ wallet }} holds {{ balance }} {{ symbol }}, below the alert threshold of {{ threshold }}",
+    ),
+    (
+        "low_balance",
+        "es",
+        "La wallet {{ wallet }} tiene {{ balance }} {{ symbol }}, por debajo del umbral de alerta de {{ threshold }}",
+    ),
+    (
+        "low_balance",
+        "de",
+        "Wallet {{ wallet }} hält {{ balance }} {{ symbol }}, unter der Alarmschwelle von {{ threshold }}",
+    ),
+    (
+        "digest",
+        "en",
+        "{{ count }} {{ event }} notifications:{% for message in messages %}\n- {{ message }}{% endfor %}",
+    ),
+    (
+        "digest",
+        "es",
+        "{{ count }} notificaciones {{ event }}:{% for message in messages %}\n- {{ message }}{% endfor %}",
+    ),
+    (
+        "digest",
+        "de",
+        "{{ count }} {{ event }}-Benachrichtigungen:{% for message in messages %}\n- {{ message }}{% endfor %}",
+    ),
+];
+
+// Names of the order types by locale, for the `order_type` filter
+const ORDER_TYPE_NAMES: &[(&str, &str, &str)] = &[
+    ("en", "Buy", "Buy"),
+    ("en", "Sell", "Sell"),
+    ("en", "StopLoss", "Stop Loss"),
+    ("en", "TakeProfit", "Take Profit"),
+    ("en", "StopBuy", "Stop Buy"),
+    ("en", "DeadMansSwitch", "Dead Man's Switch"),
+    ("es", "Buy", "de compra"),
+    ("es", "Sell", "de venta"),
+    ("es", "StopLoss", "stop loss"),
+    ("es", "TakeProfit", "take profit"),
+    ("es", "StopBuy", "stop de compra"),
+    ("es", "DeadMansSwitch", "de hombre muerto"),
+    ("de", "Buy", "Kauf"),
+    ("de", "Sell", "Verkauf"),
+    ("de", "StopLoss", "Stop-Loss"),
+    ("de", "TakeProfit", "Take-Profit"),
+    ("de", "StopBuy", "Stop-Buy"),
+    ("de", "DeadMansSwitch", "Totmannschalter"),
+];
+
+fn builtin_template(name: &str, locale: &str) -> Option<&'static str> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin_name, builtin_locale, _)| *builtin_name == name && *builtin_locale == locale)
+        .map(|(_, _, source)| *source)
+}
+
+fn is_known_template(name: &str) -> bool {
+    BUILTIN_TEMPLATES.iter().any(|(builtin_name, _, _)| *builtin_name == name)
+}
+
+// Lowercased locale with `_` read as `-`, if it looks like a language tag such as `es` or `pt-BR`
+fn normalize_locale(locale: &str) -> Option<String> {
+    let locale = locale.trim().to_lowercase().replace('_', "-");
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|part| (1..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()));
+    if valid {
+        Some(locale)
+    } else {
+        None
+    }
+}
+
+pub fn parse_locale(locale: &str) -> Result<String> {
+    normalize_locale(locale).ok_or_else(|| anyhow!("Invalid locale: {}, expected a language tag such as en or es-MX", locale))
+}
+
+// Locales tried for a tenant's notifications: their own, the server default, then English.
+// A regional locale such as `es-MX` falls back to its language.
+fn locale_chain(app_state: &AppState, tenant: Option<&str>) -> Vec<String> {
+    let tenant_locale = tenant.and_then(|tenant| app_state.notifier.lock().unwrap().locales.get(tenant).cloned());
+    let server_locale = app_state.preferences.lock().unwrap().locale.clone();
+
+    let mut chain: Vec<String> = Vec::new();
+    for locale in [tenant_locale, server_locale, Some(DEFAULT_LOCALE.to_string())].into_iter().flatten() {
+        let language = locale.split('-').next().unwrap_or_default().to_string();
+        for candidate in [locale, language] {
+            if !chain.contains(&candidate) {
+                chain.push(candidate);
+            }
+        }
+    }
+    chain
+}
+
+fn render_source(locale: &str, source: &str, vars: &Value) -> Result<String, minijinja::Error> {
+    let mut env = Environment::new();
+    let language = locale.split('-').next().unwrap_or_default().to_string();
+    env.add_filter("order_type", move |order_type: String| {
+        [language.as_str(), DEFAULT_LOCALE]
+            .iter()
+            .find_map(|locale| {
+                ORDER_TYPE_NAMES
+                    .iter()
+                    .find(|(name_locale, variant, _)| name_locale == locale && *variant == order_type)
+                    .map(|(_, _, name)| name.to_string())
+            })
+            .unwrap_or(order_type)
+    });
+    env.render_str(source, vars)
+}
+
+// Render a notification in the tenant's language. An operator override that fails to render
+// falls back to the built in template of the same locale.
+pub fn render(app_state: &AppState, tenant: Option<&str>, name: &str, vars: &Value) -> String {
+    let overrides = app_state.notifier.lock().unwrap().templates.clone();
+
+    for locale in locale_chain(app_state, tenant) {
+        let overridden = overrides.get(&(locale.clone(), name.to_string())).map(String::as_str);
+        for source in [overridden, builtin_template(name, &locale)].into_iter().flatten() {
+            match render_source(&locale, source, vars) {
+                Ok(message) => return message,
+                Err(err) => warn!("Notification template {} ({}) failed to render: {}", name, locale, err),
+            }
+        }
+    }
+
+    // Only reached for a template without a usable built in, which is a bug
+    warn!("No notification template {} could be rendered", name);
+    format!("{}: {}", name, vars)
+}
+
+// Built in templates, with operator overrides in place of the ones they replace, by locale
+pub fn get_templates(app_state: &AppState) -> Vec<NotificationTemplate> {
+    let overrides = app_state.notifier.lock().unwrap().templates.clone();
+
+    let mut templates = BUILTIN_TEMPLATES
+        .iter()
+        .filter(|(name, locale, _)| !overrides.contains_key(&(locale.to_string(), name.to_string())))
+        .map(|(name, locale, source)| NotificationTemplate {
+            name: name.to_string(),
+            locale: locale.to_string(),
+            template: source.to_string(),
+            overridden: false,
+        })
+        .chain(overrides.iter().map(|((locale, name), template)| NotificationTemplate {
+            name: name.clone(),
+            locale: locale.clone(),
+            template: template.clone(),
+            overridden: true,
+        }))
+        .collect::<Vec<_>>();
+    templates.sort_by(|a, b| (&a.locale, &a.name).cmp(&(&b.locale, &b.name)));
+    templates
+}
+
+// Override a template for a locale, which may be one without built in templates. The
+// template has to compile, so a typo is refused here rather than found when an alert fires.
+pub fn set_template(app_state: &AppState, request: NotificationTemplateRequest) -> Result<NotificationTemplate> {
+    if !is_known_template(&request.name) {
+        let mut names = BUILTIN_TEMPLATES.iter().map(|(name, _, _)| *name).collect::<Vec<_>>();
+        names.dedup();
+        return Err(anyhow!("Unknown notification template {}, expected one of: {}", request.name, names.join(", ")));
+    }
+    let locale = parse_locale(&request.locale)?;
+    Environment::new()
+        .template_from_str(&request.template)
+        .map_err(|err| anyhow!("Invalid notification template: {}", err))?;
+
+    info!("Overriding notification template {} for locale {}", request.name, locale);
+    app_state
+        .notifier
+        .lock()
+        .unwrap()
+        .templates
+        .insert((locale.clone(), request.name.clone()), request.template.clone());
+
+    Ok(NotificationTemplate {
+        name: request.name,
+        locale,
+        template: request.template,
+        overridden: true,
+    })
+}
+
+// Drop an override, going back to the built in template if there is one
+pub fn remove_template(app_state: &AppState, locale: &str, name: &str) -> Result<NotificationTemplate> {
+    let locale = parse_locale(locale)?;
+    let template = app_state
+        .notifier
+        .lock()
+        .unwrap()
+        .templates
+        .remove(&(locale.clone(), name.to_string()))
+        .ok_or_else(|| anyhow!("Notification template {} is not overridden for locale {}", name, locale))?;
+
+    info!("Removed override of notification template {} for locale {}", name, locale);
+    Ok(NotificationTemplate {
+        name: name.to_string(),
+        locale,
+        template,
+        overridden: true,
+    })
+}
+
+// Language the tenant picked for their notifications, and the one they are rendered in
+pub fn get_locale(app_state: &AppState, tenant: &str) -> NotificationLocale {
+    let locale = app_state.notifier.lock().unwrap().locales.get(tenant).cloned();
+    let overrides = app_state.notifier.lock().unwrap().templates.clone();
+
+    // The first locale with templates of its own, so a locale nothing is translated to shows English
+    let effective_locale = locale_chain(app_state, Some(tenant))
+        .into_iter()
+        .find(|locale| {
+            BUILTIN_TEMPLATES.iter().any(|(_, builtin_locale, _)| builtin_locale == locale)
+                || overrides.keys().any(|(override_locale, _)| override_locale == locale)
+        })
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+
+    NotificationLocale { locale, effective_locale }
+}
+
+// Set or, with no locale, clear the tenant's notification language
+pub fn set_locale(app_state: &AppState, tenant: &str, locale: Option<&str>) -> Result<NotificationLocale> {
+    let locale = locale.map(parse_locale).transpose()?;
+    {
+        let mut notifier = app_state.notifier.lock().unwrap();
+        match locale {
+            Some(locale) => notifier.locales.insert(tenant.to_string(), locale),
+            None => notifier.locales.remove(tenant),
+        };
+    }
+    Ok(get_locale(app_state, tenant))
+}
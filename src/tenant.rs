@@ -0,0 +1,261 @@
+#[cfg(feature = "server")]
+use crate::auth;
+#[cfg(feature = "server")]
+use crate::metering::{self, ANONYMOUS_KEY};
+use crate::models::{AppState, LimitOrder, OrderStatus, QuotaExceeded, QuotaKind, RateWindow, TenantLimits, TenantLimitsConfig};
+#[cfg(feature = "server")]
+use crate::utils;
+use anyhow::{anyhow, Result};
+#[cfg(feature = "server")]
+use axum::{
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+use tracing::{info, warn};
+
+// Set to "true" when several tenants share one server
+const HOSTED_MODE_ENV: &str = "HOSTED_MODE";
+
+// API key allowed to change quotas and tenant limits in hosted mode
+const ADMIN_API_KEY_ENV: &str = "ADMIN_API_KEY";
+
+// Base64 encoded 32 byte secret the per-tenant encryption keys are derived from
+const MASTER_KEY_ENV: &str = "TENANT_MASTER_KEY";
+
+// Routes callers can reach without an API key in hosted mode
+#[cfg(feature = "server")]
+const PUBLIC_PATHS: &[&str] = &["/health", "/status.json", "/client.ts", "/openapi.json"];
+
+// Whether the server runs in hosted, multi-tenant mode. Tenants are identified by their API key.
+pub fn hosted_mode() -> bool {
+    std::env::var(HOSTED_MODE_ENV).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+// Whether a tenant may see or act on something owned by `owner`. Outside hosted mode everything is shared.
+pub fn can_access(tenant: &str, owner: Option<&str>) -> bool {
+    !hosted_mode() || owner == Some(tenant)
+}
+
+// Whether a tenant may see or act on an order
+pub fn can_access_order(tenant: &str, order: &LimitOrder) -> bool {
+    can_access(tenant, order.api_key.as_deref())
+}
+
+// Key a tenant's own settings are stored under. Outside hosted mode every caller shares one set.
+pub fn settings_key(tenant: &str) -> String {
+    if hosted_mode() {
+        tenant.to_string()
+    } else {
+        String::new()
+    }
+}
+
+// Whether the caller may change server wide configuration such as quotas and tenant limits
+pub fn is_admin(tenant: &str) -> bool {
+    !hosted_mode() || crate::secrets::get_secret(ADMIN_API_KEY_ENV).is_some_and(|admin| !admin.is_empty() && admin == tenant)
+}
+
+// Whether another tenant already imported the wallet, so the caller may not import it
+pub fn owned_by_other(app_state: &AppState, tenant: &str, pubkey: &str) -> bool {
+    let owners = app_state.wallet_owners.lock().unwrap();
+    hosted_mode() && owners.get(pubkey).map(|owner| owner != tenant).unwrap_or(false)
+}
+
+// Record which tenant generated or imported a wallet. A wallet belongs to one tenant only.
+pub fn register_wallet(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<()> {
+    let mut owners = app_state.wallet_owners.lock().unwrap();
+
+    match owners.get(pubkey) {
+        Some(owner) if owner != tenant && hosted_mode() => {
+            Err(anyhow!("Wallet {} is already imported by another tenant", pubkey))
+        }
+        _ => {
+            owners.insert(pubkey.to_string(), tenant.to_string());
+            Ok(())
+        }
+    }
+}
+
+// Whether a tenant may use a wallet
+pub fn can_use_wallet(app_state: &AppState, tenant: &str, pubkey: &str) -> bool {
+    let owners = app_state.wallet_owners.lock().unwrap();
+    can_access(tenant, owners.get(pubkey).map(String::as_str))
+}
+
+// Public keys of the wallets a tenant may use
+pub fn tenant_wallets(app_state: &AppState, tenant: &str) -> Vec<String> {
+    let pubkeys = {
+        let wallets = app_state.wallets.lock().unwrap();
+        wallets.keys().cloned().collect::<Vec<_>>()
+    };
+
+    pubkeys
+        .into_iter()
+        .filter(|pubkey| can_use_wallet(app_state, tenant, pubkey))
+        .collect()
+}
+
+// Master secret configured in the secrets backend, if it is a base64 encoded 32 byte key
+fn configured_master_key() -> Option<[u8; 32]> {
+    crate::secrets::get_secret(MASTER_KEY_ENV)
+        .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+}
+
+// Whether a master secret is configured, so keys derived from it survive restarts
+pub fn master_key_configured() -> bool {
+    configured_master_key().is_some()
+}
+
+// Load the master secret, falling back to a random one that does not survive restarts
+pub fn load_master_key() -> [u8; 32] {
+    match configured_master_key() {
+        Some(key) => key,
+        None => {
+            if hosted_mode() {
+                warn!("{} is not set to a base64 encoded 32 byte key, using an ephemeral one", MASTER_KEY_ENV);
+            }
+            rand::random()
+        }
+    }
+}
+
+// Key used to encrypt a tenant's stored wallets, so one tenant's key never opens another's
+pub fn encryption_key(app_state: &AppState, tenant: &str) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&app_state.tenant_master_key).expect("HMAC accepts any key length");
+    mac.update(b"wallet-encryption:");
+    mac.update(tenant.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+// Limits that apply to a tenant
+pub fn limits_for(app_state: &AppState, tenant: &str) -> TenantLimits {
+    let config = app_state.tenant_limits.lock().unwrap();
+    config.tenants.get(tenant).cloned().unwrap_or_else(|| config.default.clone())
+}
+
+// Current tenant limits configuration
+pub fn get_limits(app_state: &AppState) -> TenantLimitsConfig {
+    let config = app_state.tenant_limits.lock().unwrap();
+    config.clone()
+}
+
+// Replace the tenant limits configuration
+pub fn set_limits(app_state: &AppState, config: TenantLimitsConfig) -> Result<TenantLimitsConfig> {
+    let all = std::iter::once(&config.default).chain(config.tenants.values());
+    for limits in all {
+        if limits.max_executions_per_pass == Some(0) {
+            return Err(anyhow!("max_executions_per_pass must be greater than zero"));
+        }
+    }
+
+    let mut current = app_state.tenant_limits.lock().unwrap();
+    *current = config.clone();
+    info!("Tenant limits updated ({} tenant overrides)", config.tenants.len());
+    Ok(config)
+}
+
+// Count a request in the tenant's one minute window, refusing it once the window is full
+pub fn check_rate_limit(app_state: &AppState, tenant: &str) -> Result<(), QuotaExceeded> {
+    let max_requests = match limits_for(app_state, tenant).max_requests_per_minute {
+        Some(max_requests) => max_requests,
+        None => return Ok(()),
+    };
+
+    let now = Utc::now();
+    let mut windows = app_state.tenant_windows.lock().unwrap();
+    let window = windows.entry(tenant.to_string()).or_insert(RateWindow {
+        started_at: now,
+        requests: 0,
+    });
+
+    if now - window.started_at >= Duration::minutes(1) {
+        *window = RateWindow {
+            started_at: now,
+            requests: 0,
+        };
+    }
+
+    if window.requests >= max_requests {
+        return Err(QuotaExceeded {
+            kind: QuotaKind::RequestsPerMinute,
+            used: window.requests as f64,
+            limit: max_requests as f64,
+        });
+    }
+
+    window.requests += 1;
+    Ok(())
+}
+
+// Refuse a new order once the tenant has as many active orders as it is allowed
+pub fn check_active_orders(app_state: &AppState, tenant: &str) -> Result<(), QuotaExceeded> {
+    let max_active_orders = match limits_for(app_state, tenant).max_active_orders {
+        Some(max_active_orders) => max_active_orders,
+        None => return Ok(()),
+    };
+
+    let active = {
+        let orders = app_state.limit_orders.lock().unwrap();
+        orders
+            .values()
+            .filter(|order| matches!(order.status, OrderStatus::Active | OrderStatus::Retrying) && order.api_key.as_deref() == Some(tenant))
+            .count()
+    };
+
+    if active >= max_active_orders {
+        return Err(QuotaExceeded {
+            kind: QuotaKind::ActiveOrders,
+            used: active as f64,
+            limit: max_active_orders as f64,
+        });
+    }
+
+    Ok(())
+}
+
+// Orders the tenant may execute in one monitor pass
+pub fn execution_budget(app_state: &AppState, tenant: &str) -> Option<usize> {
+    limits_for(app_state, tenant).max_executions_per_pass
+}
+
+// Middleware requiring a registered API key in hosted mode and enforcing per-tenant rate limits
+#[cfg(feature = "server")]
+pub async fn enforce_tenant<B>(request: Request<B>, next: Next<B>) -> Response {
+    let app_state = request.extensions().get::<Arc<AppState>>().cloned();
+    let tenant = metering::api_key_from_headers(request.headers());
+    let path = request.uri().path();
+
+    if hosted_mode() && !PUBLIC_PATHS.contains(&path) {
+        if tenant == ANONYMOUS_KEY {
+            return utils::build_error_response(
+                StatusCode::UNAUTHORIZED,
+                &format!("An API key is required, send it in the {} header", metering::API_KEY_HEADER),
+            )
+            .into_response();
+        }
+
+        // Only registered keys and the admin key identify a tenant
+        let registered = app_state.as_ref().is_some_and(|app_state| auth::validate_key(app_state, &tenant));
+        if !registered && !is_admin(&tenant) {
+            warn!("Rejected request to {} with an unknown API key", path);
+            return utils::build_error_response(StatusCode::UNAUTHORIZED, "Invalid API key").into_response();
+        }
+    }
+
+    if let Some(app_state) = app_state {
+        if let Err(err) = check_rate_limit(&app_state, &tenant) {
+            warn!("Rejected request to {}: {}", path, err);
+            return utils::build_error_response(StatusCode::TOO_MANY_REQUESTS, &err.to_string()).into_response();
+        }
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,105 @@
+use crate::models::{AppState, PriceAggregation, PriceGuardConfig, ProviderPreference, PushedPrice, SourcePrice};
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use crate::price::{self, MockPriceProvider};
+use anyhow::{ensure, Result};
+use std::sync::Arc;
+
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+// Source the cached price of a token came from
+fn cached_source(app_state: &AppState, mint: &str) -> Option<String> {
+    app_state.price_meta.lock().unwrap().get(mint).map(|meta| meta.source.clone())
+}
+
+/// Verify price updates fail over between providers and follow per-token preferences, using mock providers only
+pub async fn test_price_providers() -> Result<()> {
+    println!("Beginning price provider testing...");
+
+    let primary = MockPriceProvider::named("primary");
+    let backup = MockPriceProvider::named("backup");
+
+    let mut app_state = AppState::new();
+    app_state.price_providers = vec![Box::new(primary.clone()), Box::new(backup.clone())];
+    let app_state = Arc::new(app_state);
+
+    println!("Checking the default provider order...");
+    primary.set_price(SOL, 20.0);
+    backup.set_price(SOL, 21.0);
+    price::update_prices(app_state.clone()).await?;
+    let sol_price = price::get_token_price(&app_state, SOL)?;
+    ensure!(sol_price == 20.0, "SOL was priced at {} instead of the primary provider's 20", sol_price);
+    ensure!(cached_source(&app_state, SOL).as_deref() == Some("primary"), "SOL price not attributed to the primary provider");
+
+    println!("Checking failover when the first provider is down...");
+    primary.set_failing(true);
+    primary.set_price(SOL, 25.0);
+    price::update_prices(app_state.clone()).await?;
+    let sol_price = price::get_token_price(&app_state, SOL)?;
+    ensure!(sol_price == 21.0, "SOL was priced at {} instead of failing over to the backup's 21", sol_price);
+    ensure!(cached_source(&app_state, SOL).as_deref() == Some("backup"), "SOL price not attributed to the backup provider");
+
+    println!("Checking per-token provider preferences...");
+    primary.set_failing(false);
+    backup.set_price(SOL, 22.0);
+    price::set_provider_preference(
+        &app_state,
+        ProviderPreference {
+            mint: SOL.to_string(),
+            providers: vec!["backup".to_string()],
+        },
+    )?;
+    price::update_prices(app_state.clone()).await?;
+    let sol_price = price::get_token_price(&app_state, SOL)?;
+    ensure!(sol_price == 22.0, "SOL was priced at {} instead of its preferred backup provider's 22", sol_price);
+    ensure!(
+        price::set_provider_preference(
+            &app_state,
+            ProviderPreference {
+                mint: SOL.to_string(),
+                providers: vec!["jupiter".to_string()],
+            },
+        )
+        .is_err(),
+        "a preference for a provider that is not configured was accepted"
+    );
+
+    println!("Checking read-through prices for tokens only one provider knows...");
+    backup.set_price(BONK, 0.00002);
+    let bonk = price::get_mint_price(&app_state, BONK).await?;
+    ensure!(bonk.price_usd == 0.00002 && bonk.source == "backup", "BONK was priced at {} by {}", bonk.price_usd, bonk.source);
+
+    println!("Checking that stale source prices drop out of guarded prices...");
+    let sources = HashMap::from([
+        ("fresh".to_string(), SourcePrice { price_usd: 0.00002, updated_at: Utc::now() }),
+        ("stale".to_string(), SourcePrice { price_usd: 0.001, updated_at: Utc::now() - Duration::hours(1) }),
+    ]);
+    app_state.source_prices.lock().unwrap().insert(BONK.to_string(), sources);
+    price::set_price_guard(
+        &app_state,
+        PriceGuardConfig {
+            mint: BONK.to_string(),
+            aggregation: PriceAggregation::Median,
+            weights: HashMap::new(),
+            max_divergence_percent: Some(5.0),
+        },
+    )?;
+    let guarded = price::get_trigger_price(&app_state, BONK)?;
+    ensure!(guarded == 0.00002, "BONK's guarded price {} still counts a stale source", guarded);
+    ensure!(
+        price::get_price_divergence(&app_state).iter().all(|divergence| !divergence.sources.contains_key("stale")),
+        "a stale source price is reported in the divergence"
+    );
+
+    println!("Checking that pushed prices merge with fresh sources only...");
+    let merged = price::push_external_prices(&app_state, &[PushedPrice { mint: BONK.to_string(), price_usd: 0.00004 }])?;
+    ensure!(
+        merged.first().map(|price| (price.price_usd - 0.00003).abs() < 1e-12).unwrap_or(false),
+        "pushed BONK price merged into {:?} instead of the median of the fresh sources",
+        merged.first().map(|price| price.price_usd)
+    );
+
+    println!("Price provider checks passed");
+    Ok(())
+}
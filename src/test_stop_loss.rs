@@ -1,4 +1,4 @@
-use crate::models::{AppState, LimitOrderRequest, OrderStatus, OrderType};
+use crate::models::{AppState, LimitOrder, LimitOrderRequest, OrderAction, OrderStatus, OrderType, TriggerSource};
 use crate::orders;
 use crate::price;
 use std::sync::Arc;
@@ -42,10 +42,30 @@ pub async fn test_stop_loss() -> Result<()> {
         source_token: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(), // USDC
         target_token: "So11111111111111111111111111111111111111112".to_string(), // SOL
         amount: 50.0,
+        amount_percent: None,
+        amount_usd: None,
         price_target: 15.0, // Stop loss at $15 (below current SOL price of $20)
         order_type: OrderType::StopLoss,
         expiry_time: None,
         slippage: Some(1.0),
+        priority: None,
+        heartbeat_interval_secs: None,
+        strategy: None,
+        trigger_source: TriggerSource::Spot,
+        trigger_mint: None,
+        quote_mint: None,
+        action: OrderAction::Swap,
+        max_fee_usd: None,
+        check_interval_secs: None,
+        dry_run: false,
+        priority_fee: None,
+        mev_protection: false,
+        passive: false,
+        wallet_pubkey: Some(wallet_pubkey.clone()),
+        force: false,
+        max_retries: None,
+        retry_backoff_secs: None,
+        api_key: None,
     };
     
     println!("Creating stop loss order: Sell 50 USDC if SOL price drops to $15");
@@ -133,14 +153,44 @@ async fn create_test_order(app_state: Arc<AppState>, order_request: LimitOrderRe
         source_token: order_request.source_token,
         target_token: order_request.target_token,
         amount: order_request.amount,
+        amount_percent: order_request.amount_percent,
+        amount_usd: order_request.amount_usd,
         price_target: order_request.price_target,
         order_type: order_request.order_type,
         status: OrderStatus::Active,
+        amount_raw: None,
         created_at: now,
         updated_at: now,
         expiry_time: order_request.expiry_time,
         slippage: order_request.slippage.unwrap_or(0.5),
         transaction_signature: None,
+        priority: order_request.priority.unwrap_or(0),
+        heartbeat_interval_secs: order_request.heartbeat_interval_secs,
+        last_heartbeat: None,
+        fee_estimate: None,
+        validation: None,
+        fee: None,
+        failure: None,
+        max_retries: 0,
+        retry_backoff_secs: 0,
+        retry_count: 0,
+        next_retry_at: None,
+        strategy: order_request.strategy,
+        execution: None,
+        latency: None,
+        audit_trail: Vec::new(),
+        trigger_source: order_request.trigger_source,
+        trigger_mint: order_request.trigger_mint,
+        quote_mint: order_request.quote_mint,
+        action: order_request.action,
+        max_fee_usd: order_request.max_fee_usd,
+        check_interval_secs: order_request.check_interval_secs,
+        dry_run: order_request.dry_run,
+        priority_fee: order_request.priority_fee,
+        mev_protection: order_request.mev_protection,
+        passive: None,
+        wallet_pubkey: order_request.wallet_pubkey.unwrap_or_default(),
+        api_key: order_request.api_key,
     };
     
     // Add the order to app state
@@ -182,10 +232,30 @@ pub async fn test_stop_loss_execution() -> Result<()> {
         source_token: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(), // USDC
         target_token: "So11111111111111111111111111111111111111112".to_string(), // SOL
         amount: 50.0,
+        amount_percent: None,
+        amount_usd: None,
         price_target: 15.0, // Stop loss at $15 (below current SOL price of $20)
         order_type: OrderType::StopLoss,
         expiry_time: None,
         slippage: Some(1.0),
+        priority: None,
+        heartbeat_interval_secs: None,
+        strategy: None,
+        trigger_source: TriggerSource::Spot,
+        trigger_mint: None,
+        quote_mint: None,
+        action: OrderAction::Swap,
+        max_fee_usd: None,
+        check_interval_secs: None,
+        dry_run: false,
+        priority_fee: None,
+        mev_protection: false,
+        passive: false,
+        wallet_pubkey: Some(wallet_pubkey.clone()),
+        force: false,
+        max_retries: None,
+        retry_backoff_secs: None,
+        api_key: None,
     };
     
     println!("Creating stop loss order: Sell 50 USDC if SOL price drops to $15");
@@ -272,6 +342,17 @@ pub async fn test_stop_loss_execution() -> Result<()> {
         }
     }
     
+    // A stop buy mirrors the stop loss: it waits below its target and buys on the breakout
+    println!("\nChecking a stop buy at $25...");
+    let stop_buy = LimitOrder {
+        order_type: OrderType::StopBuy,
+        price_target: 25.0,
+        ..order.clone()
+    };
+    assert!(!orders::should_execute_order_test(&stop_buy, 20.0), "Stop buy should not execute below its target");
+    assert!(orders::should_execute_order_test(&stop_buy, 25.0), "Stop buy should execute at its target");
+    assert!(orders::should_execute_order_test(&stop_buy, 27.5), "Stop buy should execute above its target");
+    
     println!("\nStop loss execution simulation completed successfully!");
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,557 @@
+use crate::alerts;
+use crate::auth;
+use crate::engine;
+use crate::models::{ApiOperation, AppState, BalanceAlertRequest, ImportMode, ImportWalletEntry, KeyScope, LimitOrder, ListOrdersQuery, NotificationConfig, OrderAction, SolReservePolicy, OrderStatus, OrderType, TenantLimits, TriggerSource};
+use crate::notify;
+use crate::orders;
+use crate::receipts;
+use crate::reserve;
+use crate::tenant;
+use crate::wallet;
+use anyhow::{ensure, Result};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const TENANT_A: &str = "tenant-a";
+const TENANT_B: &str = "tenant-b";
+const ADMIN: &str = "tenant-admin";
+
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+// Generate a wallet for a tenant and register it the way the import endpoints do
+fn add_tenant_wallet(app_state: &AppState, tenant: &str) -> Result<String> {
+    let (wallet, _) = wallet::generate_new_wallet()?;
+    let pubkey = wallet.pubkey.to_string();
+
+    tenant::register_wallet(app_state, tenant, &pubkey)?;
+    app_state.wallets.lock().unwrap().insert(pubkey.clone(), wallet);
+    Ok(pubkey)
+}
+
+// Insert an order owned by a tenant directly, bypassing the balance checks that need RPC
+fn add_tenant_order(app_state: &AppState, tenant: &str, wallet_pubkey: &str, order_type: OrderType) -> LimitOrder {
+    let now = Utc::now();
+    let order = LimitOrder {
+        id: Uuid::new_v4().to_string(),
+        source_token: USDC.to_string(),
+        target_token: SOL.to_string(),
+        amount: 10.0,
+        amount_percent: None,
+        amount_usd: None,
+        price_target: 15.0,
+        order_type,
+        status: OrderStatus::Active,
+        amount_raw: None,
+        created_at: now,
+        updated_at: now,
+        expiry_time: None,
+        slippage: 0.5,
+        transaction_signature: None,
+        priority: 0,
+        heartbeat_interval_secs: Some(60),
+        last_heartbeat: Some(now),
+        fee_estimate: None,
+        validation: None,
+        fee: None,
+        failure: None,
+        max_retries: 0,
+        retry_backoff_secs: 0,
+        retry_count: 0,
+        next_retry_at: None,
+        strategy: None,
+        execution: None,
+        latency: None,
+        audit_trail: Vec::new(),
+        trigger_source: TriggerSource::Spot,
+        trigger_mint: None,
+        quote_mint: None,
+        action: OrderAction::Swap,
+        max_fee_usd: None,
+        check_interval_secs: None,
+        dry_run: false,
+        priority_fee: None,
+        mev_protection: false,
+        passive: None,
+        wallet_pubkey: wallet_pubkey.to_string(),
+        api_key: Some(tenant.to_string()),
+    };
+
+    app_state.limit_orders.lock().unwrap().insert(order.id.clone(), order.clone());
+    order
+}
+
+/// Verify that in hosted mode one tenant can never see or act on another tenant's wallets and orders
+pub async fn test_tenant_isolation() -> Result<()> {
+    println!("Beginning tenant isolation testing...");
+    std::env::set_var("HOSTED_MODE", "true");
+    std::env::set_var("ADMIN_API_KEY", ADMIN);
+
+    let app_state = Arc::new(AppState::new());
+    app_state.token_prices.lock().unwrap().insert(SOL.to_string(), 20.0);
+    app_state.token_prices.lock().unwrap().insert(USDC.to_string(), 1.0);
+
+    let wallet_a = add_tenant_wallet(&app_state, TENANT_A)?;
+    let wallet_b = add_tenant_wallet(&app_state, TENANT_B)?;
+    let order_a = add_tenant_order(&app_state, TENANT_A, &wallet_a, OrderType::StopLoss);
+    let order_b = add_tenant_order(&app_state, TENANT_B, &wallet_b, OrderType::DeadMansSwitch);
+
+    println!("Checking wallet ownership...");
+    ensure!(
+        tenant::register_wallet(&app_state, TENANT_A, &wallet_b).is_err(),
+        "tenant A re-imported tenant B's wallet"
+    );
+    ensure!(
+        wallet::find_tenant_wallet(&app_state, TENANT_A, Some(&wallet_b)).is_err(),
+        "tenant A can use tenant B's wallet"
+    );
+    let default_wallet = wallet::find_tenant_wallet(&app_state, TENANT_A, None)?;
+    ensure!(default_wallet.pubkey.to_string() == wallet_a, "tenant A's default wallet is not its own");
+    ensure!(
+        wallet::find_tenant_wallet(&app_state, "tenant-c", None).is_err(),
+        "a tenant without wallets got someone else's wallet"
+    );
+
+    println!("Checking order listings...");
+    let listed = orders::get_limit_orders(app_state.clone(), TENANT_A, &ListOrdersQuery::default());
+    ensure!(
+        listed.len() == 1 && listed[0].id == order_a.id,
+        "tenant A listed {} orders instead of only its own",
+        listed.len()
+    );
+    ensure!(
+        orders::get_limit_orders(app_state.clone(), "tenant-c", &ListOrdersQuery::default()).is_empty(),
+        "a tenant without orders listed someone else's"
+    );
+
+    println!("Checking order actions across tenants...");
+    ensure!(
+        orders::cancel_limit_order(app_state.clone(), TENANT_A, &order_b.id).await.is_err(),
+        "tenant A cancelled tenant B's order"
+    );
+    ensure!(
+        orders::archive_order(&app_state, TENANT_A, &order_b.id).is_err(),
+        "tenant A archived tenant B's order"
+    );
+    let refreshed = orders::record_heartbeat(app_state.clone(), TENANT_A, None)?;
+    ensure!(refreshed.is_empty(), "tenant A's heartbeat refreshed tenant B's dead man's switch");
+    ensure!(
+        orders::record_heartbeat(app_state.clone(), TENANT_A, Some(&order_b.id)).is_err(),
+        "tenant A sent a heartbeat for tenant B's order"
+    );
+    let status_b = app_state.limit_orders.lock().unwrap()[&order_b.id].status.clone();
+    ensure!(status_b == OrderStatus::Active, "tenant B's order changed status to {:?}", status_b);
+
+    println!("Checking failed orders, order history and strategy stats...");
+    orders::set_order_status(&app_state, &order_b.id, OrderStatus::Executing)?;
+    orders::set_order_status(&app_state, &order_b.id, OrderStatus::Failed)?;
+    ensure!(
+        orders::get_failed_orders(&app_state, TENANT_A, None).is_empty(),
+        "tenant A listed tenant B's failed order"
+    );
+    ensure!(
+        orders::get_failed_orders(&app_state, TENANT_B, None).len() == 1,
+        "tenant B cannot list its own failed order"
+    );
+    let history_b = orders::get_order_history(&app_state, TENANT_B, &order_b.id)?;
+    ensure!(
+        history_b.iter().map(|event| event.status.clone()).collect::<Vec<_>>() == vec![OrderStatus::Executing, OrderStatus::Failed],
+        "tenant B's order history does not record its transitions"
+    );
+    ensure!(
+        orders::get_order_history(&app_state, TENANT_A, &order_b.id).is_err(),
+        "tenant A read tenant B's order history"
+    );
+    let stats = orders::get_strategy_stats(&app_state, TENANT_A, None, None);
+    ensure!(
+        stats.iter().map(|s| s.orders).sum::<usize>() == 1,
+        "tenant A's strategy stats include other tenants' orders"
+    );
+
+    println!("Checking execution receipts...");
+    let receipt = receipts::issue_receipt(&app_state, &order_b, 14.0, Utc::now())?;
+    ensure!(receipts::verify_receipt(&receipt)?, "a freshly issued receipt does not verify");
+    let mut tampered = receipt.clone();
+    tampered.receipt.trigger_price = 16.0;
+    ensure!(!receipts::verify_receipt(&tampered)?, "a receipt with altered contents still verifies");
+    ensure!(
+        receipts::get_receipt(&app_state, TENANT_B, &order_b.id).is_ok(),
+        "tenant B cannot read its own receipt"
+    );
+    ensure!(
+        receipts::get_receipt(&app_state, TENANT_A, &order_b.id).is_err(),
+        "tenant A read tenant B's receipt"
+    );
+
+    println!("Checking order backtraces...");
+    ensure!(
+        orders::backtrace_order(&app_state, TENANT_A, &order_a.id).is_ok(),
+        "tenant A cannot backtrace its own order"
+    );
+    ensure!(
+        orders::backtrace_order(&app_state, TENANT_B, &order_a.id).is_err(),
+        "tenant B backtraced tenant A's order"
+    );
+
+    println!("Checking per-tenant engine budgets...");
+    let second_a = add_tenant_order(&app_state, TENANT_A, &wallet_a, OrderType::StopLoss);
+    let order_c = add_tenant_order(&app_state, TENANT_B, &wallet_b, OrderType::StopLoss);
+    app_state.tenant_limits.lock().unwrap().tenants.insert(
+        TENANT_A.to_string(),
+        TenantLimits {
+            max_executions_per_pass: Some(1),
+            ..TenantLimits::default()
+        },
+    );
+    let triggered = vec![(order_a.clone(), 14.0), (second_a.clone(), 14.0), (order_c.clone(), 14.0)];
+    let queue = engine::build_execution_queue(&app_state, triggered);
+    let queued_a = queue
+        .iter()
+        .filter(|queued| queued.order_id == order_a.id || queued.order_id == second_a.id)
+        .count();
+    ensure!(queued_a == 1, "tenant A had {} orders queued with a budget of 1", queued_a);
+    ensure!(
+        queue.iter().any(|queued| queued.order_id == order_c.id),
+        "tenant A's budget held back tenant B's order"
+    );
+
+    engine::set_execution_queue(&app_state, queue);
+    ensure!(
+        engine::get_execution_queue(&app_state, TENANT_A).len() == 1,
+        "tenant A can see other tenants' queued orders"
+    );
+
+    println!("Checking per-tenant rate limits...");
+    app_state.tenant_limits.lock().unwrap().default = TenantLimits {
+        max_requests_per_minute: Some(2),
+        ..TenantLimits::default()
+    };
+    ensure!(tenant::check_rate_limit(&app_state, TENANT_B).is_ok(), "first request was rate limited");
+    ensure!(tenant::check_rate_limit(&app_state, TENANT_B).is_ok(), "second request was rate limited");
+    ensure!(tenant::check_rate_limit(&app_state, TENANT_B).is_err(), "third request was not rate limited");
+    ensure!(
+        tenant::check_rate_limit(&app_state, "tenant-c").is_ok(),
+        "one tenant's requests used up another's rate limit"
+    );
+
+    println!("Checking API key registration...");
+    let created = auth::create_key(&app_state, ADMIN, "tenant d", None)?;
+    ensure!(auth::validate_key(&app_state, &created.key), "a newly created key is not accepted");
+    ensure!(!auth::validate_key(&app_state, "sld_unknown"), "an unregistered key was accepted");
+    ensure!(
+        !serde_json::to_string(&auth::list_keys(&app_state, ADMIN))?.contains("key_hash"),
+        "key listings expose key hashes"
+    );
+    ensure!(
+        auth::create_key(&app_state, TENANT_A, "tenant e", None).is_err(),
+        "a tenant created an unscoped API key"
+    );
+    auth::revoke_key(&app_state, ADMIN, &created.record.id)?;
+    ensure!(!auth::validate_key(&app_state, &created.key), "a revoked key is still accepted");
+
+    println!("Checking subaccount keys...");
+    let scope = KeyScope {
+        wallet: wallet_a.clone(),
+        operations: vec![ApiOperation::CreateOrders, ApiOperation::CancelOrders, ApiOperation::CreateOrders],
+        order_types: vec![OrderType::StopLoss],
+        tenant: TENANT_B.to_string(),
+    };
+    ensure!(
+        auth::create_key(&app_state, TENANT_B, "bot", Some(scope.clone())).is_err(),
+        "tenant B created a subaccount key on tenant A's wallet"
+    );
+    let bot = auth::create_key(&app_state, TENANT_A, "bot", Some(scope))?;
+    let bot_scope = auth::key_scope(&app_state, &bot.key).expect("subaccount key has a scope");
+    ensure!(
+        bot_scope.tenant == TENANT_A,
+        "a subaccount key acts for {} instead of its wallet's owner", bot_scope.tenant
+    );
+    ensure!(
+        bot_scope.operations.len() == 2,
+        "a subaccount key keeps duplicate operations: {:?}", bot_scope.operations
+    );
+    ensure!(
+        !serde_json::to_string(&bot.record)?.contains(TENANT_A),
+        "a subaccount key listing exposes its tenant's key"
+    );
+    ensure!(
+        auth::list_keys(&app_state, TENANT_B).is_empty() && auth::list_keys(&app_state, TENANT_A).len() == 1,
+        "subaccount keys are listed to the wrong tenant"
+    );
+
+    let order_body = |wallet: &str, order_type: &str| {
+        serde_json::json!({ "wallet_pubkey": wallet, "order_type": order_type }).to_string().into_bytes()
+    };
+    let check = |operation, body: &[u8]| auth::check_scope(&app_state, &bot_scope, operation, "/", None, body);
+    ensure!(
+        check(ApiOperation::CreateOrders, &order_body(&wallet_a, "StopLoss")).is_ok(),
+        "a subaccount key could not create an allowed order"
+    );
+    ensure!(
+        check(ApiOperation::CreateOrders, &order_body(&wallet_b, "StopLoss")).is_err(),
+        "a subaccount key created an order on another wallet"
+    );
+    ensure!(
+        check(ApiOperation::CreateOrders, &order_body(&wallet_a, "Buy")).is_err(),
+        "a subaccount key created an order type it was not granted"
+    );
+    ensure!(
+        check(ApiOperation::Swap, &order_body(&wallet_a, "StopLoss")).is_err(),
+        "a subaccount key used an operation it was not granted"
+    );
+    let cancel_body = serde_json::json!({ "order_id": order_b.id }).to_string().into_bytes();
+    ensure!(
+        check(ApiOperation::CancelOrders, &cancel_body).is_err(),
+        "a subaccount key cancelled another wallet's order"
+    );
+
+    ensure!(
+        auth::revoke_key(&app_state, TENANT_B, &bot.record.id).is_err(),
+        "tenant B revoked tenant A's subaccount key"
+    );
+    auth::revoke_key(&app_state, TENANT_A, &bot.record.id)?;
+
+    println!("Checking balance alerts across tenants...");
+    let alert_request = |wallet: &str| BalanceAlertRequest {
+        wallet: wallet.to_string(),
+        mint: SOL.to_string(),
+        threshold: 1.0,
+    };
+    ensure!(
+        alerts::create_balance_alert(&app_state, TENANT_A, alert_request(&wallet_b)).is_err(),
+        "tenant A created an alert on tenant B's wallet"
+    );
+    let alert_b = alerts::create_balance_alert(&app_state, TENANT_B, alert_request(&wallet_b))?;
+    ensure!(
+        alerts::get_tenant_balance_alerts(&app_state, TENANT_A).is_empty(),
+        "tenant A listed tenant B's balance alert"
+    );
+    ensure!(
+        alerts::remove_balance_alert(&app_state, TENANT_A, &alert_b.id).is_err(),
+        "tenant A deleted tenant B's balance alert"
+    );
+
+    println!("Checking SOL reserve policies...");
+    reserve::set_policy(&app_state, TENANT_A, SolReservePolicy {
+        enabled: true,
+        min_sol: 0.05,
+        target_sol: 0.2,
+        funding_token: USDC.to_string(),
+        slippage: None,
+    })?;
+    ensure!(
+        reserve::get_policy(&app_state, TENANT_B).is_none(),
+        "tenant A's SOL reserve policy applies to tenant B"
+    );
+    ensure!(
+        reserve::get_policy(&app_state, TENANT_A).is_some(),
+        "tenant A cannot read its own SOL reserve policy"
+    );
+
+    println!("Checking notification configs...");
+    notify::set_config(&app_state, TENANT_A, NotificationConfig {
+        daily_digest_hour: Some(8),
+        ..NotificationConfig::default()
+    });
+    ensure!(
+        notify::get_config(&app_state, TENANT_B).daily_digest_hour.is_none(),
+        "tenant A's notification config applies to tenant B"
+    );
+    ensure!(
+        notify::get_config(&app_state, TENANT_A).daily_digest_hour == Some(8),
+        "tenant A cannot read its own notification config"
+    );
+
+    println!("Checking batch wallet imports...");
+    let (new_wallet, _) = wallet::generate_new_wallet()?;
+    let new_pubkey = new_wallet.pubkey.to_string();
+    let key_b = app_state.wallets.lock().unwrap()[&wallet_b].keypair.to_base58_string();
+    let entries = [
+        (new_wallet.keypair.to_base58_string(), "fresh"),
+        (key_b, "tenant B's"),
+        ("not a key".to_string(), "invalid"),
+    ]
+    .into_iter()
+    .map(|(private_key, label)| ImportWalletEntry {
+        private_key: Some(private_key),
+        mnemonic: None,
+        label: Some(label.to_string()),
+    })
+    .collect::<Vec<_>>();
+
+    let atomic = wallet::import_wallets(&app_state, TENANT_A, &entries, None, ImportMode::Atomic);
+    ensure!(atomic.imported == 0, "an atomic batch with invalid entries imported wallets");
+    ensure!(
+        !app_state.wallets.lock().unwrap().contains_key(&new_pubkey),
+        "an atomic batch with invalid entries stored a wallet"
+    );
+
+    let best_effort = wallet::import_wallets(&app_state, TENANT_A, &entries, None, ImportMode::BestEffort);
+    ensure!(best_effort.imported == 1, "a best effort batch imported {} wallets, expected 1", best_effort.imported);
+    ensure!(best_effort.results[0].imported, "a best effort batch skipped a valid entry");
+    ensure!(
+        best_effort.results[1].error.is_some() && best_effort.results[2].error.is_some(),
+        "a best effort batch did not report its invalid entries"
+    );
+    ensure!(
+        wallet::find_tenant_wallet(&app_state, TENANT_B, Some(&wallet_b)).is_ok(),
+        "tenant B lost its wallet to tenant A's batch import"
+    );
+    ensure!(
+        app_state.wallet_labels.lock().unwrap().get(&new_pubkey).map(String::as_str) == Some("fresh"),
+        "a batch import did not keep the wallet's label"
+    );
+
+    println!("Checking wallet removal and export...");
+    ensure!(
+        wallet::export_wallet(&app_state, TENANT_B, &wallet_a).is_err(),
+        "tenant B exported tenant A's wallet"
+    );
+    ensure!(
+        wallet::remove_wallet(&app_state, TENANT_B, &new_pubkey).is_err(),
+        "tenant B removed tenant A's wallet"
+    );
+    let exported = wallet::export_wallet(&app_state, TENANT_A, &new_pubkey)?;
+    ensure!(
+        wallet::import_from_private_key(&exported.private_key)?.pubkey.to_string() == new_pubkey,
+        "an exported key does not import back to the same wallet"
+    );
+    wallet::remove_wallet(&app_state, TENANT_A, &new_pubkey)?;
+    ensure!(
+        wallet::find_tenant_wallet(&app_state, TENANT_A, Some(&new_pubkey)).is_err(),
+        "a removed wallet can still be used"
+    );
+
+    println!("Checking per-tenant encryption keys...");
+    ensure!(
+        tenant::encryption_key(&app_state, TENANT_A) != tenant::encryption_key(&app_state, TENANT_B),
+        "tenants share an encryption key"
+    );
+    ensure!(
+        tenant::encryption_key(&app_state, TENANT_A) == tenant::encryption_key(&app_state, TENANT_A),
+        "a tenant's encryption key is not stable"
+    );
+
+    #[cfg(feature = "server")]
+    check_order_endpoints(&app_state, &order_a).await?;
+    #[cfg(feature = "server")]
+    check_admin_endpoints(&app_state).await?;
+
+    println!("\nTenant isolation test completed!");
+    Ok(())
+}
+
+// API key headers of a request made by the given tenant
+#[cfg(feature = "server")]
+fn headers_for(api_key: &str) -> axum::http::HeaderMap {
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert(crate::metering::API_KEY_HEADER, axum::http::HeaderValue::from_str(api_key).unwrap());
+    headers
+}
+
+// Verify that the order endpoints answer 404 for an order of another tenant
+#[cfg(feature = "server")]
+async fn check_order_endpoints(app_state: &Arc<AppState>, order: &LimitOrder) -> Result<()> {
+    use crate::api;
+    use axum::extract::{Extension, Path, Query};
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    println!("Checking order endpoints across tenants...");
+    let state = || Extension(app_state.clone());
+    let path = || Path(order.id.clone());
+
+    let status = api::backtrace_order(state(), path(), headers_for(TENANT_B)).await.into_response().status();
+    ensure!(status == StatusCode::NOT_FOUND, "tenant B backtraced tenant A's order ({})", status);
+    let status = api::backtrace_order(state(), path(), headers_for(TENANT_A)).await.into_response().status();
+    ensure!(status == StatusCode::OK, "tenant A could not backtrace its own order ({})", status);
+
+    let query = || Query(serde_json::from_value(serde_json::json!({ "prices": "14,16" })).unwrap());
+    let status = api::what_if_order(state(), path(), query(), headers_for(TENANT_B)).await.into_response().status();
+    ensure!(status == StatusCode::NOT_FOUND, "tenant B simulated tenant A's order ({})", status);
+
+    Ok(())
+}
+
+// Verify that tenants cannot change engine-wide settings through the API, while the admin API key can
+#[cfg(feature = "server")]
+async fn check_admin_endpoints(app_state: &Arc<AppState>) -> Result<()> {
+    use crate::api;
+    use axum::extract::{Extension, Path};
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use axum::Json;
+
+    println!("Checking admin-only endpoints...");
+    let tenant = headers_for(TENANT_A);
+    let admin = headers_for(ADMIN);
+    let state = || Extension(app_state.clone());
+
+    engine::pause_trading(app_state, "tenant isolation check");
+    let statuses = vec![
+        ("resume trading", api::resume_trading(state(), tenant.clone()).await.into_response().status()),
+        (
+            "set the vacation mode policy",
+            api::set_liquidation_policy(
+                state(),
+                tenant.clone(),
+                Json(serde_json::from_value(serde_json::json!({
+                    "enabled": true,
+                    "wallets": [],
+                    "drawdown_percent": 10.0,
+                    "window_hours": 1.0,
+                    "tokens": [SOL],
+                    "stable_token": USDC,
+                    "slippage": null,
+                }))?),
+            )
+            .await
+            .into_response()
+            .status(),
+        ),
+        (
+            "set a price guard",
+            api::set_price_guard(
+                state(),
+                tenant.clone(),
+                Json(serde_json::from_value(serde_json::json!({
+                    "mint": SOL,
+                    "aggregation": "Median",
+                    "max_divergence_percent": 5.0,
+                }))?),
+            )
+            .await
+            .into_response()
+            .status(),
+        ),
+        ("remove a price guard", api::remove_price_guard(state(), tenant.clone(), Path(SOL.to_string())).await.into_response().status()),
+        (
+            "set an exposure limit",
+            api::set_exposure_limit(
+                state(),
+                tenant.clone(),
+                Json(serde_json::from_value(serde_json::json!({
+                    "mint": SOL,
+                    "max_portfolio_percent": 1.0,
+                    "action": "Reject",
+                }))?),
+            )
+            .await
+            .into_response()
+            .status(),
+        ),
+        ("remove an exposure limit", api::remove_exposure_limit(state(), tenant.clone(), Path(SOL.to_string())).await.into_response().status()),
+    ];
+    for (action, status) in statuses {
+        ensure!(status == StatusCode::FORBIDDEN, "a tenant could {} ({})", action, status);
+    }
+    ensure!(engine::trading_paused(app_state).is_some(), "a tenant resumed trading");
+    ensure!(app_state.price_guards.lock().unwrap().is_empty(), "a tenant set a price guard");
+
+    let status = api::resume_trading(state(), admin).await.into_response().status();
+    ensure!(status == StatusCode::OK, "the admin API key could not resume trading ({})", status);
+    ensure!(engine::trading_paused(app_state).is_none(), "the admin API key did not resume trading");
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use crate::models::{AppState, CachedPayload, TokenInfo, UpstreamCacheEntry};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use reqwest::{header, Client, StatusCode};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+// Directory the cached payloads are written to, so restarts and instances sharing it skip the download
+const UPSTREAM_CACHE_DIR_ENV: &str = "UPSTREAM_CACHE_DIR";
+const DEFAULT_UPSTREAM_CACHE_DIR: &str = "upstream_cache";
+
+// A large, rarely changing upstream payload and how long a copy of it stays fresh
+pub struct CachedResource {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub ttl_secs: u64,
+}
+
+// Jupiter's list of verified tokens, several megabytes of metadata
+pub const JUPITER_TOKEN_LIST: CachedResource = CachedResource {
+    name: "jupiter_tokens",
+    url: "https://tokens.jup.ag/tokens?tags=verified",
+    ttl_secs: 6 * 60 * 60,
+};
+
+// Jupiter's map of the AMM program ids it routes through to their DEX labels
+pub const JUPITER_PROGRAM_LABELS: CachedResource = CachedResource {
+    name: "jupiter_program_labels",
+    url: "https://quote-api.jup.ag/v6/program-id-to-label",
+    ttl_secs: 24 * 60 * 60,
+};
+
+// Every payload the proxy caches
+const RESOURCES: &[&CachedResource] = &[&JUPITER_TOKEN_LIST, &JUPITER_PROGRAM_LABELS];
+
+fn cache_dir() -> PathBuf {
+    PathBuf::from(std::env::var(UPSTREAM_CACHE_DIR_ENV).unwrap_or_else(|_| DEFAULT_UPSTREAM_CACHE_DIR.to_string()))
+}
+
+fn cache_path(resource: &CachedResource) -> PathBuf {
+    cache_dir().join(format!("{}.json", resource.name))
+}
+
+fn is_fresh(resource: &CachedResource, payload: &CachedPayload) -> bool {
+    Utc::now() - payload.fetched_at < Duration::seconds(resource.ttl_secs as i64)
+}
+
+// Copy of a payload held in memory, or written to disk by an earlier run or another instance
+fn load_cached(app_state: &AppState, resource: &CachedResource) -> Option<CachedPayload> {
+    if let Some(payload) = app_state.upstream_cache.lock().unwrap().get(resource.name) {
+        return Some(payload.clone());
+    }
+
+    let payload = std::fs::read(cache_path(resource))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<CachedPayload>(&bytes).ok())
+        .filter(|payload| payload.url == resource.url)?;
+
+    let mut cache = app_state.upstream_cache.lock().unwrap();
+    cache.insert(resource.name.to_string(), payload.clone());
+    Some(payload)
+}
+
+fn store_cached(app_state: &AppState, resource: &CachedResource, payload: &CachedPayload) {
+    let written = std::fs::create_dir_all(cache_dir())
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(serde_json::to_vec(payload)?))
+        .and_then(|bytes| Ok(std::fs::write(cache_path(resource), bytes)?));
+    if let Err(err) = written {
+        warn!("Failed to write cached {} to disk: {}", resource.name, err);
+    }
+
+    let mut cache = app_state.upstream_cache.lock().unwrap();
+    cache.insert(resource.name.to_string(), payload.clone());
+}
+
+// Download the payload, sending the ETag of the cached copy so an unchanged payload is not sent again
+async fn revalidate(app_state: &AppState, resource: &CachedResource, cached: Option<CachedPayload>) -> Result<CachedPayload> {
+    let mut request = Client::new().get(resource.url);
+    if let Some(etag) = cached.as_ref().and_then(|payload| payload.etag.as_deref()) {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch {}: {}", resource.name, e))?;
+
+    let payload = match (response.status(), cached) {
+        (StatusCode::NOT_MODIFIED, Some(cached)) => CachedPayload {
+            fetched_at: Utc::now(),
+            ..cached
+        },
+        (status, _) if status.is_success() => {
+            let etag = response
+                .headers()
+                .get(header::ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(str::to_string);
+            let body = response
+                .text()
+                .await
+                .map_err(|e| anyhow!("Failed to read {}: {}", resource.name, e))?;
+
+            info!("Downloaded {} ({} bytes)", resource.name, body.len());
+            CachedPayload {
+                url: resource.url.to_string(),
+                etag,
+                fetched_at: Utc::now(),
+                body,
+            }
+        }
+        (status, _) => return Err(anyhow!("{} returned error status {}", resource.name, status)),
+    };
+
+    store_cached(app_state, resource, &payload);
+    Ok(payload)
+}
+
+// Body of a cached upstream payload, downloaded again only once its copy is older than the TTL.
+// A stale copy is served when the upstream cannot be reached.
+pub async fn fetch(app_state: &AppState, resource: &CachedResource) -> Result<String> {
+    let cached = load_cached(app_state, resource);
+    if let Some(payload) = cached.as_ref().filter(|payload| is_fresh(resource, payload)) {
+        return Ok(payload.body.clone());
+    }
+
+    match revalidate(app_state, resource, cached.clone()).await {
+        Ok(payload) => Ok(payload.body),
+        Err(err) => match cached {
+            Some(stale) => {
+                warn!("Serving stale {} from {}: {}", resource.name, stale.fetched_at, err);
+                Ok(stale.body)
+            }
+            None => Err(err),
+        },
+    }
+}
+
+fn cache_entry(app_state: &AppState, resource: &CachedResource) -> UpstreamCacheEntry {
+    let cached = load_cached(app_state, resource);
+    UpstreamCacheEntry {
+        name: resource.name.to_string(),
+        url: resource.url.to_string(),
+        ttl_secs: resource.ttl_secs,
+        cached: cached.is_some(),
+        etag: cached.as_ref().and_then(|payload| payload.etag.clone()),
+        fetched_at: cached.as_ref().map(|payload| payload.fetched_at),
+        expires_at: cached
+            .as_ref()
+            .map(|payload| payload.fetched_at + Duration::seconds(resource.ttl_secs as i64)),
+        size_bytes: cached.as_ref().map_or(0, |payload| payload.body.len()),
+    }
+}
+
+// State of every cached payload
+pub fn get_cache_status(app_state: &AppState) -> Vec<UpstreamCacheEntry> {
+    RESOURCES.iter().map(|resource| cache_entry(app_state, resource)).collect()
+}
+
+// Revalidate one cached payload, or all of them, regardless of their age
+pub async fn refresh(app_state: &AppState, name: Option<&str>) -> Result<Vec<UpstreamCacheEntry>> {
+    let resources = RESOURCES
+        .iter()
+        .filter(|resource| name.is_none() || name == Some(resource.name))
+        .collect::<Vec<_>>();
+    if resources.is_empty() {
+        return Err(anyhow!("Unknown cached payload: {}", name.unwrap_or_default()));
+    }
+
+    let mut entries = Vec::new();
+    for resource in resources {
+        let cached = load_cached(app_state, resource);
+        revalidate(app_state, resource, cached).await?;
+        entries.push(cache_entry(app_state, resource));
+    }
+
+    info!("Refreshed {} cached upstream payloads", entries.len());
+    Ok(entries)
+}
+
+// Jupiter's verified tokens, optionally only those whose symbol, name or mint contain `search`
+pub async fn get_token_list(app_state: &AppState, search: Option<&str>) -> Result<Vec<TokenInfo>> {
+    let body = fetch(app_state, &JUPITER_TOKEN_LIST).await?;
+    let tokens = serde_json::from_str::<Vec<TokenInfo>>(&body)
+        .map_err(|e| anyhow!("Failed to parse Jupiter token list: {}", e))?;
+
+    let search = search.map(str::trim).filter(|search| !search.is_empty()).map(str::to_lowercase);
+    Ok(match search {
+        Some(search) => tokens
+            .into_iter()
+            .filter(|token| {
+                token.symbol.to_lowercase().contains(&search)
+                    || token.name.to_lowercase().contains(&search)
+                    || token.address.to_lowercase() == search
+            })
+            .collect(),
+        None => tokens,
+    })
+}
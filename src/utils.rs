@@ -1,20 +1,81 @@
+#[cfg(feature = "server")]
+use crate::error::ApiError;
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "server")]
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+#[cfg(feature = "server")]
 use serde_json::json;
 
+// Content type of MessagePack encoded bodies
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+// Wire format of a response body
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    MessagePack,
+}
+
+#[cfg(feature = "server")]
+impl ResponseFormat {
+    // MessagePack when the Accept header asks for it, JSON otherwise
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let wants_msgpack = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|media_type| media_type.split(';').next().unwrap_or("").trim())
+            .any(|media_type| {
+                media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE)
+                    || media_type.eq_ignore_ascii_case("application/x-msgpack")
+            });
+        
+        if wants_msgpack {
+            ResponseFormat::MessagePack
+        } else {
+            ResponseFormat::Json
+        }
+    }
+}
+
 // Custom API response type that implements IntoResponse
+#[cfg(feature = "server")]
 pub struct ApiResponse {
     status: StatusCode,
     body: Json<serde_json::Value>,
+    format: ResponseFormat,
 }
 
+#[cfg(feature = "server")]
+impl ApiResponse {
+    // Encode the body in the negotiated format instead of JSON
+    pub fn with_format(mut self, format: ResponseFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[cfg(feature = "server")]
 impl IntoResponse for ApiResponse {
     fn into_response(self) -> Response {
-        (self.status, self.body).into_response()
+        match self.format {
+            ResponseFormat::Json => (self.status, self.body).into_response(),
+            // Named encoding keeps field names so the payload mirrors the JSON one
+            ResponseFormat::MessagePack => match rmp_serde::to_vec_named(&self.body.0) {
+                Ok(bytes) => (self.status, [(header::CONTENT_TYPE, MSGPACK_CONTENT_TYPE)], bytes).into_response(),
+                Err(err) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to encode MessagePack response: {}", err),
+                )
+                    .into_response(),
+            },
+        }
     }
 }
 
@@ -38,32 +99,58 @@ pub fn ui_amount_to_token_amount(ui_amount: f64, decimals: u8) -> u64 {
     (ui_amount * 10f64.powi(decimals as i32)) as u64
 }
 
-// Helper to build a consistent API response
+// Exact base-unit amount as a string, rounded rather than truncated so 0.29 stays 29 cents.
+// Amounts are string encoded in responses so JavaScript clients don't lose precision.
+pub fn raw_amount_string(ui_amount: f64, decimals: u8) -> String {
+    ((ui_amount * 10f64.powi(decimals as i32)).round() as u64).to_string()
+}
+
+// Helper to build a consistent API response. Errors carry a machine-readable `code` derived
+// from the status and message.
+#[cfg(feature = "server")]
 pub fn build_api_response<T: serde::Serialize>(
     status: StatusCode,
     data: Option<T>,
     error: Option<String>,
+) -> ApiResponse {
+    let code = error.as_deref().map(|error| ApiError::with_status(status, error).code());
+    build_coded_response(status, data, error, code)
+}
+
+// Helper to build an API response whose error has a known code
+#[cfg(feature = "server")]
+pub fn build_coded_response<T: serde::Serialize>(
+    status: StatusCode,
+    data: Option<T>,
+    error: Option<String>,
+    code: Option<&str>,
 ) -> ApiResponse {
     let success = error.is_none();
     
-    let response = json!({
+    let mut response = json!({
         "success": success,
         "data": data,
         "error": error,
     });
+    if let Some(code) = code {
+        response["code"] = json!(code);
+    }
     
     ApiResponse {
         status,
-        body: Json(response)
+        body: Json(response),
+        format: ResponseFormat::Json,
     }
 }
 
 // Helper to build error responses
+#[cfg(feature = "server")]
 pub fn build_error_response(status: StatusCode, error: &str) -> ApiResponse {
     build_api_response::<()>(status, None, Some(error.to_string()))
 }
 
 // Helper to build success responses
+#[cfg(feature = "server")]
 pub fn build_success_response<T: serde::Serialize>(data: T) -> ApiResponse {
     build_api_response(StatusCode::OK, Some(data), None)
 }
@@ -74,4 +161,113 @@ pub fn validate_amount(amount: f64) -> Result<()> {
         return Err(anyhow!("Amount must be greater than zero"));
     }
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Validate an amount given as a share of a balance, in percent, instead of a fixed amount
+pub fn validate_amount_percent(amount_percent: f64, amount: f64) -> Result<()> {
+    if amount != 0.0 {
+        return Err(anyhow!("Set either amount or amount_percent, not both"));
+    }
+    if amount_percent <= 0.0 || amount_percent > 100.0 {
+        return Err(anyhow!("amount_percent must be greater than 0 and at most 100"));
+    }
+    Ok(())
+}
+
+// Validate an amount given in US dollars instead of token units
+pub fn validate_amount_usd(amount_usd: f64, amount: f64, amount_percent: Option<f64>) -> Result<()> {
+    if amount != 0.0 || amount_percent.is_some() {
+        return Err(anyhow!("Set only one of amount, amount_percent or amount_usd"));
+    }
+    if !amount_usd.is_finite() || amount_usd <= 0.0 {
+        return Err(anyhow!("amount_usd must be greater than zero"));
+    }
+    Ok(())
+}
+
+// Validate a slippage tolerance in percent
+pub fn validate_slippage(slippage: f64) -> Result<()> {
+    if slippage <= 0.0 || slippage > 50.0 {
+        return Err(anyhow!("Slippage must be greater than 0% and at most 50%"));
+    }
+    Ok(())
+}
+
+// Parse a comma separated list of positive numbers, e.g. `10,12,15`
+pub fn parse_positive_list(values: &str) -> Result<Vec<f64>> {
+    let parsed = values
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<f64>()
+                .ok()
+                .filter(|value| *value > 0.0)
+                .ok_or_else(|| anyhow!("Invalid value: {}", v))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    
+    if parsed.is_empty() {
+        return Err(anyhow!("At least one value is required"));
+    }
+    
+    Ok(parsed)
+}
+
+// Parse a timezone given as `UTC`, `Z` or a fixed offset like `+05:30`, `-0800` or `+02`
+pub fn parse_timezone(tz: &str) -> Result<FixedOffset> {
+    let tz = tz.trim();
+    if tz.eq_ignore_ascii_case("utc") || tz == "Z" {
+        return Ok(FixedOffset::east_opt(0).unwrap());
+    }
+    
+    let (sign, rest) = match tz.chars().next() {
+        Some('+') => (1, &tz[1..]),
+        Some('-') => (-1, &tz[1..]),
+        _ => return Err(anyhow!("Invalid timezone: {} (expected UTC or an offset like +05:30)", tz)),
+    };
+    
+    let digits = rest.replace(':', "");
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>()?, 0),
+        4 => (digits[..2].parse::<i32>()?, digits[2..].parse::<i32>()?),
+        _ => return Err(anyhow!("Invalid timezone offset: {}", tz)),
+    };
+    
+    if hours > 14 || minutes > 59 {
+        return Err(anyhow!("Invalid timezone offset: {}", tz));
+    }
+    
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .ok_or_else(|| anyhow!("Invalid timezone offset: {}", tz))
+}
+
+// Whether a JSON field holds a timestamp that should follow the caller's timezone
+fn is_timestamp_field(key: &str) -> bool {
+    key == "timestamp" || key == "last_heartbeat" || key == "last_updated" || key.ends_with("_at") || key.ends_with("_time")
+}
+
+// Render every timestamp field in a response in the given timezone; storage stays UTC
+pub fn localize_timestamps(value: &mut serde_json::Value, offset: &FixedOffset) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if let serde_json::Value::String(text) = field {
+                    if is_timestamp_field(key) {
+                        if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+                            *text = timestamp.with_timezone(offset).to_rfc3339();
+                        }
+                    }
+                } else {
+                    localize_timestamps(field, offset);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                localize_timestamps(item, offset);
+            }
+        }
+        _ => {}
+    }
+}
@@ -1,19 +1,40 @@
-use crate::models::{TokenBalance, Wallet};
+use crate::models::{AppState, BatchImportResponse, ExportedWallet, FeeBreakdown, ImportMode, ImportWalletEntry, ImportWalletResult, RemovedWallet, TokenBalance, Wallet, WalletSummary};
+use crate::tenant;
 use anyhow::{anyhow, Result};
-use solana_client::rpc_client::RpcClient;
+use bip39::Mnemonic;
+use chrono::Utc;
+use solana_account_decoder::UiAccountData;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
 use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    derivation_path::DerivationPath,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
+    signer::keypair::keypair_from_seed_and_derivation_path,
+    system_instruction,
+    transaction::Transaction,
 };
-use spl_associated_token_account::get_associated_token_address;
+use std::collections::HashSet;
+use std::str::FromStr;
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
 use std::time::Duration;
-use tracing::{error, info};
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
 
 // Constants
 const SOLANA_MAINNET_URL: &str = "https://api.mainnet-beta.solana.com";
 const SOLANA_DEVNET_URL: &str = "https://api.devnet.solana.com";
 const SOL_DECIMALS: u8 = 9;
 
+// Password `/export_wallet` requires; exports are refused while it is unset
+const WALLET_EXPORT_PASSWORD_ENV: &str = "WALLET_EXPORT_PASSWORD";
+
+// Timeout of a single RPC request
+const RPC_TIMEOUT_SECS: u64 = 30;
+
+// Rent-exempt minimum for a new associated token account (165 bytes)
+pub const ATA_RENT_SOL: f64 = 0.00203928;
+
 // Common token mint addresses for testing
 pub struct KnownTokens;
 
@@ -53,33 +74,24 @@ pub fn get_rpc_url() -> String {
     std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| SOLANA_DEVNET_URL.to_string())
 }
 
-// Generate a new wallet with a random keypair
+// Async RPC client shared through the app state, so every call reuses its HTTP connections
+pub fn new_rpc_client() -> RpcClient {
+    RpcClient::new_with_timeout_and_commitment(
+        get_rpc_url(),
+        Duration::from_secs(RPC_TIMEOUT_SECS),
+        CommitmentConfig::confirmed(),
+    )
+}
+
+// Generate a new wallet from a fresh 12 word BIP39 mnemonic
 pub fn generate_new_wallet() -> Result<(Wallet, String)> {
-    // Generate a random keypair
-    let keypair = Keypair::new();
-    let pubkey = keypair.pubkey();
-    
-    // For the purpose of this demo, we'll create a simple mnemonic
-    // In a real application, you would use proper BIP39 derivation
-    let words = [
-        "abandon", "ability", "able", "about", "above", "absent",
-        "absorb", "abstract", "absurd", "abuse", "access", "accident",
-        "account", "accuse", "achieve", "acid", "acoustic", "acquire",
-        "across", "act", "action", "actor", "actress", "actual",
-    ];
-    
-    // Generate 12 random indices
-    let mut mnemonic = String::new();
-    
-    for i in 0..12 {
-        let index = (rand::random::<u8>() as usize) % words.len();
-        if i > 0 {
-            mnemonic.push(' ');
-        }
-        mnemonic.push_str(words[index]);
-    }
-    
-    Ok((Wallet { keypair, pubkey }, mnemonic))
+    // 128 bits of entropy encode to 12 words
+    let entropy: [u8; 16] = rand::random();
+    let mnemonic = Mnemonic::from_entropy(&entropy)
+        .map_err(|e| anyhow!("Failed to generate mnemonic: {}", e))?;
+
+    let wallet = wallet_from_mnemonic(&mnemonic)?;
+    Ok((wallet, mnemonic.to_string()))
 }
 
 // Import wallet from private key
@@ -91,99 +103,424 @@ pub fn import_from_private_key(private_key: &str) -> Result<Wallet> {
     Ok(Wallet { keypair, pubkey })
 }
 
-// Import wallet from mnemonic (simplified for demo)
+// Import wallet from a BIP39 mnemonic
 pub fn import_from_mnemonic(mnemonic_phrase: &str) -> Result<Wallet> {
-    // For demo purposes, we'll generate a deterministic keypair from the mnemonic
-    // In a real application, you'd use proper BIP39/44 derivation
-    use sha2::{Sha256, Digest};
-    
-    // Create a hash of the mnemonic
-    let mut hasher = Sha256::new();
-    hasher.update(mnemonic_phrase.as_bytes());
-    let result = hasher.finalize();
-    
-    // Use the hash as seed for the keypair
-    let mut seed = [0u8; 32];
-    seed.copy_from_slice(&result[0..32]);
-    
-    // Create a keypair using the seed
-    let keypair = Keypair::new();  // For demo, just create a new keypair
-                                   // In production, use proper derivation from the seed
+    let mnemonic = Mnemonic::parse_normalized(mnemonic_phrase.trim())
+        .map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+
+    wallet_from_mnemonic(&mnemonic)
+}
+
+// Import a wallet from whichever key was provided
+pub fn import_from_key(private_key: Option<&str>, mnemonic: Option<&str>) -> Result<Wallet> {
+    match (private_key, mnemonic) {
+        (Some(private_key), _) => import_from_private_key(private_key),
+        (None, Some(mnemonic)) => import_from_mnemonic(mnemonic),
+        (None, None) => Err(anyhow!("Either private_key or mnemonic must be provided")),
+    }
+}
+
+// Name a wallet, ignoring blank labels
+pub fn set_label(app_state: &AppState, pubkey: &str, label: Option<&str>) {
+    if let Some(label) = label.map(str::trim).filter(|label| !label.is_empty()) {
+        let mut labels = app_state.wallet_labels.lock().unwrap();
+        labels.insert(pubkey.to_string(), label.to_string());
+    }
+}
+
+// Import several wallets in one call, reporting the outcome of each entry. An atomic batch imports
+// nothing unless every entry can be imported, a best effort batch imports every entry that can.
+pub fn import_wallets(
+    app_state: &AppState,
+    tenant: &str,
+    entries: &[ImportWalletEntry],
+    passphrase: Option<&str>,
+    mode: ImportMode,
+) -> BatchImportResponse {
+    let passphrase_error = passphrase.and_then(|passphrase| crate::keystore::check_passphrase(passphrase).err());
+    let mut seen = HashSet::new();
+    let mut valid = Vec::new();
+    let mut results = Vec::new();
+
+    // Check every entry before anything is stored
+    for (index, entry) in entries.iter().enumerate() {
+        let (pubkey, error) = match import_from_key(entry.private_key.as_deref(), entry.mnemonic.as_deref()) {
+            Ok(wallet) => {
+                let pubkey = wallet.pubkey.to_string();
+                let error = if let Some(err) = &passphrase_error {
+                    Some(err.to_string())
+                } else if !seen.insert(pubkey.clone()) {
+                    Some(format!("Wallet {} appears more than once in the batch", pubkey))
+                } else if tenant::owned_by_other(app_state, tenant, &pubkey) {
+                    Some(format!("Wallet {} is already imported by another tenant", pubkey))
+                } else {
+                    valid.push((index, wallet));
+                    None
+                };
+                (Some(pubkey), error)
+            }
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        results.push(ImportWalletResult {
+            index,
+            label: entry.label.clone(),
+            pubkey,
+            imported: false,
+            error,
+        });
+    }
+
+    let has_errors = |results: &[ImportWalletResult]| results.iter().any(|result| result.error.is_some());
+    if mode == ImportMode::Atomic && has_errors(&results) {
+        return batch_import_response(mode, results);
+    }
+
+    // Keys are written to the keystore before any wallet is added, so an atomic batch can undo them
+    if let Some(passphrase) = passphrase {
+        let mut written = Vec::new();
+        for (index, wallet) in &valid {
+            let pubkey = wallet.pubkey.to_string();
+            let existed = crate::keystore::is_stored(&pubkey);
+            match crate::keystore::store_wallet(app_state, tenant, wallet, passphrase) {
+                Ok(()) if !existed => written.push(pubkey),
+                Ok(()) => {}
+                Err(err) => results[*index].error = Some(format!("Failed to store wallet: {}", err)),
+            }
+        }
+
+        if mode == ImportMode::Atomic && has_errors(&results) {
+            for pubkey in written {
+                if let Err(err) = crate::keystore::remove_stored(&pubkey) {
+                    error!("Failed to roll back keystore file of {}: {}", pubkey, err);
+                }
+            }
+            return batch_import_response(mode, results);
+        }
+    }
+
+    for (index, wallet) in valid {
+        let result = &mut results[index];
+        if result.error.is_some() {
+            continue;
+        }
+
+        let pubkey = wallet.pubkey.to_string();
+        match tenant::register_wallet(app_state, tenant, &pubkey) {
+            Ok(()) => {
+                set_label(app_state, &pubkey, result.label.as_deref());
+                add_wallet(app_state, wallet);
+                result.imported = true;
+            }
+            Err(err) => result.error = Some(err.to_string()),
+        }
+    }
+
+    batch_import_response(mode, results)
+}
+
+// Summarize the outcome of a batch import
+fn batch_import_response(mode: ImportMode, results: Vec<ImportWalletResult>) -> BatchImportResponse {
+    let imported = results.iter().filter(|result| result.imported).count();
+    info!("Batch import ({:?}): {} of {} wallets imported", mode, imported, results.len());
+
+    BatchImportResponse {
+        mode,
+        imported,
+        failed: results.len() - imported,
+        results,
+    }
+}
+
+// Derive the first account at m/44'/501'/0'/0', the path used by Phantom and the Solana CLI
+fn wallet_from_mnemonic(mnemonic: &Mnemonic) -> Result<Wallet> {
+    let seed = mnemonic.to_seed("");
+    let derivation_path = DerivationPath::new_bip44(Some(0), Some(0));
+    let keypair = keypair_from_seed_and_derivation_path(&seed, Some(derivation_path))
+        .map_err(|e| anyhow!("Failed to derive keypair: {}", e))?;
     let pubkey = keypair.pubkey();
-    
+
     Ok(Wallet { keypair, pubkey })
 }
 
+// Imported wallet with the given public key, or the first imported wallet when none is given
+pub fn find_wallet(app_state: &AppState, pubkey: Option<&str>) -> Result<Wallet> {
+    let pubkey = match pubkey {
+        Some(pubkey) => pubkey.to_string(),
+        None => {
+            let pubkeys = app_state.wallets.lock().unwrap().keys().cloned().collect::<Vec<_>>();
+            default_wallet(app_state, pubkeys).ok_or_else(|| anyhow!("No wallet imported"))?
+        }
+    };
+
+    let wallets = app_state.wallets.lock().unwrap();
+    wallets
+        .get(&pubkey)
+        .cloned()
+        .ok_or_else(|| anyhow!("Wallet {} is not imported", pubkey))
+}
+
+// Like find_wallet, but in hosted mode only among the wallets the tenant generated or imported
+pub fn find_tenant_wallet(app_state: &AppState, tenant: &str, pubkey: Option<&str>) -> Result<Wallet> {
+    if !tenant::hosted_mode() {
+        return find_wallet(app_state, pubkey);
+    }
+
+    match pubkey {
+        // Another tenant's wallet is reported exactly like one that does not exist
+        Some(pubkey) if !tenant::can_use_wallet(app_state, tenant, pubkey) => {
+            Err(anyhow!("Wallet {} is not imported", pubkey))
+        }
+        Some(pubkey) => find_wallet(app_state, Some(pubkey)),
+        None => {
+            let first = default_wallet(app_state, tenant::tenant_wallets(app_state, tenant))
+                .ok_or_else(|| anyhow!("No wallet imported"))?;
+            find_wallet(app_state, Some(&first))
+        }
+    }
+}
+
+// Make a wallet available for signing. The time it was first added is kept across lock and unlock.
+pub fn add_wallet(app_state: &AppState, wallet: Wallet) {
+    let pubkey = wallet.pubkey.to_string();
+    app_state
+        .wallet_added_at
+        .lock()
+        .unwrap()
+        .entry(pubkey.clone())
+        .or_insert_with(Utc::now);
+    app_state.wallets.lock().unwrap().insert(pubkey, wallet);
+}
+
+// Sort wallets in the order they were added; wallets without a recorded time go last, by pubkey
+fn sort_by_added(app_state: &AppState, pubkeys: &mut [String]) {
+    let added_at = app_state.wallet_added_at.lock().unwrap();
+    pubkeys.sort_by(|a, b| {
+        let key = |pubkey: &String| (added_at.get(pubkey).is_none(), added_at.get(pubkey).copied());
+        key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+    });
+}
+
+// The wallet requests use when they do not name one: the earliest added of the candidates
+fn default_wallet(app_state: &AppState, mut pubkeys: Vec<String>) -> Option<String> {
+    sort_by_added(app_state, &mut pubkeys);
+    pubkeys.into_iter().next()
+}
+
+// Wallets the tenant may use, in the order they were added, marking the default one
+pub fn list_wallets(app_state: &AppState, tenant: &str) -> Vec<WalletSummary> {
+    let mut pubkeys = tenant::tenant_wallets(app_state, tenant);
+    sort_by_added(app_state, &mut pubkeys);
+
+    let labels = app_state.wallet_labels.lock().unwrap().clone();
+    let added_at = app_state.wallet_added_at.lock().unwrap().clone();
+
+    pubkeys
+        .into_iter()
+        .enumerate()
+        .map(|(index, pubkey)| WalletSummary {
+            label: labels.get(&pubkey).cloned(),
+            added_at: added_at.get(&pubkey).copied(),
+            default: index == 0,
+            frozen: crate::freeze::is_frozen(app_state, &pubkey),
+            pubkey,
+        })
+        .collect()
+}
+
+// Forget a wallet: cancel its open and DCA orders, then drop its keypair, label and keystore file
+pub fn remove_wallet(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<RemovedWallet> {
+    find_tenant_wallet(app_state, tenant, Some(pubkey))?;
+
+    let cancelled_orders = crate::orders::cancel_wallet_orders(app_state, pubkey)?;
+    let cancelled_dca_orders = crate::dca::cancel_wallet_dca_orders(app_state, pubkey);
+
+    app_state.wallets.lock().unwrap().remove(pubkey);
+    app_state.wallet_labels.lock().unwrap().remove(pubkey);
+    app_state.wallet_added_at.lock().unwrap().remove(pubkey);
+    app_state.wallet_owners.lock().unwrap().remove(pubkey);
+
+    let removed_from_keystore = crate::keystore::is_stored(pubkey);
+    if removed_from_keystore {
+        crate::keystore::remove_stored(pubkey)?;
+    }
+
+    info!("Removed wallet {}", pubkey);
+    Ok(RemovedWallet {
+        pubkey: pubkey.to_string(),
+        cancelled_orders,
+        cancelled_dca_orders,
+        removed_from_keystore,
+    })
+}
+
+// Refuse an export unless the password matches WALLET_EXPORT_PASSWORD. Without it, exports are disabled.
+pub fn check_export_password(password: &str) -> Result<()> {
+    let configured = crate::secrets::get_secret(WALLET_EXPORT_PASSWORD_ENV)
+        .filter(|configured| !configured.is_empty())
+        .ok_or_else(|| anyhow!("Wallet export is disabled, set {} to enable it", WALLET_EXPORT_PASSWORD_ENV))?;
+
+    // Compare digests so the time taken does not reveal how much of the password matched
+    if Sha256::digest(password.as_bytes()) != Sha256::digest(configured.as_bytes()) {
+        return Err(anyhow!("Invalid export password"));
+    }
+    Ok(())
+}
+
+// Base58 secret key of a wallet the tenant may use
+pub fn export_wallet(app_state: &AppState, tenant: &str, pubkey: &str) -> Result<ExportedWallet> {
+    let wallet = find_tenant_wallet(app_state, tenant, Some(pubkey))?;
+
+    warn!("Exporting the secret key of wallet {}", pubkey);
+    Ok(ExportedWallet {
+        pubkey: pubkey.to_string(),
+        private_key: bs58::encode(wallet.keypair.to_bytes()).into_string(),
+    })
+}
+
 // Get token balances for a wallet
-pub async fn get_token_balances(wallet: &Wallet) -> Result<Vec<TokenBalance>> {
-    let client = RpcClient::new_with_timeout(
-        get_rpc_url(),
-        Duration::from_secs(30),
-    );
-    
+pub async fn get_token_balances(client: &RpcClient, wallet: &Wallet) -> Result<Vec<TokenBalance>> {
     let mut balances = Vec::new();
     
+    #[cfg(feature = "chaos")]
+    crate::chaos::rpc_fault("get_balance")?;
+    
     // Get SOL balance first
-    let sol_balance = client.get_balance(&wallet.pubkey)?;
+    let sol_balance = client.get_balance(&wallet.pubkey).await?;
     let sol_balance_float = sol_balance as f64 / 10f64.powi(9); // SOL has 9 decimals
     
     balances.push(TokenBalance {
         mint: "So11111111111111111111111111111111111111112".to_string(), // Native SOL mint address
         symbol: "SOL".to_string(),
         amount: sol_balance_float,
+        raw_amount: sol_balance.to_string(),
+        decimals: SOL_DECIMALS,
     });
     
-    // Get SPL token accounts - simplified approach since the RPC methods might vary by version
-    // In a production app, you would handle more token fetching details
-    // For demo purposes, we'll just return the SOL balance
-    // and add a few mock token balances for testing
-    
-    // Add some mock token balances for testing
-    if rand::random::<u8>() % 2 == 0 {
-        balances.push(TokenBalance {
-            mint: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(), // USDC
-            symbol: "USDC".to_string(),
-            amount: 100.0,
-        });
+    // Classic SPL token accounts and Token-2022 accounts live under different programs
+    for program_id in [spl_token::id(), token_2022_program_id()] {
+        #[cfg(feature = "chaos")]
+        crate::chaos::rpc_fault("get_token_accounts_by_owner")?;
+        
+        let accounts = client
+            .get_token_accounts_by_owner(&wallet.pubkey, TokenAccountsFilter::ProgramId(program_id))
+            .await?;
+        
+        for account in accounts {
+            let (mint, raw_amount, decimals) = match parse_token_account(&account.account.data) {
+                Some(parsed) => parsed,
+                None => {
+                    error!("Skipping unparseable token account {}", account.pubkey);
+                    continue;
+                }
+            };
+            
+            // A wallet can hold several accounts of the same mint, report them as one balance
+            match balances.iter_mut().find(|balance| balance.mint == mint) {
+                Some(balance) => {
+                    let total = balance.raw_amount.parse::<u128>().unwrap_or(0) + raw_amount;
+                    balance.raw_amount = total.to_string();
+                    balance.amount = total as f64 / 10f64.powi(decimals as i32);
+                }
+                None => balances.push(TokenBalance {
+                    symbol: KnownTokens::get_symbol(&mint),
+                    mint,
+                    amount: raw_amount as f64 / 10f64.powi(decimals as i32),
+                    raw_amount: raw_amount.to_string(),
+                    decimals,
+                }),
+            }
+        }
     }
     
-    if rand::random::<u8>() % 2 == 0 {
-        balances.push(TokenBalance {
-            mint: "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string(), // BONK
-            symbol: "BONK".to_string(),
-            amount: 1000000.0,
-        });
+    Ok(balances)
+}
+
+// Token-2022 program, which spl-token 3.x does not export
+fn token_2022_program_id() -> Pubkey {
+    spl_token_2022::id()
+}
+
+// Token program owning a mint, classic SPL Token or Token-2022
+async fn token_program_for_mint(client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    let owner = client
+        .get_account(mint)
+        .await
+        .map_err(|e| anyhow!("Failed to load mint {}: {}", mint, e))?
+        .owner;
+    
+    if owner == spl_token::id() || owner == token_2022_program_id() {
+        Ok(owner)
+    } else {
+        Err(anyhow!("{} is not a token mint", mint))
     }
+}
+
+// Mint, raw amount and decimals of a jsonParsed token account
+fn parse_token_account(data: &UiAccountData) -> Option<(String, u128, u8)> {
+    let parsed = match data {
+        UiAccountData::Json(parsed) => &parsed.parsed,
+        _ => return None,
+    };
     
-    Ok(balances)
+    let info = parsed.get("info")?;
+    let mint = info.get("mint")?.as_str()?.to_string();
+    let token_amount = info.get("tokenAmount")?;
+    let raw_amount = token_amount.get("amount")?.as_str()?.parse().ok()?;
+    let decimals = token_amount.get("decimals")?.as_u64()? as u8;
+    
+    Some((mint, raw_amount, decimals))
 }
 
 // Check if wallet has sufficient balance for a token
-pub async fn has_sufficient_balance(wallet: &Wallet, token_mint: &str, amount_needed: f64) -> Result<bool> {
-    let balances = get_token_balances(wallet).await?;
-    
-    // Get token decimals
-    let decimals = match KnownTokens::get_decimals(token_mint) {
-        Ok(value) => value,
-        Err(_) => {
-            error!("Unknown token mint: {}, assuming 9 decimals", token_mint);
-            9 // Default to 9 decimals if unknown
+pub async fn has_sufficient_balance(client: &RpcClient, wallet: &Wallet, token_mint: &str, amount_needed: f64) -> Result<bool> {
+    let balances = get_token_balances(client, wallet).await?;
+    Ok(balance_covers(&balances, token_mint, amount_needed))
+}
+
+// Share of a token balance, in percent, from balances already fetched. SOL keeps enough back to pay fees.
+pub fn balance_share(balances: &[TokenBalance], token_mint: &str, percent: f64) -> f64 {
+    let held = balances
+        .iter()
+        .find(|balance| balance.mint == token_mint)
+        .map_or(0.0, |balance| balance.amount);
+    let available = if token_mint == "So11111111111111111111111111111111111111112" {
+        (held - crate::liquidation::SOL_FEE_RESERVE).max(0.0)
+    } else {
+        held
+    };
+    available * percent / 100.0
+}
+
+// Check a set of balances already fetched for a sufficient amount of a token
+pub fn balance_covers(balances: &[TokenBalance], token_mint: &str, amount_needed: f64) -> bool {
+    // Token not found in balances
+    let balance = match balances.iter().find(|balance| balance.mint == token_mint) {
+        Some(balance) => balance,
+        None => return false,
+    };
+    
+    // Balances read from the chain carry their mint's decimals, older snapshots may not
+    let decimals = if !balance.raw_amount.is_empty() {
+        balance.decimals as i32
+    } else {
+        match KnownTokens::get_decimals(token_mint) {
+            Ok(value) => value,
+            Err(_) => {
+                error!("Unknown token mint: {}, assuming 9 decimals", token_mint);
+                9 // Default to 9 decimals if unknown
+            }
         }
     };
     
     // Convert amount to raw units based on decimals
-    let amount_raw = (amount_needed * 10f64.powi(decimals)) as u64;
-    
-    // Check if token exists in balances and has sufficient amount
-    for balance in balances {
-        if balance.mint == token_mint {
-            let balance_raw = (balance.amount * 10f64.powi(decimals)) as u64;
-            return Ok(balance_raw >= amount_raw);
-        }
-    }
+    let amount_raw = (amount_needed * 10f64.powi(decimals)) as u128;
+    let balance_raw = balance
+        .raw_amount
+        .parse::<u128>()
+        .unwrap_or_else(|_| (balance.amount * 10f64.powi(decimals)) as u128);
     
-    // Token not found in balances
-    Ok(false)
+    balance_raw >= amount_raw
 }
 
 // Get the associated token account for a mint and owner
@@ -192,14 +529,9 @@ pub fn get_token_account(wallet_pubkey: &Pubkey, mint: &Pubkey) -> Pubkey {
 }
 
 // Estimate transaction fees based on recent block data
-pub async fn estimate_transaction_fees() -> Result<f64> {
-    let client = RpcClient::new_with_timeout(
-        get_rpc_url(),
-        Duration::from_secs(30),
-    );
-    
+pub async fn estimate_transaction_fees(client: &RpcClient) -> Result<f64> {
     // Get recent blockhash - not used in this simplified approach but kept for future improvements
-    let _recent_block_hash = client.get_latest_blockhash()?;
+    let _recent_block_hash = client.get_latest_blockhash().await?;
     
     // Since get_fee_calculator_for_blockhash is deprecated, we'll use a simpler approach
     // Estimate based on typical transaction costs
@@ -212,4 +544,112 @@ pub async fn estimate_transaction_fees() -> Result<f64> {
     
     info!("Estimated transaction fee: {} SOL", estimated_sol_with_buffer);
     Ok(estimated_sol_with_buffer)
-} 
\ No newline at end of file
+}
+
+// Transfer SOL or an SPL token to another address, returning the transaction signature
+pub async fn transfer_tokens(client: &RpcClient, wallet: &Wallet, token_mint: &str, destination: &str, amount: f64) -> Result<String> {
+    let destination = Pubkey::from_str(destination)
+        .map_err(|e| anyhow!("Invalid destination address: {}", e))?;
+    let decimals = KnownTokens::get_decimals(token_mint)?;
+    let amount_raw = (amount * 10f64.powi(decimals)) as u64;
+    
+    let instructions = if token_mint == "So11111111111111111111111111111111111111112" {
+        vec![system_instruction::transfer(&wallet.pubkey, &destination, amount_raw)]
+    } else {
+        let mint = Pubkey::from_str(token_mint)?;
+        // Token accounts and the transfer belong to the program that owns the mint
+        let token_program = token_program_for_mint(client, &mint).await?;
+        let source_account = get_associated_token_address_with_program_id(&wallet.pubkey, &mint, &token_program);
+        let destination_account = get_associated_token_address_with_program_id(&destination, &mint, &token_program);
+        
+        let transfer = if token_program == token_2022_program_id() {
+            spl_token_2022::instruction::transfer_checked(
+                &token_program,
+                &source_account,
+                &mint,
+                &destination_account,
+                &wallet.pubkey,
+                &[],
+                amount_raw,
+                decimals as u8,
+            )?
+        } else {
+            spl_token::instruction::transfer_checked(
+                &token_program,
+                &source_account,
+                &mint,
+                &destination_account,
+                &wallet.pubkey,
+                &[],
+                amount_raw,
+                decimals as u8,
+            )?
+        };
+        
+        vec![
+            // Make sure the destination can receive the token
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &wallet.pubkey,
+                &destination,
+                &mint,
+                &token_program,
+            ),
+            transfer,
+        ]
+    };
+    
+    let recent_blockhash = client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&wallet.pubkey),
+        &[&wallet.keypair],
+        recent_blockhash,
+    );
+    
+    let signature = client
+        .send_and_confirm_transaction(&transaction)
+        .await
+        .map_err(|e| anyhow!("Failed to send transfer: {}", e))?;
+    
+    info!("Transferred {} {} to {}. Signature: {}", amount, KnownTokens::get_symbol(token_mint), destination, signature);
+    Ok(signature.to_string())
+}
+
+// Check whether receiving a token would require creating its associated token account
+pub async fn needs_token_account(client: &RpcClient, owner: &Pubkey, token_mint: &str) -> Result<bool> {
+    // Native SOL is held directly by the wallet
+    if token_mint == "So11111111111111111111111111111111111111112" {
+        return Ok(false);
+    }
+    
+    let mint = Pubkey::from_str(token_mint)?;
+    let token_account = get_token_account(owner, &mint);
+    let account = client
+        .get_account_with_commitment(&token_account, client.commitment())
+        .await?
+        .value;
+    
+    Ok(account.is_none())
+}
+
+// Estimate the SOL an order needs: network fees plus rent for a new target token account
+pub async fn estimate_order_fees(client: &RpcClient, owner: &Pubkey, target_token: &str) -> FeeBreakdown {
+    let network_fee_sol = estimate_transaction_fees(client).await
+        .unwrap_or(0.01); // Default to 0.01 SOL if estimation fails
+    
+    let ata_rent_sol = match needs_token_account(client, owner, target_token).await {
+        Ok(true) => ATA_RENT_SOL,
+        Ok(false) => 0.0,
+        Err(err) => {
+            // Assume the account is missing so the check errs on the safe side
+            error!("Failed to check token account for {}: {}", target_token, err);
+            ATA_RENT_SOL
+        }
+    };
+    
+    FeeBreakdown {
+        network_fee_sol,
+        ata_rent_sol,
+        total_sol: network_fee_sol + ata_rent_sol,
+    }
+}
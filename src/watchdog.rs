@@ -0,0 +1,167 @@
+use crate::engine;
+use crate::models::{AppState, EngineHealth, NotificationEvent, WatchdogConfig};
+use crate::notify;
+use crate::swap;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+// Dependency names reported in the engine health
+pub const DEPENDENCY_QUOTE: &str = "jupiter_quote";
+pub const DEPENDENCY_RPC: &str = "solana_rpc";
+
+// How often the canaries run
+const WATCHDOG_INTERVAL_SECS: u64 = 30;
+
+// Canary quote: 0.01 SOL into USDC
+const CANARY_INPUT_MINT: &str = "So11111111111111111111111111111111111111112";
+const CANARY_OUTPUT_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const CANARY_INPUT_AMOUNT: u64 = 10_000_000;
+
+// How long the RPC canary waits for the current slot
+const CANARY_RPC_TIMEOUT_SECS: u64 = 10;
+
+// Pause reasons set by the watchdog start with this, so it only lifts its own pauses
+const DEGRADED_PAUSE_PREFIX: &str = "Engine degraded";
+
+// Current engine health
+pub fn get_health(app_state: &AppState) -> EngineHealth {
+    let health = app_state.engine_health.lock().unwrap();
+    health.clone()
+}
+
+// Whether the engine is running blind
+pub fn is_degraded(app_state: &AppState) -> bool {
+    let health = app_state.engine_health.lock().unwrap();
+    health.degraded
+}
+
+// Replace the watchdog config
+pub fn set_config(app_state: &AppState, config: WatchdogConfig) -> Result<WatchdogConfig> {
+    if config.failure_threshold == 0 {
+        return Err(anyhow!("failure_threshold must be at least 1"));
+    }
+
+    let mut health = app_state.engine_health.lock().unwrap();
+    health.config = config.clone();
+    info!("Watchdog config updated: {:?}", config);
+    Ok(config)
+}
+
+// Fetch a small quote to check the aggregator is answering
+async fn canary_quote() -> Result<()> {
+    let quote = swap::get_swap_quote(CANARY_INPUT_MINT, CANARY_OUTPUT_MINT, CANARY_INPUT_AMOUNT, 1.0).await?;
+    if quote.out_amount_units()? == 0 {
+        return Err(anyhow!("Canary quote returned no output"));
+    }
+    Ok(())
+}
+
+// Fetch the current slot to check the RPC node is answering
+async fn canary_rpc(app_state: &AppState) -> Result<()> {
+    #[cfg(feature = "chaos")]
+    crate::chaos::rpc_fault("get_slot")?;
+    
+    // The shared client waits longer than a canary should
+    time::timeout(Duration::from_secs(CANARY_RPC_TIMEOUT_SECS), app_state.rpc_client.get_slot())
+        .await
+        .map_err(|_| anyhow!("Canary RPC call timed out"))?
+        .map_err(|e| anyhow!("Canary RPC call failed: {}", e))?;
+    Ok(())
+}
+
+// Record a canary result, returning whether the dependency is over the failure threshold
+fn record_check(app_state: &AppState, dependency: &str, result: &Result<()>) -> bool {
+    let mut health = app_state.engine_health.lock().unwrap();
+    let threshold = health.config.failure_threshold;
+    let now = Utc::now();
+    let status = health.dependencies.entry(dependency.to_string()).or_default();
+
+    status.last_checked = Some(now);
+    match result {
+        Ok(()) => {
+            status.consecutive_failures = 0;
+            status.last_success = Some(now);
+            status.last_error = None;
+        }
+        Err(err) => {
+            status.consecutive_failures += 1;
+            status.last_error = Some(err.to_string());
+            warn!("Watchdog check {} failed ({} in a row): {}", dependency, status.consecutive_failures, err);
+        }
+    }
+
+    status.consecutive_failures >= threshold
+}
+
+// Mark the engine degraded, pausing executions if configured
+async fn enter_degraded(app_state: &AppState, failing: &[&str]) {
+    let pause = {
+        let mut health = app_state.engine_health.lock().unwrap();
+        if health.degraded {
+            return;
+        }
+        health.degraded = true;
+        health.degraded_since = Some(Utc::now());
+        health.config.pause_on_degraded
+    };
+
+    let reason = format!("{}: {} failing", DEGRADED_PAUSE_PREFIX, failing.join(", "));
+    error!("{}", reason);
+
+    if pause {
+        engine::pause_trading(app_state, &reason);
+    }
+    notify::notify(app_state, NotificationEvent::EngineDegraded, reason).await;
+}
+
+// Clear the degraded state once every dependency answers again
+async fn leave_degraded(app_state: &AppState) {
+    {
+        let mut health = app_state.engine_health.lock().unwrap();
+        if !health.degraded {
+            return;
+        }
+        health.degraded = false;
+        health.degraded_since = None;
+    }
+
+    // Only lift the pause the watchdog put in place itself, other pauses stay
+    engine::lift_pause(app_state, DEGRADED_PAUSE_PREFIX);
+
+    info!("Upstream dependencies healthy again, engine no longer degraded");
+    notify::notify(
+        app_state,
+        NotificationEvent::EngineRecovered,
+        "Upstream dependencies healthy again".to_string(),
+    ).await;
+}
+
+// Background task running canary checks against the quote API and the RPC node
+pub async fn run_watchdog(app_state: Arc<AppState>) {
+    info!("Starting upstream watchdog task");
+
+    loop {
+        time::sleep(time::Duration::from_secs(WATCHDOG_INTERVAL_SECS)).await;
+
+        let quote_result = canary_quote().await;
+        let rpc_result = canary_rpc(&app_state).await;
+
+        let mut failing = Vec::new();
+        if record_check(&app_state, DEPENDENCY_QUOTE, &quote_result) {
+            failing.push(DEPENDENCY_QUOTE);
+        }
+        if record_check(&app_state, DEPENDENCY_RPC, &rpc_result) {
+            failing.push(DEPENDENCY_RPC);
+        }
+
+        if !failing.is_empty() {
+            enter_degraded(&app_state, &failing).await;
+        } else if quote_result.is_ok() && rpc_result.is_ok() {
+            leave_degraded(&app_state).await;
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::models::{AppState, WatchlistEntry, WatchlistRequest};
+use crate::wallet::KnownTokens;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tracing::info;
+
+// Tokens a watchlist starts with
+const DEFAULT_WATCHLIST: &[&str] = &[
+    "So11111111111111111111111111111111111111112",  // SOL
+    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC
+];
+
+// Most tokens one watchlist may hold, since every one of them is priced on each update
+const MAX_WATCHLIST_LEN: usize = 50;
+
+fn default_watchlist() -> Vec<WatchlistEntry> {
+    let now = Utc::now();
+    DEFAULT_WATCHLIST
+        .iter()
+        .map(|mint| WatchlistEntry {
+            mint: mint.to_string(),
+            symbol: KnownTokens::get_symbol(mint),
+            added_at: now,
+            price_usd: None,
+        })
+        .collect()
+}
+
+// The tenant's watchlist with the latest cached prices. A tenant that never changed it gets the defaults.
+pub fn get_watchlist(app_state: &AppState, tenant: &str) -> Vec<WatchlistEntry> {
+    let mut entries = {
+        let mut watchlists = app_state.watchlists.lock().unwrap();
+        watchlists.entry(tenant.to_string()).or_insert_with(default_watchlist).clone()
+    };
+
+    let prices = app_state.token_prices.lock().unwrap();
+    for entry in &mut entries {
+        entry.price_usd = prices.get(&entry.mint).copied();
+    }
+    entries
+}
+
+// Add a token to the tenant's watchlist
+pub fn add_to_watchlist(app_state: &AppState, tenant: &str, request: WatchlistRequest) -> Result<WatchlistEntry> {
+    let mint = request.mint.trim().to_string();
+    Pubkey::from_str(&mint).map_err(|_| anyhow!("Invalid mint: {}", mint))?;
+
+    let mut watchlists = app_state.watchlists.lock().unwrap();
+    let watchlist = watchlists.entry(tenant.to_string()).or_insert_with(default_watchlist);
+
+    if watchlist.iter().any(|entry| entry.mint == mint) {
+        return Err(anyhow!("{} is already on the watchlist", mint));
+    }
+    if watchlist.len() >= MAX_WATCHLIST_LEN {
+        return Err(anyhow!("A watchlist holds at most {} tokens", MAX_WATCHLIST_LEN));
+    }
+
+    let entry = WatchlistEntry {
+        symbol: KnownTokens::get_symbol(&mint),
+        mint,
+        added_at: Utc::now(),
+        price_usd: None,
+    };
+    watchlist.push(entry.clone());
+
+    info!("Added {} to the watchlist", entry.symbol);
+    Ok(entry)
+}
+
+// Remove a token from the tenant's watchlist
+pub fn remove_from_watchlist(app_state: &AppState, tenant: &str, mint: &str) -> Result<WatchlistEntry> {
+    let mut watchlists = app_state.watchlists.lock().unwrap();
+    let watchlist = watchlists.entry(tenant.to_string()).or_insert_with(default_watchlist);
+
+    let index = watchlist
+        .iter()
+        .position(|entry| entry.mint == mint)
+        .ok_or_else(|| anyhow!("{} is not on the watchlist", mint))?;
+    let entry = watchlist.remove(index);
+
+    info!("Removed {} from the watchlist", entry.symbol);
+    Ok(entry)
+}
+
+// Tokens on any watchlist, which the price updater always tracks
+pub fn watched_mints(app_state: &AppState) -> Vec<String> {
+    let watchlists = app_state.watchlists.lock().unwrap();
+    if watchlists.is_empty() {
+        return DEFAULT_WATCHLIST.iter().map(|mint| mint.to_string()).collect();
+    }
+
+    let mut mints = Vec::new();
+    for entry in watchlists.values().flatten() {
+        if !mints.contains(&entry.mint) {
+            mints.push(entry.mint.clone());
+        }
+    }
+    mints
+}
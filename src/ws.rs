@@ -0,0 +1,220 @@
+use crate::events;
+use crate::error::ApiError;
+use crate::integrity;
+use crate::metering;
+use crate::models::{AppState, OrderType, StreamEvent, OrderValidation, QuoteRequest, WsCommand, WsRequest, WsResponse};
+use crate::orders;
+use crate::swap;
+use crate::tenant;
+use crate::utils::{self, ResponseFormat};
+use crate::wallet::KnownTokens;
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Extension,
+    },
+    http::HeaderMap,
+    response::IntoResponse,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tracing::{error, info, warn};
+
+// Replies waiting to be written back to one socket
+const REPLY_BUFFER: usize = 64;
+
+// Handler upgrading `/ws` to a WebSocket channel streaming order and price events and accepting commands
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    // Commands on the socket are metered against the key that opened it
+    let api_key = metering::api_key_from_headers(&headers);
+    // Events are encoded in the format the upgrade request accepts
+    let event_format = ResponseFormat::from_headers(&headers);
+    ws.on_upgrade(move |socket| handle_socket(socket, app_state, api_key, event_format))
+}
+
+// Stream events to the socket while reading commands off it and writing their replies back as they complete.
+// Commands run concurrently, so replies can arrive out of order and are matched by id.
+// Text frames carry JSON; binary frames carry MessagePack and are answered in MessagePack.
+async fn handle_socket(mut socket: WebSocket, app_state: Arc<AppState>, api_key: String, event_format: ResponseFormat) {
+    info!("WebSocket client connected");
+    let (reply_tx, mut reply_rx) = mpsc::channel::<(WsResponse, ResponseFormat)>(REPLY_BUFFER);
+    let mut events = events::subscribe(&app_state);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    // A slow client misses events rather than holding up everyone else
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("WebSocket client lagging, skipped {} events", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                // Tenants only hear about their own orders and wallets
+                let visible = match &event {
+                    StreamEvent::OrderUpdated { order } | StreamEvent::OrderWarning { order, .. } => {
+                        tenant::can_access_order(&api_key, order)
+                    }
+                    StreamEvent::WalletActivity { activity } => tenant::can_use_wallet(&app_state, &api_key, &activity.wallet),
+                    StreamEvent::PricesUpdated { .. } => true,
+                };
+                if !visible {
+                    continue;
+                }
+                let message = match encode(&event, event_format) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        error!("Failed to serialize WebSocket event: {}", err);
+                        continue;
+                    }
+                };
+                if socket.send(message).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let (frame, format) = match message {
+                    Some(Ok(Message::Text(text))) => (
+                        serde_json::from_str::<Value>(&text).map_err(|e| anyhow!("Invalid JSON: {}", e)),
+                        ResponseFormat::Json,
+                    ),
+                    Some(Ok(Message::Binary(bytes))) => (
+                        rmp_serde::from_slice::<Value>(&bytes).map_err(|e| anyhow!("Invalid MessagePack: {}", e)),
+                        ResponseFormat::MessagePack,
+                    ),
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => {
+                        warn!("WebSocket receive error: {}", err);
+                        break;
+                    }
+                };
+
+                let app_state = app_state.clone();
+                let api_key = api_key.clone();
+                let reply_tx = reply_tx.clone();
+                tokio::spawn(async move {
+                    let response = match frame {
+                        Ok(value) => handle_message(app_state, &api_key, value).await,
+                        Err(err) => reply(None, Err(err)),
+                    };
+                    let _ = reply_tx.send((response, format)).await;
+                });
+            }
+            Some((response, format)) = reply_rx.recv() => {
+                let message = match encode(&response, format) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        error!("Failed to serialize WebSocket reply: {}", err);
+                        continue;
+                    }
+                };
+                if socket.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    info!("WebSocket client disconnected");
+}
+
+// Encode an outgoing frame; replies use the same format as the command they answer
+fn encode<T: Serialize>(frame: &T, format: ResponseFormat) -> Result<Message> {
+    Ok(match format {
+        ResponseFormat::Json => Message::Text(serde_json::to_string(frame)?),
+        ResponseFormat::MessagePack => Message::Binary(rmp_serde::to_vec_named(frame)?),
+    })
+}
+
+// Run one decoded command frame, always producing a reply
+async fn handle_message(app_state: Arc<AppState>, api_key: &str, value: Value) -> WsResponse {
+    // Pull the id out first so even malformed commands can be correlated
+    let id = value.get("id").and_then(Value::as_str).map(str::to_string);
+
+    let request = match serde_json::from_value::<WsRequest>(value) {
+        Ok(request) => request,
+        Err(err) => return reply(id, Err(anyhow!("Invalid command: {}", err))),
+    };
+
+    // Each command counts as a request, just like its HTTP equivalent
+    if let Err(err) = metering::record_request(&app_state, api_key) {
+        return reply(request.id, Err(err.into()));
+    }
+
+    let result = run_command(app_state, api_key, request.command).await;
+    reply(request.id, result)
+}
+
+// Execute a command, returning its payload
+async fn run_command(app_state: Arc<AppState>, api_key: &str, command: WsCommand) -> Result<Value> {
+    // Safe mode only serves reads, like the HTTP routes
+    if !matches!(command, WsCommand::Quote(_)) {
+        integrity::ensure_writable(&app_state)?;
+    }
+    
+    match command {
+        WsCommand::CreateOrder(mut request) => {
+            info!("Creating limit order over WebSocket: {:?}", request);
+            request.api_key = Some(api_key.to_string());
+
+            if request.price_target <= 0.0 && request.order_type != OrderType::DeadMansSwitch {
+                return Err(anyhow!("Price target must be greater than zero"));
+            }
+
+            let mut validation = OrderValidation::default();
+            let order = orders::create_limit_order(app_state, *request, &mut validation).await?;
+            Ok(serde_json::to_value(order)?)
+        }
+        WsCommand::CancelOrder(request) => {
+            info!("Canceling limit order over WebSocket: {}", request.order_id);
+            let order = orders::cancel_limit_order(app_state, api_key, &request.order_id).await?;
+            Ok(serde_json::to_value(order)?)
+        }
+        WsCommand::Quote(request) => Ok(serde_json::to_value(quote(&request).await?)?),
+    }
+}
+
+// Fetch a Jupiter quote for a UI amount of the source token
+async fn quote(request: &QuoteRequest) -> Result<swap::JupiterQuoteResponse> {
+    utils::validate_amount(request.amount)?;
+    let slippage = request.slippage.unwrap_or(0.5);
+    utils::validate_slippage(slippage)?;
+
+    let decimals = KnownTokens::get_decimals(&request.source_token)?;
+    let amount = utils::ui_amount_to_token_amount(request.amount, decimals as u8);
+
+    swap::get_swap_quote(&request.source_token, &request.target_token, amount, slippage).await
+}
+
+// Wrap a command result into a reply frame
+fn reply(id: Option<String>, result: Result<Value>) -> WsResponse {
+    match result {
+        Ok(data) => WsResponse {
+            id,
+            success: true,
+            data: Some(data),
+            error: None,
+            code: None,
+        },
+        Err(err) => {
+            let error = ApiError::from(err);
+            WsResponse {
+                id,
+                success: false,
+                data: None,
+                code: Some(error.code().to_string()),
+                error: Some(error.to_string()),
+            }
+        }
+    }
+}
@@ -19,6 +19,7 @@ const walletDetails = document.getElementById('wallet-details');
 const orderTypeSelect = document.getElementById('order-type');
 const amountLabel = document.getElementById('amount-label');
 const priceTargetLabel = document.getElementById('price-target-label');
+const watchlistForm = document.getElementById('watchlist-form');
 
 // Known token data
 const knownTokens = [
@@ -28,6 +29,12 @@ const knownTokens = [
     { symbol: 'GMT', mint: '7i5KKsX2weiTkry7jA4ZwSuXGhs5eJBEjY8vVxR4pfRx' }
 ];
 
+// Latest USD price by mint, kept current by WebSocket price events
+const latestPrices = {};
+
+// Mints on the watchlist, in the order they were added
+let watchlist = [];
+
 // Bootstrap modals
 const loadingModal = new bootstrap.Modal(document.getElementById('loadingModal'));
 const walletGeneratedModal = new bootstrap.Modal(document.getElementById('walletGeneratedModal'));
@@ -39,6 +46,10 @@ generateWalletBtn.addEventListener('click', generateWallet);
 importWalletBtn.addEventListener('click', importWallet);
 refreshBalancesBtn.addEventListener('click', fetchBalances);
 refreshPricesBtn.addEventListener('click', fetchPrices);
+watchlistForm.addEventListener('submit', (e) => {
+    e.preventDefault();
+    addToWatchlist(document.getElementById('watchlist-mint').value.trim());
+});
 refreshOrdersBtn.addEventListener('click', fetchOrders);
 limitOrderForm.addEventListener('submit', (e) => {
     e.preventDefault();
@@ -58,6 +69,9 @@ orderTypeSelect.addEventListener('change', () => {
     } else if (orderType === 'stop_loss') {
         amountLabel.textContent = 'Amount to Sell';
         priceTargetLabel.textContent = 'Sell when price drops to';
+    } else if (orderType === 'take_profit') {
+        amountLabel.textContent = 'Amount to Sell';
+        priceTargetLabel.textContent = 'Sell when price rises to';
     }
 });
 
@@ -80,6 +94,9 @@ async function initApp() {
     // Populate token selects
     populateTokenSelects();
     
+    // Live order and price updates instead of polling
+    subscribeToEvents();
+    
     // Set initial form labels
     if (orderTypeSelect) {
         // Trigger the change event to set initial label text
@@ -240,34 +257,22 @@ async function fetchBalances() {
     }
 }
 
-// Fetch token prices
+// Fetch the watchlist with its token prices
 async function fetchPrices() {
     tokenPricesDiv.innerHTML = '<p>Loading prices...</p>';
     
     try {
-        const response = await fetch(`${API_URL}/get_prices`);
+        const response = await fetch(`${API_URL}/watchlist`);
         const data = await response.json();
         
         if (data.success) {
-            const prices = data.data;
-            
-            if (prices.length === 0) {
-                tokenPricesDiv.innerHTML = '<p>No price data available</p>';
-                return;
-            }
-            
-            // Render the prices
-            let html = '';
-            prices.forEach(price => {
-                html += `
-                    <div class="token-price">
-                        <div class="token-symbol">${price.symbol}</div>
-                        <div class="token-amount">$${parseFloat(price.price_usd).toFixed(6)}</div>
-                    </div>
-                `;
+            watchlist = data.data.map(entry => entry.mint);
+            data.data.forEach(entry => {
+                if (entry.price_usd !== null) {
+                    latestPrices[entry.mint] = entry.price_usd;
+                }
             });
-            
-            tokenPricesDiv.innerHTML = html;
+            renderPrices();
         } else {
             tokenPricesDiv.innerHTML = `<p class="text-danger">Error: ${data.error}</p>`;
         }
@@ -276,6 +281,83 @@ async function fetchPrices() {
     }
 }
 
+// Render the latest known prices of the watched tokens
+function renderPrices() {
+    if (watchlist.length === 0) {
+        tokenPricesDiv.innerHTML = '<p>No tokens on the watchlist</p>';
+        return;
+    }
+    
+    let html = '';
+    watchlist.forEach(mint => {
+        const price = mint in latestPrices ? `$${parseFloat(latestPrices[mint]).toFixed(6)}` : 'Pending';
+        html += `
+            <div class="token-price">
+                <div class="token-symbol">${getTokenSymbol(mint)}</div>
+                <div class="token-amount">${price}</div>
+                <button class="btn btn-sm btn-link text-danger" onclick="removeFromWatchlist('${mint}')">Remove</button>
+            </div>
+        `;
+    });
+    
+    tokenPricesDiv.innerHTML = html;
+}
+
+// Add a token to the watchlist
+async function addToWatchlist(mint) {
+    try {
+        const response = await fetch(`${API_URL}/watchlist`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ mint })
+        });
+        const data = await response.json();
+        
+        if (data.success) {
+            watchlistForm.reset();
+            fetchPrices();
+        } else {
+            alert(`Error: ${data.error}`);
+        }
+    } catch (error) {
+        alert(`Error: ${error.message}`);
+    }
+}
+
+// Remove a token from the watchlist
+async function removeFromWatchlist(mint) {
+    try {
+        const response = await fetch(`${API_URL}/watchlist/${mint}`, { method: 'DELETE' });
+        const data = await response.json();
+        
+        if (data.success) {
+            fetchPrices();
+        } else {
+            alert(`Error: ${data.error}`);
+        }
+    } catch (error) {
+        alert(`Error: ${error.message}`);
+    }
+}
+
+// Listen on the WebSocket for order status changes and price updates, reconnecting when it drops
+function subscribeToEvents() {
+    const socket = new WebSocket(`${API_URL.replace(/^http/, 'ws')}/ws`);
+    
+    socket.addEventListener('message', (message) => {
+        const event = JSON.parse(message.data);
+        
+        if (event.event === 'order_updated') {
+            fetchOrders();
+        } else if (event.event === 'prices_updated') {
+            Object.assign(latestPrices, event.prices);
+            renderPrices();
+        }
+    });
+    
+    socket.addEventListener('close', () => setTimeout(subscribeToEvents, 5000));
+}
+
 // Fetch active orders
 async function fetchOrders() {
     if (!localStorage.getItem('walletPubkey')) {
@@ -0,0 +1,306 @@
+// Build tasks for the API server, run with `cargo xtask <task>`
+use serde_json::{Map, Value};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::process::exit;
+
+const DEFAULT_SPEC_PATH: &str = "openapi.json";
+const CLIENT_PATH: &str = "static/client.ts";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    match args.first().map(String::as_str) {
+        Some("client") => {
+            let spec_path = args.get(1).map(String::as_str).unwrap_or(DEFAULT_SPEC_PATH);
+            if let Err(err) = generate_client(spec_path, CLIENT_PATH) {
+                eprintln!("error: {}", err);
+                exit(1);
+            }
+        }
+        _ => {
+            eprintln!("Usage: cargo xtask client [openapi.json]");
+            exit(2);
+        }
+    }
+}
+
+// Read an OpenAPI document and write a typed TypeScript client for it
+fn generate_client(spec_path: &str, output_path: &str) -> Result<(), String> {
+    let spec = fs::read_to_string(spec_path).map_err(|e| format!("Failed to read {}: {}", spec_path, e))?;
+    let spec: Value = serde_json::from_str(&spec).map_err(|e| format!("Invalid OpenAPI JSON: {}", e))?;
+
+    let client = render_client(&spec)?;
+    fs::write(output_path, client).map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+    println!("Wrote {} from {}", output_path, spec_path);
+    Ok(())
+}
+
+// TypeScript type for a JSON schema
+fn ts_type(schema: &Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return reference.rsplit('/').next().unwrap_or("unknown").to_string();
+    }
+
+    for (key, separator) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+            let joined = variants.iter().map(ts_type).collect::<Vec<_>>().join(separator);
+            return with_nullable(schema, format!("({})", joined));
+        }
+    }
+
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let literals = values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ");
+        return with_nullable(schema, literals);
+    }
+
+    // OpenAPI 3.1 writes nullable types as `type: ["string", "null"]`
+    let types = match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+
+    let rendered = types
+        .iter()
+        .map(|kind| match *kind {
+            "string" => "string".to_string(),
+            "integer" | "number" => "number".to_string(),
+            "boolean" => "boolean".to_string(),
+            "null" => "null".to_string(),
+            "array" => {
+                let items = schema.get("items").map(ts_type).unwrap_or_else(|| "unknown".to_string());
+                format!("{}[]", wrap_union(items))
+            }
+            "object" => object_type(schema),
+            _ => "unknown".to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let rendered = match rendered.len() {
+        0 if schema.get("properties").is_some() => object_type(schema),
+        0 => "unknown".to_string(),
+        _ => rendered.join(" | "),
+    };
+
+    with_nullable(schema, rendered)
+}
+
+// Parenthesize unions so `[]` applies to the whole type
+fn wrap_union(ts: String) -> String {
+    if ts.contains(" | ") && !ts.starts_with('(') {
+        format!("({})", ts)
+    } else {
+        ts
+    }
+}
+
+// Append `| null` for OpenAPI 3.0 `nullable: true`
+fn with_nullable(schema: &Value, ts: String) -> String {
+    if schema.get("nullable").and_then(Value::as_bool).unwrap_or(false) {
+        format!("{} | null", ts)
+    } else {
+        ts
+    }
+}
+
+// Inline object type with required and optional fields
+fn object_type(schema: &Value) -> String {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        Some(properties) if !properties.is_empty() => properties,
+        _ => {
+            return match schema.get("additionalProperties") {
+                Some(Value::Object(_)) => format!("Record<string, {}>", ts_type(&schema["additionalProperties"])),
+                _ => "Record<string, unknown>".to_string(),
+            };
+        }
+    };
+
+    let fields = properties
+        .iter()
+        .map(|(name, property)| {
+            let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+            format!("  {}{}: {};", name, optional, ts_type(property))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("{{\n{}\n}}", fields)
+}
+
+// Method name for an operation, from its operationId or its method and path
+fn operation_name(method: &str, path: &str, operation: &Map<String, Value>) -> String {
+    let raw = operation
+        .get("operationId")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} {}", method, path));
+
+    let mut name = String::new();
+    let mut upper = false;
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper && !name.is_empty() {
+                name.push(c.to_ascii_uppercase());
+            } else {
+                name.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    name
+}
+
+// JSON schema of the successful response of an operation
+fn response_type(operation: &Map<String, Value>) -> String {
+    let responses = match operation.get("responses").and_then(Value::as_object) {
+        Some(responses) => responses,
+        None => return "unknown".to_string(),
+    };
+
+    responses
+        .iter()
+        .find(|(status, _)| status.starts_with('2'))
+        .and_then(|(_, response)| response.pointer("/content/application~1json/schema"))
+        .map(ts_type)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Render the whole client module
+fn render_client(spec: &Value) -> Result<String, String> {
+    let mut out = String::new();
+    let title = spec.pointer("/info/title").and_then(Value::as_str).unwrap_or("API");
+    let version = spec.pointer("/info/version").and_then(Value::as_str).unwrap_or("");
+
+    writeln!(out, "// Generated by `cargo xtask client` from the {} {} OpenAPI spec. Do not edit.", title, version).unwrap();
+    writeln!(out).unwrap();
+
+    if let Some(schemas) = spec.pointer("/components/schemas").and_then(Value::as_object) {
+        for (name, schema) in schemas {
+            writeln!(out, "export type {} = {};\n", name, ts_type(schema)).unwrap();
+        }
+    }
+
+    out.push_str(CLIENT_PRELUDE);
+
+    let paths = spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or("OpenAPI spec has no paths")?;
+
+    for (path, item) in paths {
+        let item = match item.as_object() {
+            Some(item) => item,
+            None => continue,
+        };
+
+        for method in ["get", "post", "put", "patch", "delete"] {
+            let operation = match item.get(method).and_then(Value::as_object) {
+                Some(operation) => operation,
+                None => continue,
+            };
+            render_operation(&mut out, method, path, operation);
+        }
+    }
+
+    out.push_str("}\n");
+    Ok(out)
+}
+
+// Render one client method
+fn render_operation(out: &mut String, method: &str, path: &str, operation: &Map<String, Value>) {
+    let parameters = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut args = Vec::new();
+    let mut query_fields = Vec::new();
+    let mut query_required = false;
+
+    for parameter in &parameters {
+        let name = parameter.get("name").and_then(Value::as_str).unwrap_or("param");
+        let kind = parameter.get("schema").map(ts_type).unwrap_or_else(|| "string".to_string());
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => args.push(format!("{}: {}", name, kind)),
+            Some("query") => {
+                let required = parameter.get("required").and_then(Value::as_bool).unwrap_or(false);
+                query_required |= required;
+                query_fields.push(format!("{}{}: {}", name, if required { "" } else { "?" }, kind));
+            }
+            _ => {}
+        }
+    }
+
+    let body = operation
+        .get("requestBody")
+        .and_then(|body| body.pointer("/content/application~1json/schema"))
+        .map(ts_type);
+    if let Some(body) = &body {
+        args.push(format!("body: {}", body));
+    }
+    if !query_fields.is_empty() {
+        // Only default the query object when every field in it is optional
+        let default = if query_required { "" } else { " = {}" };
+        args.push(format!("query: {{ {} }}{}", query_fields.join("; "), default));
+    }
+
+    // `/orders/{id}` becomes `/orders/${encodeURIComponent(id)}`
+    let url = path.replace('{', "${encodeURIComponent(").replace('}', ")}");
+    let query = if query_fields.is_empty() { "undefined" } else { "query" };
+    let body_arg = if body.is_some() { "body" } else { "undefined" };
+
+    out.push('\n');
+    if let Some(summary) = operation.get("summary").and_then(Value::as_str) {
+        writeln!(out, "  /** {} */", summary).unwrap();
+    }
+    writeln!(
+        out,
+        "  {}({}): Promise<{}> {{\n    return this.request(\"{}\", `{}`, {}, {});\n  }}",
+        operation_name(method, path, operation),
+        args.join(", "),
+        response_type(operation),
+        method.to_uppercase(),
+        url,
+        query,
+        body_arg
+    )
+    .unwrap();
+}
+
+const CLIENT_PRELUDE: &str = r#"export class ApiError extends Error {
+  constructor(public status: number, public body: unknown) {
+    super(`Request failed with status ${status}`);
+  }
+}
+
+export class ApiClient {
+  constructor(private baseUrl: string = "", private fetchImpl: typeof fetch = fetch) {}
+
+  private async request<T>(method: string, path: string, query?: Record<string, unknown>, body?: unknown): Promise<T> {
+    const params = new URLSearchParams();
+    for (const [key, value] of Object.entries(query ?? {})) {
+      if (value !== undefined && value !== null) params.append(key, String(value));
+    }
+    const search = params.toString();
+    const response = await this.fetchImpl(`${this.baseUrl}${path}${search ? `?${search}` : ""}`, {
+      method,
+      headers: body === undefined ? undefined : { "Content-Type": "application/json" },
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const payload = await response.json().catch(() => undefined);
+    if (!response.ok) throw new ApiError(response.status, payload);
+    return payload as T;
+  }
+"#;